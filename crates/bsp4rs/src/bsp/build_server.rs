@@ -56,11 +56,13 @@ impl Notification for OnBuildExit {
 
 /// The workspace build targets request is sent from the client to the server to ask
 /// for the list of all available build targets in the workspace.
+/// With the cargo extension params, only the targets of the requested kinds are returned.
 #[derive(Debug)]
 pub enum WorkspaceBuildTargets {}
 
 impl Request for WorkspaceBuildTargets {
-    type Params = ();
+    /// The params are optional, the client may send no params to get all the build targets.
+    type Params = Option<WorkspaceBuildTargetsParams>;
     type Result = WorkspaceBuildTargetsResult;
     const METHOD: &'static str = "workspace/buildTargets";
 }
@@ -249,6 +251,18 @@ impl Request for BuildTargetCleanCache {
     const METHOD: &'static str = "buildTarget/cleanCache";
 }
 
+/// The ping request is an extension of the protocol. It is answered immediately,
+/// also while the builds are running, so that the client can check if the server
+/// is still responsive.
+#[derive(Debug)]
+pub enum BuildPing {}
+
+impl Request for BuildPing {
+    type Params = ();
+    type Result = PingResult;
+    const METHOD: &'static str = "build/ping";
+}
+
 /// Notification sent from the client to the server when the user wants to send
 /// input to the stdin of the running target.
 #[derive(Debug)]
@@ -356,4 +370,9 @@ mod tests {
     fn clean_cache_method() {
         assert_eq!(BuildTargetCleanCache::METHOD, "buildTarget/cleanCache");
     }
+
+    #[test]
+    fn ping_method() {
+        assert_eq!(BuildPing::METHOD, "build/ping");
+    }
 }
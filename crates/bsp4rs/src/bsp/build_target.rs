@@ -91,7 +91,8 @@ mod tests {
           "dataKind": "cargo",
           "data": {
             "edition": "",
-            "requiredFeatures": []
+            "requiredFeatures": [],
+            "crateTypes": []
           }
         }
         "#
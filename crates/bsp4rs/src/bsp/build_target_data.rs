@@ -35,7 +35,8 @@ mod tests {
           "dataKind": "cargo",
           "data": {
             "edition": "",
-            "requiredFeatures": []
+            "requiredFeatures": [],
+            "crateTypes": []
           }
         }
         "#
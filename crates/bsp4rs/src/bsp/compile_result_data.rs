@@ -5,7 +5,9 @@ use crate::*;
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", tag = "dataKind", content = "data")]
-pub enum NamedCompileResultData {}
+pub enum NamedCompileResultData {
+    Cargo(CargoCompileResult),
+}
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -14,4 +16,28 @@ pub enum CompileResultData {
     Other(OtherData),
 }
 
-impl CompileResultData {}
+impl CompileResultData {
+    pub fn cargo(data: CargoCompileResult) -> Self {
+        Self::Named(NamedCompileResultData::Cargo(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn cargo_compile_result_data() {
+        assert_json_snapshot!(CompileResultData::cargo(CargoCompileResult::default()),
+            @r#"
+        {
+          "dataKind": "cargo",
+          "data": {
+            "errors": 0
+          }
+        }
+        "#
+        );
+    }
+}
@@ -5,7 +5,9 @@ use crate::*;
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", tag = "dataKind", content = "data")]
-pub enum NamedDiagnosticData {}
+pub enum NamedDiagnosticData {
+    RustFormatReplacement(RustFormatReplacement),
+}
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -14,4 +16,24 @@ pub enum DiagnosticData {
     Other(OtherData),
 }
 
-impl DiagnosticData {}
+impl DiagnosticData {
+    pub fn rust_format_replacement(data: RustFormatReplacement) -> Self {
+        Self::Named(NamedDiagnosticData::RustFormatReplacement(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_deserialization;
+
+    #[test]
+    fn rust_format_replacement_data() {
+        test_deserialization(
+            r#"{"dataKind":"rust-format-replacement","data":{"replacement":"fn main() {}"}}"#,
+            &DiagnosticData::rust_format_replacement(RustFormatReplacement {
+                replacement: "fn main() {}".to_string(),
+            }),
+        );
+    }
+}
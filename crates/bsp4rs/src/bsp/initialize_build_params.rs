@@ -20,6 +20,68 @@ pub struct InitializeBuildParams {
     pub data: Option<InitializeBuildParamsData>,
 }
 
+impl InitializeBuildParams {
+    /// Returns a builder of the params with the `bsp_version` set to [`PROTOCOL_VERSION`].
+    pub fn builder() -> InitializeBuildParamsBuilder {
+        InitializeBuildParamsBuilder {
+            params: InitializeBuildParams {
+                bsp_version: PROTOCOL_VERSION.to_string(),
+                ..InitializeBuildParams::default()
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct InitializeBuildParamsBuilder {
+    params: InitializeBuildParams,
+}
+
+impl InitializeBuildParamsBuilder {
+    pub fn display_name(mut self, display_name: impl Into<String>) -> Self {
+        self.params.display_name = display_name.into();
+        self
+    }
+
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.params.version = version.into();
+        self
+    }
+
+    pub fn bsp_version(mut self, bsp_version: impl Into<String>) -> Self {
+        self.params.bsp_version = bsp_version.into();
+        self
+    }
+
+    pub fn root_uri(mut self, root_uri: impl Into<URI>) -> Self {
+        self.params.root_uri = root_uri.into();
+        self
+    }
+
+    /// Adds the language to the `capabilities.language_ids`.
+    pub fn language_id(mut self, language_id: impl Into<LanguageId>) -> Self {
+        self.params
+            .capabilities
+            .language_ids
+            .push(language_id.into());
+        self
+    }
+
+    pub fn capabilities(mut self, capabilities: BuildClientCapabilities) -> Self {
+        self.params.capabilities = capabilities;
+        self
+    }
+
+    pub fn data(mut self, data: InitializeBuildParamsData) -> Self {
+        self.params.data = Some(data);
+        self
+    }
+
+    pub fn build(self) -> InitializeBuildParams {
+        self.params
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -49,4 +111,30 @@ mod tests {
             &InitializeBuildParams::default(),
         );
     }
+
+    #[test]
+    fn initialize_build_params_builder() {
+        let manual = InitializeBuildParams {
+            display_name: "test_name".to_string(),
+            version: "1.0.0".to_string(),
+            bsp_version: PROTOCOL_VERSION.to_string(),
+            root_uri: URI::from("file:///test"),
+            capabilities: BuildClientCapabilities {
+                language_ids: vec!["rust".into()],
+            },
+            data: None,
+        };
+
+        let built = InitializeBuildParams::builder()
+            .display_name("test_name")
+            .version("1.0.0")
+            .root_uri("file:///test")
+            .language_id("rust")
+            .build();
+
+        assert_eq!(
+            serde_json::to_value(&built).unwrap(),
+            serde_json::to_value(&manual).unwrap()
+        );
+    }
 }
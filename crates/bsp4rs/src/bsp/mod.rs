@@ -57,6 +57,7 @@ mod output_path_item_kind;
 mod output_paths_item;
 mod output_paths_params;
 mod output_paths_result;
+mod ping_result;
 mod position;
 mod print_params;
 mod publish_diagnostics_params;
@@ -157,6 +158,7 @@ pub use output_path_item_kind::*;
 pub use output_paths_item::*;
 pub use output_paths_params::*;
 pub use output_paths_result::*;
+pub use ping_result::*;
 pub use position::*;
 pub use print_params::*;
 pub use publish_diagnostics_params::*;
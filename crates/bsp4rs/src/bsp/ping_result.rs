@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PingResult {
+    /// Time since the server started, in milliseconds.
+    pub uptime_ms: u64,
+    /// Method of the last request handled by the server (other than ping),
+    /// if any request was handled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_handled_method: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn ping_result() {
+        let test_data = PingResult {
+            uptime_ms: 1,
+            last_handled_method: Some("test_method".to_string()),
+        };
+
+        assert_json_snapshot!(test_data,
+            @r#"
+        {
+          "uptimeMs": 1,
+          "lastHandledMethod": "test_method"
+        }
+        "#
+        );
+        assert_json_snapshot!(PingResult::default(),
+            @r#"
+        {
+          "uptimeMs": 0
+        }
+        "#
+        );
+    }
+}
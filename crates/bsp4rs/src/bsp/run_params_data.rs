@@ -5,7 +5,9 @@ use crate::*;
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", tag = "dataKind", content = "data")]
-pub enum NamedRunParamsData {}
+pub enum NamedRunParamsData {
+    CargoRun(CargoRunParams),
+}
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -14,4 +16,29 @@ pub enum RunParamsData {
     Other(OtherData),
 }
 
-impl RunParamsData {}
+impl RunParamsData {
+    pub fn cargo_run(data: CargoRunParams) -> Self {
+        Self::Named(NamedRunParamsData::CargoRun(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_deserialization;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn cargo_run_params_data() {
+        test_deserialization(
+            r#"{"dataKind":"cargo-run","data":{"env":{"RUST_LOG":"debug"},"cwd":"/test_cwd"}}"#,
+            &RunParamsData::cargo_run(CargoRunParams {
+                env: EnvironmentVariables::new(BTreeMap::from([(
+                    "RUST_LOG".to_string(),
+                    "debug".to_string(),
+                )])),
+                cwd: Some("/test_cwd".to_string()),
+            }),
+        );
+    }
+}
@@ -5,7 +5,9 @@ use crate::*;
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", tag = "dataKind", content = "data")]
-pub enum NamedTestFinishData {}
+pub enum NamedTestFinishData {
+    CargoBench(CargoBenchResult),
+}
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -14,4 +16,25 @@ pub enum TestFinishData {
     Other(OtherData),
 }
 
-impl TestFinishData {}
+impl TestFinishData {
+    pub fn cargo_bench(data: CargoBenchResult) -> Self {
+        Self::Named(NamedTestFinishData::CargoBench(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_deserialization;
+
+    #[test]
+    fn cargo_bench_result_data() {
+        test_deserialization(
+            r#"{"dataKind":"cargo-bench","data":{"median":1234,"deviation":56}}"#,
+            &TestFinishData::cargo_bench(CargoBenchResult {
+                median: 1234,
+                deviation: 56,
+            }),
+        );
+    }
+}
@@ -5,7 +5,9 @@ use crate::*;
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case", tag = "dataKind", content = "data")]
-pub enum NamedTestResultData {}
+pub enum NamedTestResultData {
+    DebugSessionAddress(DebugSessionAddress),
+}
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -14,4 +16,24 @@ pub enum TestResultData {
     Other(OtherData),
 }
 
-impl TestResultData {}
+impl TestResultData {
+    pub fn debug_session_address(data: DebugSessionAddress) -> Self {
+        Self::Named(NamedTestResultData::DebugSessionAddress(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_deserialization;
+
+    #[test]
+    fn debug_session_address_data() {
+        test_deserialization(
+            r#"{"dataKind":"debug-session-address","data":{"uri":"file:///test"}}"#,
+            &TestResultData::debug_session_address(DebugSessionAddress {
+                uri: "file:///test".into(),
+            }),
+        );
+    }
+}
@@ -1,5 +1,9 @@
+use std::path::{Path, PathBuf};
+
 use serde::{Deserialize, Serialize};
 
+const FILE_SCHEME_PREFIX: &str = "file://";
+
 /// A resource identifier that is a valid URI according to rfc3986:
 /// https://tools.ietf.org/html/rfc3986
 #[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
@@ -10,6 +14,37 @@ impl URI {
     pub fn new(input: String) -> Self {
         Self(input)
     }
+
+    /// Creates the `file://` URI of the path. Windows paths are normalized to use
+    /// forward slashes, for example `C:\project` is mapped into `file:///C:/project`.
+    pub fn from_file_path(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_string_lossy().replace('\\', "/");
+        if has_drive_letter(&path) {
+            Self(format!("{}/{}", FILE_SCHEME_PREFIX, path))
+        } else {
+            Self(format!("{}{}", FILE_SCHEME_PREFIX, path))
+        }
+    }
+
+    /// Returns the path of the `file://` URI, `None` for the other schemes.
+    pub fn to_file_path(&self) -> Option<PathBuf> {
+        let path = self.0.strip_prefix(FILE_SCHEME_PREFIX)?;
+        match path.strip_prefix('/') {
+            Some(windows_path) if has_drive_letter(windows_path) => {
+                Some(PathBuf::from(windows_path))
+            }
+            _ => Some(PathBuf::from(path)),
+        }
+    }
+}
+
+/// Checks if the path starts with a Windows drive letter, e.g. `C:/`.
+fn has_drive_letter(path: &str) -> bool {
+    let mut chars = path.chars();
+    matches!(
+        (chars.next(), chars.next()),
+        (Some(letter), Some(':')) if letter.is_ascii_alphabetic()
+    )
 }
 
 impl std::ops::Deref for URI {
@@ -25,3 +60,44 @@ impl From<&str> for URI {
         Self(input.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_deserialization;
+
+    #[test]
+    fn file_uri_round_trip() {
+        let uri = URI::from_file_path("/test_root/src/lib.rs");
+
+        assert_eq!(uri, URI::from("file:///test_root/src/lib.rs"));
+        assert_eq!(
+            uri.to_file_path(),
+            Some(PathBuf::from("/test_root/src/lib.rs"))
+        );
+        test_deserialization(r#""file:///test_root/src/lib.rs""#, &uri);
+    }
+
+    #[test]
+    fn windows_file_uri_round_trip() {
+        let uri = URI::from_file_path(r"C:\test_root\src\lib.rs");
+
+        assert_eq!(uri, URI::from("file:///C:/test_root/src/lib.rs"));
+        assert_eq!(
+            uri.to_file_path(),
+            Some(PathBuf::from("C:/test_root/src/lib.rs"))
+        );
+    }
+
+    #[test]
+    fn custom_scheme_uri_round_trip() {
+        let uri = URI::from("test_scheme://test_target");
+
+        assert_eq!(uri.to_file_path(), None);
+        test_deserialization(r#""test_scheme://test_target""#, &uri);
+        assert_eq!(
+            serde_json::to_string(&uri).unwrap(),
+            r#""test_scheme://test_target""#
+        );
+    }
+}
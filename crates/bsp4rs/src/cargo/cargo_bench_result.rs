@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+/// `CargoBenchResult` is the cargo-specific data of the test finish notification of
+/// a benchmark, with the timing reported by the libtest bench harness.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CargoBenchResult {
+    /// Median time of the benchmark iteration, in nanoseconds.
+    pub median: u64,
+    /// Difference between the longest and the shortest iteration, in nanoseconds.
+    pub deviation: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn cargo_bench_result() {
+        let test_data = CargoBenchResult {
+            median: 1234,
+            deviation: 56,
+        };
+
+        assert_json_snapshot!(test_data, @r#"
+        {
+          "median": 1234,
+          "deviation": 56
+        }
+        "#);
+        assert_json_snapshot!(CargoBenchResult::default(), @r#"
+        {
+          "median": 0,
+          "deviation": 0
+        }
+        "#);
+    }
+}
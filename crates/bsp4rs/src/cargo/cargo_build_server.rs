@@ -24,6 +24,65 @@ impl Request for SetCargoFeatures {
     const METHOD: &'static str = "workspace/setCargoFeatures";
 }
 
+/// The enable cargo features request is sent from the client to the server to
+/// enable provided features (together with the features they enable)
+/// for the specified Cargo package.
+#[derive(Debug)]
+pub enum EnableCargoFeatures {}
+
+impl Request for EnableCargoFeatures {
+    type Params = SetCargoFeaturesParams;
+    type Result = SetCargoFeaturesResult;
+    const METHOD: &'static str = "workspace/enableCargoFeatures";
+}
+
+/// The disable cargo features request is sent from the client to the server to
+/// disable provided features for the specified Cargo package. Features required
+/// by other enabled features are not disabled.
+#[derive(Debug)]
+pub enum DisableCargoFeatures {}
+
+impl Request for DisableCargoFeatures {
+    type Params = SetCargoFeaturesParams;
+    type Result = SetCargoFeaturesResult;
+    const METHOD: &'static str = "workspace/disableCargoFeatures";
+}
+
+/// The set cargo feature mode request is sent from the client to the server to
+/// set the mode of enabling the features for all the packages (e.g. all features).
+#[derive(Debug)]
+pub enum SetCargoFeatureMode {}
+
+impl Request for SetCargoFeatureMode {
+    type Params = SetCargoFeatureModeParams;
+    type Result = SetCargoFeaturesResult;
+    const METHOD: &'static str = "workspace/setCargoFeatureMode";
+}
+
+/// The cargo reload request is sent from the client to the server to reload the build
+/// configuration, like the `workspace/reload` request, and responds with the build targets
+/// changed by the reload, so that the client knows whether to request them again.
+#[derive(Debug)]
+pub enum CargoWorkspaceReload {}
+
+impl Request for CargoWorkspaceReload {
+    type Params = ();
+    type Result = WorkspaceReloadResult;
+    const METHOD: &'static str = "workspace/cargoReload";
+}
+
+/// The doc request is sent from the client to the server to generate the documentation
+/// of the given build targets (without their dependencies). The server communicates
+/// during the documentation generation with the same notifications as during the compilation.
+#[derive(Debug)]
+pub enum BuildTargetDoc {}
+
+impl Request for BuildTargetDoc {
+    type Params = DocParams;
+    type Result = DocResult;
+    const METHOD: &'static str = "buildTarget/doc";
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -37,4 +96,32 @@ mod tests {
     fn set_cargo_features_method() {
         assert_eq!(SetCargoFeatures::METHOD, "workspace/setCargoFeatures");
     }
+
+    #[test]
+    fn enable_cargo_features_method() {
+        assert_eq!(EnableCargoFeatures::METHOD, "workspace/enableCargoFeatures");
+    }
+
+    #[test]
+    fn disable_cargo_features_method() {
+        assert_eq!(
+            DisableCargoFeatures::METHOD,
+            "workspace/disableCargoFeatures"
+        );
+    }
+
+    #[test]
+    fn set_cargo_feature_mode_method() {
+        assert_eq!(SetCargoFeatureMode::METHOD, "workspace/setCargoFeatureMode");
+    }
+
+    #[test]
+    fn cargo_workspace_reload_method() {
+        assert_eq!(CargoWorkspaceReload::METHOD, "workspace/cargoReload");
+    }
+
+    #[test]
+    fn doc_method() {
+        assert_eq!(BuildTargetDoc::METHOD, "buildTarget/doc");
+    }
 }
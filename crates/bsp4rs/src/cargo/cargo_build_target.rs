@@ -10,6 +10,9 @@ use std::collections::BTreeSet;
 pub struct CargoBuildTarget {
     pub edition: RustEdition,
     pub required_features: BTreeSet<Feature>,
+    /// Crate types of the target, e.g. `lib` and `cdylib` for a C-compatible library.
+    #[serde(default)]
+    pub crate_types: Vec<RustCrateType>,
 }
 
 #[cfg(test)]
@@ -23,6 +26,7 @@ mod tests {
         let test_data = CargoBuildTarget {
             edition: RustEdition::E2015,
             required_features: BTreeSet::from(["test_requiredFeature".into()]),
+            crate_types: vec![RustCrateType::Lib],
         };
 
         assert_json_snapshot!(test_data,
@@ -31,6 +35,9 @@ mod tests {
           "edition": "2015",
           "requiredFeatures": [
             "test_requiredFeature"
+          ],
+          "crateTypes": [
+            2
           ]
         }
         "#
@@ -39,7 +46,8 @@ mod tests {
             @r#"
         {
           "edition": "",
-          "requiredFeatures": []
+          "requiredFeatures": [],
+          "crateTypes": []
         }
         "#
         );
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// `CargoCompileResult` is the cargo-specific data of the compile result,
+/// so that the client knows the outcome without counting the diagnostics.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CargoCompileResult {
+    /// The total number of reported errors compiling the targets.
+    pub errors: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn cargo_compile_result() {
+        let test_data = CargoCompileResult { errors: 1 };
+
+        assert_json_snapshot!(test_data, @r#"
+        {
+          "errors": 1
+        }
+        "#);
+        assert_json_snapshot!(CargoCompileResult::default(), @r#"
+        {
+          "errors": 0
+        }
+        "#);
+    }
+}
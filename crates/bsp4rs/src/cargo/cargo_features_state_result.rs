@@ -8,6 +8,9 @@ pub struct CargoFeaturesStateResult {
     /// The list of Cargo packages with assigned to them target
     /// identifiers and available features.
     pub packages_features: Vec<PackageFeatures>,
+    /// The active mode of enabling the features.
+    #[serde(default)]
+    pub feature_mode: FeatureMode,
 }
 
 #[cfg(test)]
@@ -25,11 +28,13 @@ mod tests {
                 example_package_features(PACKAGE_ID, FEATURE),
                 example_package_features(PACKAGE_ID2, FEATURE2),
             ],
+            feature_mode: FeatureMode::AllFeatures,
         };
 
         assert_json_snapshot!(CargoFeaturesStateResult::default(), @r#"
         {
-          "packagesFeatures": []
+          "packagesFeatures": [],
+          "featureMode": "default"
         }
         "#);
         assert_json_snapshot!(test_data, @r#"
@@ -69,7 +74,8 @@ mod tests {
                 "feature2"
               ]
             }
-          ]
+          ],
+          "featureMode": "allFeatures"
         }
         "#);
     }
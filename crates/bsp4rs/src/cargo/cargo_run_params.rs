@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// `CargoRunParams` is the cargo-specific data of the run params, applied to the process
+/// of the run binary. The `environmentVariables` and `workingDirectory` fields of the run
+/// params take precedence over it.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CargoRunParams {
+    /// Environment variables set for the binary, in addition to the ones of the server.
+    #[serde(default)]
+    pub env: EnvironmentVariables,
+    /// Absolute path of the working directory of the binary. The workspace root
+    /// is used if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_json_snapshot;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn cargo_run_params() {
+        let test_data = CargoRunParams {
+            env: EnvironmentVariables::new(BTreeMap::from([(
+                "RUST_LOG".to_string(),
+                "debug".to_string(),
+            )])),
+            cwd: Some("/test_cwd".to_string()),
+        };
+
+        assert_json_snapshot!(test_data, @r#"
+        {
+          "env": {
+            "RUST_LOG": "debug"
+          },
+          "cwd": "/test_cwd"
+        }
+        "#);
+
+        assert_json_snapshot!(CargoRunParams::default(), @r#"
+        {
+          "env": {}
+        }
+        "#);
+    }
+}
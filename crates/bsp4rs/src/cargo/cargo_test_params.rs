@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Data kind of the test params data naming the tests to run.
+pub const CARGO_TEST_DATA_KIND: &str = "cargo-test";
+/// Data kind of the test params data naming the tests to compile for debugging.
+pub const CARGO_TEST_DEBUG_DATA_KIND: &str = "cargo-test-debug";
+
+/// `CargoTestParams` is the cargo-specific data of the test params, so that the client
+/// can run the single tests (e.g. from the gutter) without knowing the test harness arguments.
+/// With the `cargo-test-debug` data kind, the tests are only compiled and the debug session
+/// address of the test executable is returned instead, see the `debug-session-address`
+/// data of the test result.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CargoTestParams {
+    /// Names of the tests to run, matched exactly. All the tests are run if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub test_names: Vec<String>,
+    /// Number of the threads running the tests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test_threads: Option<u32>,
+}
+
+/// The cargo-specific data of the test params. Its data kinds are not defined by the spec,
+/// so it is sent as the [`OtherData`] of the [`TestParamsData`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CargoTestParamsData {
+    CargoTest(CargoTestParams),
+    CargoTestDebug(CargoTestParams),
+}
+
+impl CargoTestParamsData {
+    /// Reads the data of the test params, if it is of one of the cargo data kinds.
+    pub fn from_test_params_data(data: &TestParamsData) -> Option<CargoTestParamsData> {
+        let TestParamsData::Other(other) = data else {
+            return None;
+        };
+        match other.data_kind.as_str() {
+            CARGO_TEST_DATA_KIND => other.to_typed().ok().map(CargoTestParamsData::CargoTest),
+            CARGO_TEST_DEBUG_DATA_KIND => other
+                .to_typed()
+                .ok()
+                .map(CargoTestParamsData::CargoTestDebug),
+            _ => None,
+        }
+    }
+}
+
+impl From<CargoTestParamsData> for TestParamsData {
+    fn from(data: CargoTestParamsData) -> TestParamsData {
+        let (data_kind, params) = match &data {
+            CargoTestParamsData::CargoTest(params) => (CARGO_TEST_DATA_KIND, params),
+            CargoTestParamsData::CargoTestDebug(params) => (CARGO_TEST_DEBUG_DATA_KIND, params),
+        };
+        TestParamsData::Other(
+            OtherData::from_typed(data_kind, params).expect("the test params are valid JSON"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_deserialization;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn cargo_test_params() {
+        let test_data = CargoTestParams {
+            test_names: vec!["tests::test_name".to_string()],
+            test_threads: Some(1),
+        };
+
+        assert_json_snapshot!(test_data, @r#"
+        {
+          "testNames": [
+            "tests::test_name"
+          ],
+          "testThreads": 1
+        }
+        "#);
+        assert_json_snapshot!(CargoTestParams::default(), @"{}");
+    }
+
+    #[test]
+    fn cargo_test_params_data() {
+        let data = CargoTestParamsData::CargoTest(CargoTestParams {
+            test_names: vec!["test_name".to_string()],
+            test_threads: None,
+        });
+        test_deserialization(
+            r#"{"dataKind":"cargo-test","data":{"testNames":["test_name"]}}"#,
+            &TestParamsData::from(data.clone()),
+        );
+
+        assert_eq!(
+            CargoTestParamsData::from_test_params_data(&data.clone().into()),
+            Some(data)
+        );
+    }
+
+    #[test]
+    fn cargo_test_debug_params_data() {
+        let data = CargoTestParamsData::CargoTestDebug(CargoTestParams {
+            test_names: vec!["test_name".to_string()],
+            test_threads: None,
+        });
+        test_deserialization(
+            r#"{"dataKind":"cargo-test-debug","data":{"testNames":["test_name"]}}"#,
+            &TestParamsData::from(data.clone()),
+        );
+
+        assert_eq!(
+            CargoTestParamsData::from_test_params_data(&data.clone().into()),
+            Some(data)
+        );
+    }
+
+    #[test]
+    fn other_test_params_data() {
+        let data = TestParamsData::scala_test_suites(vec!["test_suite".to_string()]);
+
+        assert_eq!(CargoTestParamsData::from_test_params_data(&data), None);
+    }
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocResult {
+    /// An optional request id to know the origin of this report.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin_id: Option<Identifier>,
+    /// A status code for the execution.
+    pub status_code: StatusCode,
+    /// The `index.html` files of the generated documentation, one per documented crate.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub index_files: Vec<URI>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_deserialization;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn doc_result() {
+        let test_data = DocResult {
+            origin_id: Some("test_originId".into()),
+            status_code: StatusCode::Ok,
+            index_files: vec!["file:///test/target/doc/test_crate/index.html".into()],
+        };
+
+        assert_json_snapshot!(test_data, @r#"
+        {
+          "originId": "test_originId",
+          "statusCode": 1,
+          "indexFiles": [
+            "file:///test/target/doc/test_crate/index.html"
+          ]
+        }
+        "#);
+        assert_json_snapshot!(DocResult::default(), @r#"
+        {
+          "statusCode": 1
+        }
+        "#);
+    }
+
+    #[test]
+    fn doc_result_deserialization() {
+        test_deserialization(
+            r#"{"statusCode":1,"indexFiles":["file:///test/target/doc/test_crate/index.html"]}"#,
+            &DocResult {
+                origin_id: None,
+                status_code: StatusCode::Ok,
+                index_files: vec!["file:///test/target/doc/test_crate/index.html".into()],
+            },
+        );
+        test_deserialization(
+            r#"{"statusCode":2}"#,
+            &DocResult {
+                status_code: StatusCode::Error,
+                ..DocResult::default()
+            },
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+use std::collections::BTreeSet;
+
+/// Mode of enabling the features, applied to all the packages built by the server.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FeatureMode {
+    /// Features enabled for each package with the `workspace/setCargoFeatures` request.
+    #[default]
+    Default,
+    /// All features of the packages are enabled (`--all-features`).
+    AllFeatures,
+    /// Default features of the packages are disabled (`--no-default-features`).
+    NoDefault,
+    /// The features are enabled (`--features`) together with the default features.
+    Custom(BTreeSet<Feature>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn feature_mode() {
+        assert_json_snapshot!(FeatureMode::Default, @r#""default""#);
+        assert_json_snapshot!(FeatureMode::AllFeatures, @r#""allFeatures""#);
+        assert_json_snapshot!(FeatureMode::NoDefault, @r#""noDefault""#);
+        assert_json_snapshot!(FeatureMode::Custom(BTreeSet::from(["feature".into()])), @r#"
+        {
+          "custom": [
+            "feature"
+          ]
+        }
+        "#);
+    }
+}
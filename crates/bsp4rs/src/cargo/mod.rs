@@ -1,16 +1,36 @@
+mod cargo_bench_result;
 mod cargo_build_server;
 mod cargo_build_target;
+mod cargo_compile_result;
 mod cargo_features_state_result;
+mod cargo_run_params;
+mod cargo_test_params;
+mod doc_params;
+mod doc_result;
+mod feature_mode;
 mod package_features;
+mod set_cargo_feature_mode_params;
 mod set_cargo_features_params;
 mod set_cargo_features_result;
+mod workspace_build_targets_params;
+mod workspace_reload_result;
 
+pub use cargo_bench_result::*;
 pub use cargo_build_server::*;
 pub use cargo_build_target::*;
+pub use cargo_compile_result::*;
 pub use cargo_features_state_result::*;
+pub use cargo_run_params::*;
+pub use cargo_test_params::*;
+pub use doc_params::*;
+pub use doc_result::*;
+pub use feature_mode::*;
 pub use package_features::*;
+pub use set_cargo_feature_mode_params::*;
 pub use set_cargo_features_params::*;
 pub use set_cargo_features_result::*;
+pub use workspace_build_targets_params::*;
+pub use workspace_reload_result::*;
 
 #[cfg(test)]
 pub mod tests {
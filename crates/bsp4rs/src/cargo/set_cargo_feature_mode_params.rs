@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetCargoFeatureModeParams {
+    /// The mode of enabling the features to be set.
+    pub feature_mode: FeatureMode,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::test_deserialization;
+
+    #[test]
+    fn set_cargo_feature_mode_params() {
+        test_deserialization(
+            r#"{"featureMode": "allFeatures"}"#,
+            &SetCargoFeatureModeParams {
+                feature_mode: FeatureMode::AllFeatures,
+            },
+        );
+        test_deserialization(
+            r#"{"featureMode": "default"}"#,
+            &SetCargoFeatureModeParams::default(),
+        );
+    }
+}
@@ -7,6 +7,9 @@ use crate::*;
 pub struct SetCargoFeaturesResult {
     /// The status code of the operation.
     pub status_code: StatusCode,
+    /// The reason of the failure, if the features state could not be changed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
 }
 
 #[cfg(test)]
@@ -17,9 +20,16 @@ mod tests {
     #[test]
     fn set_cargo_features_result() {
         let test_data = SetCargoFeaturesResult {
-            status_code: StatusCode::Ok,
+            status_code: StatusCode::Error,
+            message: Some("test_message".to_string()),
         };
         assert_json_snapshot!(test_data, @r#"
+        {
+          "statusCode": 2,
+          "message": "test_message"
+        }
+        "#);
+        assert_json_snapshot!(SetCargoFeaturesResult::default(), @r#"
         {
           "statusCode": 1
         }
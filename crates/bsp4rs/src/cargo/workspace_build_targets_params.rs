@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// `WorkspaceBuildTargetsParams` is the cargo extension of the `workspace/buildTargets`
+/// request params, so that the client can request only some of the build targets.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceBuildTargetsParams {
+    /// Only the build targets of these kinds are returned, all of them if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kinds: Option<Vec<RustTargetKind>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn workspace_build_targets_params() {
+        let test_data = WorkspaceBuildTargetsParams {
+            kinds: Some(vec![RustTargetKind::Bin]),
+        };
+
+        assert_json_snapshot!(test_data, @r#"
+        {
+          "kinds": [
+            2
+          ]
+        }
+        "#);
+        assert_json_snapshot!(WorkspaceBuildTargetsParams::default(), @"{}");
+    }
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// `WorkspaceReloadResult` is the result of the `workspace/cargoReload` request,
+/// so that the client knows whether it has to request the build targets again.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceReloadResult {
+    /// Whether any of the build targets was added, removed or changed by the reload.
+    pub changed: bool,
+    /// The build targets added by the reload.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added: Vec<BuildTargetIdentifier>,
+    /// The build targets removed by the reload.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<BuildTargetIdentifier>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn workspace_reload_result() {
+        let test_data = WorkspaceReloadResult {
+            changed: true,
+            added: vec![BuildTargetIdentifier::default()],
+            removed: vec![BuildTargetIdentifier::default()],
+        };
+
+        assert_json_snapshot!(test_data, @r#"
+        {
+          "changed": true,
+          "added": [
+            {
+              "uri": ""
+            }
+          ],
+          "removed": [
+            {
+              "uri": ""
+            }
+          ]
+        }
+        "#);
+        assert_json_snapshot!(WorkspaceReloadResult::default(), @r#"
+        {
+          "changed": false
+        }
+        "#);
+    }
+}
@@ -33,6 +33,21 @@ pub struct OtherData {
     pub data: serde_json::Value,
 }
 
+impl OtherData {
+    /// Serializes the typed payload of the given kind, e.g. the data of an extension.
+    pub fn from_typed<T: Serialize>(data_kind: &str, data: &T) -> serde_json::Result<OtherData> {
+        Ok(OtherData {
+            data_kind: data_kind.to_string(),
+            data: serde_json::to_value(data)?,
+        })
+    }
+
+    /// Deserializes the payload into the type expected for its data kind.
+    pub fn to_typed<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        T::deserialize(&self.data)
+    }
+}
+
 pub mod tests {
     use serde::Deserialize;
 
@@ -44,3 +59,46 @@ pub mod tests {
         assert_eq!(&value, expected);
     }
 }
+
+#[cfg(test)]
+mod other_data_tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    const TEST_DATA_KIND: &str = "test_dataKind";
+
+    #[test]
+    fn typed_data_round_trip() {
+        let cargo_target = CargoBuildTarget {
+            edition: RustEdition::E2021,
+            required_features: BTreeSet::from(["test_requiredFeature".into()]),
+            crate_types: vec![RustCrateType::Lib],
+        };
+
+        let other_data = OtherData::from_typed(TEST_DATA_KIND, &cargo_target).unwrap();
+
+        assert_eq!(other_data.data_kind, TEST_DATA_KIND);
+        assert_eq!(
+            other_data.data,
+            serde_json::json!({
+                "edition": "2021",
+                "requiredFeatures": ["test_requiredFeature"],
+                "crateTypes": [2],
+            })
+        );
+        assert_eq!(
+            other_data.to_typed::<CargoBuildTarget>().unwrap(),
+            cargo_target
+        );
+    }
+
+    #[test]
+    fn typed_data_of_other_type() {
+        let other_data = OtherData {
+            data_kind: TEST_DATA_KIND.to_string(),
+            data: serde_json::json!("test_data"),
+        };
+
+        assert!(other_data.to_typed::<CargoBuildTarget>().is_err());
+    }
+}
@@ -2,18 +2,29 @@ mod feature;
 mod feature_dependency_graph;
 mod rust_build_server;
 mod rust_cfg_options;
+mod rust_clean_server_state_result;
 mod rust_crate_type;
 mod rust_dep_kind;
 mod rust_dep_kind_info;
 mod rust_dependencies;
 mod rust_dependency;
 mod rust_edition;
+mod rust_format_check_params;
+mod rust_format_check_result;
+mod rust_format_replacement;
+mod rust_future_incompat_package;
+mod rust_future_incompat_report_result;
 mod rust_package;
 mod rust_package_origin;
+mod rust_package_source_kind;
+mod rust_proc_macro_artifact;
 mod rust_raw_dependencies;
 mod rust_raw_dependency;
+mod rust_resolve_spec_params;
+mod rust_resolve_spec_result;
 mod rust_target;
 mod rust_target_kind;
+mod rust_toolchain_result;
 mod rust_workspace_params;
 mod rust_workspace_result;
 
@@ -21,17 +32,28 @@ pub use feature::*;
 pub use feature_dependency_graph::*;
 pub use rust_build_server::*;
 pub use rust_cfg_options::*;
+pub use rust_clean_server_state_result::*;
 pub use rust_crate_type::*;
 pub use rust_dep_kind::*;
 pub use rust_dep_kind_info::*;
 pub use rust_dependencies::*;
 pub use rust_dependency::*;
 pub use rust_edition::*;
+pub use rust_format_check_params::*;
+pub use rust_format_check_result::*;
+pub use rust_format_replacement::*;
+pub use rust_future_incompat_package::*;
+pub use rust_future_incompat_report_result::*;
 pub use rust_package::*;
 pub use rust_package_origin::*;
+pub use rust_package_source_kind::*;
+pub use rust_proc_macro_artifact::*;
 pub use rust_raw_dependencies::*;
 pub use rust_raw_dependency::*;
+pub use rust_resolve_spec_params::*;
+pub use rust_resolve_spec_result::*;
 pub use rust_target::*;
 pub use rust_target_kind::*;
+pub use rust_toolchain_result::*;
 pub use rust_workspace_params::*;
 pub use rust_workspace_result::*;
@@ -16,6 +16,67 @@ impl Request for RustWorkspace {
     const METHOD: &'static str = "buildTarget/rustWorkspace";
 }
 
+/// The resolve spec request is sent from the client to the server to find the build
+/// targets of the workspace package matching the given Cargo package ID specification.
+#[derive(Debug)]
+pub enum RustResolveSpec {}
+
+impl Request for RustResolveSpec {
+    type Params = RustResolveSpecParams;
+    type Result = RustResolveSpecResult;
+    const METHOD: &'static str = "rust/resolveSpec";
+}
+
+/// The clean server state request is sent from the client to the server to remove
+/// the files stored by the server in the target directory and to reset its
+/// in-memory caches, without cleaning the build artifacts.
+#[derive(Debug)]
+pub enum RustCleanServerState {}
+
+impl Request for RustCleanServerState {
+    type Params = ();
+    type Result = RustCleanServerStateResult;
+    const METHOD: &'static str = "rust/cleanServerState";
+}
+
+/// The toolchain request is sent from the client to the server to query for
+/// the Rust toolchain used by the server to build the project.
+#[derive(Debug)]
+pub enum RustToolchain {}
+
+impl Request for RustToolchain {
+    type Params = ();
+    type Result = RustToolchainResult;
+    const METHOD: &'static str = "rust/toolchain";
+}
+
+/// The future incompatibility report request is sent from the client to the server
+/// to find the packages with code that will be rejected by a future version of Rust.
+///
+/// The request builds the workspace with the `--future-incompat-report` flag.
+#[derive(Debug)]
+pub enum RustFutureIncompatReport {}
+
+impl Request for RustFutureIncompatReport {
+    type Params = ();
+    type Result = RustFutureIncompatReportResult;
+    const METHOD: &'static str = "rust/futureIncompatReport";
+}
+
+/// The format check request is sent from the client to the server to find the files
+/// of the given build targets' packages that are not formatted.
+///
+/// The request runs `cargo fmt --check`, the server publishes the diagnostics with
+/// the formatted text for each unformatted part of the files.
+#[derive(Debug)]
+pub enum RustFormatCheck {}
+
+impl Request for RustFormatCheck {
+    type Params = RustFormatCheckParams;
+    type Result = RustFormatCheckResult;
+    const METHOD: &'static str = "rust/formatCheck";
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -24,4 +85,32 @@ mod test {
     fn rust_workspace_method() {
         assert_eq!(RustWorkspace::METHOD, "buildTarget/rustWorkspace");
     }
+
+    #[test]
+    fn rust_resolve_spec_method() {
+        assert_eq!(RustResolveSpec::METHOD, "rust/resolveSpec");
+    }
+
+    #[test]
+    fn rust_clean_server_state_method() {
+        assert_eq!(RustCleanServerState::METHOD, "rust/cleanServerState");
+    }
+
+    #[test]
+    fn rust_toolchain_method() {
+        assert_eq!(RustToolchain::METHOD, "rust/toolchain");
+    }
+
+    #[test]
+    fn rust_future_incompat_report_method() {
+        assert_eq!(
+            RustFutureIncompatReport::METHOD,
+            "rust/futureIncompatReport"
+        );
+    }
+
+    #[test]
+    fn rust_format_check_method() {
+        assert_eq!(RustFormatCheck::METHOD, "rust/formatCheck");
+    }
 }
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RustCleanServerStateResult {
+    /// Indicates whether the server state was removed or not.
+    pub cleaned: bool,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn rust_clean_server_state_result() {
+        let result = RustCleanServerStateResult { cleaned: true };
+
+        assert_json_snapshot!(result, @r#"
+        {
+          "cleaned": true
+        }
+        "#);
+        assert_json_snapshot!(RustCleanServerStateResult::default(), @r#"
+        {
+          "cleaned": false
+        }
+        "#);
+    }
+}
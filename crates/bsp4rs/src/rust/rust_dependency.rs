@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::*;
+use std::collections::BTreeSet;
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -14,6 +15,10 @@ pub struct RustDependency {
     /// Array of dependency kinds.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dep_kinds: Option<Vec<RustDepKindInfo>>,
+    /// Features of the dependency enabled in the workspace. Cargo unifies the features
+    /// requested by all the packages depending on it, so the set is the same for each of them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub features: Option<BTreeSet<Feature>>,
 }
 
 #[cfg(test)]
@@ -27,6 +32,7 @@ mod test {
             name: Some("test_name".to_string()),
             pkg: "test_target".to_string(),
             dep_kinds: Some(vec![RustDepKindInfo::default()]),
+            features: Some(BTreeSet::from(["test_feature".into()])),
         };
 
         assert_json_snapshot!(dependency, @r#"
@@ -37,6 +43,9 @@ mod test {
             {
               "kind": ""
             }
+          ],
+          "features": [
+            "test_feature"
           ]
         }
         "#);
@@ -9,6 +9,7 @@ impl RustEdition {
     pub const E2015: RustEdition = RustEdition::new("2015");
     pub const E2018: RustEdition = RustEdition::new("2018");
     pub const E2021: RustEdition = RustEdition::new("2021");
+    pub const E2024: RustEdition = RustEdition::new("2024");
 
     pub const fn new(tag: &'static str) -> Self {
         Self(std::borrow::Cow::Borrowed(tag))
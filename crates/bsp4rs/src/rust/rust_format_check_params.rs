@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RustFormatCheckParams {
+    /// A sequence of build targets whose packages are checked.
+    pub targets: Vec<BuildTargetIdentifier>,
+    /// A unique identifier generated by the client to identify this request.
+    /// The server may include this id in triggered notifications or responses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin_id: Option<Identifier>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tests::test_deserialization;
+
+    #[test]
+    fn rust_format_check_params() {
+        test_deserialization(
+            r#"{"targets":[{"uri":""}],"originId":"test_originId"}"#,
+            &RustFormatCheckParams {
+                targets: vec![BuildTargetIdentifier::default()],
+                origin_id: Some("test_originId".into()),
+            },
+        );
+        test_deserialization(r#"{"targets":[]}"#, &RustFormatCheckParams::default());
+    }
+}
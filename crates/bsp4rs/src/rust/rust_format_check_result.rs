@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RustFormatCheckResult {
+    /// The files that are not formatted, each reported with the diagnostics
+    /// of the `rust-format-replacement` data kind.
+    pub unformatted_files: Vec<URI>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn rust_format_check_result() {
+        let result = RustFormatCheckResult {
+            unformatted_files: vec!["file:///test/src/lib.rs".into()],
+        };
+
+        assert_json_snapshot!(result, @r#"
+        {
+          "unformattedFiles": [
+            "file:///test/src/lib.rs"
+          ]
+        }
+        "#);
+
+        assert_json_snapshot!(RustFormatCheckResult::default(), @r#"
+        {
+          "unformattedFiles": []
+        }
+        "#);
+    }
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+/// `RustFormatReplacement` is the data of the formatting diagnostic, the client can
+/// replace the range of the diagnostic with the formatted text to fix it.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RustFormatReplacement {
+    /// The formatted text of the lines in the range of the diagnostic.
+    pub replacement: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn rust_format_replacement() {
+        let data = RustFormatReplacement {
+            replacement: "fn main() {}\n".to_string(),
+        };
+
+        assert_json_snapshot!(data, @r#"
+        {
+          "replacement": "fn main() {}\n"
+        }
+        "#);
+
+        assert_json_snapshot!(RustFormatReplacement::default(), @r#"
+        {
+          "replacement": ""
+        }
+        "#);
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RustFutureIncompatPackage {
+    /// The package with the code that will be rejected by a future version of Rust,
+    /// as reported by Cargo, for example `foo v0.1.0`.
+    pub package: String,
+    /// Names of the future incompatibility lints triggered by the package.
+    pub lints: Vec<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn rust_future_incompat_package() {
+        let package = RustFutureIncompatPackage {
+            package: "test_package v0.1.0".to_string(),
+            lints: vec!["test_lint".to_string()],
+        };
+
+        assert_json_snapshot!(package, @r#"
+        {
+          "package": "test_package v0.1.0",
+          "lints": [
+            "test_lint"
+          ]
+        }
+        "#);
+
+        assert_json_snapshot!(RustFutureIncompatPackage::default(), @r#"
+        {
+          "package": "",
+          "lints": []
+        }
+        "#);
+    }
+}
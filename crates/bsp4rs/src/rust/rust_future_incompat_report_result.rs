@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RustFutureIncompatReportResult {
+    /// The packages affected by the future incompatibilities.
+    pub packages: Vec<RustFutureIncompatPackage>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn rust_future_incompat_report_result() {
+        let result = RustFutureIncompatReportResult {
+            packages: vec![RustFutureIncompatPackage::default()],
+        };
+
+        assert_json_snapshot!(result, @r#"
+        {
+          "packages": [
+            {
+              "package": "",
+              "lints": []
+            }
+          ]
+        }
+        "#);
+
+        assert_json_snapshot!(RustFutureIncompatReportResult::default(), @r#"
+        {
+          "packages": []
+        }
+        "#);
+    }
+}
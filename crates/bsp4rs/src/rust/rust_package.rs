@@ -35,6 +35,9 @@ pub struct RustPackage {
     /// `null` for the root package and path dependencies.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source: Option<String>,
+    /// Kind of the source of the package, based on its source ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_kind: Option<RustPackageSourceKind>,
     /// Corresponds to source files which can be compiled into a crate from this package.
     /// Contains only resolved targets without conflicts.
     pub resolved_targets: Vec<RustTarget>,
@@ -85,6 +88,7 @@ mod test {
             origin: RustPackageOrigin::WORKSPACE,
             edition: RustEdition::default(),
             source: Some("test_source".to_string()),
+            source_kind: Some(RustPackageSourceKind::REGISTRY),
             resolved_targets: vec![RustTarget::default()],
             all_targets: vec![RustTarget::default()],
             features: FeatureDependencyGraph::new(BTreeMap::from([(
@@ -113,6 +117,7 @@ mod test {
           "origin": "workspace",
           "edition": "",
           "source": "test_source",
+          "sourceKind": "registry",
           "resolvedTargets": [
             {
               "name": "",
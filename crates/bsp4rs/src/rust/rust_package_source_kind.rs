@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RustPackageSourceKind(pub std::borrow::Cow<'static, str>);
+
+impl RustPackageSourceKind {
+    /// The package is checked out from a git repository.
+    pub const GIT: RustPackageSourceKind = RustPackageSourceKind::new("git");
+    /// The package is in a local directory.
+    pub const PATH: RustPackageSourceKind = RustPackageSourceKind::new("path");
+    /// The package is downloaded from a registry.
+    pub const REGISTRY: RustPackageSourceKind = RustPackageSourceKind::new("registry");
+
+    pub const fn new(tag: &'static str) -> Self {
+        Self(std::borrow::Cow::Borrowed(tag))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn rust_package_source_kind() {
+        assert_json_snapshot!(RustPackageSourceKind::REGISTRY, @r#""registry""#);
+        assert_json_snapshot!(RustPackageSourceKind::GIT, @r#""git""#);
+        assert_json_snapshot!(RustPackageSourceKind::PATH, @r#""path""#);
+    }
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Compiled output of a procedural macro target, needed by the client to expand the macros.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RustProcMacroArtifact {
+    /// File path to the dynamic library (`.dll`, `.so` or `.dylib`) of the target.
+    pub path: URI,
+    /// Whether the library has been built. If not, `path` is the location
+    /// where the library is expected after building the target.
+    pub built: bool,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn rust_proc_macro_artifact() {
+        let artifact = RustProcMacroArtifact {
+            path: "test_path".into(),
+            built: true,
+        };
+
+        assert_json_snapshot!(artifact, @r#"
+        {
+          "path": "test_path",
+          "built": true
+        }
+        "#);
+
+        assert_json_snapshot!(RustProcMacroArtifact::default(), @r#"
+        {
+          "path": "",
+          "built": false
+        }
+        "#);
+    }
+}
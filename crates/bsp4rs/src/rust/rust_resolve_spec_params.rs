@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RustResolveSpecParams {
+    /// Cargo package ID specification, for example `foo`, `foo@1.2.3`
+    /// or `path+file:///path/to/foo#1.2.3`.
+    pub spec: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tests::test_deserialization;
+
+    #[test]
+    fn rust_resolve_spec_params() {
+        test_deserialization(
+            r#"{"spec":"foo@1.2.3"}"#,
+            &RustResolveSpecParams {
+                spec: "foo@1.2.3".to_string(),
+            },
+        );
+        test_deserialization(r#"{"spec":""}"#, &RustResolveSpecParams::default());
+    }
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RustResolveSpecResult {
+    /// Build targets of the package matching the specification.
+    pub targets: Vec<BuildTargetIdentifier>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn rust_resolve_spec_result() {
+        let result = RustResolveSpecResult {
+            targets: vec![BuildTargetIdentifier::default()],
+        };
+
+        assert_json_snapshot!(result, @r#"
+        {
+          "targets": [
+            {
+              "uri": ""
+            }
+          ]
+        }
+        "#);
+        assert_json_snapshot!(RustResolveSpecResult::default(), @r#"
+        {
+          "targets": []
+        }
+        "#);
+    }
+}
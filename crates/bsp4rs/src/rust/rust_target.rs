@@ -25,6 +25,9 @@ pub struct RustTarget {
     /// A sequence of required features.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub required_features: Option<BTreeSet<Feature>>,
+    /// Compiled dynamic library of a procedural macro target.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proc_macro_artifact: Option<RustProcMacroArtifact>,
 }
 
 #[cfg(test)]
@@ -42,6 +45,7 @@ mod test {
             edition: RustEdition::default(),
             doctest: false,
             required_features: Some(BTreeSet::from(["test_feature".into()])),
+            proc_macro_artifact: Some(RustProcMacroArtifact::default()),
         };
 
         assert_json_snapshot!(target, @r#"
@@ -56,7 +60,11 @@ mod test {
           "doctest": false,
           "requiredFeatures": [
             "test_feature"
-          ]
+          ],
+          "procMacroArtifact": {
+            "path": "",
+            "built": false
+          }
         }
         "#);
 
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RustToolchainResult {
+    /// Version of the compiler, for example `1.72.0` or `1.74.0-nightly`.
+    pub version: String,
+    /// Target triple of the host, for example `x86_64-unknown-linux-gnu`.
+    pub host: String,
+    /// Path to the sysroot of the toolchain.
+    pub sysroot: URI,
+    /// Release channel of the toolchain: `stable`, `beta`, `nightly` or `dev`.
+    pub channel: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tests::test_deserialization;
+
+    #[test]
+    fn rust_toolchain_result() {
+        test_deserialization(
+            r#"{"version":"1.72.0","host":"x86_64-unknown-linux-gnu","sysroot":"file:///toolchain","channel":"stable"}"#,
+            &RustToolchainResult {
+                version: "1.72.0".to_string(),
+                host: "x86_64-unknown-linux-gnu".to_string(),
+                sysroot: "file:///toolchain".into(),
+                channel: "stable".to_string(),
+            },
+        );
+        test_deserialization(
+            r#"{"version":"","host":"","sysroot":"","channel":""}"#,
+            &RustToolchainResult::default(),
+        );
+    }
+}
@@ -0,0 +1,712 @@
+//! Checks that the params and the results of all the requests and notifications
+//! are serialized with camelCase keys and survive a serde round-trip.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use std::path::Path;
+
+use bsp4rs::bazel::*;
+use bsp4rs::bsp::*;
+use bsp4rs::cancel::*;
+use bsp4rs::cargo::*;
+use bsp4rs::rust::*;
+use bsp4rs::{Notification, OtherData, Request, PROTOCOL_VERSION};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Fields whose keys are not the names of the fields (e.g. the names of the features
+/// or the environment variables).
+const KNOWN_DIVERGENT_FIELDS: &[&str] = &[
+    "availableFeatures",
+    "cfgOptions",
+    "dependencies",
+    "env",
+    "environmentVariables",
+    "features",
+    "rawDependencies",
+];
+
+/// Value with every optional field set and every list non-empty,
+/// so that all the keys of the type are serialized.
+trait Fixture {
+    fn fixture() -> Self;
+}
+
+fn fixture<T: Fixture>() -> T {
+    T::fixture()
+}
+
+/// Implements [`Fixture`] for each type with the value next to it.
+macro_rules! fixtures {
+    ($($type:ty => $value:expr),* $(,)?) => {
+        $(impl Fixture for $type {
+            fn fixture() -> Self {
+                $value
+            }
+        })*
+    };
+}
+
+impl Fixture for () {
+    fn fixture() -> Self {}
+}
+
+impl<T: Fixture> Fixture for Option<T> {
+    fn fixture() -> Self {
+        Some(T::fixture())
+    }
+}
+
+impl<T: Fixture> Fixture for Vec<T> {
+    fn fixture() -> Self {
+        vec![T::fixture()]
+    }
+}
+
+impl<T: Fixture + Ord> Fixture for BTreeSet<T> {
+    fn fixture() -> Self {
+        BTreeSet::from([T::fixture()])
+    }
+}
+
+fixtures! {
+    String => "test_string".to_string(),
+    URI => "file:///test_uri".into(),
+    Identifier => "test_id".into(),
+    OriginId => "test_originId".into(),
+    LanguageId => "rust".into(),
+    Feature => "test_feature".into(),
+    Jar => "test_jar".into(),
+    EnvironmentVariables => EnvironmentVariables::new(BTreeMap::from([(
+        "TEST_ENV".to_string(),
+        "test_value".to_string(),
+    )])),
+    OtherData => OtherData {
+        data_kind: "test_dataKind".to_string(),
+        data: serde_json::json!({ "testKey": "test_value" }),
+    },
+    BuildTargetIdentifier => BuildTargetIdentifier {
+        uri: URI::fixture(),
+    },
+    TextDocumentIdentifier => TextDocumentIdentifier {
+        uri: URI::fixture(),
+    },
+    TaskId => TaskId {
+        id: Identifier::fixture(),
+        parents: fixture(),
+    },
+    Range => Range {
+        start: Position {
+            line: 1,
+            character: 2,
+        },
+        end: Position {
+            line: 3,
+            character: 4,
+        },
+    },
+    Location => Location {
+        uri: URI::fixture(),
+        range: Range::fixture(),
+    },
+    RequestId => RequestId::String("test_requestId".to_string()),
+    CancelRequestParams => CancelRequestParams {
+        id: RequestId::fixture(),
+    },
+    InitializeBuildParams => InitializeBuildParams {
+        display_name: String::fixture(),
+        version: String::fixture(),
+        bsp_version: PROTOCOL_VERSION.to_string(),
+        root_uri: URI::fixture(),
+        capabilities: BuildClientCapabilities {
+            language_ids: fixture(),
+        },
+        data: Some(InitializeBuildParamsData::Other(OtherData::fixture())),
+    },
+    InitializeBuildResult => InitializeBuildResult {
+        display_name: String::fixture(),
+        version: String::fixture(),
+        bsp_version: PROTOCOL_VERSION.to_string(),
+        capabilities: BuildServerCapabilities {
+            compile_provider: Some(CompileProvider {
+                language_ids: fixture(),
+            }),
+            test_provider: Some(TestProvider {
+                language_ids: fixture(),
+            }),
+            run_provider: Some(RunProvider {
+                language_ids: fixture(),
+            }),
+            debug_provider: Some(DebugProvider {
+                language_ids: fixture(),
+            }),
+            inverse_sources_provider: Some(true),
+            dependency_sources_provider: Some(true),
+            dependency_modules_provider: Some(true),
+            resources_provider: Some(true),
+            output_paths_provider: Some(true),
+            build_target_changed_provider: Some(true),
+            jvm_run_environment_provider: Some(true),
+            jvm_test_environment_provider: Some(true),
+            cargo_features_provider: Some(true),
+            can_reload: Some(true),
+        },
+        data: Some(InitializeBuildResultData::Other(OtherData::fixture())),
+    },
+    PingResult => PingResult {
+        uptime_ms: 1,
+        last_handled_method: Some(BuildPing::METHOD.to_string()),
+    },
+    WorkspaceBuildTargetsParams => WorkspaceBuildTargetsParams {
+        kinds: Some(vec![RustTargetKind::Bin]),
+    },
+    CargoBuildTarget => CargoBuildTarget {
+        edition: RustEdition::E2021,
+        required_features: fixture(),
+        crate_types: vec![RustCrateType::Bin],
+    },
+    BuildTarget => BuildTarget {
+        id: BuildTargetIdentifier::fixture(),
+        display_name: fixture(),
+        base_directory: fixture(),
+        tags: vec![BuildTargetTag::APPLICATION],
+        language_ids: fixture(),
+        dependencies: fixture(),
+        capabilities: BuildTargetCapabilities {
+            can_compile: Some(true),
+            can_test: Some(true),
+            can_run: Some(true),
+            can_debug: Some(true),
+        },
+        data: Some(BuildTargetData::cargo(CargoBuildTarget::fixture())),
+    },
+    WorkspaceBuildTargetsResult => WorkspaceBuildTargetsResult {
+        targets: fixture(),
+    },
+    WorkspaceReloadResult => WorkspaceReloadResult {
+        changed: true,
+        added: fixture(),
+        removed: fixture(),
+    },
+    SourcesParams => SourcesParams {
+        targets: fixture(),
+    },
+    SourcesResult => SourcesResult {
+        items: vec![SourcesItem {
+            target: BuildTargetIdentifier::fixture(),
+            sources: vec![SourceItem {
+                uri: URI::fixture(),
+                kind: SourceItemKind::Directory,
+                generated: true,
+            }],
+            roots: fixture(),
+        }],
+    },
+    InverseSourcesParams => InverseSourcesParams {
+        text_document: TextDocumentIdentifier::fixture(),
+    },
+    InverseSourcesResult => InverseSourcesResult {
+        targets: fixture(),
+    },
+    DependencySourcesParams => DependencySourcesParams {
+        targets: fixture(),
+    },
+    DependencySourcesResult => DependencySourcesResult {
+        items: vec![DependencySourcesItem {
+            target: BuildTargetIdentifier::fixture(),
+            sources: fixture(),
+        }],
+    },
+    DependencyModulesParams => DependencyModulesParams {
+        targets: fixture(),
+    },
+    DependencyModulesResult => DependencyModulesResult {
+        items: vec![DependencyModulesItem {
+            target: BuildTargetIdentifier::fixture(),
+            modules: vec![DependencyModule {
+                name: String::fixture(),
+                version: String::fixture(),
+                data: Some(DependencyModuleData::Other(OtherData::fixture())),
+            }],
+        }],
+    },
+    ResourcesParams => ResourcesParams {
+        targets: fixture(),
+    },
+    ResourcesResult => ResourcesResult {
+        items: vec![ResourcesItem {
+            target: BuildTargetIdentifier::fixture(),
+            resources: fixture(),
+        }],
+    },
+    OutputPathsParams => OutputPathsParams {
+        targets: fixture(),
+    },
+    OutputPathsResult => OutputPathsResult {
+        items: vec![OutputPathsItem {
+            target: BuildTargetIdentifier::fixture(),
+            output_paths: vec![OutputPathItem {
+                uri: URI::fixture(),
+                kind: OutputPathItemKind::Directory,
+            }],
+        }],
+    },
+    CompileParams => CompileParams {
+        targets: fixture(),
+        origin_id: fixture(),
+        arguments: fixture(),
+    },
+    CompileResult => CompileResult {
+        origin_id: fixture(),
+        status_code: StatusCode::Error,
+        data: Some(CompileResultData::cargo(CargoCompileResult { errors: 1 })),
+    },
+    RunParams => RunParams {
+        target: BuildTargetIdentifier::fixture(),
+        origin_id: fixture(),
+        arguments: fixture(),
+        environment_variables: fixture(),
+        working_directory: fixture(),
+        data: Some(RunParamsData::cargo_run(CargoRunParams {
+            env: EnvironmentVariables::fixture(),
+            cwd: fixture(),
+        })),
+    },
+    RunResult => RunResult {
+        origin_id: fixture(),
+        status_code: StatusCode::Error,
+    },
+    TestParams => TestParams {
+        targets: fixture(),
+        origin_id: fixture(),
+        arguments: fixture(),
+        environment_variables: fixture(),
+        working_directory: fixture(),
+        data: Some(
+            CargoTestParamsData::CargoTest(CargoTestParams {
+                test_names: fixture(),
+                test_threads: Some(1),
+            })
+            .into(),
+        ),
+    },
+    TestResult => TestResult {
+        origin_id: fixture(),
+        status_code: StatusCode::Error,
+        data: Some(TestResultData::debug_session_address(
+            DebugSessionAddress::fixture(),
+        )),
+    },
+    DocParams => DocParams {
+        targets: fixture(),
+        origin_id: fixture(),
+        arguments: fixture(),
+    },
+    DocResult => DocResult {
+        origin_id: fixture(),
+        status_code: StatusCode::Error,
+        index_files: fixture(),
+    },
+    DebugSessionParams => DebugSessionParams {
+        targets: fixture(),
+        data: Some(DebugSessionParamsData::Other(OtherData::fixture())),
+    },
+    DebugSessionAddress => DebugSessionAddress {
+        uri: URI::fixture(),
+    },
+    CleanCacheParams => CleanCacheParams {
+        targets: fixture(),
+    },
+    CleanCacheResult => CleanCacheResult {
+        message: fixture(),
+        cleaned: true,
+    },
+    WorkspaceLibrariesResult => WorkspaceLibrariesResult {
+        libraries: vec![LibraryItem {
+            id: BuildTargetIdentifier::fixture(),
+            dependencies: fixture(),
+            jars: fixture(),
+            source_jars: fixture(),
+        }],
+    },
+    WorkspaceDirectoriesResult => WorkspaceDirectoriesResult {
+        included_directories: vec![DirectoryItem {
+            uri: URI::fixture(),
+        }],
+        excluded_directories: vec![DirectoryItem {
+            uri: URI::fixture(),
+        }],
+    },
+    RustWorkspaceParams => RustWorkspaceParams {
+        targets: fixture(),
+    },
+    RustTarget => RustTarget {
+        name: String::fixture(),
+        crate_root_url: URI::fixture(),
+        kind: RustTargetKind::Bin,
+        crate_types: Some(vec![RustCrateType::Bin]),
+        edition: RustEdition::E2021,
+        doctest: true,
+        required_features: fixture(),
+        proc_macro_artifact: Some(RustProcMacroArtifact {
+            path: URI::fixture(),
+            built: true,
+        }),
+    },
+    FeatureDependencyGraph => FeatureDependencyGraph::new(BTreeMap::from([(Feature::fixture(), fixture())])),
+    RustPackage => RustPackage {
+        id: String::fixture(),
+        root_url: URI::fixture(),
+        name: String::fixture(),
+        version: String::fixture(),
+        origin: RustPackageOrigin::WORKSPACE,
+        edition: RustEdition::E2021,
+        source: fixture(),
+        source_kind: Some(RustPackageSourceKind::PATH),
+        resolved_targets: fixture(),
+        all_targets: fixture(),
+        features: FeatureDependencyGraph::fixture(),
+        enabled_features: fixture(),
+        cfg_options: Some(RustCfgOptions::new(BTreeMap::from([(
+            "test_cfg".to_string(),
+            fixture(),
+        )]))),
+        env: fixture(),
+        out_dir_url: fixture(),
+        proc_macro_artifact: fixture(),
+    },
+    RustWorkspaceResult => RustWorkspaceResult {
+        packages: fixture(),
+        raw_dependencies: RustRawDependencies::new(BTreeMap::from([(
+            String::fixture(),
+            vec![RustRawDependency {
+                name: String::fixture(),
+                rename: fixture(),
+                kind: Some(RustDepKind::DEV),
+                target: fixture(),
+                optional: true,
+                uses_default_features: true,
+                features: fixture(),
+            }],
+        )])),
+        dependencies: RustDependencies::new(BTreeMap::from([(
+            String::fixture(),
+            vec![RustDependency {
+                pkg: String::fixture(),
+                name: fixture(),
+                dep_kinds: Some(vec![RustDepKindInfo {
+                    kind: RustDepKind::DEV,
+                    target: fixture(),
+                }]),
+                features: fixture(),
+            }],
+        )])),
+        resolved_targets: fixture(),
+    },
+    RustResolveSpecParams => RustResolveSpecParams {
+        spec: String::fixture(),
+    },
+    RustResolveSpecResult => RustResolveSpecResult {
+        targets: fixture(),
+    },
+    RustCleanServerStateResult => RustCleanServerStateResult { cleaned: true },
+    RustToolchainResult => RustToolchainResult {
+        version: String::fixture(),
+        host: String::fixture(),
+        sysroot: URI::fixture(),
+        channel: String::fixture(),
+    },
+    RustFutureIncompatReportResult => RustFutureIncompatReportResult {
+        packages: vec![RustFutureIncompatPackage {
+            package: String::fixture(),
+            lints: fixture(),
+        }],
+    },
+    RustFormatCheckParams => RustFormatCheckParams {
+        targets: fixture(),
+        origin_id: fixture(),
+    },
+    RustFormatCheckResult => RustFormatCheckResult {
+        unformatted_files: fixture(),
+    },
+    FeatureMode => FeatureMode::Custom(fixture()),
+    CargoFeaturesStateResult => CargoFeaturesStateResult {
+        packages_features: vec![PackageFeatures {
+            package_id: String::fixture(),
+            targets: fixture(),
+            available_features: FeatureDependencyGraph::fixture(),
+            enabled_features: fixture(),
+        }],
+        feature_mode: FeatureMode::fixture(),
+    },
+    SetCargoFeaturesParams => SetCargoFeaturesParams {
+        package_id: String::fixture(),
+        features: fixture(),
+    },
+    SetCargoFeatureModeParams => SetCargoFeatureModeParams {
+        feature_mode: FeatureMode::fixture(),
+    },
+    SetCargoFeaturesResult => SetCargoFeaturesResult {
+        status_code: StatusCode::Error,
+        message: fixture(),
+    },
+    ShowMessageParams => ShowMessageParams {
+        r#type: MessageType::Warning,
+        task: fixture(),
+        origin_id: fixture(),
+        message: String::fixture(),
+    },
+    LogMessageParams => LogMessageParams {
+        r#type: MessageType::Warning,
+        task: fixture(),
+        origin_id: fixture(),
+        message: String::fixture(),
+    },
+    PublishDiagnosticsParams => PublishDiagnosticsParams {
+        text_document: TextDocumentIdentifier::fixture(),
+        build_target: BuildTargetIdentifier::fixture(),
+        origin_id: fixture(),
+        diagnostics: vec![Diagnostic {
+            range: Range::fixture(),
+            severity: Some(DiagnosticSeverity::Warning),
+            code: Some(DiagnosticCode::String("test_code".to_string())),
+            code_description: Some(CodeDescription {
+                href: URI::fixture(),
+            }),
+            source: fixture(),
+            message: String::fixture(),
+            tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+            related_information: Some(vec![DiagnosticRelatedInformation {
+                location: Location::fixture(),
+                message: String::fixture(),
+            }]),
+            data: Some(DiagnosticData::rust_format_replacement(
+                RustFormatReplacement {
+                    replacement: String::fixture(),
+                },
+            )),
+        }],
+        reset: true,
+    },
+    DidChangeBuildTarget => DidChangeBuildTarget {
+        changes: vec![BuildTargetEvent {
+            target: BuildTargetIdentifier::fixture(),
+            kind: Some(BuildTargetEventKind::Changed),
+            data: Some(BuildTargetEventData::Other(OtherData::fixture())),
+        }],
+    },
+    TaskStartParams => TaskStartParams {
+        task_id: TaskId::fixture(),
+        origin_id: fixture(),
+        event_time: Some(1),
+        message: fixture(),
+        data: Some(TaskStartData::test_start(TestStart {
+            display_name: String::fixture(),
+            location: fixture(),
+        })),
+    },
+    TaskProgressParams => TaskProgressParams {
+        task_id: TaskId::fixture(),
+        origin_id: fixture(),
+        event_time: Some(1),
+        message: fixture(),
+        total: Some(2),
+        progress: Some(1),
+        unit: fixture(),
+        data: Some(TaskProgressData::Other(OtherData::fixture())),
+    },
+    TaskFinishParams => TaskFinishParams {
+        task_id: TaskId::fixture(),
+        origin_id: fixture(),
+        event_time: Some(1),
+        message: fixture(),
+        status: StatusCode::Error,
+        data: Some(TaskFinishData::test_finish(TestFinish {
+            display_name: String::fixture(),
+            message: fixture(),
+            status: TestStatus::Failed,
+            location: fixture(),
+            data: Some(TestFinishData::cargo_bench(CargoBenchResult {
+                median: 1,
+                deviation: 2,
+            })),
+        })),
+    },
+    PrintParams => PrintParams {
+        origin_id: Identifier::fixture(),
+        task: fixture(),
+        message: String::fixture(),
+    },
+    ReadParams => ReadParams {
+        origin_id: Identifier::fixture(),
+        task: fixture(),
+        message: String::fixture(),
+    },
+}
+
+fn assert_keys_camel_case(method: &str, value: &serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                assert!(
+                    !key.contains('_') && !key.starts_with(char::is_uppercase),
+                    "key `{}` of {} is not camelCase",
+                    key,
+                    method
+                );
+                if !KNOWN_DIVERGENT_FIELDS.contains(&key.as_str()) {
+                    assert_keys_camel_case(method, value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values
+            .iter()
+            .for_each(|v| assert_keys_camel_case(method, v)),
+        _ => (),
+    }
+}
+
+fn assert_camel_case_round_trip<T>(method: &str, value: T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let json = serde_json::to_value(&value).unwrap();
+    assert_keys_camel_case(method, &json);
+    assert_eq!(
+        serde_json::from_value::<T>(json).unwrap(),
+        value,
+        "{}",
+        method
+    );
+}
+
+/// Checks the params and the results of the requests, returns their methods.
+macro_rules! assert_requests_camel_case {
+    ($($request:ty),* $(,)?) => {
+        vec![$({
+            assert_camel_case_round_trip(
+                <$request as Request>::METHOD,
+                <<$request as Request>::Params as Fixture>::fixture(),
+            );
+            assert_camel_case_round_trip(
+                <$request as Request>::METHOD,
+                <<$request as Request>::Result as Fixture>::fixture(),
+            );
+            <$request as Request>::METHOD
+        }),*]
+    };
+}
+
+/// Checks the params of the notifications, returns their methods.
+macro_rules! assert_notifications_camel_case {
+    ($($notification:ty),* $(,)?) => {
+        vec![$({
+            assert_camel_case_round_trip(
+                <$notification as Notification>::METHOD,
+                <<$notification as Notification>::Params as Fixture>::fixture(),
+            );
+            <$notification as Notification>::METHOD
+        }),*]
+    };
+}
+
+fn checked_request_methods() -> Vec<&'static str> {
+    assert_requests_camel_case!(
+        BuildInitialize,
+        BuildShutdown,
+        BuildPing,
+        WorkspaceBuildTargets,
+        WorkspaceReload,
+        BuildTargetSources,
+        BuildTargetInverseSources,
+        BuildTargetDependencySources,
+        BuildTargetDependencyModules,
+        BuildTargetResources,
+        BuildTargetOutputPaths,
+        BuildTargetCompile,
+        BuildTargetRun,
+        BuildTargetTest,
+        BuildTargetDoc,
+        DebugSessionStart,
+        BuildTargetCleanCache,
+        WorkspaceLibraries,
+        WorkspaceDirectories,
+        RustWorkspace,
+        RustResolveSpec,
+        RustCleanServerState,
+        RustToolchain,
+        RustFutureIncompatReport,
+        RustFormatCheck,
+        CargoFeaturesState,
+        SetCargoFeatures,
+        EnableCargoFeatures,
+        DisableCargoFeatures,
+        SetCargoFeatureMode,
+        CargoWorkspaceReload,
+    )
+}
+
+fn checked_notification_methods() -> Vec<&'static str> {
+    assert_notifications_camel_case!(
+        OnBuildInitialized,
+        OnBuildExit,
+        OnBuildShowMessage,
+        OnBuildLogMessage,
+        OnBuildPublishDiagnostics,
+        OnBuildTargetDidChange,
+        OnBuildTaskStart,
+        OnBuildTaskProgress,
+        OnBuildTaskFinish,
+        OnRunPrintStdout,
+        OnRunPrintStderr,
+        OnRunReadStdin,
+        CancelRequest,
+    )
+}
+
+/// Methods of all the requests and notifications defined in the crate sources.
+/// The whitespace is removed first, so the layout of the constants doesn't matter.
+fn defined_methods(dir: &Path, methods: &mut BTreeSet<String>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            defined_methods(&path, methods);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            let source: String = std::fs::read_to_string(&path)
+                .unwrap()
+                .split_whitespace()
+                .collect();
+            methods.extend(source.split("constMETHOD:").skip(1).filter_map(|item| {
+                let (_, value) = item.split(';').next()?.split_once('=')?;
+                Some(value.strip_prefix('"')?.strip_suffix('"')?.to_string())
+            }));
+        }
+    }
+}
+
+#[test]
+fn requests_camel_case() {
+    checked_request_methods();
+}
+
+#[test]
+fn notifications_camel_case() {
+    checked_notification_methods();
+}
+
+#[test]
+fn all_methods_checked() {
+    let mut defined = BTreeSet::new();
+    defined_methods(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("src"),
+        &mut defined,
+    );
+    let checked: BTreeSet<String> = checked_request_methods()
+        .into_iter()
+        .chain(checked_notification_methods())
+        .map(String::from)
+        .collect();
+
+    assert!(!defined.is_empty());
+    assert_eq!(checked, defined);
+}
@@ -7,6 +7,7 @@ use std::path::PathBuf;
 use simplelog::*;
 
 use cargo_bsp::server;
+use cargo_bsp::server::logging::{log_level_from_env, ClientLogger};
 
 #[cfg(debug_assertions)]
 fn debug_log_file_path() -> PathBuf {
@@ -26,7 +27,8 @@ fn log_file_path() -> PathBuf {
 }
 
 pub fn main() -> server::Result<()> {
-    // Setting logger configuration and logging files location
+    // Setting logger configuration and logging files location. The loggers accept
+    // all levels, the logs are filtered by the maximal level (changed by the client).
     CombinedLogger::init(vec![
         WriteLogger::new(
             LevelFilter::Trace,
@@ -46,8 +48,10 @@ pub fn main() -> server::Result<()> {
             TerminalMode::Stderr,
             ColorChoice::Auto,
         ),
+        Box::new(ClientLogger),
     ])
     .unwrap();
+    log::set_max_level(log_level_from_env());
 
-    server::run_server()
+    server::run_server(server::Transport::from_args(env::args().skip(1))?)
 }
@@ -15,6 +15,12 @@ use stdx::process::streaming_output;
 
 use crate::cargo_communication::cargo_types::event::CargoMessage;
 
+/// Capacity of the channel between the actor and the consumer of Cargo messages. When the
+/// consumer lags (e.g. on a huge dump of diagnostics), the actor blocks on sending instead of
+/// buffering the messages in memory, and Cargo blocks on writing to the full pipe. The bound is
+/// large enough that on the usual output the actor does not wait for the consumer.
+pub(crate) const CARGO_MESSAGES_BOUND: usize = 1024;
+
 pub struct CargoActor {
     sender: Sender<CargoMessage>,
     stdout: ChildStdout,
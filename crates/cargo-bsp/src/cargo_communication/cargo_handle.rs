@@ -15,19 +15,24 @@ pub use cargo_metadata::diagnostic::{
     DiagnosticSpanMacroExpansion,
 };
 use command_group::{CommandGroup, GroupChild};
-use crossbeam_channel::{unbounded, Receiver};
+use crossbeam_channel::{bounded, Receiver};
 use log::info;
 use mockall::automock;
 
-use crate::cargo_communication::cargo_actor::CargoActor;
+use crate::cargo_communication::cargo_actor::{CargoActor, CARGO_MESSAGES_BOUND};
 use crate::cargo_communication::cargo_types::event::CargoMessage;
 
 pub struct CargoHandle {
     /// The handle to the actual cargo process. As we cannot cancel directly from with
     /// a read syscall dropping and therefore terminating the process is our best option.
     child: GroupChild,
-    thread: jod_thread::JoinHandle<io::Result<bool>>,
+    /// Dropped before the thread is joined, so that the actor blocked on the full channel
+    /// finishes (see [`CARGO_MESSAGES_BOUND`]).
     receiver: Receiver<CargoMessage>,
+    thread: jod_thread::JoinHandle<io::Result<bool>>,
+    /// Cargo prints at least one message, unlike the executables built by Cargo
+    /// (see [`CargoHandle::spawn_executable`]), which may print nothing.
+    expects_messages: bool,
 }
 
 impl CargoHandler<CargoMessage> for CargoHandle {
@@ -41,10 +46,12 @@ impl CargoHandler<CargoMessage> for CargoHandle {
     }
 
     fn join(mut self) -> io::Result<ExitStatus> {
+        // Dropped first, so that the actor blocked on the full channel finishes.
+        drop(self.receiver);
         let _ = self.child.kill();
         let exit_status = self.child.wait()?;
         let read_at_least_one_message = self.thread.join()?;
-        if read_at_least_one_message {
+        if read_at_least_one_message || !self.expects_messages {
             Ok(exit_status)
         } else {
             Err(io::Error::new(
@@ -60,28 +67,44 @@ impl CargoHandler<CargoMessage> for CargoHandle {
 
 impl CargoHandle {
     pub fn spawn(command: &mut Command) -> io::Result<CargoHandle> {
+        CargoHandle::spawn_command(command, true)
+    }
+
+    /// Spawns the executable built by Cargo, such as the binary of the run request.
+    pub fn spawn_executable(command: &mut Command) -> io::Result<CargoHandle> {
+        CargoHandle::spawn_command(command, false)
+    }
+
+    fn spawn_command(command: &mut Command, expects_messages: bool) -> io::Result<CargoHandle> {
         info!("Created command: {:?}", command);
         command
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .stdin(Stdio::null());
-        let mut child = command.group_spawn()?;
+        let mut child = command.group_spawn().map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Failed to spawn Cargo command {:?}: {}", command, e),
+            )
+        })?;
 
         let stdout = child.inner().stdout.take().unwrap();
         let stderr = child.inner().stderr.take().unwrap();
 
-        let (sender, receiver) = unbounded();
+        let (sender, receiver) = bounded(CARGO_MESSAGES_BOUND);
         let actor = CargoActor::new(sender, stdout, stderr);
         let thread = jod_thread::Builder::new().spawn(move || actor.run())?;
         Ok(CargoHandle {
             child,
-            thread,
             receiver,
+            thread,
+            expects_messages,
         })
     }
 }
 
-/// The trait was created for easier mocking in tests. It is implemented only by CargoHandle.
+/// The trait was created for easier mocking in tests. It is implemented only by CargoHandle
+/// (and TokioCargoHandle with the `tokio-output-reader` feature).
 #[automock]
 pub trait CargoHandler<T> {
     fn receiver(&self) -> &Receiver<T>;
@@ -90,3 +113,53 @@ pub trait CargoHandler<T> {
 
     fn join(self) -> io::Result<ExitStatus>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_consumer_bounds_buffered_messages() {
+        const LINES_COUNT: usize = 5 * CARGO_MESSAGES_BOUND;
+        let mut command = Command::new("sh");
+        command.args(["-c", &format!("yes '{{}}' | head -n {}", LINES_COUNT)]);
+
+        let cargo_handle = CargoHandle::spawn(&mut command).unwrap();
+        // The consumer lags, the actor fills the channel and blocks.
+        std::thread::sleep(std::time::Duration::from_millis(500));
+
+        assert_eq!(cargo_handle.receiver().len(), CARGO_MESSAGES_BOUND);
+        // No message is lost once the consumer catches up.
+        assert_eq!(cargo_handle.receiver().iter().count(), LINES_COUNT);
+        assert!(cargo_handle.join().unwrap().success());
+    }
+
+    #[test]
+    fn executable_may_print_nothing() {
+        let mut command = Command::new("true");
+        let cargo_handle = CargoHandle::spawn(&mut command).unwrap();
+        assert_eq!(cargo_handle.receiver().iter().count(), 0);
+        assert!(cargo_handle.join().is_err());
+
+        let cargo_handle = CargoHandle::spawn_executable(&mut command).unwrap();
+        assert_eq!(cargo_handle.receiver().iter().count(), 0);
+
+        assert!(cargo_handle.join().unwrap().success());
+    }
+
+    #[test]
+    fn spawn_not_existing_cargo() {
+        let mut command = Command::new("/not_existing/cargo");
+        command.arg("build");
+
+        let err = CargoHandle::spawn(&mut command).err().unwrap();
+
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let message = err.to_string();
+        assert!(
+            message.starts_with(r#"Failed to spawn Cargo command "/not_existing/cargo" "build": "#)
+        );
+        // The OS error is included.
+        assert!(message.contains("os error"));
+    }
+}
@@ -1,15 +1,34 @@
-use crate::cargo_communication::cargo_types::command_utils::CommandType;
+use crate::cargo_communication::cargo_types::command_utils::{
+    filter_client_arguments, CommandType,
+};
 use bsp4rs::bsp::{CompileParams, RunParams, TestParams};
+use bsp4rs::cargo::{CargoTestParams, CargoTestParamsData, DocParams};
 use bsp4rs::rust::RustWorkspaceParams;
 
 pub(crate) trait CommandCreationDetails {
-    fn get_command_arguments(&self) -> Vec<String>;
+    /// Arguments passed by the client in the request params.
+    fn get_client_arguments(&self) -> Vec<String>;
+
+    fn get_command_arguments(&self) -> Vec<String> {
+        filter_client_arguments(&self.get_client_arguments(), &Self::get_command_type()).kept
+    }
+
+    /// Client arguments that are not passed to the command.
+    fn get_dropped_arguments(&self) -> Vec<String> {
+        filter_client_arguments(&self.get_client_arguments(), &Self::get_command_type()).dropped
+    }
+
+    /// Arguments of the test executable launched under the debugger, `None` if the
+    /// request does not debug the tests.
+    fn get_debug_arguments(&self) -> Option<Vec<String>> {
+        None
+    }
 
     fn get_command_type() -> CommandType;
 }
 
 impl CommandCreationDetails for CompileParams {
-    fn get_command_arguments(&self) -> Vec<String> {
+    fn get_client_arguments(&self) -> Vec<String> {
         self.arguments.clone().unwrap_or_default()
     }
 
@@ -19,7 +38,7 @@ impl CommandCreationDetails for CompileParams {
 }
 
 impl CommandCreationDetails for RunParams {
-    fn get_command_arguments(&self) -> Vec<String> {
+    fn get_client_arguments(&self) -> Vec<String> {
         self.arguments.clone().unwrap_or_default()
     }
 
@@ -29,23 +48,95 @@ impl CommandCreationDetails for RunParams {
 }
 
 impl CommandCreationDetails for TestParams {
+    fn get_client_arguments(&self) -> Vec<String> {
+        self.arguments.clone().unwrap_or_default()
+    }
+
+    /// The debugged tests are only compiled, the test harness arguments are passed to
+    /// the test executable by the debugger instead (see [`Self::get_debug_arguments`]).
     fn get_command_arguments(&self) -> Vec<String> {
+        if self.get_debug_arguments().is_some() {
+            return vec![];
+        }
         let mut args = vec![
             "--show-output".into(),
             "-Z".into(),
             "unstable-options".into(),
             "--format=json".into(),
         ];
-        args.extend(self.arguments.clone().unwrap_or_default());
+        args.extend(
+            filter_client_arguments(&self.get_client_arguments(), &Self::get_command_type()).kept,
+        );
+        if let Some(CargoTestParamsData::CargoTest(data)) = cargo_test_data(self) {
+            extend_with_test_filter(&mut args, &data);
+        }
         args
     }
 
+    fn get_debug_arguments(&self) -> Option<Vec<String>> {
+        match cargo_test_data(self) {
+            Some(CargoTestParamsData::CargoTestDebug(data)) => {
+                let mut args = filter_client_arguments(
+                    &self.get_client_arguments(),
+                    &Self::get_command_type(),
+                )
+                .kept;
+                extend_with_test_filter(&mut args, &data);
+                Some(args)
+            }
+            _ => None,
+        }
+    }
+
     fn get_command_type() -> CommandType {
         CommandType::Test
     }
 }
 
+/// The cargo-specific data of the test params, if the client sent it.
+fn cargo_test_data(params: &TestParams) -> Option<CargoTestParamsData> {
+    params
+        .data
+        .as_ref()
+        .and_then(CargoTestParamsData::from_test_params_data)
+}
+
+/// Appends the names of the tests to run, matched exactly, and the number of the test threads.
+fn extend_with_test_filter(args: &mut Vec<String>, data: &CargoTestParams) {
+    args.extend(data.test_names.iter().cloned());
+    if !data.test_names.is_empty() && !args.iter().any(|a| a == "--exact") {
+        args.push("--exact".into());
+    }
+    if let Some(threads) = data.test_threads {
+        args.push(format!("--test-threads={}", threads));
+    }
+}
+
+impl CommandCreationDetails for DocParams {
+    fn get_client_arguments(&self) -> Vec<String> {
+        self.arguments.clone().unwrap_or_default()
+    }
+
+    /// Only the requested targets are documented, not their dependencies.
+    fn get_command_arguments(&self) -> Vec<String> {
+        let mut args =
+            filter_client_arguments(&self.get_client_arguments(), &Self::get_command_type()).kept;
+        if !args.iter().any(|a| a == "--no-deps") {
+            args.push("--no-deps".into());
+        }
+        args
+    }
+
+    fn get_command_type() -> CommandType {
+        CommandType::Doc
+    }
+}
+
 impl CommandCreationDetails for RustWorkspaceParams {
+    fn get_client_arguments(&self) -> Vec<String> {
+        vec![]
+    }
+
     fn get_command_arguments(&self) -> Vec<String> {
         vec![
             "--workspace".into(),
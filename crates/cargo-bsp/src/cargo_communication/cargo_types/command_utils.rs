@@ -1,7 +1,10 @@
 //! Contains necessary additional structs and functions for creating Cargo commands.
 
-use crate::project_model::target_details::TargetDetails;
+use crate::project_model::target_details::{CargoTargetKind, TargetDetails};
+use bsp4rs::cargo::FeatureMode;
+use bsp4rs::rust::Feature;
 use serde_enum_str::{Deserialize_enum_str, Serialize_enum_str};
+use std::collections::BTreeSet;
 use std::ops::Deref;
 
 #[derive(Debug, Deserialize_enum_str, Serialize_enum_str, Clone)]
@@ -11,52 +14,351 @@ pub(crate) enum CommandType {
     Test,
     Run,
     Check,
+    Doc,
+}
+
+impl CommandType {
+    /// The client arguments of the command are passed after the `--` separator,
+    /// to the built binary (or the test harness), instead of Cargo.
+    pub(crate) fn passes_arguments_after_separator(&self) -> bool {
+        match self {
+            CommandType::Build | CommandType::Test | CommandType::Run => true,
+            CommandType::Check | CommandType::Doc => false,
+        }
+    }
 }
 
 const FEATURE_FLAG: &str = "--features";
 
+/// Flags set by the server itself, which cannot be passed by the client.
+const SERVER_CONTROLLED_FLAGS: [&str; 3] = ["--manifest-path", "--message-format", "--target-dir"];
+
+/// Separates the arguments of the Cargo command from the arguments passed to the binary.
+const ARGS_SEPARATOR: &str = "--";
+
+/// Flags of the Cargo commands, which always require a value. The list is kept short,
+/// so that only the clearly invalid arguments are rejected.
+const FLAGS_REQUIRING_VALUE: [&str; 9] = [
+    FEATURE_FLAG,
+    "-F",
+    "--target",
+    "--profile",
+    "--jobs",
+    "-j",
+    "--config",
+    "--color",
+    "-Z",
+];
+
+/// Client arguments split into the ones passed to the command and the dropped ones.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ClientArguments {
+    pub kept: Vec<String>,
+    pub dropped: Vec<String>,
+}
+
+/// Splits the client arguments into the ones parsed by Cargo and the ones after
+/// the `--` separator (added by the server or by the client).
+fn split_cargo_arguments<'a>(
+    args: &'a [String],
+    command_type: &CommandType,
+) -> (&'a [String], &'a [String]) {
+    if command_type.passes_arguments_after_separator() {
+        return (&[], args);
+    }
+    match args.iter().position(|a| a == ARGS_SEPARATOR) {
+        Some(separator_index) => args.split_at(separator_index),
+        None => (args, &[]),
+    }
+}
+
+/// Drops the server controlled flags (together with their values) and the repeated
+/// flags from the client arguments parsed by Cargo. Arguments after the `--` separator
+/// are passed untouched.
+pub(crate) fn filter_client_arguments(
+    args: &[String],
+    command_type: &CommandType,
+) -> ClientArguments {
+    let (cargo_args, binary_args) = split_cargo_arguments(args, command_type);
+
+    let mut result = ClientArguments::default();
+    let mut cargo_args = cargo_args.iter().peekable();
+    while let Some(arg) = cargo_args.next() {
+        let flag_name = arg.split('=').next().unwrap_or_default();
+        if SERVER_CONTROLLED_FLAGS.contains(&flag_name) {
+            result.dropped.push(arg.clone());
+            if !arg.contains('=') {
+                result
+                    .dropped
+                    .extend(cargo_args.next_if(|v| !v.starts_with('-')).cloned());
+            }
+            continue;
+        }
+        let has_value = cargo_args.peek().is_some_and(|v| !v.starts_with('-'));
+        if arg.starts_with('-') && !has_value && result.kept.contains(arg) {
+            result.dropped.push(arg.clone());
+            continue;
+        }
+        result.kept.push(arg.clone());
+    }
+    result.kept.extend(binary_args.iter().cloned());
+    result
+}
+
+/// Checks the client arguments before the command is spawned, so that the client gets
+/// the list of the problems instead of the (often confusing) error of Cargo.
+/// Currently only the flags requiring a value, which are given without it, are reported.
+/// Arguments after the `--` separator are not checked.
+pub(crate) fn validate_client_arguments(
+    args: &[String],
+    command_type: &CommandType,
+) -> Result<(), String> {
+    let (cargo_args, _) = split_cargo_arguments(args, command_type);
+    let problems: Vec<String> = cargo_args
+        .iter()
+        .enumerate()
+        .filter(|(_, arg)| FLAGS_REQUIRING_VALUE.contains(&arg.as_str()))
+        // The negative number is a valid value, e.g. of the `--jobs` flag.
+        .filter(|(i, _)| {
+            cargo_args
+                .get(i + 1)
+                .map_or(true, |value| value.starts_with("--"))
+        })
+        .map(|(_, flag)| format!("`{}` requires a value, but none was given", flag))
+        .collect();
+    match problems.is_empty() {
+        true => Ok(()),
+        false => Err(format!("Invalid arguments: {}", problems.join("; "))),
+    }
+}
+
 impl TargetDetails {
+    /// Enabled features of the package together with the `required-features` of the target,
+    /// so that Cargo does not skip the target. `default` feature is enabled by Cargo itself.
     pub fn get_enabled_features_str(&self) -> Option<String> {
-        let only_default_feature_enabled =
-            self.enabled_features.len() == 1 && !self.default_features_disabled();
-        match self.enabled_features.is_empty() || only_default_feature_enabled {
+        let features: Vec<String> = self
+            .enabled_features
+            .union(&self.required_features)
+            .filter_map(|f| match f.as_str() {
+                "default" => None,
+                _ => Some(f.deref().clone()),
+            })
+            .collect();
+        match features.is_empty() {
             true => None,
-            false => Some(
-                self.enabled_features
-                    .iter()
-                    .filter_map(|f| match f.as_str() {
-                        "default" => None,
-                        _ => Some(f.deref().clone()),
-                    })
-                    .collect::<Vec<String>>()
-                    .join(", "),
-            ),
+            false => Some(features.join(", ")),
         }
     }
 }
 
 /// Creates additional flags for the command to specify the packages, targets and features.
-pub(crate) fn targets_details_to_args(targets_details: &[TargetDetails]) -> Vec<String> {
-    targets_details
+/// Targets of the same package are selected after a single `--package` flag, so that
+/// only the requested targets are built and the features flags are not repeated.
+/// The flags of the [`FeatureMode`] are added once, as Cargo rejects repeated flags.
+pub(crate) fn targets_details_to_args(
+    targets_details: &[TargetDetails],
+    feature_mode: &FeatureMode,
+) -> Vec<String> {
+    let mut args: Vec<String> = group_targets_by_package(targets_details)
+        .into_iter()
+        .flat_map(|package_targets| package_targets_args(&package_targets, feature_mode))
+        .collect();
+    args.extend(feature_mode_args(feature_mode));
+    args
+}
+
+/// Groups the targets in the order of the first occurrence of their packages.
+fn group_targets_by_package(targets_details: &[TargetDetails]) -> Vec<Vec<&TargetDetails>> {
+    let mut packages: Vec<Vec<&TargetDetails>> = Vec::new();
+    for target in targets_details {
+        match packages
+            .iter_mut()
+            .find(|p| p[0].package_name == target.package_name)
+        {
+            Some(package_targets) => package_targets.push(target),
+            None => packages.push(vec![target]),
+        }
+    }
+    packages
+}
+
+/// The targets share the enabled features of the package, the `required-features`
+/// of all the targets are enabled.
+fn package_targets_args(
+    package_targets: &[&TargetDetails],
+    feature_mode: &FeatureMode,
+) -> Vec<String> {
+    let mut args = vec![
+        "--package".to_string(),
+        package_targets[0].package_name.clone(),
+    ];
+    let mut selectors: Vec<Vec<String>> = Vec::new();
+    for target in package_targets {
+        let selector = target_selector_args(target);
+        if !selectors.contains(&selector) {
+            selectors.push(selector);
+        }
+    }
+    args.extend(selectors.into_iter().flatten());
+    let package_target = TargetDetails {
+        required_features: package_targets
+            .iter()
+            .flat_map(|t| t.required_features.iter().cloned())
+            .collect(),
+        ..package_targets[0].clone()
+    };
+    args.extend(target_features_args(&package_target, feature_mode));
+    args
+}
+
+/// In the default mode, the features enabled for the package of the target are passed.
+/// Otherwise only the `required-features` of the target are, if not enabled by the mode.
+fn target_features_args(target: &TargetDetails, feature_mode: &FeatureMode) -> Vec<String> {
+    let mut args = Vec::new();
+    match feature_mode {
+        FeatureMode::Default => {
+            if let Some(features) = target.get_enabled_features_str() {
+                args.push(FEATURE_FLAG.to_string());
+                args.push(features);
+            }
+            if target.default_features_disabled() {
+                args.push("--no-default-features".to_string());
+            }
+        }
+        FeatureMode::AllFeatures => {}
+        FeatureMode::NoDefault | FeatureMode::Custom(_) => {
+            if !target.required_features.is_empty() {
+                args.push(FEATURE_FLAG.to_string());
+                args.push(features_str(&target.required_features));
+            }
+        }
+    }
+    args
+}
+
+fn feature_mode_args(feature_mode: &FeatureMode) -> Vec<String> {
+    match feature_mode {
+        FeatureMode::Default => vec![],
+        FeatureMode::AllFeatures => vec!["--all-features".to_string()],
+        FeatureMode::NoDefault => vec!["--no-default-features".to_string()],
+        FeatureMode::Custom(features) if features.is_empty() => vec![],
+        FeatureMode::Custom(features) => vec![FEATURE_FLAG.to_string(), features_str(features)],
+    }
+}
+
+fn features_str(features: &BTreeSet<Feature>) -> String {
+    features
         .iter()
-        .flat_map(|t| {
-            let mut loc_args = Vec::new();
-            loc_args.push("--package".to_string());
-            loc_args.push(t.package_name.clone());
-            if t.kind.is_lib() {
-                loc_args.push("--lib".to_string());
-            } else {
-                loc_args.push(format!("--{}", t.kind));
-                loc_args.push(t.name.clone());
+        .map(|f| f.deref().clone())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Flags selecting the target of the given kind, e.g. `--example <name>` for examples.
+/// All library kinds are selected with `--lib`, as a package has at most one library.
+fn target_selector_args(target: &TargetDetails) -> Vec<String> {
+    if target.kind.is_lib() {
+        return vec!["--lib".to_string()];
+    }
+    let selector = match target.kind {
+        CargoTargetKind::Test => "--test",
+        CargoTargetKind::Bench => "--bench",
+        CargoTargetKind::Example => "--example",
+        _ => "--bin",
+    };
+    vec![selector.to_string(), target.name.clone()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test_case(&["--manifest-path", "other/Cargo.toml", "--release"], &["--release"],
+    &["--manifest-path", "other/Cargo.toml"] ;
+    "flag_with_separate_value"
+    )]
+    #[test_case(&["--message-format=short", "--target-dir=/tmp", "--release"], &["--release"],
+    &["--message-format=short", "--target-dir=/tmp"] ;
+    "flags_with_joined_values"
+    )]
+    #[test_case(&["--release", "--features", "f1", "--release", "--features", "f2"],
+    &["--release", "--features", "f1", "--features", "f2"], &["--release"] ;
+    "repeated_flag"
+    )]
+    fn test_filter_client_arguments_drops_arguments(
+        args: &[&str],
+        expected_kept: &[&str],
+        expected_dropped: &[&str],
+    ) {
+        assert_eq!(
+            filter_client_arguments(&to_args(args), &CommandType::Doc),
+            ClientArguments {
+                kept: to_args(expected_kept),
+                dropped: to_args(expected_dropped),
             }
-            if let Some(features) = t.get_enabled_features_str() {
-                loc_args.push(FEATURE_FLAG.to_string());
-                loc_args.push(features);
+        );
+    }
+
+    #[test_case(&["--release", "--", "--manifest-path", "--release", "arg"] ; "after_separator")]
+    #[test_case(&["--arg1", "--arg2"] ; "without_separator")]
+    fn test_filter_client_arguments_passes_arguments(args: &[&str]) {
+        assert_eq!(
+            filter_client_arguments(&to_args(args), &CommandType::Doc),
+            ClientArguments {
+                kept: to_args(args),
+                dropped: vec![],
             }
-            if t.default_features_disabled() {
-                loc_args.push("--no-default-features".to_string());
+        );
+    }
+
+    #[test_case(&["--features"], "`--features` requires a value, but none was given" ;
+    "dangling_features"
+    )]
+    #[test_case(&["--features", "--release"], "`--features` requires a value, but none was given" ;
+    "features_followed_by_flag"
+    )]
+    #[test_case(&["--target", "--features", "--", "arg"],
+    "`--target` requires a value, but none was given; `--features` requires a value, but none was given" ;
+    "problems_listed"
+    )]
+    fn test_validate_client_arguments_rejects_arguments(args: &[&str], expected_problems: &str) {
+        assert_eq!(
+            validate_client_arguments(&to_args(args), &CommandType::Doc),
+            Err(format!("Invalid arguments: {}", expected_problems))
+        );
+    }
+
+    #[test_case(&["--features", "f1", "--release"] ; "features_with_value")]
+    #[test_case(&["--features=f1"] ; "features_with_joined_value")]
+    #[test_case(&["--jobs", "-1"] ; "negative_jobs")]
+    #[test_case(&["--release", "--", "--features"] ; "after_separator")]
+    #[test_case(&[] ; "no_arguments")]
+    fn test_validate_client_arguments_accepts_arguments(args: &[&str]) {
+        assert_eq!(
+            validate_client_arguments(&to_args(args), &CommandType::Doc),
+            Ok(())
+        );
+    }
+
+    /// The arguments of the binary can collide with the flags of Cargo.
+    #[test_case(CommandType::Build ; "build")]
+    #[test_case(CommandType::Run ; "run")]
+    #[test_case(CommandType::Test ; "test")]
+    fn test_binary_arguments_passed_untouched(command_type: CommandType) {
+        let args = to_args(&["--manifest-path", "--release", "--release", "--features"]);
+
+        assert_eq!(
+            filter_client_arguments(&args, &command_type),
+            ClientArguments {
+                kept: args.clone(),
+                dropped: vec![],
             }
-            loc_args
-        })
-        .collect()
+        );
+        assert_eq!(validate_client_arguments(&args, &command_type), Ok(()));
+    }
 }
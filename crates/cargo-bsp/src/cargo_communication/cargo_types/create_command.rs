@@ -1,101 +1,319 @@
-//! CreateCommand trait implementation for the Compile/Run/Test/RustWorkspaceParams.
+//! CreateCommand trait implementation for the Compile/Run/Test/Doc/RustWorkspaceParams.
 //! The trait allows creating commands regardless if it is the compile, run, test or rust_workspace request.
 //!
-//! The created commands are: `cargo build`, `cargo run`, `cargo test` and `cargo check` to compile,
-//! run and test the project.
+//! The created commands are: `cargo build`, `cargo test` and `cargo check` to compile,
+//! run and test the project, and `cargo doc` to document it. The binary of the run request
+//! is built by `cargo build` and then started directly, see [`CreateCommand::create_executable_command`].
 //!
 //! The requested commands have additional flags:
 //!
 //! `--message-format=json` for all commands. This flag formats information to JSON and
-//! provides [additional information about build](https://doc.rust-lang.org/cargo/reference/external-tools.html)
+//! provides [additional information about build](https://doc.rust-lang.org/cargo/reference/external-tools.html).
+//! `--message-format=json-diagnostic-rendered-ansi` is used instead, if the ANSI colored
+//! diagnostics are enabled in the [`ServerSettings`].
 //!
 //! `--show-output -Z unstable-options --format=json` for `cargo test`
 //! (only with `+nightly`). These flags format information about the tests to JSON and
 //! allows additional information, such as when each single tests started and finished,
 //! their stdout and stderr
 //!
+//! The names of the tests from the `cargo-test` data of the test params, followed by `--exact`,
+//! and `--test-threads` for `cargo test`, so that the client can run the single tests.
+//!
+//! `--no-run` (without the test harness flags) for `cargo test` with the `cargo-test-debug`
+//! data of the test params, so that the test executable is only compiled to be debugged.
+//!
 //! `--workspace --all-targets -Z unstable-options --keep-going` for `cargo check`.
 //! `--all-targets` is needed to compile:
 //! - build scripts even if a crate doesn't contain library or binary targets,
 //! - dev dependencies during build script evaluation
 //! `--keep-going` is needed to compile as many proc macro artifacts as possible.
+//!
+//! `--keep-going` for `cargo build`, if enabled in the [`ServerSettings`].
+//!
+//! `--no-deps` for `cargo doc`, so that only the requested targets are documented.
+//!
+//! `--workspace --future-incompat-report` for `cargo build` of the future incompatibility
+//! report, see [`create_future_incompat_command`].
+//!
+//! The environment variables and the working directory of the run params for the started binary,
+//! while Cargo runs with the server's environment in the workspace root. The `cargo-run` data
+//! of the run params is used, if these fields are not set.
+//!
+//! `--profile` for `cargo build`, `cargo test` and `cargo doc`, if set in
+//! the [`ServerSettings`].
+//!
+//! `RUSTDOCFLAGS` environment variable for `cargo test`, so that doctests are built with
+//! the `build.rustdocflags` from the Cargo configuration and the flags from the [`ServerSettings`].
+//!
+//! Flags controlled by the server (such as `--manifest-path`) and repeated flags
+//! are dropped from the client arguments parsed by Cargo, see [`filter_client_arguments`].
+//! The client arguments passed to the binary (after the `--` separator) are untouched.
+//! The clearly invalid client arguments (such as `--features` without a value) fail
+//! the request before the command is created, see [`validate_client_arguments`].
+//!
+//! [`filter_client_arguments`]: crate::cargo_communication::cargo_types::command_utils::filter_client_arguments
+//! [`validate_client_arguments`]: crate::cargo_communication::cargo_types::command_utils::validate_client_arguments
 
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use cargo_metadata::camino::Utf8Path;
 
 use crate::cargo_communication::cargo_types::command_creation_details::CommandCreationDetails;
 use crate::cargo_communication::cargo_types::command_utils::{
-    targets_details_to_args, CommandType,
+    targets_details_to_args, validate_client_arguments, CommandType,
 };
+use crate::project_model::cargo_config::rustdoc_flags;
 use crate::project_model::target_details::TargetDetails;
-use bsp4rs::bsp::{CompileParams, RunParams, TestParams};
+use crate::server::config::ServerSettings;
+use crate::server::ServerError;
+use bsp4rs::bsp::{CompileParams, NamedRunParamsData, RunParams, RunParamsData, TestParams};
+use bsp4rs::cargo::DocParams;
 use bsp4rs::rust::RustWorkspaceParams;
 use std::process::Command;
 
 pub(crate) trait CreateCommand: CommandCreationDetails {
-    fn create_requested_command(&self, root: &Path, targets_details: &[TargetDetails]) -> Command {
-        let targets_args = targets_details_to_args(targets_details);
+    /// Fails with the problems of the client arguments, before any command is spawned.
+    fn validate_arguments(&self) -> Result<(), ServerError> {
+        validate_client_arguments(&self.get_command_arguments(), &Self::get_command_type())
+            .map_err(ServerError::InvalidParams)
+    }
+
+    fn create_requested_command(
+        &self,
+        root: &Path,
+        targets_details: &[TargetDetails],
+        settings: &ServerSettings,
+    ) -> Command {
+        let targets_args = targets_details_to_args(targets_details, &settings.feature_mode);
         create_requested_command(
             Self::get_command_type(),
             root,
             targets_args,
             self.get_command_arguments(),
+            settings,
         )
     }
+
+    /// Command starting the executable built by the requested command, once the build
+    /// finishes. Only the run request starts the built executable itself.
+    fn create_executable_command(&self, _root: &Path, _executable: &Utf8Path) -> Option<Command> {
+        None
+    }
 }
 
 impl CreateCommand for CompileParams {}
 
-impl CreateCommand for RunParams {}
+impl CreateCommand for RunParams {
+    /// The arguments are passed to the binary, which may accept any of them,
+    /// only the working directory is checked.
+    fn validate_arguments(&self) -> Result<(), ServerError> {
+        run_environment(self)
+            .map(|_| ())
+            .map_err(ServerError::InvalidParams)
+    }
+
+    /// The binary is only built, it is started once the build finishes
+    /// (see [`Self::create_executable_command`]).
+    fn create_requested_command(
+        &self,
+        root: &Path,
+        targets_details: &[TargetDetails],
+        settings: &ServerSettings,
+    ) -> Command {
+        let targets_args = targets_details_to_args(targets_details, &settings.feature_mode);
+        create_requested_command(CommandType::Build, root, targets_args, vec![], settings)
+    }
+
+    /// Cargo runs in the workspace root with the server's environment, so that the environment
+    /// of the binary (e.g. `RUSTFLAGS`) doesn't affect the build. The binary is started with
+    /// the environment variables and the working directory of the run params instead
+    /// (the workspace root, if not set).
+    fn create_executable_command(&self, root: &Path, executable: &Utf8Path) -> Option<Command> {
+        let run_environment = run_environment(self).unwrap_or_default();
+        let mut cmd = Command::new(executable);
+        cmd.current_dir(run_environment.cwd.as_deref().unwrap_or(root))
+            .envs(run_environment.env)
+            .args(self.get_command_arguments());
+        Some(cmd)
+    }
+}
 
-impl CreateCommand for TestParams {}
+/// Environment variables and the working directory of the run binary.
+#[derive(Debug, Default, PartialEq)]
+struct RunEnvironment {
+    env: BTreeMap<String, String>,
+    cwd: Option<PathBuf>,
+}
+
+/// The standard fields of the run params take precedence over the `cargo-run` data:
+/// their environment variables override the ones of the data with the same name,
+/// and their working directory is used instead of the one of the data.
+fn run_environment(params: &RunParams) -> Result<RunEnvironment, String> {
+    let data = match &params.data {
+        Some(RunParamsData::Named(NamedRunParamsData::CargoRun(data))) => Some(data),
+        _ => None,
+    };
+    let mut env: BTreeMap<String, String> = data.map(|d| d.env.0.clone()).unwrap_or_default();
+    if let Some(environment_variables) = &params.environment_variables {
+        env.extend(environment_variables.0.clone());
+    }
+    let cwd = match (&params.working_directory, data.and_then(|d| d.cwd.as_ref())) {
+        (Some(uri), _) => Some(
+            uri.to_file_path()
+                .ok_or_else(|| format!("The working directory {} is not a file URI", uri.0))?,
+        ),
+        (None, Some(cwd)) => Some(PathBuf::from(cwd)),
+        (None, None) => None,
+    };
+    if let Some(cwd) = &cwd {
+        validate_working_directory(cwd)?;
+    }
+    Ok(RunEnvironment { env, cwd })
+}
+
+fn validate_working_directory(cwd: &Path) -> Result<(), String> {
+    if !cwd.is_absolute() {
+        return Err(format!(
+            "The working directory {} is not an absolute path",
+            cwd.display()
+        ));
+    }
+    if !cwd.is_dir() {
+        return Err(format!(
+            "The working directory {} does not exist",
+            cwd.display()
+        ));
+    }
+    Ok(())
+}
+
+impl CreateCommand for TestParams {
+    /// The debugged tests are compiled with `--no-run`, so that the test executable
+    /// can be launched by the debugger.
+    fn create_requested_command(
+        &self,
+        root: &Path,
+        targets_details: &[TargetDetails],
+        settings: &ServerSettings,
+    ) -> Command {
+        let mut targets_args = targets_details_to_args(targets_details, &settings.feature_mode);
+        if self.get_debug_arguments().is_some() {
+            targets_args.push("--no-run".into());
+        }
+        create_requested_command(
+            Self::get_command_type(),
+            root,
+            targets_args,
+            self.get_command_arguments(),
+            settings,
+        )
+    }
+}
+
+impl CreateCommand for DocParams {}
 
 impl CreateCommand for RustWorkspaceParams {
-    fn create_requested_command(&self, root: &Path, _: &[TargetDetails]) -> Command {
+    fn create_requested_command(
+        &self,
+        root: &Path,
+        _: &[TargetDetails],
+        settings: &ServerSettings,
+    ) -> Command {
         let mut cmd = create_requested_command(
             Self::get_command_type(),
             root,
             vec![],
             self.get_command_arguments(),
+            settings,
         );
         cmd.env("RUSTC_BOOTSTRAP", "1");
         cmd
     }
 }
 
+/// The whole workspace is built with the same flags from the [`ServerSettings`] as for
+/// the compile request, so that the report is of the build the client would run.
+pub(crate) fn create_future_incompat_command(root: &Path, settings: &ServerSettings) -> Command {
+    let mut targets_args = vec![
+        "--workspace".to_string(),
+        "--future-incompat-report".to_string(),
+    ];
+    targets_args.extend(targets_details_to_args(&[], &settings.feature_mode));
+    create_requested_command(CommandType::Build, root, targets_args, vec![], settings)
+}
+
+fn message_format_arg(settings: &ServerSettings) -> &'static str {
+    match settings.ansi_diagnostics {
+        true => "--message-format=json-diagnostic-rendered-ansi",
+        false => "--message-format=json",
+    }
+}
+
 fn create_requested_command(
     command_type: CommandType,
     root: &Path,
     targets_args: Vec<String>,
     command_args: Vec<String>,
+    settings: &ServerSettings,
 ) -> Command {
     let mut cmd = Command::new(toolchain::cargo());
     cmd.current_dir(root);
     if let CommandType::Test = command_type {
         cmd.arg("+nightly");
+        if let Some(flags) = doctest_rustdoc_flags(root, settings) {
+            cmd.env("RUSTDOCFLAGS", flags);
+        }
     }
     cmd.arg(command_type.to_string());
     cmd.args(targets_args);
-    cmd.arg("--message-format=json");
-    match command_type {
-        CommandType::Build | CommandType::Test | CommandType::Run => {
-            cmd.arg("--");
-        }
-        CommandType::Check => {}
+    if let (CommandType::Build, true) = (&command_type, settings.keep_going) {
+        cmd.arg("--keep-going");
+    }
+    if let (CommandType::Build | CommandType::Test | CommandType::Doc, Some(profile)) =
+        (&command_type, &settings.profile)
+    {
+        cmd.args(["--profile", profile]);
+    }
+    cmd.args(settings.lock_flags());
+    cmd.arg(message_format_arg(settings));
+    if command_type.passes_arguments_after_separator() {
+        cmd.arg("--");
     }
     cmd.args(command_args);
     cmd
 }
 
+/// Setting `RUSTDOCFLAGS` makes Cargo ignore `build.rustdocflags`, therefore the flags from
+/// the configuration are included as well. Flags already set in the server's environment
+/// take precedence (as they would in Cargo) and are inherited by the command.
+fn doctest_rustdoc_flags(root: &Path, settings: &ServerSettings) -> Option<String> {
+    if env::var_os("CARGO_ENCODED_RUSTDOCFLAGS").is_some() || env::var_os("RUSTDOCFLAGS").is_some()
+    {
+        return None;
+    }
+    let mut flags = rustdoc_flags(root);
+    flags.extend(settings.rustdoc_flags.iter().cloned());
+    (!flags.is_empty()).then(|| flags.join(" "))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::project_model::target_details::CargoTargetKind::{Bin, Lib};
+    use crate::cargo_communication::execution::execution_types::create_unit_graph_command::CreateUnitGraphCommand;
+    use crate::project_model::target_details::CargoTargetKind;
+    use crate::project_model::target_details::CargoTargetKind::{Bench, Bin, Example, Lib};
     use crate::project_model::DefaultFeature;
-    use bsp4rs::bsp::{CompileParams, RunParams, TestParams};
+    use bsp4rs::bsp::{CompileParams, EnvironmentVariables, RunParams, TestParams, URI};
+    use bsp4rs::cargo::{CargoRunParams, CargoTestParams, CargoTestParamsData, FeatureMode};
     use bsp4rs::rust::{Feature, RustWorkspaceParams};
     use insta::assert_debug_snapshot;
-    use std::collections::BTreeSet;
+    use std::collections::{BTreeMap, BTreeSet};
     use std::ffi::OsStr;
+    use tempfile::tempdir;
+    use test_case::test_case;
 
     const TEST_ARGS: [&str; 2] = ["--arg1", "--arg2"];
     const TEST_BIN_NAME: &str = "test_bin1";
@@ -111,6 +329,7 @@ mod tests {
                 package_abs_path: Default::default(),
                 package_name: TEST_PACKAGE_NAMES[0].to_string(),
                 enabled_features: BTreeSet::from([Feature::default_feature_name()]),
+                required_features: BTreeSet::new(),
             },
             TargetDetails {
                 name: TEST_LIB_NAME.to_string(),
@@ -119,6 +338,7 @@ mod tests {
                 package_name: TEST_PACKAGE_NAMES[1].to_string(),
                 // No `default` feature, means that default features are disabled
                 enabled_features: BTreeSet::from([Feature("test_feature1".to_string())]),
+                required_features: BTreeSet::new(),
             },
         ]
     }
@@ -133,8 +353,11 @@ mod tests {
     #[test]
     fn test_compile_params_create_command() {
         let compile_params = test_compile_params();
-        let cmd = compile_params
-            .create_requested_command(Path::new(TEST_ROOT), &default_target_details());
+        let cmd = compile_params.create_requested_command(
+            Path::new(TEST_ROOT),
+            &default_target_details(),
+            &ServerSettings::default(),
+        );
         let args: Vec<&OsStr> = cmd.get_args().collect();
         let cwd = cmd.get_current_dir().unwrap();
 
@@ -160,6 +383,380 @@ mod tests {
         assert_eq!(cwd, Path::new(TEST_ROOT));
     }
 
+    #[test]
+    fn test_doc_params_create_command_with_filtered_arguments() {
+        let doc_params = DocParams {
+            arguments: Some(
+                [
+                    "--manifest-path",
+                    "other/Cargo.toml",
+                    "--release",
+                    "--release",
+                ]
+                .map(String::from)
+                .to_vec(),
+            ),
+            ..Default::default()
+        };
+        let cmd = doc_params.create_requested_command(
+            Path::new(TEST_ROOT),
+            &[],
+            &ServerSettings::default(),
+        );
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+
+        assert_debug_snapshot!(args, @r#"
+        [
+            "doc",
+            "--message-format=json",
+            "--release",
+            "--no-deps",
+        ]
+        "#);
+        assert_debug_snapshot!(doc_params.get_dropped_arguments(), @r#"
+        [
+            "--manifest-path",
+            "other/Cargo.toml",
+            "--release",
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_compile_params_create_command_with_binary_arguments_untouched() {
+        let compile_params = CompileParams {
+            arguments: Some(
+                ["--manifest-path", "input.toml", "--release", "--release"]
+                    .map(String::from)
+                    .to_vec(),
+            ),
+            ..Default::default()
+        };
+        let cmd = compile_params.create_requested_command(
+            Path::new(TEST_ROOT),
+            &[],
+            &ServerSettings::default(),
+        );
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+
+        assert_debug_snapshot!(args, @r#"
+        [
+            "build",
+            "--message-format=json",
+            "--",
+            "--manifest-path",
+            "input.toml",
+            "--release",
+            "--release",
+        ]
+        "#);
+        assert!(compile_params.get_dropped_arguments().is_empty());
+    }
+
+    #[test]
+    fn test_doc_params_with_dangling_features_rejected() {
+        let doc_params = DocParams {
+            arguments: Some(["--release", "--features"].map(String::from).to_vec()),
+            ..Default::default()
+        };
+
+        let err = doc_params.validate_arguments().unwrap_err();
+
+        assert!(matches!(err, ServerError::InvalidParams(_)));
+        assert_eq!(
+            err.to_string(),
+            "Invalid arguments: `--features` requires a value, but none was given"
+        );
+        assert!(DocParams::default().validate_arguments().is_ok());
+    }
+
+    #[test]
+    fn test_run_params_arguments_not_validated() {
+        let run_params = RunParams {
+            arguments: Some(vec!["--features".to_string()]),
+            ..RunParams::default()
+        };
+
+        assert!(run_params.validate_arguments().is_ok());
+    }
+
+    #[test]
+    fn test_compile_params_create_command_with_keep_going() {
+        let compile_params = test_compile_params();
+        let settings = ServerSettings {
+            keep_going: true,
+            ..ServerSettings::default()
+        };
+        let cmd = compile_params.create_requested_command(
+            Path::new(TEST_ROOT),
+            &default_target_details()[0..1],
+            &settings,
+        );
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+
+        assert_debug_snapshot!(args, @r#"
+        [
+            "build",
+            "--package",
+            "test_package1",
+            "--bin",
+            "test_bin1",
+            "--keep-going",
+            "--message-format=json",
+            "--",
+            "--arg1",
+            "--arg2",
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_compile_params_create_command_with_profile() {
+        let settings = ServerSettings {
+            profile: Some("myprofile".to_string()),
+            ..ServerSettings::default()
+        };
+        let cmd =
+            CompileParams::default().create_requested_command(Path::new(TEST_ROOT), &[], &settings);
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+
+        assert_debug_snapshot!(args, @r#"
+        [
+            "build",
+            "--profile",
+            "myprofile",
+            "--message-format=json",
+            "--",
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_future_incompat_command_with_settings() {
+        let settings = ServerSettings {
+            keep_going: true,
+            profile: Some("myprofile".to_string()),
+            locked: true,
+            feature_mode: FeatureMode::AllFeatures,
+            ..ServerSettings::default()
+        };
+        let cmd = create_future_incompat_command(Path::new(TEST_ROOT), &settings);
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+
+        assert_debug_snapshot!(args, @r#"
+        [
+            "build",
+            "--workspace",
+            "--future-incompat-report",
+            "--all-features",
+            "--keep-going",
+            "--profile",
+            "myprofile",
+            "--locked",
+            "--message-format=json",
+            "--",
+        ]
+        "#);
+        assert_eq!(cmd.get_current_dir().unwrap(), Path::new(TEST_ROOT));
+    }
+
+    #[test_case(FeatureMode::Default, &["--features", "test_feature1", "--no-default-features"] ;
+    "default"
+    )]
+    #[test_case(FeatureMode::AllFeatures, &["--all-features"] ; "all_features")]
+    #[test_case(FeatureMode::NoDefault, &["--no-default-features"] ; "no_default")]
+    #[test_case(FeatureMode::Custom(BTreeSet::from([Feature::from("f1"), Feature::from("f2")])),
+    &["--features", "f1, f2"] ;
+    "custom"
+    )]
+    fn test_compile_params_create_command_with_feature_mode(
+        feature_mode: FeatureMode,
+        expected_features_args: &[&str],
+    ) {
+        let settings = ServerSettings {
+            feature_mode,
+            ..ServerSettings::default()
+        };
+        let cmd = CompileParams::default().create_requested_command(
+            Path::new(TEST_ROOT),
+            &default_target_details()[1..2],
+            &settings,
+        );
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+
+        let expected_args: Vec<&OsStr> = ["build", "--package", TEST_PACKAGE_NAMES[1], "--lib"]
+            .iter()
+            .chain(expected_features_args)
+            .chain(&["--message-format=json", "--"])
+            .copied()
+            .map(OsStr::new)
+            .collect();
+        assert_eq!(args, expected_args);
+    }
+
+    #[test]
+    fn test_feature_mode_keeps_required_features() {
+        let target_details = TargetDetails {
+            required_features: BTreeSet::from([Feature::from("gated")]),
+            ..default_target_details()[0].clone()
+        };
+        let settings = ServerSettings {
+            feature_mode: FeatureMode::NoDefault,
+            ..ServerSettings::default()
+        };
+        let cmd = CompileParams::default().create_requested_command(
+            Path::new(TEST_ROOT),
+            &[target_details],
+            &settings,
+        );
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+
+        assert_eq!(
+            args[4..8],
+            [
+                "--features",
+                "gated",
+                "--no-default-features",
+                "--message-format=json"
+            ]
+            .map(OsStr::new)
+        );
+    }
+
+    #[test]
+    fn test_compile_params_create_command_with_ansi_diagnostics() {
+        let settings = ServerSettings {
+            ansi_diagnostics: true,
+            ..ServerSettings::default()
+        };
+        let cmd =
+            CompileParams::default().create_requested_command(Path::new(TEST_ROOT), &[], &settings);
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+
+        assert_eq!(
+            args,
+            [
+                "build",
+                "--message-format=json-diagnostic-rendered-ansi",
+                "--"
+            ]
+            .map(OsStr::new)
+        );
+    }
+
+    #[test_case(ServerSettings { locked: true, ..ServerSettings::default() }, "--locked" ; "locked")]
+    #[test_case(ServerSettings { offline: true, ..ServerSettings::default() }, "--offline" ; "offline")]
+    #[test_case(ServerSettings { frozen: true, ..ServerSettings::default() }, "--frozen" ; "frozen")]
+    fn test_create_command_with_lock_flag(settings: ServerSettings, flag: &str) {
+        let root = Path::new(TEST_ROOT);
+        let commands = [
+            CompileParams::default().create_requested_command(root, &[], &settings),
+            CompileParams::default().create_unit_graph_command(root, &[], &settings),
+            TestParams::default().create_requested_command(root, &[], &settings),
+            RustWorkspaceParams::default().create_requested_command(root, &[], &settings),
+        ];
+
+        for cmd in commands {
+            let args: Vec<&OsStr> = cmd.get_args().collect();
+            let flag_position = args
+                .iter()
+                .position(|arg| *arg == flag)
+                .unwrap_or_else(|| panic!("{flag} missing in {args:?}"));
+            // The flag is passed to Cargo, not to the compiled program.
+            let separator_position = args.iter().position(|arg| *arg == "--");
+            assert!(flag_position < separator_position.unwrap_or(args.len()));
+        }
+    }
+
+    #[test]
+    fn test_create_command_without_lock_flags() {
+        let cmd = CompileParams::default().create_requested_command(
+            Path::new(TEST_ROOT),
+            &[],
+            &ServerSettings::default(),
+        );
+
+        assert!(!cmd
+            .get_args()
+            .any(|arg| ["--locked", "--offline", "--frozen"]
+                .map(OsStr::new)
+                .contains(&arg)));
+    }
+
+    fn test_package_targets() -> [TargetDetails; 3] {
+        let target_details = |name: &str, kind| TargetDetails {
+            name: name.to_string(),
+            kind,
+            package_name: TEST_PACKAGE_NAMES[0].to_string(),
+            enabled_features: BTreeSet::from([Feature::from("test_feature1")]),
+            ..TargetDetails::default()
+        };
+        [
+            target_details(TEST_LIB_NAME, Lib),
+            target_details(TEST_BIN_NAME, Bin),
+            TargetDetails {
+                required_features: BTreeSet::from([Feature::from("gated")]),
+                ..target_details("test_example", Example)
+            },
+        ]
+    }
+
+    fn compile_command_args(targets_details: &[TargetDetails]) -> Vec<String> {
+        let cmd = CompileParams::default().create_requested_command(
+            Path::new(TEST_ROOT),
+            targets_details,
+            &ServerSettings::default(),
+        );
+        cmd.get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_compile_params_create_command_selects_lib_only() {
+        let args = compile_command_args(&test_package_targets()[0..1]);
+
+        assert_eq!(
+            args[1..4],
+            ["--package", TEST_PACKAGE_NAMES[0], "--lib"].map(String::from)
+        );
+        assert!(!args.iter().any(|a| a == "--bin" || a == "--example"));
+    }
+
+    #[test]
+    fn test_compile_params_create_command_selects_single_bin() {
+        let args = compile_command_args(&test_package_targets()[1..2]);
+
+        assert_eq!(
+            args[1..5],
+            ["--package", TEST_PACKAGE_NAMES[0], "--bin", TEST_BIN_NAME].map(String::from)
+        );
+        assert!(!args.iter().any(|a| a == "--lib" || a == "--example"));
+    }
+
+    #[test]
+    fn test_compile_params_create_command_combines_package_targets() {
+        let args = compile_command_args(&test_package_targets());
+
+        assert_debug_snapshot!(args, @r#"
+        [
+            "build",
+            "--package",
+            "test_package1",
+            "--lib",
+            "--bin",
+            "test_bin1",
+            "--example",
+            "test_example",
+            "--features",
+            "gated, test_feature1",
+            "--no-default-features",
+            "--message-format=json",
+            "--",
+        ]
+        "#);
+    }
+
     fn test_run_params() -> RunParams {
         RunParams {
             arguments: Some(vec![TEST_ARGS[0].to_string(), TEST_ARGS[1].to_string()]),
@@ -171,26 +768,186 @@ mod tests {
     fn test_run_params_create_command() {
         let run_params = test_run_params();
         let target_details = default_target_details();
-        let cmd = run_params.create_requested_command(Path::new(TEST_ROOT), &target_details[0..1]);
+        let cmd = run_params.create_requested_command(
+            Path::new(TEST_ROOT),
+            &target_details[0..1],
+            &ServerSettings::default(),
+        );
         let args: Vec<&OsStr> = cmd.get_args().collect();
         let cwd = cmd.get_current_dir().unwrap();
 
         assert_debug_snapshot!(args, @r#"
         [
-            "run",
+            "build",
             "--package",
             "test_package1",
             "--bin",
             "test_bin1",
             "--message-format=json",
             "--",
-            "--arg1",
-            "--arg2",
         ]
         "#);
         assert_eq!(cwd, Path::new(TEST_ROOT));
     }
 
+    #[test]
+    fn test_run_params_create_command_enables_required_features() {
+        let target_details = TargetDetails {
+            required_features: BTreeSet::from([Feature::from("gated")]),
+            ..default_target_details()[0].clone()
+        };
+        let cmd = test_run_params().create_requested_command(
+            Path::new(TEST_ROOT),
+            &[target_details],
+            &ServerSettings::default(),
+        );
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+
+        assert_debug_snapshot!(args, @r#"
+        [
+            "build",
+            "--package",
+            "test_package1",
+            "--bin",
+            "test_bin1",
+            "--features",
+            "gated",
+            "--message-format=json",
+            "--",
+        ]
+        "#);
+    }
+
+    fn test_cargo_run_data(cwd: &Path) -> CargoRunParams {
+        CargoRunParams {
+            env: EnvironmentVariables::new(BTreeMap::from([
+                ("TEST_ENV".to_string(), "test_value".to_string()),
+                ("TEST_DATA_ENV".to_string(), "test_data_value".to_string()),
+            ])),
+            cwd: Some(cwd.to_str().unwrap().to_string()),
+        }
+    }
+
+    #[test]
+    fn test_run_params_create_executable_command() {
+        let cmd = test_run_params()
+            .create_executable_command(
+                Path::new(TEST_ROOT),
+                Utf8Path::new("/test_root/target/debug/test_bin1"),
+            )
+            .unwrap();
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+
+        assert_eq!(cmd.get_program(), "/test_root/target/debug/test_bin1");
+        assert_eq!(args, TEST_ARGS);
+        assert_eq!(cmd.get_current_dir().unwrap(), Path::new(TEST_ROOT));
+        assert_eq!(cmd.get_envs().count(), 0);
+    }
+
+    #[test]
+    fn test_run_params_create_executable_command_with_cargo_run_data() {
+        let cwd = tempdir().unwrap();
+        let run_params = RunParams {
+            data: Some(RunParamsData::cargo_run(test_cargo_run_data(cwd.path()))),
+            ..test_run_params()
+        };
+
+        let build_cmd = run_params.create_requested_command(
+            Path::new(TEST_ROOT),
+            &default_target_details()[0..1],
+            &ServerSettings::default(),
+        );
+        let cmd = run_params
+            .create_executable_command(
+                Path::new(TEST_ROOT),
+                Utf8Path::new("/test_root/target/debug/test_bin1"),
+            )
+            .unwrap();
+        let envs: Vec<(&OsStr, Option<&OsStr>)> = cmd.get_envs().collect();
+
+        // Cargo builds in the workspace root with the server's environment,
+        // only the binary is started with the run environment.
+        assert_eq!(build_cmd.get_current_dir().unwrap(), Path::new(TEST_ROOT));
+        assert_eq!(build_cmd.get_envs().count(), 0);
+        assert_eq!(cmd.get_current_dir().unwrap(), cwd.path());
+        assert_eq!(
+            envs,
+            [
+                (
+                    OsStr::new("TEST_DATA_ENV"),
+                    Some(OsStr::new("test_data_value"))
+                ),
+                (OsStr::new("TEST_ENV"), Some(OsStr::new("test_value"))),
+            ]
+        );
+        assert!(run_params.validate_arguments().is_ok());
+    }
+
+    #[test]
+    fn test_compile_params_create_no_executable_command() {
+        assert!(test_compile_params()
+            .create_executable_command(Path::new(TEST_ROOT), Utf8Path::new("/test_bin"))
+            .is_none());
+    }
+
+    #[test]
+    fn test_run_params_fields_take_precedence_over_cargo_run_data() {
+        let data_cwd = tempdir().unwrap();
+        let cwd = tempdir().unwrap();
+        let run_params = RunParams {
+            environment_variables: Some(EnvironmentVariables::new(BTreeMap::from([(
+                "TEST_ENV".to_string(),
+                "test_params_value".to_string(),
+            )]))),
+            working_directory: Some(URI::from_file_path(cwd.path())),
+            data: Some(RunParamsData::cargo_run(test_cargo_run_data(
+                data_cwd.path(),
+            ))),
+            ..test_run_params()
+        };
+
+        let run_environment = run_environment(&run_params).unwrap();
+
+        assert_eq!(
+            run_environment,
+            RunEnvironment {
+                env: BTreeMap::from([
+                    ("TEST_DATA_ENV".to_string(), "test_data_value".to_string()),
+                    ("TEST_ENV".to_string(), "test_params_value".to_string()),
+                ]),
+                cwd: Some(cwd.path().to_path_buf()),
+            }
+        );
+    }
+
+    #[test_case(Some("/test_missing_cwd"), None,
+        "The working directory /test_missing_cwd does not exist" ; "missing cwd of the data")]
+    #[test_case(Some("relative/cwd"), None,
+        "The working directory relative/cwd is not an absolute path" ; "relative cwd of the data")]
+    #[test_case(None, Some("https://test_cwd"),
+        "The working directory https://test_cwd is not a file URI" ; "not a file working directory")]
+    #[test_case(None, Some("file:///test_missing_cwd"),
+        "The working directory /test_missing_cwd does not exist" ; "missing working directory")]
+    fn test_run_params_with_invalid_cwd_rejected(
+        data_cwd: Option<&str>,
+        working_directory: Option<&str>,
+        expected_error: &str,
+    ) {
+        let run_params = RunParams {
+            working_directory: working_directory.map(URI::from),
+            data: Some(RunParamsData::cargo_run(CargoRunParams {
+                cwd: data_cwd.map(String::from),
+                ..CargoRunParams::default()
+            })),
+            ..test_run_params()
+        };
+
+        let err = run_params.validate_arguments().unwrap_err();
+
+        assert!(matches!(err, ServerError::InvalidParams(_)));
+        assert_eq!(err.to_string(), expected_error);
+    }
+
     fn test_test_params() -> TestParams {
         TestParams {
             arguments: Some(vec![TEST_ARGS[0].to_string(), TEST_ARGS[1].to_string()]),
@@ -201,8 +958,11 @@ mod tests {
     #[test]
     fn test_test_params_create_command() {
         let test_params = test_test_params();
-        let cmd =
-            test_params.create_requested_command(Path::new(TEST_ROOT), &default_target_details());
+        let cmd = test_params.create_requested_command(
+            Path::new(TEST_ROOT),
+            &default_target_details(),
+            &ServerSettings::default(),
+        );
         let args: Vec<&OsStr> = cmd.get_args().collect();
         let cwd = cmd.get_current_dir().unwrap();
 
@@ -233,11 +993,178 @@ mod tests {
         assert_eq!(cwd, Path::new(TEST_ROOT));
     }
 
+    #[test]
+    fn test_test_params_create_command_with_named_test() {
+        let test_params = TestParams {
+            data: Some(
+                CargoTestParamsData::CargoTest(CargoTestParams {
+                    test_names: vec!["tests::single_test".to_string()],
+                    test_threads: Some(1),
+                })
+                .into(),
+            ),
+            ..TestParams::default()
+        };
+        let cmd = test_params.create_requested_command(
+            Path::new(TEST_ROOT),
+            &[],
+            &ServerSettings::default(),
+        );
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+
+        assert_debug_snapshot!(args, @r#"
+        [
+            "+nightly",
+            "test",
+            "--message-format=json",
+            "--",
+            "--show-output",
+            "-Z",
+            "unstable-options",
+            "--format=json",
+            "tests::single_test",
+            "--exact",
+            "--test-threads=1",
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_test_params_create_command_for_debugging() {
+        let test_params = TestParams {
+            data: Some(
+                CargoTestParamsData::CargoTestDebug(CargoTestParams {
+                    test_names: vec!["tests::single_test".to_string()],
+                    test_threads: None,
+                })
+                .into(),
+            ),
+            ..test_test_params()
+        };
+        let cmd = test_params.create_requested_command(
+            Path::new(TEST_ROOT),
+            &default_target_details(),
+            &ServerSettings::default(),
+        );
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+
+        assert_debug_snapshot!(args, @r#"
+        [
+            "+nightly",
+            "test",
+            "--package",
+            "test_package1",
+            "--bin",
+            "test_bin1",
+            "--package",
+            "test_package2",
+            "--lib",
+            "--features",
+            "test_feature1",
+            "--no-default-features",
+            "--no-run",
+            "--message-format=json",
+            "--",
+        ]
+        "#);
+        assert_eq!(
+            test_params.get_debug_arguments().unwrap(),
+            vec!["--arg1", "--arg2", "tests::single_test", "--exact"]
+        );
+    }
+
+    #[test_case(Example, "--example" ; "example")]
+    #[test_case(Bench, "--bench" ; "bench")]
+    fn test_test_params_create_command_target_selector(kind: CargoTargetKind, selector: &str) {
+        let target_details = TargetDetails {
+            name: "test_target".to_string(),
+            kind,
+            package_name: TEST_PACKAGE_NAMES[0].to_string(),
+            enabled_features: BTreeSet::from([Feature::default_feature_name()]),
+            ..TargetDetails::default()
+        };
+        let cmd = TestParams::default().create_requested_command(
+            Path::new(TEST_ROOT),
+            &[target_details],
+            &ServerSettings::default(),
+        );
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+
+        assert_eq!(
+            args[2..6],
+            ["--package", TEST_PACKAGE_NAMES[0], selector, "test_target"].map(OsStr::new)
+        );
+    }
+
+    #[test]
+    fn test_test_params_create_command_with_rustdoc_flags() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join(".cargo")).unwrap();
+        std::fs::write(
+            root.path().join(".cargo").join("config.toml"),
+            "[build]\nrustdocflags = [\"--cfg\", \"doctest_cfg\"]\n",
+        )
+        .unwrap();
+        let settings = ServerSettings {
+            rustdoc_flags: vec!["-Dwarnings".to_string()],
+            ..ServerSettings::default()
+        };
+        let cmd = test_test_params().create_requested_command(root.path(), &[], &settings);
+        let envs: Vec<(&OsStr, Option<&OsStr>)> = cmd.get_envs().collect();
+
+        assert_debug_snapshot!(envs, @r#"
+        [
+            (
+                "RUSTDOCFLAGS",
+                Some(
+                    "--cfg doctest_cfg -Dwarnings",
+                ),
+            ),
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_doc_params_create_command() {
+        let doc_params = DocParams {
+            arguments: Some(vec![TEST_ARGS[0].to_string(), "--no-deps".to_string()]),
+            ..DocParams::default()
+        };
+        let cmd = doc_params.create_requested_command(
+            Path::new(TEST_ROOT),
+            &default_target_details(),
+            &ServerSettings::default(),
+        );
+        let args: Vec<&OsStr> = cmd.get_args().collect();
+
+        assert_debug_snapshot!(args, @r#"
+        [
+            "doc",
+            "--package",
+            "test_package1",
+            "--bin",
+            "test_bin1",
+            "--package",
+            "test_package2",
+            "--lib",
+            "--features",
+            "test_feature1",
+            "--no-default-features",
+            "--message-format=json",
+            "--arg1",
+            "--no-deps",
+        ]
+        "#);
+    }
+
     #[test]
     fn test_rust_workspace_params_create_command() {
         let rust_workspace_params = RustWorkspaceParams::default();
-        let cmd = rust_workspace_params
-            .create_requested_command(Path::new(TEST_ROOT), &default_target_details());
+        let cmd = rust_workspace_params.create_requested_command(
+            Path::new(TEST_ROOT),
+            &default_target_details(),
+            &ServerSettings::default(),
+        );
         let cwd = cmd.get_current_dir().unwrap();
         let args: Vec<&OsStr> = cmd.get_args().collect();
         let envs: Vec<(&OsStr, Option<&OsStr>)> = cmd.get_envs().collect();
@@ -292,4 +1219,24 @@ mod feature_tests {
             .unwrap_or("".to_string());
         assert_eq!(enabled_features_string, expected);
     }
+
+    #[test_case(BTreeSet::from([Feature::from("default")]), "f1" ; "default_enabled")]
+    #[test_case(BTreeSet::from([Feature::from("f1"), Feature::from("f2")]), "f1, f2" ;
+    "already_enabled"
+    )]
+    fn test_get_enabled_features_string_with_required_features(
+        enabled_features: BTreeSet<Feature>,
+        expected: &str,
+    ) {
+        let target_details = TargetDetails {
+            enabled_features,
+            required_features: BTreeSet::from([Feature::from("f1")]),
+            ..TargetDetails::default()
+        };
+
+        assert_eq!(
+            target_details.get_enabled_features_str().as_deref(),
+            Some(expected)
+        );
+    }
 }
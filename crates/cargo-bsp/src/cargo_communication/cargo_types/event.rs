@@ -1,13 +1,54 @@
+use std::time::Instant;
+
 use serde::Serialize;
 
 /// Indicates, where the message is coming from:
-/// - Cancel when the client canceled the request,
+/// - Cancel when the request was canceled (by the client or the server shutdown),
 /// - CargoEvent when there is a new message from Cargo (from stdout or stderr),
-/// - CargoFinish when Cargo command finished its execution.
+/// - CargoFinish when Cargo command finished its execution,
+/// - Timeout when Cargo command produced no output within the configured time.
 pub enum Event {
-    Cancel,
+    Cancel(CancelReason),
     CargoEvent(CargoMessage),
     CargoFinish,
+    Timeout,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CancelReason {
+    Client,
+    Timeout,
+    Shutdown,
+}
+
+impl CancelReason {
+    pub fn message(&self) -> &'static str {
+        match self {
+            CancelReason::Client => "canceled by client",
+            CancelReason::Timeout => "canceled due to command timeout",
+            CancelReason::Shutdown => "canceled due to server shutdown",
+        }
+    }
+}
+
+/// Sent in the data of the response error, so that the client can distinguish
+/// why the request was canceled.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelData {
+    pub reason: CancelReason,
+    /// Time since the start of the request handling.
+    pub elapsed_ms: u64,
+}
+
+impl CancelData {
+    pub fn new(reason: CancelReason, start_time: Instant) -> CancelData {
+        CancelData {
+            reason,
+            elapsed_ms: start_time.elapsed().as_millis() as u64,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
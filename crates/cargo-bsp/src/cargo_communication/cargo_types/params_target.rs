@@ -1,9 +1,10 @@
-//! ParamsTarget trait implementation for the Compile/Run/Test/RustWorkspaceParams.
+//! ParamsTarget trait implementation for the Compile/Run/Test/Doc/RustWorkspaceParams.
 //! Necessary for getting the list of build targets from the compile/run/test/check requests.
 
 use crate::project_model::workspace::ProjectWorkspace;
 use bsp4rs::bsp::BuildTargetIdentifier;
 use bsp4rs::bsp::{CompileParams, RunParams, TestParams};
+use bsp4rs::cargo::DocParams;
 use bsp4rs::rust::RustWorkspaceParams;
 
 pub(crate) trait ParamsTarget {
@@ -36,6 +37,12 @@ impl ParamsTarget for TestParams {
     }
 }
 
+impl ParamsTarget for DocParams {
+    fn get_targets(&self, _: &ProjectWorkspace) -> Vec<BuildTargetIdentifier> {
+        self.targets.clone()
+    }
+}
+
 impl ParamsTarget for RustWorkspaceParams {
     fn get_targets(&self, _: &ProjectWorkspace) -> Vec<BuildTargetIdentifier> {
         self.targets.clone()
@@ -47,7 +54,6 @@ pub mod tests {
     use super::*;
     use crate::cargo_communication::utils::{test_package, test_target, test_target_id};
     use bsp4rs::bsp::{BuildTargetIdentifier, TestParams};
-    use std::collections::HashMap;
 
     const PACKAGE1: &str = "package1";
     const PACKAGE2: &str = "package2";
@@ -61,34 +67,24 @@ pub mod tests {
     const TEST: &str = "test";
 
     fn test_workspace() -> ProjectWorkspace {
-        let packages = vec![test_package(PACKAGE1), test_package(PACKAGE2)];
-
-        let bin_target_1_id = test_target_id(BIN_TARGET1);
-        let bin_target_2_id = test_target_id(BIN_TARGET2);
-        let lib_target_id = test_target_id(LIB_TARGET);
-        let test_target_1_id = test_target_id(TEST_TARGET1);
-        let test_target_2_id = test_target_id(TEST_TARGET2);
-
-        let target_id_to_package_name = HashMap::from([
-            (bin_target_1_id.clone(), PACKAGE1.to_string()),
-            (bin_target_2_id.clone(), PACKAGE2.to_string()),
-            (lib_target_id.clone(), PACKAGE1.to_string()),
-            (test_target_1_id.clone(), PACKAGE1.to_string()),
-            (test_target_2_id.clone(), PACKAGE2.to_string()),
-        ]);
-        let target_id_to_target_data = HashMap::from([
-            (bin_target_1_id, test_target(BIN_TARGET1, BIN)),
-            (bin_target_2_id, test_target(BIN_TARGET2, BIN)),
-            (lib_target_id, test_target(LIB_TARGET, LIB)),
-            (test_target_1_id, test_target(TEST_TARGET1, TEST)),
-            (test_target_2_id, test_target(TEST_TARGET2, TEST)),
-        ]);
-        ProjectWorkspace {
-            packages,
-            target_id_to_package_name,
-            target_id_to_target_data,
-            ..ProjectWorkspace::default()
-        }
+        let packages = vec![
+            test_package(
+                PACKAGE1,
+                vec![
+                    test_target(BIN_TARGET1, BIN),
+                    test_target(LIB_TARGET, LIB),
+                    test_target(TEST_TARGET1, TEST),
+                ],
+            ),
+            test_package(
+                PACKAGE2,
+                vec![
+                    test_target(BIN_TARGET2, BIN),
+                    test_target(TEST_TARGET2, TEST),
+                ],
+            ),
+        ];
+        ProjectWorkspace::from_packages(packages, Default::default())
     }
 
     fn test_params(targets: Vec<BuildTargetIdentifier>) -> TestParams {
@@ -101,18 +97,18 @@ pub mod tests {
     #[test]
     fn test_sorting_targets() {
         let unsorted_test_targets = vec![
-            test_target_id(BIN_TARGET1),
-            test_target_id(BIN_TARGET2),
-            test_target_id(LIB_TARGET),
-            test_target_id(TEST_TARGET1),
-            test_target_id(TEST_TARGET2),
+            test_target_id(PACKAGE1, BIN_TARGET1, BIN),
+            test_target_id(PACKAGE2, BIN_TARGET2, BIN),
+            test_target_id(PACKAGE1, LIB_TARGET, LIB),
+            test_target_id(PACKAGE1, TEST_TARGET1, TEST),
+            test_target_id(PACKAGE2, TEST_TARGET2, TEST),
         ];
         let sorted_test_targets = vec![
-            test_target_id(TEST_TARGET2),
-            test_target_id(BIN_TARGET2),
-            test_target_id(TEST_TARGET1),
-            test_target_id(BIN_TARGET1),
-            test_target_id(LIB_TARGET),
+            test_target_id(PACKAGE2, TEST_TARGET2, TEST),
+            test_target_id(PACKAGE2, BIN_TARGET2, BIN),
+            test_target_id(PACKAGE1, TEST_TARGET1, TEST),
+            test_target_id(PACKAGE1, BIN_TARGET1, BIN),
+            test_target_id(PACKAGE1, LIB_TARGET, LIB),
         ];
         let result = test_params(unsorted_test_targets).get_targets(&test_workspace());
         assert_eq!(result, sorted_test_targets);
@@ -3,38 +3,45 @@
 
 use crate::utils::uri::file_uri;
 use bsp4rs::bsp::{EnvironmentVariables, URI};
-use bsp4rs::rust::RustCfgOptions;
+use bsp4rs::rust::{Feature, RustCfgOptions, RustCrateType, RustProcMacroArtifact, RustTarget};
+use cargo_metadata::camino::Utf8Path;
 use cargo_metadata::{Artifact, BuildScript, Package};
-use std::collections::btree_map::Entry;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
 
 const DYNAMIC_LIBRARY_EXTENSIONS: [&str; 3] = ["dll", "so", "dylib"];
 const PROC_MACRO: &str = "proc-macro";
 
-pub(super) fn map_cfg_options(script: Option<&BuildScript>) -> RustCfgOptions {
-    script.map_or(RustCfgOptions::new(BTreeMap::new()), |s| {
-        let mut cfg_options: BTreeMap<String, Vec<String>> = BTreeMap::new();
+/// Collects the cfg options set by Cargo for the package: the default ones of the target,
+/// `feature="..."` for each enabled feature and the ones emitted by the build script.
+pub(super) fn map_cfg_options(
+    script: Option<&BuildScript>,
+    target_cfgs: &[String],
+    enabled_features: &BTreeSet<Feature>,
+) -> RustCfgOptions {
+    let feature_cfgs = enabled_features
+        .iter()
+        .map(|f| format!("feature=\"{}\"", f.as_str()));
+    let script_cfgs = script.into_iter().flat_map(|s| s.cfgs.iter().cloned());
 
-        s.cfgs.iter().for_each(|cfg| {
+    let mut cfg_options: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    target_cfgs
+        .iter()
+        .cloned()
+        .chain(feature_cfgs)
+        .chain(script_cfgs)
+        .for_each(|cfg| {
             let mut parts = cfg.splitn(2, '=');
-            let key = parts.next();
-            let value = parts.next().map(|v| v.trim_matches('"').to_string());
-
-            if let Some(k) = key {
-                if let Some(v) = value {
-                    if let Entry::Vacant(e) = cfg_options.entry(k.to_string()) {
-                        e.insert(vec![v]);
-                    } else {
-                        cfg_options.get_mut(k).unwrap().push(v);
-                    }
-                } else {
-                    cfg_options.insert(k.to_string(), vec![]);
+            let key = parts.next().unwrap_or_default();
+            let values = cfg_options.entry(key.to_string()).or_default();
+            if let Some(value) = parts.next().map(|v| v.trim_matches('"').to_string()) {
+                if !values.contains(&value) {
+                    values.push(value);
                 }
             }
         });
 
-        RustCfgOptions::new(cfg_options)
-    })
+    RustCfgOptions::new(cfg_options)
 }
 
 pub(super) fn map_env(script: Option<&BuildScript>, package: &Package) -> EnvironmentVariables {
@@ -81,6 +88,8 @@ pub(super) fn map_env(script: Option<&BuildScript>, package: &Package) -> Enviro
         for (k, v) in &s.env {
             env.insert(k.clone(), v.clone());
         }
+        // Set by Cargo for the packages with a build script, e.g. for `include!`.
+        env.insert("OUT_DIR".to_string(), s.out_dir.to_string());
     }
     EnvironmentVariables::new(env)
 }
@@ -97,10 +106,244 @@ pub(super) fn map_proc_macro_artifact(artifacts: &[Artifact]) -> Option<URI> {
                 && a.target.crate_types.contains(&PROC_MACRO.to_string())
         })
         .flat_map(|a| a.filenames.clone())
-        .find(|f| {
-            DYNAMIC_LIBRARY_EXTENSIONS
-                .iter()
-                .any(|&e| f.extension().map_or(false, |ex| ex == e))
-        })
+        .find(|f| is_dynamic_library(f))
         .map(|f| URI::new(f.to_string()))
 }
+
+/// Finds the dynamic library of the proc-macro target among the artifacts of its package.
+/// If the target has not been built, returns the path where `cargo build` places the
+/// library in the `output_dir` of the profile.
+pub(super) fn map_target_proc_macro_artifact(
+    target: &RustTarget,
+    artifacts: &[Artifact],
+    output_dir: &Utf8Path,
+) -> Option<RustProcMacroArtifact> {
+    if !matches!(&target.crate_types, Some(types) if types.contains(&RustCrateType::ProcMacro)) {
+        return None;
+    }
+    let built_library = artifacts
+        .iter()
+        .filter(|a| a.target.name == target.name)
+        .flat_map(|a| a.filenames.iter())
+        .find(|f| is_dynamic_library(f));
+    let artifact = match built_library {
+        Some(library) => RustProcMacroArtifact {
+            path: file_uri(library),
+            built: true,
+        },
+        None => RustProcMacroArtifact {
+            path: file_uri(output_dir.join(format!(
+                "{}{}{}",
+                DLL_PREFIX,
+                target.name.replace('-', "_"),
+                DLL_SUFFIX
+            ))),
+            built: false,
+        },
+    };
+    Some(artifact)
+}
+
+fn is_dynamic_library(path: &Utf8Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| DYNAMIC_LIBRARY_EXTENSIONS.contains(&ext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cargo_metadata::{
+        ArtifactBuilder, ArtifactDebuginfo, ArtifactProfileBuilder, PackageId, TargetBuilder,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_map_cfg_options() {
+        let script: BuildScript = serde_json::from_value(json!({
+            "package_id": "test_package 0.1.0 (path+file:///test_package)",
+            "linked_libs": [],
+            "linked_paths": [],
+            "cfgs": ["has_feature_x", "custom_cfg=\"value\""],
+            "env": [],
+            "out_dir": "/test_package/target/debug/build/test_package-hash/out",
+        }))
+        .unwrap();
+        let target_cfgs = ["unix", "target_os=\"linux\""].map(String::from);
+        let enabled_features = BTreeSet::from([Feature::from("default"), Feature::from("f1")]);
+
+        let cfg_options = map_cfg_options(Some(&script), &target_cfgs, &enabled_features);
+
+        assert_eq!(
+            cfg_options,
+            RustCfgOptions::new(BTreeMap::from([
+                ("custom_cfg".to_string(), vec!["value".to_string()]),
+                (
+                    "feature".to_string(),
+                    vec!["default".to_string(), "f1".to_string()]
+                ),
+                ("has_feature_x".to_string(), vec![]),
+                ("target_os".to_string(), vec!["linux".to_string()]),
+                ("unix".to_string(), vec![]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_map_cfg_options_without_build_script() {
+        let cfg_options = map_cfg_options(None, &[], &BTreeSet::new());
+
+        assert_eq!(cfg_options, RustCfgOptions::default());
+    }
+
+    fn test_package() -> Package {
+        serde_json::from_value(json!({
+            "name": "test-package",
+            "version": "0.1.0",
+            "id": "test_package 0.1.0 (path+file:///test_package)",
+            "source": null,
+            "dependencies": [],
+            "targets": [],
+            "features": {},
+            "manifest_path": "/test_package/Cargo.toml",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_map_env_and_out_dir_url() {
+        let script: BuildScript = serde_json::from_value(json!({
+            "package_id": "test_package 0.1.0 (path+file:///test_package)",
+            "linked_libs": [],
+            "linked_paths": [],
+            "cfgs": [],
+            "env": [["GENERATED_VAR", "value"]],
+            "out_dir": "/test_package/target/debug/build/test_package-hash/out",
+        }))
+        .unwrap();
+
+        let env = map_env(Some(&script), &test_package());
+        let out_dir_url = map_out_dir_url(Some(&script));
+
+        assert_eq!(env.get("GENERATED_VAR"), Some(&"value".to_string()));
+        assert_eq!(
+            env.get("OUT_DIR"),
+            Some(&"/test_package/target/debug/build/test_package-hash/out".to_string())
+        );
+        assert_eq!(
+            env.get("CARGO_MANIFEST_DIR"),
+            Some(&"/test_package".to_string())
+        );
+        assert_eq!(
+            env.get("CARGO_CRATE_NAME"),
+            Some(&"test_package".to_string())
+        );
+        assert_eq!(
+            out_dir_url,
+            Some(file_uri(
+                "/test_package/target/debug/build/test_package-hash/out"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_map_env_and_out_dir_url_without_build_script() {
+        let env = map_env(None, &test_package());
+
+        // Only the variables set by Cargo for each package.
+        assert!(env.keys().all(|k| k == "CARGO" || k.starts_with("CARGO_")));
+        assert_eq!(env.get("OUT_DIR"), None);
+        assert_eq!(map_out_dir_url(None), None);
+    }
+
+    fn test_proc_macro_target() -> RustTarget {
+        RustTarget {
+            name: "test-macro".to_string(),
+            crate_types: Some(vec![RustCrateType::ProcMacro]),
+            ..RustTarget::default()
+        }
+    }
+
+    fn test_proc_macro_compiler_artifact(filename: &str) -> Artifact {
+        let target = TargetBuilder::default()
+            .name("test-macro".to_string())
+            .kind(vec![PROC_MACRO.to_string()])
+            .crate_types(vec![PROC_MACRO.to_string()])
+            .src_path("/test_package/src/lib.rs".to_string())
+            .build()
+            .unwrap();
+        let profile = ArtifactProfileBuilder::default()
+            .opt_level("0".to_string())
+            .debuginfo(ArtifactDebuginfo::default())
+            .debug_assertions(false)
+            .overflow_checks(false)
+            .test(false)
+            .build()
+            .unwrap();
+        ArtifactBuilder::default()
+            .package_id(PackageId {
+                repr: "test_package 0.1.0 (path+file:///test_package)".into(),
+            })
+            .manifest_path("/test_package/Cargo.toml".to_string())
+            .target(target)
+            .profile(profile)
+            .executable(None)
+            .features(vec![])
+            .filenames(vec![filename.into()])
+            .fresh(false)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_built_proc_macro_artifact() {
+        let library = "/test_package/target/debug/deps/libtest_macro-hash.so";
+        let artifacts = [test_proc_macro_compiler_artifact(library)];
+
+        let artifact = map_target_proc_macro_artifact(
+            &test_proc_macro_target(),
+            &artifacts,
+            Utf8Path::new("/test_package/target/debug"),
+        );
+
+        assert_eq!(
+            artifact,
+            Some(RustProcMacroArtifact {
+                path: file_uri(library),
+                built: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_unbuilt_proc_macro_artifact() {
+        let artifact = map_target_proc_macro_artifact(
+            &test_proc_macro_target(),
+            &[],
+            Utf8Path::new("/test_package/target/debug"),
+        );
+
+        assert_eq!(
+            artifact,
+            Some(RustProcMacroArtifact {
+                path: file_uri(format!(
+                    "/test_package/target/debug/{}test_macro{}",
+                    DLL_PREFIX, DLL_SUFFIX
+                )),
+                built: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_no_artifact_for_non_proc_macro_target() {
+        let target = RustTarget {
+            crate_types: Some(vec![RustCrateType::Lib]),
+            ..RustTarget::default()
+        };
+
+        let artifact =
+            map_target_proc_macro_artifact(&target, &[], Utf8Path::new("/test_package/target"));
+
+        assert_eq!(artifact, None);
+    }
+}
@@ -5,9 +5,11 @@
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::io;
+use std::time::Instant;
 
 use bsp_server::RequestId;
 use bsp_server::{ErrorCode, Message, Response, ResponseError};
+use cargo_metadata::camino::Utf8PathBuf;
 pub use cargo_metadata::diagnostic::{
     Applicability, Diagnostic, DiagnosticCode, DiagnosticLevel, DiagnosticSpan,
     DiagnosticSpanMacroExpansion,
@@ -19,9 +21,12 @@ use serde::Deserialize;
 use serde_json::to_value;
 
 use crate::cargo_communication::cargo_handle::CargoHandler;
-use crate::cargo_communication::cargo_types::event::{CargoMessage, Event};
+use crate::cargo_communication::cargo_types::event::{
+    CancelData, CancelReason, CargoMessage, Event,
+};
 use crate::cargo_communication::check::cargo_message_to_package_info::{
     map_cfg_options, map_env, map_out_dir_url, map_proc_macro_artifact,
+    map_target_proc_macro_artifact,
 };
 use bsp4rs::rust::RustWorkspaceResult;
 
@@ -36,6 +41,14 @@ where
     req_id: RequestId,
     build_scripts: HashMap<PackageId, BuildScript>,
     compiler_artifacts: HashMap<PackageId, Vec<Artifact>>,
+    /// Default cfg options of the target, see [`rustc_cfgs`].
+    ///
+    /// [`rustc_cfgs`]: crate::project_model::rustc_cfg::rustc_cfgs
+    target_cfgs: Vec<String>,
+    /// Output directory of the build profile, where the proc-macro libraries are
+    /// expected if they have not been built.
+    output_dir: Utf8PathBuf,
+    start_time: Instant,
 }
 
 impl<C> CheckActor<C>
@@ -47,6 +60,8 @@ where
         cargo_handle: C,
         req_id: RequestId,
         cancel_receiver: Receiver<Event>,
+        target_cfgs: Vec<String>,
+        output_dir: Utf8PathBuf,
     ) -> CheckActor<C> {
         CheckActor {
             sender,
@@ -55,6 +70,9 @@ where
             req_id,
             build_scripts: HashMap::new(),
             compiler_artifacts: HashMap::new(),
+            target_cfgs,
+            output_dir,
+            start_time: Instant::now(),
         }
     }
 
@@ -72,13 +90,15 @@ where
     pub fn run(&mut self, result: RustWorkspaceResult, packages: Vec<Package>) {
         while let Some(event) = self.next_event() {
             match event {
-                Event::Cancel => {
-                    self.cancel();
+                Event::Cancel(reason) => {
+                    self.cancel(reason);
                     return;
                 }
                 Event::CargoFinish => {
                     break;
                 }
+                // Check actor does not use the command timeout.
+                Event::Timeout => {}
                 Event::CargoEvent(message) => {
                     self.handle_message(message);
                 }
@@ -133,10 +153,22 @@ where
                 // We can unwrap, as there would be no RustWorkspaceResult without this package.
                 let package = packages.iter().find(|&p| p.id == package_id).unwrap();
 
-                p.cfg_options = Some(map_cfg_options(script));
+                p.cfg_options = Some(map_cfg_options(
+                    script,
+                    &self.target_cfgs,
+                    &p.enabled_features,
+                ));
                 p.env = Some(map_env(script, package));
                 p.out_dir_url = map_out_dir_url(script);
                 p.proc_macro_artifact = map_proc_macro_artifact(artifacts);
+                for target in p
+                    .resolved_targets
+                    .iter_mut()
+                    .chain(p.all_targets.iter_mut())
+                {
+                    target.proc_macro_artifact =
+                        map_target_proc_macro_artifact(target, artifacts, &self.output_dir);
+                }
                 p
             })
             .collect();
@@ -145,13 +177,13 @@ where
         self.send_response(Ok(result));
     }
 
-    fn cancel(&mut self) {
+    fn cancel(&mut self, reason: CancelReason) {
         if let Some(cargo_handle) = self.cargo_handle.take() {
             cargo_handle.cancel();
             let error = ResponseError {
                 code: ErrorCode::RequestCanceled as i32,
-                message: "canceled by client".to_string(),
-                data: None,
+                message: reason.message().to_string(),
+                data: Some(to_value(CancelData::new(reason, self.start_time)).unwrap()),
             };
             self.send(
                 Response {
@@ -1,9 +1,6 @@
 //! Creates and spawns `cargo check` command and runs a new [`CheckActor`] in
 //! a new thread. Implementation of [`RequestHandle`].
 
-use std::io;
-use std::io::ErrorKind;
-
 use bsp_server::{Message, RequestId};
 use crossbeam_channel::unbounded;
 
@@ -15,8 +12,11 @@ use crate::cargo_communication::cargo_types::create_command::CreateCommand;
 use crate::cargo_communication::cargo_types::event::Event;
 use crate::cargo_communication::cargo_types::params_target::ParamsTarget;
 use crate::cargo_communication::request_handle::RequestHandle;
+use crate::project_model::output_paths::{profile_output_dir, DEFAULT_PROFILE};
 use crate::project_model::rust_extension::{get_metadata, resolve_rust_workspace_result};
+use crate::project_model::rustc_cfg::rustc_cfgs;
 use crate::server::global_state::GlobalState;
+use crate::server::ServerError;
 
 impl RequestHandle {
     pub fn spawn_check<R>(
@@ -24,30 +24,55 @@ impl RequestHandle {
         req_id: RequestId,
         params: R::Params,
         global_state: &mut GlobalState,
-    ) -> io::Result<RequestHandle>
+    ) -> Result<RequestHandle, ServerError>
     where
         R: Request + 'static,
         R::Params: CreateCommand + ParamsTarget + Send,
     {
+        // Without the metadata the result would be empty, so the request fails
+        // before the command is spawned.
+        let (metadata, unknown_editions) = get_metadata(
+            &mut global_state.metadata_cache,
+            &global_state.config.workspace_manifest,
+        )?;
+
         let root_path = global_state.config.root_path();
         let build_targets = params.get_targets(&global_state.workspace);
 
         // The command does not need information about targets, as it is invoked with
         // `--all-targets` flag.
-        let mut command = params.create_requested_command(root_path, &[]);
-        let cargo_handle = CargoHandle::spawn(&mut command)?;
+        let mut command =
+            params.create_requested_command(root_path, &[], &global_state.config.settings);
+        let cargo_handle = CargoHandle::spawn(&mut command).map_err(ServerError::Spawn)?;
+
+        let result = resolve_rust_workspace_result(
+            &mut global_state.workspace,
+            &build_targets,
+            &metadata,
+            &unknown_editions,
+        );
 
-        let metadata = get_metadata(&global_state.config.workspace_manifest)
-            .map_err(|e| io::Error::new(ErrorKind::Other, e.to_string()))?;
-        let result =
-            resolve_rust_workspace_result(&mut global_state.workspace, &build_targets, &metadata);
+        let profile = global_state
+            .config
+            .settings
+            .profile
+            .as_deref()
+            .unwrap_or(DEFAULT_PROFILE);
+        let output_dir = profile_output_dir(&metadata.target_directory, profile);
 
         let (cancel_sender, cancel_receiver) = unbounded::<Event>();
-        let mut actor: CheckActor<CargoHandle> =
-            CheckActor::new(sender_to_main, cargo_handle, req_id, cancel_receiver);
+        let mut actor: CheckActor<CargoHandle> = CheckActor::new(
+            sender_to_main,
+            cargo_handle,
+            req_id,
+            cancel_receiver,
+            rustc_cfgs(root_path),
+            output_dir,
+        );
 
-        let thread =
-            jod_thread::Builder::new().spawn(move || actor.run(result, metadata.packages))?;
+        let thread = jod_thread::Builder::new()
+            .spawn(move || actor.run(result, metadata.packages))
+            .map_err(ServerError::Spawn)?;
         Ok(RequestHandle {
             cancel_sender,
             _thread: thread,
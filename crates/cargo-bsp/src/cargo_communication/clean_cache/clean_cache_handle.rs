@@ -0,0 +1,57 @@
+//! Creates the `cargo clean` commands for the packages of the requested targets and runs
+//! them in a new thread, so that the main loop is not blocked. Implementation of
+//! [`RequestHandle`].
+
+use std::time::Instant;
+
+use bsp_server::{ErrorCode, Message, RequestId, Response, ResponseError};
+use crossbeam_channel::unbounded;
+use serde_json::to_value;
+
+use bsp4rs::bsp::CleanCacheParams;
+
+use crate::cargo_communication::cargo_types::event::{CancelData, Event};
+use crate::cargo_communication::request_handle::RequestHandle;
+use crate::project_model::clean_cache::{clean_cache, clean_commands};
+use crate::server::global_state::GlobalState;
+use crate::server::{Result, ServerError};
+
+impl RequestHandle {
+    pub fn spawn_clean_cache(
+        sender_to_main: Box<dyn Fn(Message) + Send>,
+        req_id: RequestId,
+        params: CleanCacheParams,
+        global_state: &mut GlobalState,
+    ) -> Result<RequestHandle, ServerError> {
+        let commands = clean_commands(
+            global_state.config.root_path(),
+            &global_state.workspace,
+            &params.targets,
+            &global_state.config.settings.lock_flags(),
+        );
+        let start_time = Instant::now();
+
+        let (cancel_sender, cancel_receiver) = unbounded::<Event>();
+        let thread = jod_thread::Builder::new()
+            .spawn(move || {
+                let response = match clean_cache(commands, &cancel_receiver) {
+                    Ok(result) => Response::new_ok(req_id, result),
+                    Err(reason) => Response {
+                        id: req_id,
+                        result: None,
+                        error: Some(ResponseError {
+                            code: ErrorCode::RequestCanceled as i32,
+                            message: reason.message().to_string(),
+                            data: Some(to_value(CancelData::new(reason, start_time)).unwrap()),
+                        }),
+                    },
+                };
+                sender_to_main(response.into());
+            })
+            .map_err(ServerError::Spawn)?;
+        Ok(RequestHandle {
+            cancel_sender,
+            _thread: thread,
+        })
+    }
+}
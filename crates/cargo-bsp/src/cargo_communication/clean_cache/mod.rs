@@ -0,0 +1,5 @@
+//! Handles communication with Cargo regarding the clean cache request
+//! ([`BuildTargetCleanCache`](bsp4rs::bsp::BuildTargetCleanCache)), which runs `cargo clean`
+//! for the packages of the requested targets.
+
+mod clean_cache_handle;
@@ -2,18 +2,18 @@
 //! and creates the appropriate notifications for the client.
 
 use cargo_metadata::diagnostic::DiagnosticLevel;
-use cargo_metadata::{BuildFinished, CompilerMessage, Message};
+use cargo_metadata::{BuildFinished, BuildScript, CompilerMessage, Message, PackageId, Target};
 use log::warn;
 use path_absolutize::*;
 use paths::AbsPath;
 
 use crate::cargo_communication::cargo_handle::CargoHandler;
+use bsp4rs::bsp::StatusCode;
 use bsp4rs::bsp::{
-    CompileReport, DiagnosticSeverity, LogMessageParams, MessageType, OnBuildLogMessage,
-    OnBuildPublishDiagnostics, PublishDiagnosticsParams, TaskFinishData, TaskId, TaskStartData,
-    TestStart, TestStatus, TestTask,
+    BuildTargetIdentifier, CompileReport, DiagnosticSeverity, LogMessageParams, MessageType,
+    OnBuildLogMessage, OnBuildPublishDiagnostics, PublishDiagnosticsParams, TaskFinishData, TaskId,
+    TaskStartData, TestFinish, TestStart, TestStatus, TestTask,
 };
-use bsp4rs::bsp::{Identifier, OriginId, StatusCode};
 use bsp4rs::Request;
 
 use crate::cargo_communication::cargo_types::event::CargoMessage;
@@ -30,7 +30,7 @@ use crate::cargo_communication::execution::execution_types::test::{
     SuiteEvent, SuiteResults, TestEvent, TestResult, TestType,
 };
 use crate::cargo_communication::execution::utils::{
-    generate_random_id, generate_task_id, get_current_time,
+    generate_random_id, generate_task_id, now_millis, package_name,
 };
 
 impl<R, C> ExecutionActor<R, C>
@@ -43,13 +43,54 @@ where
     pub(super) fn handle_cargo_information(&mut self, message: Message) {
         match message {
             Message::CompilerArtifact(msg) => {
-                self.report_compile_step(serde_json::to_string(&msg).ok());
+                self.state
+                    .compile_state
+                    .record_artifact_freshness(msg.fresh);
+                if msg.target.kind.iter().any(|kind| kind == "custom-build") {
+                    self.start_build_script(&msg.package_id);
+                }
+                let target_id = self.find_artifact_target_id(&msg.package_id, &msg.target);
+                if let Some(id) = &target_id {
+                    self.state.compile_state.record_first_artifact_time(id);
+                }
+                // The documentation of a crate is reported as its `index.html` file.
+                self.state.compile_state.doc_index_files.extend(
+                    msg.filenames
+                        .iter()
+                        .filter(|f| f.file_name() == Some("index.html"))
+                        .cloned(),
+                );
+                if let (TaskState::Test(test_state), true) =
+                    (&mut self.state.task_state, msg.profile.test)
+                {
+                    // Older Cargo versions report only the filenames of the test executables.
+                    if let Some(executable) =
+                        msg.executable.as_ref().or(msg.filenames.first()).cloned()
+                    {
+                        test_state.test_executables.push(executable);
+                    }
+                }
+                if let (TaskState::Run(run_state), Some(executable)) =
+                    (&mut self.state.task_state, &msg.executable)
+                {
+                    if target_id.is_some_and(|id| self.build_targets.contains(&id)) {
+                        run_state.executables.push(executable.clone());
+                    }
+                }
+                // The skipped fresh artifact is still counted, so that the progress
+                // reaches the total number of the compilation steps.
+                if msg.fresh && self.skips_fresh_compile() {
+                    self.state.compile_state.increase_compilation_step();
+                } else {
+                    self.report_compile_step(serde_json::to_string(&msg).ok());
+                }
             }
             Message::CompilerMessage(msg) => {
                 self.handle_diagnostic(msg);
             }
             Message::BuildScriptExecuted(msg) => {
                 self.report_compile_step(serde_json::to_string(&msg).ok());
+                self.report_build_script(&msg);
             }
             Message::BuildFinished(msg) => {
                 self.finish_compile(msg);
@@ -78,7 +119,79 @@ where
         );
     }
 
+    /// The subtask of the build script is started when the build script is compiled
+    /// (Cargo reports it as the artifact of the `custom-build` target) and finished when
+    /// it was executed. The build script is reported once per package.
+    fn start_build_script(&mut self, package_id: &PackageId) {
+        let compile_state = &self.state.compile_state;
+        if compile_state
+            .executed_build_scripts
+            .contains(&package_id.repr)
+            || compile_state
+                .running_build_scripts
+                .contains_key(&package_id.repr)
+        {
+            return;
+        }
+        let task_id = generate_task_id(&compile_state.task_id);
+        self.report_task_start(
+            task_id.clone(),
+            Some(format!(
+                "Running build script for {}",
+                package_name(package_id)
+            )),
+            None,
+        );
+        self.state
+            .compile_state
+            .running_build_scripts
+            .insert(package_id.repr.clone(), task_id);
+    }
+
+    /// The build script without the reported artifact (e.g. with an older Cargo version)
+    /// is started and finished at once.
+    fn report_build_script(&mut self, msg: &BuildScript) {
+        if self
+            .state
+            .compile_state
+            .executed_build_scripts
+            .contains(&msg.package_id.repr)
+        {
+            return;
+        }
+        self.start_build_script(&msg.package_id);
+        let compile_state = &mut self.state.compile_state;
+        compile_state
+            .executed_build_scripts
+            .insert(msg.package_id.repr.clone());
+        if let Some(task_id) = compile_state
+            .running_build_scripts
+            .remove(&msg.package_id.repr)
+        {
+            self.report_task_finish(task_id, StatusCode::Ok, None, None);
+        }
+    }
+
+    /// The build scripts still running when the build finished have failed
+    /// (or were not executed at all).
+    fn finish_running_build_scripts(&mut self) {
+        let running_build_scripts =
+            std::mem::take(&mut self.state.compile_state.running_build_scripts);
+        for (_, task_id) in running_build_scripts {
+            self.report_task_finish(task_id, StatusCode::Error, None, None);
+        }
+    }
+
     fn handle_diagnostic(&mut self, msg: CompilerMessage) {
+        // Diagnostics without a primary span (e.g. the manifest warnings) are not attached
+        // to any file, so they are reported even if the target is not known.
+        if !msg.message.spans.iter().any(|s| s.is_primary) {
+            self.send_global_message(GlobalMessage {
+                level: msg.message.level,
+                message: msg.message.message,
+            });
+            return;
+        }
         // Diagnostics in Cargo are identified by root path, however in BSP
         // they are identified by the BuildTargetId.
         let abs_root_path = match self.root_path.absolutize() {
@@ -88,7 +201,7 @@ where
                 return;
             }
         };
-        let build_target_id = match self.src_path_to_target_id.get(&msg.target.src_path) {
+        let build_target_id = match self.find_target_id(&msg.package_id, &msg.target) {
             Some(id) => id,
             None => {
                 warn!(
@@ -100,9 +213,10 @@ where
         };
         let diagnostic_msg = map_cargo_diagnostic_to_bsp(
             &msg.message,
-            self.params.origin_id().map(|id| OriginId::new(id.0)),
+            self.message_origin_id(),
             build_target_id,
             AbsPath::assert(&abs_root_path),
+            self.ansi_diagnostics,
         );
         match diagnostic_msg {
             DiagnosticMessage::Diagnostics(diagnostics) => {
@@ -112,6 +226,45 @@ where
         }
     }
 
+    /// Finds the build target of the Cargo message by its src path or, if the
+    /// src path is not known (e.g. for the generated files), by its package and name.
+    /// If neither is known, the message is attributed to the package's primary target.
+    fn find_target_id(
+        &self,
+        package_id: &PackageId,
+        target: &Target,
+    ) -> Option<&BuildTargetIdentifier> {
+        self.src_path_to_target_id
+            .get(&target.src_path)
+            .or_else(|| {
+                self.package_target_to_target_id.get(&(
+                    package_id.repr.clone(),
+                    target.name.clone(),
+                    target.kind.clone(),
+                ))
+            })
+            .or_else(|| self.package_to_primary_target_id.get(&package_id.repr))
+    }
+
+    /// Only the exact target of the artifact, as the artifacts of the other targets
+    /// (e.g. of the build script) would be attributed to the primary target of the package.
+    fn find_artifact_target_id(
+        &self,
+        package_id: &PackageId,
+        target: &Target,
+    ) -> Option<BuildTargetIdentifier> {
+        if target.kind.iter().any(|kind| kind == "custom-build") {
+            return None;
+        }
+        self.package_target_to_target_id
+            .get(&(
+                package_id.repr.clone(),
+                target.name.clone(),
+                target.kind.clone(),
+            ))
+            .cloned()
+    }
+
     fn publish_diagnostic(&mut self, diagnostics: Vec<PublishDiagnosticsParams>) {
         diagnostics.into_iter().for_each(|diagnostic| {
             // Count errors and warnings.
@@ -124,10 +277,40 @@ where
                     }
                 }
             });
-            self.send_notification::<OnBuildPublishDiagnostics>(diagnostic);
+            if self.stream_diagnostics {
+                self.send_notification::<OnBuildPublishDiagnostics>(diagnostic);
+            } else {
+                self.batch_diagnostic(diagnostic);
+            }
         })
     }
 
+    /// Diagnostics for the same file and target are sent in a single notification,
+    /// which is published when a diagnostic for another file arrives or the build finishes.
+    fn batch_diagnostic(&mut self, diagnostic: PublishDiagnosticsParams) {
+        if let Some(pending) = self
+            .state
+            .compile_state
+            .pending_diagnostics
+            .as_mut()
+            .filter(|p| {
+                p.text_document == diagnostic.text_document
+                    && p.build_target == diagnostic.build_target
+            })
+        {
+            pending.diagnostics.extend(diagnostic.diagnostics);
+            return;
+        }
+        self.flush_diagnostics();
+        self.state.compile_state.pending_diagnostics = Some(diagnostic);
+    }
+
+    pub(super) fn flush_diagnostics(&mut self) {
+        if let Some(diagnostic) = self.state.compile_state.pending_diagnostics.take() {
+            self.send_notification::<OnBuildPublishDiagnostics>(diagnostic);
+        }
+    }
+
     fn send_global_message(&self, global_msg: GlobalMessage) {
         let message_type = match global_msg.level {
             DiagnosticLevel::Ice | DiagnosticLevel::Error => MessageType::Error,
@@ -140,43 +323,86 @@ where
         self.send_notification::<OnBuildLogMessage>(LogMessageParams {
             r#type: message_type,
             task: Some(self.state.compile_state.task_id.clone()),
-            origin_id: self.params.origin_id().map(|id| OriginId::new(id.0)),
+            origin_id: self.message_origin_id(),
             message: global_msg.message,
         });
     }
 
     fn finish_compile(&mut self, msg: BuildFinished) {
+        self.flush_diagnostics();
+        self.finish_running_build_scripts();
+        self.state.compile_state.build_finished = true;
+        self.state.compile_state.fold_summary();
+        self.report_compile_targets_finish(StatusCode::Ok);
+        let message = if self.skips_fresh_compile() && self.state.compile_state.is_no_op().is_some()
+        {
+            "Skipped compilation, the build targets are up to date"
+        } else {
+            "Finished compilation"
+        };
+        self.report_task_finish(
+            self.state.compile_state.task_id.clone(),
+            StatusCode::Ok,
+            Some(message.to_string()),
+            None,
+        );
+        // Start execution task if compile finished with success.
+        if msg.success {
+            self.start_execution_task()
+        } else {
+            self.state.compile_state.build_failed = true;
+            self.state.task_state = TaskState::Compile
+        }
+    }
+
+    /// Cargo may fail before compiling any target (e.g. when resolving the dependencies
+    /// fails), then there is no `BuildFinished` message and the error is reported
+    /// only on stderr.
+    pub(super) fn finish_compile_before_build(&mut self) {
+        let pre_build_errors = &self.state.compile_state.pre_build_errors;
+        let message = if pre_build_errors.is_empty() {
+            "Cargo failed before compiling any target".to_string()
+        } else {
+            format!(
+                "Cargo failed before compiling any target:\n{}",
+                pre_build_errors.join("\n")
+            )
+        };
+        self.log_message(
+            MessageType::Error,
+            message,
+            Some(self.state.root_task_id.clone()),
+        );
+        self.report_compile_targets_finish(StatusCode::Error);
+        self.report_task_finish(
+            self.state.compile_state.task_id.clone(),
+            StatusCode::Error,
+            Some("Compilation failed".to_string()),
+            None,
+        );
+    }
+
+    fn report_compile_targets_finish(&self, status: StatusCode) {
+        let build_finish_time = now_millis();
         self.build_targets.iter().for_each(|id| {
             // We can unwrap here, as for all iterated ids, the target state was created.
             let compile_target_state = self.state.compile_state.target_states.get(id).unwrap();
             #[allow(deprecated)]
             let compile_report = TaskFinishData::compile_report(CompileReport {
                 target: id.clone(),
-                origin_id: self.params.origin_id().map(|id| Identifier::new(id.0)),
+                origin_id: self.task_origin_id(),
                 errors: self.state.compile_state.errors,
                 warnings: self.state.compile_state.warnings,
-                time: Some(get_current_time() - compile_target_state.start_time),
-                no_op: None,
+                time: Some(compile_target_state.compile_time(build_finish_time)),
+                no_op: self.state.compile_state.is_no_op(),
             });
             self.report_task_finish(
                 compile_target_state.task_id.clone(),
-                StatusCode::Ok,
+                status.clone(),
                 None,
                 Some(compile_report),
             );
         });
-        self.report_task_finish(
-            self.state.compile_state.task_id.clone(),
-            StatusCode::Ok,
-            Some("Finished compilation".to_string()),
-            None,
-        );
-        // Start execution task if compile finished with success.
-        if msg.success {
-            self.start_execution_task()
-        } else {
-            self.state.task_state = TaskState::Compile
-        }
     }
 
     fn start_execution_task(&self) {
@@ -199,6 +425,10 @@ where
         match test_type {
             TestType::Suite(event) => self.handle_test_suite(event),
             TestType::Test(event) => self.handle_single_test(event),
+            TestType::Bench(result) => {
+                let name = result.name.clone();
+                self.finish_test_task(&name, None, result.map_to_test_notification())
+            }
         }
     }
 
@@ -210,8 +440,10 @@ where
                     let new_id = generate_random_id();
                     task_id.id = new_id.clone();
                     test_state.suite_task_id.id = new_id;
-                    test_state.suite_task_progress.total = s.test_count as i64;
-                    test_state.suite_task_progress.progress = 0;
+                    test_state.suite_task_progress = SuiteTaskProgress {
+                        total: s.test_count as i64,
+                        ..SuiteTaskProgress::default()
+                    };
                     // Because the targets are sorted, we know which one is currently tested.
                     test_state.current_build_target = self.build_targets.pop();
                     let target = match test_state.current_build_target.clone() {
@@ -228,7 +460,16 @@ where
                     );
                 }
                 SuiteEvent::Ok(result) | SuiteEvent::Failed(result) => {
-                    test_state.suite_task_progress = SuiteTaskProgress::default();
+                    let started = std::mem::take(&mut test_state.suite_task_progress).started;
+                    // The final progress is reported even if no test was run,
+                    // so that the client does not wait for the tests of the suite.
+                    self.report_task_progress(
+                        task_id.clone(),
+                        None,
+                        Some(started),
+                        Some(started),
+                        Some("tests".to_string()),
+                    );
                     self.report_suite_finished(task_id, result)
                 }
             }
@@ -248,7 +489,7 @@ where
                 task_id,
                 StatusCode::Ok,
                 None,
-                Some(result.to_test_report(tested_target)),
+                Some(result.to_test_report(tested_target, self.task_origin_id())),
             )
         }
     }
@@ -257,6 +498,9 @@ where
         if let TaskState::Test(test_state) = &mut self.state.task_state {
             match event {
                 TestEvent::Started(started) => {
+                    let suite_progress = &mut test_state.suite_task_progress;
+                    suite_progress.started += 1;
+                    suite_progress.total = suite_progress.total.max(suite_progress.started);
                     let test_task_id = generate_task_id(&test_state.suite_task_id);
                     test_state
                         .single_test_task_ids
@@ -280,22 +524,27 @@ where
     }
 
     fn finish_single_test(&mut self, mut test_result: TestResult, status: TestStatus) {
+        let name = test_result.name.clone();
+        let stdout = test_result.handle_test_stdout();
+        self.finish_test_task(&name, stdout, test_result.map_to_test_notification(status));
+    }
+
+    /// Finishes the task of the started test (or benchmark) and reports the suite progress.
+    fn finish_test_task(&mut self, name: &str, stdout: Option<String>, test_finish: TestFinish) {
         if let TaskState::Test(test_state) = &mut self.state.task_state {
-            if let Some(id) = test_state.single_test_task_ids.remove(&test_result.name) {
+            if let Some(id) = test_state.single_test_task_ids.remove(name) {
                 let test_task_id = test_state.suite_task_id.clone();
                 let total = test_state.suite_task_progress.total;
                 let progress = test_state.suite_task_progress.progress + 1;
                 test_state.suite_task_progress.progress = progress;
-                if let Some(message) = test_result.handle_test_stdout() {
+                if let Some(message) = stdout {
                     self.log_message(MessageType::Log, message, Some(id.clone()));
                 }
                 self.report_task_finish(
                     id,
                     StatusCode::Ok,
                     None,
-                    Some(TaskFinishData::test_finish(
-                        test_result.map_to_test_notification(status),
-                    )),
+                    Some(TaskFinishData::test_finish(test_finish)),
                 );
                 self.report_task_progress(
                     test_task_id,
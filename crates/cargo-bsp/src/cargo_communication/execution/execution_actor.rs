@@ -1,6 +1,6 @@
 //! Handles messages from Cargo build/run/test commands, parsing them and preparing appropriate
 //! notifications and responses. Also handles information about the finish of
-//! Cargo command and the cancel request from the client.
+//! Cargo command, the cancel request from the client and the command timeout.
 //!
 //! The hierarchy of tasks:
 //! - for every request there is a root task with no `data-kind` and its subtask
@@ -20,7 +20,10 @@
 //! We assume that test suites are executed one after another in specific order,
 //! see [`cargo_types/test.rs`].
 
+use std::io;
 use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::time::Duration;
 
 use bsp_server::Message;
 use bsp_server::RequestId;
@@ -29,13 +32,14 @@ pub use cargo_metadata::diagnostic::{
     DiagnosticSpanMacroExpansion,
 };
 use cargo_metadata::Message as CargoMetadataMessage;
-use crossbeam_channel::{never, select, Receiver};
+use crossbeam_channel::{after, never, select, Receiver};
 use log::warn;
 
 use serde::Deserialize;
 
 use crate::cargo_communication::cargo_handle::CargoHandler;
-use crate::cargo_communication::cargo_types::event::{CargoMessage, Event};
+use crate::cargo_communication::cargo_types::create_command::CreateCommand;
+use crate::cargo_communication::cargo_types::event::{CancelReason, CargoMessage, Event};
 use crate::cargo_communication::cargo_types::params_target::ParamsTarget;
 use crate::cargo_communication::execution::execution_actor_state::{
     ExecutionActorState, TaskState,
@@ -43,7 +47,10 @@ use crate::cargo_communication::execution::execution_actor_state::{
 use crate::cargo_communication::execution::execution_types::cargo_result::CargoResult;
 use crate::cargo_communication::execution::execution_types::create_unit_graph_command::CreateUnitGraphCommand;
 use crate::cargo_communication::execution::execution_types::origin_id::WithOriginId;
-use crate::project_model::workspace::{ProjectWorkspace, SrcPathToTargetId};
+use crate::project_model::workspace::{
+    PackageTargetToTargetId, PackageToPrimaryTargetId, ProjectWorkspace, SrcPathToTargetId,
+};
+use crate::server::config::ServerSettings;
 use bsp4rs::bsp::{BuildTargetIdentifier, StatusCode};
 use bsp4rs::bsp::{CompileTask, MessageType, TaskStartData};
 use bsp4rs::Request;
@@ -58,19 +65,32 @@ where
     // sender for notifications and responses to main loop
     pub(super) sender: Box<dyn Fn(Message) + Send>,
     pub(super) cargo_handle: Option<C>,
+    /// Spawns the executable built by the run request (see [`Self::start_executable`]).
+    pub(super) spawn_executable: Option<Box<dyn Fn(&mut Command) -> io::Result<C> + Send>>,
     cancel_receiver: Receiver<Event>,
     pub(super) req_id: RequestId,
     pub(super) params: R::Params,
     pub(super) root_path: PathBuf,
     pub(super) build_targets: Vec<BuildTargetIdentifier>,
     pub(super) src_path_to_target_id: SrcPathToTargetId,
+    pub(super) package_target_to_target_id: PackageTargetToTargetId,
+    pub(super) package_to_primary_target_id: PackageToPrimaryTargetId,
     pub(super) state: ExecutionActorState,
+    /// Cargo command is cancelled if no message arrives within this time while compiling.
+    pub(super) command_timeout: Option<Duration>,
+    /// Diagnostics are published one by one instead of being batched per file.
+    pub(super) stream_diagnostics: bool,
+    /// The ANSI colored rendered diagnostics are attached to the published diagnostics.
+    pub(super) ansi_diagnostics: bool,
+    /// The compilation of the run and test requests with only fresh artifacts is reported
+    /// as skipped.
+    pub(super) skip_fresh_compile: bool,
 }
 
 impl<R, C> ExecutionActor<R, C>
 where
     R: Request,
-    R::Params: CreateUnitGraphCommand + ParamsTarget + WithOriginId,
+    R::Params: CreateUnitGraphCommand + CreateCommand + ParamsTarget + WithOriginId,
     R::Result: CargoResult,
     C: CargoHandler<CargoMessage>,
 {
@@ -82,11 +102,13 @@ where
         cargo_handle: C,
         cancel_receiver: Receiver<Event>,
         workspace: &ProjectWorkspace,
+        settings: &ServerSettings,
     ) -> ExecutionActor<R, C> {
         let build_targets = params.get_targets(workspace);
         ExecutionActor {
             sender,
             cargo_handle: Some(cargo_handle),
+            spawn_executable: None,
             cancel_receiver,
             req_id,
             state: ExecutionActorState::new::<R>(params.origin_id(), &build_targets),
@@ -94,25 +116,88 @@ where
             params,
             root_path: root_path.to_path_buf(),
             src_path_to_target_id: workspace.src_path_to_target_id.clone(),
+            package_target_to_target_id: workspace.get_package_target_to_target_id(),
+            package_to_primary_target_id: workspace.get_package_to_primary_target_id(),
+            command_timeout: settings.command_timeout,
+            stream_diagnostics: settings.stream_diagnostics,
+            ansi_diagnostics: settings.ansi_diagnostics,
+            skip_fresh_compile: settings.skip_fresh_compile,
         }
     }
 
     pub fn next_event(&self) -> Option<Event> {
         let cargo_chan = self.cargo_handle.as_ref().map(|cargo| cargo.receiver());
+        // The timer is created for each event, so it is reset with every message from Cargo.
+        let timeout = self.event_timeout().map(after).unwrap_or_else(never);
         select! {
             recv(self.cancel_receiver) -> msg => msg.ok(),
             recv(cargo_chan.unwrap_or(&never())) -> msg => match msg {
                 Ok(msg) => Some(Event::CargoEvent(msg)),
                 Err(_) => Some(Event::CargoFinish),
-            }
+            },
+            recv(timeout) -> _ => Some(Event::Timeout),
         }
     }
 
+    /// The timeout applies only until the compilation finishes, the executed binaries
+    /// and tests may legitimately print nothing for a long time.
+    fn event_timeout(&self) -> Option<Duration> {
+        let compiling = matches!(self.state.task_state, TaskState::Compile)
+            || !self.state.compile_state.build_finished;
+        self.command_timeout.filter(|_| compiling)
+    }
+
     fn handle_cargo_event(&mut self, message: CargoMessage) {
+        let waits_for_lock =
+            matches!(&message, CargoMessage::CargoStderr(msg) if is_file_lock_wait(msg));
+        self.update_build_lock_wait(waits_for_lock);
         // handle information and create notification based on that
         match message {
             CargoMessage::CargoStdout(msg) => self.deserialize_and_handle_cargo_information(msg),
-            CargoMessage::CargoStderr(msg) => self.log_message(MessageType::Error, msg, None),
+            CargoMessage::CargoStderr(msg) => {
+                self.state.compile_state.record_pre_build_stderr(&msg);
+                self.state.compile_state.record_summary_line(&msg);
+                let message_type = self.stderr_message_type(&msg);
+                self.log_message(message_type, msg, None)
+            }
+        }
+    }
+
+    /// Cargo prints nothing while it is blocked on the file lock, so the client is told
+    /// about the pause, and about its end with the next message from Cargo.
+    fn update_build_lock_wait(&mut self, waits_for_lock: bool) {
+        if self.state.compile_state.waiting_for_build_lock == waits_for_lock {
+            return;
+        }
+        self.state.compile_state.waiting_for_build_lock = waits_for_lock;
+        let message = match waits_for_lock {
+            true => "Waiting for build lock held by another process",
+            false => "Acquired build lock",
+        };
+        self.report_task_progress(
+            self.state.compile_state.task_id.clone(),
+            Some(message.to_string()),
+            None,
+            None,
+            None,
+        );
+    }
+
+    /// Only the requests executing the targets after the compilation skip it.
+    pub(super) fn skips_fresh_compile(&self) -> bool {
+        self.skip_fresh_compile && !matches!(self.state.task_state, TaskState::Compile)
+    }
+
+    /// Stderr is used by Cargo and build scripts also for messages that are not errors,
+    /// therefore only lines starting with `error` or following the failed build are
+    /// reported as errors. Warnings about the manifests are reported as warnings.
+    fn stderr_message_type(&self, msg: &str) -> MessageType {
+        if self.state.compile_state.build_failed || msg.trim_start().starts_with("error") {
+            MessageType::Error
+        } else if is_manifest_warning(msg) {
+            MessageType::Warning
+        } else {
+            MessageType::Log
         }
     }
 
@@ -122,14 +207,28 @@ where
 
         while let Some(event) = self.next_event() {
             match event {
-                Event::Cancel => {
-                    self.cancel();
+                Event::Cancel(reason) => {
+                    self.cancel(reason);
                     return;
                 }
-                Event::CargoFinish => {
-                    self.finish_request();
+                Event::Timeout => {
+                    self.timeout();
                     return;
                 }
+                Event::CargoFinish => {
+                    let command_result = self.cargo_handle.take().unwrap().join();
+                    match self.start_executable(&command_result) {
+                        Some(Ok(cargo_handle)) => self.cargo_handle = Some(cargo_handle),
+                        Some(Err(e)) => {
+                            self.finish_request(Err(e));
+                            return;
+                        }
+                        None => {
+                            self.finish_request(command_result);
+                            return;
+                        }
+                    }
+                }
                 Event::CargoEvent(message) => {
                     self.handle_cargo_event(message);
                 }
@@ -164,11 +263,59 @@ where
         self.handle_cargo_information(message);
     }
 
-    fn finish_request(&mut self) {
-        let command_result = self.cargo_handle.take().unwrap().join();
+    /// The binary of the run request is only built by Cargo, its executable is started
+    /// once the build succeeds and its output is handled in the same way as the output of Cargo.
+    /// `None` if there is nothing to start.
+    fn start_executable(&mut self, build_result: &io::Result<ExitStatus>) -> Option<io::Result<C>> {
+        let run_state = match &mut self.state.task_state {
+            TaskState::Run(run_state) if !run_state.executable_started => run_state,
+            _ => return None,
+        };
+        if !matches!(build_result, Ok(exit_status) if exit_status.success()) {
+            return None;
+        }
+        run_state.executable_started = true;
+        let executable = match run_state.executables.as_slice() {
+            [executable] => executable.clone(),
+            [] => {
+                return Some(Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "The requested target produced no executable to run",
+                )))
+            }
+            executables => {
+                return Some(Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!(
+                        "The requested targets produced {} executables, only one can be run",
+                        executables.len()
+                    ),
+                )))
+            }
+        };
+        let mut command = self
+            .params
+            .create_executable_command(&self.root_path, &executable)?;
+        let spawn_executable = self.spawn_executable.as_ref()?;
+        Some(spawn_executable(&mut command))
+    }
 
-        self.finish_execution_task();
-        self.report_task_finish(self.state.root_task_id.clone(), StatusCode::Ok, None, None);
+    fn finish_request(&mut self, command_result: io::Result<ExitStatus>) {
+        // Cargo might have finished without the `BuildFinished` message.
+        self.flush_diagnostics();
+        let command_failed = matches!(&command_result, Ok(status) if !status.success());
+        if command_failed && !self.state.compile_state.build_finished {
+            self.finish_compile_before_build();
+            self.report_task_finish(
+                self.state.root_task_id.clone(),
+                StatusCode::Error,
+                None,
+                None,
+            );
+        } else {
+            self.finish_execution_task();
+            self.report_task_finish(self.state.root_task_id.clone(), StatusCode::Ok, None, None);
+        }
         self.send_response(command_result);
     }
 
@@ -190,11 +337,18 @@ where
         }
     }
 
+    pub fn timeout(&mut self) {
+        if let Some(cargo_handle) = self.cargo_handle.take() {
+            cargo_handle.cancel();
+            self.send_timeout_response();
+        }
+    }
+
     // TODO: Cancel should cancel all started tasks, not only the root task.
-    pub fn cancel(&mut self) {
+    pub fn cancel(&mut self, reason: CancelReason) {
         if let Some(cargo_handle) = self.cargo_handle.take() {
             cargo_handle.cancel();
-            self.send_cancel_response();
+            self.send_cancel_response(reason);
         } else {
             warn!(
                 "Tried to cancel request {} that was already finished",
@@ -204,6 +358,21 @@ where
     }
 }
 
+/// E.g. `Blocking waiting for file lock on build directory`, Cargo waits in the same way
+/// for the lock of the package cache.
+fn is_file_lock_wait(msg: &str) -> bool {
+    msg.trim_start()
+        .starts_with("Blocking waiting for file lock")
+}
+
+/// Cargo reports the problems with the manifests (e.g. unused manifest keys) as
+/// `warning: <path to Cargo.toml>: <message>` lines.
+fn is_manifest_warning(msg: &str) -> bool {
+    msg.trim_start()
+        .strip_prefix("warning: ")
+        .is_some_and(|rest| rest.contains("Cargo.toml: "))
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -225,12 +394,14 @@ pub mod tests {
     const TEST_REQ_ID: &str = "test_req_id";
     const RANDOM_TASK_ID: &str = "random_task_id";
     const TIMESTAMP: &str = "timestamp";
+    const ELAPSED_TIME: &str = "elapsed_time";
     const TEST_ARGUMENTS: &str = "test_arguments";
     const TEST_ROOT_PATH: &str = "/test_root_path";
     const TEST_PACKAGE: &str = "test_package";
     const BIN_KIND: &str = "bin";
     const LIB_KIND: &str = "lib";
     const TEST_SRC_PATH: &str = "test_src_path";
+    const TEST_SRC_PATH2: &str = "test_src_path2";
 
     enum TestCase {
         OneTarget,
@@ -256,7 +427,7 @@ pub mod tests {
     ) -> TestEndpoints<R>
     where
         R: Request,
-        R::Params: CreateUnitGraphCommand + ParamsTarget + WithOriginId,
+        R::Params: CreateUnitGraphCommand + CreateCommand + ParamsTarget + WithOriginId,
         R::Result: CargoResult,
     {
         let (sender_to_main, receiver_from_actor) = unbounded::<Message>();
@@ -275,6 +446,7 @@ pub mod tests {
                 cargo_handle,
                 cancel_receiver,
                 &workspace,
+                &ServerSettings::default(),
             ),
             receiver_from_actor,
             _cancel_sender: cancel_sender,
@@ -289,10 +461,13 @@ pub mod tests {
     }
 
     fn test_simple_workspace() -> ProjectWorkspace {
-        let packages = vec![test_package(TEST_PACKAGE)];
-        let test_target_id = test_target_id(TEST_TARGET);
+        let packages = vec![test_package(
+            TEST_PACKAGE,
+            vec![test_target(TEST_TARGET, BIN_KIND)],
+        )];
+        let test_target_id = test_target_id(TEST_PACKAGE, TEST_TARGET, BIN_KIND);
 
-        let target_id_to_package_name =
+        let target_id_to_package_id =
             HashMap::from([(test_target_id.clone(), TEST_PACKAGE.to_string())]);
         let target_id_to_target_data =
             HashMap::from([(test_target_id.clone(), test_target(TEST_TARGET, BIN_KIND))]);
@@ -300,18 +475,22 @@ pub mod tests {
             HashMap::from([(Utf8PathBuf::from(TEST_SRC_PATH), test_target_id)]);
         ProjectWorkspace {
             packages,
-            target_id_to_package_name,
+            target_id_to_package_id,
             target_id_to_target_data,
             src_path_to_target_id,
+            ..ProjectWorkspace::default()
         }
     }
 
     fn test_complex_workspace() -> ProjectWorkspace {
         let mut workspace = test_simple_workspace();
-        let test_target_id = test_target_id(TEST_TARGET2);
+        let test_target_id = test_target_id(TEST_PACKAGE, TEST_TARGET2, LIB_KIND);
         workspace
-            .target_id_to_package_name
+            .target_id_to_package_id
             .insert(test_target_id.clone(), TEST_PACKAGE.to_string());
+        workspace.packages[0]
+            .targets
+            .push(test_target(TEST_TARGET2, LIB_KIND));
         workspace
             .target_id_to_target_data
             .insert(test_target_id.clone(), test_target(TEST_TARGET2, LIB_KIND));
@@ -329,9 +508,9 @@ pub mod tests {
         use std::io;
 
         fn default_compile_params(test_case: TestCase) -> CompileParams {
-            let mut targets = vec![test_target_id(TEST_TARGET)];
+            let mut targets = vec![test_target_id(TEST_PACKAGE, TEST_TARGET, BIN_KIND)];
             if let TestCase::MultipleTargets = test_case {
-                targets.push(test_target_id(TEST_TARGET2));
+                targets.push(test_target_id(TEST_PACKAGE, TEST_TARGET2, LIB_KIND));
             }
             CompileParams {
                 targets,
@@ -359,6 +538,35 @@ pub mod tests {
             )
         }
 
+        #[test]
+        fn settings_applied_to_actor() {
+            let (_cancel_sender, cancel_receiver) = unbounded::<Event>();
+            let settings = ServerSettings {
+                command_timeout: Some(Duration::from_secs(1)),
+                stream_diagnostics: true,
+                ansi_diagnostics: true,
+                skip_fresh_compile: true,
+                ..ServerSettings::default()
+            };
+
+            let req_actor: ExecutionActor<BuildTargetCompile, MockCargoHandler<CargoMessage>> =
+                ExecutionActor::new(
+                    Box::new(|_| {}),
+                    TEST_REQ_ID.to_string().into(),
+                    default_compile_params(TestCase::OneTarget),
+                    Path::new(TEST_ROOT_PATH),
+                    MockCargoHandler::new(),
+                    cancel_receiver,
+                    &test_simple_workspace(),
+                    &settings,
+                );
+
+            assert_eq!(req_actor.command_timeout, Some(Duration::from_secs(1)));
+            assert!(req_actor.stream_diagnostics);
+            assert!(req_actor.ansi_diagnostics);
+            assert!(req_actor.skip_fresh_compile);
+        }
+
         mod unit_graph_tests {
             use super::*;
             use crate::cargo_communication::execution::execution_types::unit_graph::UnitGraph;
@@ -524,7 +732,7 @@ pub mod tests {
                   "params": {
                     "data": {
                       "target": {
-                        "uri": "test_target"
+                        "uri": "cargo:///test_package?name=test_target&kind=bin"
                       }
                     },
                     "dataKind": "compile-task",
@@ -546,7 +754,7 @@ pub mod tests {
                   "params": {
                     "data": {
                       "target": {
-                        "uri": "test_target2"
+                        "uri": "cargo:///test_package?name=test_target2&kind=lib"
                       }
                     },
                     "dataKind": "compile-task",
@@ -585,6 +793,175 @@ pub mod tests {
             no_more_msg(receiver_from_actor);
         }
 
+        #[test]
+        #[cfg(unix)]
+        fn compile_failed_before_build() {
+            use std::os::unix::process::ExitStatusExt;
+            use std::process::ExitStatus;
+
+            const RESOLUTION_ERROR: &str =
+                "error: failed to select a version for the requirement `foo = \"^99\"`";
+            const RESOLUTION_NOTE: &str = "candidate versions found which didn't match: 1.0.0";
+
+            let (sender_to_actor, receiver_from_cargo) = unbounded::<CargoMessage>();
+            let mut mock_cargo_handle = MockCargoHandler::new();
+            // Cargo exits with code 101 on errors.
+            mock_cargo_handle
+                .expect_join()
+                .returning(|| Ok(ExitStatus::from_raw(101 << 8)));
+            mock_cargo_handle
+                .expect_receiver()
+                .return_const(receiver_from_cargo);
+            let TestEndpoints {
+                req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetCompile>(
+                mock_cargo_handle,
+                default_compile_params(TestCase::OneTarget),
+                TestCase::OneTarget,
+            );
+
+            let _ = jod_thread::Builder::new()
+                .spawn(move || req_actor.run())
+                .expect("failed to spawn thread")
+                .detach();
+
+            // No `BuildFinished` message, Cargo fails while resolving the dependencies.
+            sender_to_actor
+                .send(CargoMessage::CargoStderr(RESOLUTION_ERROR.to_string()))
+                .unwrap();
+            sender_to_actor
+                .send(CargoMessage::CargoStderr(RESOLUTION_NOTE.to_string()))
+                .unwrap();
+            drop(sender_to_actor);
+
+            // Compilation tasks started and stderr lines logged.
+            for _ in 0..4 {
+                let _ = receiver_from_actor.recv().unwrap();
+            }
+
+            let mut settings = Settings::clone_current();
+            settings.add_redaction(".params.eventTime", TIMESTAMP);
+            settings.add_redaction(".params.taskId.id", RANDOM_TASK_ID);
+            settings.add_redaction(".params.data.time", 0);
+            settings.bind(|| {
+                assert_json_snapshot!(receiver_from_actor.recv().unwrap(), @r#"
+                {
+                  "method": "build/logMessage",
+                  "params": {
+                    "message": "Cargo failed before compiling any target:\nerror: failed to select a version for the requirement `foo = \"^99\"`\ncandidate versions found which didn't match: 1.0.0",
+                    "originId": "test_origin_id",
+                    "task": {
+                      "id": "test_origin_id"
+                    },
+                    "type": 1
+                  }
+                }
+                "#);
+                assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
+                    ".params.taskId.parents" => format!("[{RANDOM_TASK_ID}]"),
+                }, @r#"
+                {
+                  "method": "build/taskFinish",
+                  "params": {
+                    "data": {
+                      "errors": 0,
+                      "originId": "test_origin_id",
+                      "target": {
+                        "uri": "cargo:///test_package?name=test_target&kind=bin"
+                      },
+                      "time": 0,
+                      "warnings": 0
+                    },
+                    "dataKind": "compile-report",
+                    "eventTime": "timestamp",
+                    "originId": "test_origin_id",
+                    "status": 2,
+                    "taskId": {
+                      "id": "random_task_id",
+                      "parents": "[random_task_id]"
+                    }
+                  }
+                }
+                "#);
+                assert_json_snapshot!(receiver_from_actor.recv().unwrap(), @r#"
+                {
+                  "method": "build/taskFinish",
+                  "params": {
+                    "eventTime": "timestamp",
+                    "message": "Compilation failed",
+                    "originId": "test_origin_id",
+                    "status": 2,
+                    "taskId": {
+                      "id": "random_task_id",
+                      "parents": [
+                        "test_origin_id"
+                      ]
+                    }
+                  }
+                }
+                "#);
+                assert_json_snapshot!(receiver_from_actor.recv().unwrap(), @r#"
+                {
+                  "method": "build/taskFinish",
+                  "params": {
+                    "eventTime": "timestamp",
+                    "originId": "test_origin_id",
+                    "status": 2,
+                    "taskId": {
+                      "id": "random_task_id"
+                    }
+                  }
+                }
+                "#);
+            });
+            match receiver_from_actor.recv().unwrap() {
+                Message::Response(resp) => {
+                    assert_eq!(resp.result.unwrap()["statusCode"], 2);
+                }
+                msg => panic!("expected a response, got: {:?}", msg),
+            }
+            no_more_msg(receiver_from_actor);
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn failed_compile_result_has_errors_count() {
+            use std::os::unix::process::ExitStatusExt;
+            use std::process::ExitStatus;
+
+            let TestEndpoints {
+                mut req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetCompile>(
+                MockCargoHandler::new(),
+                default_compile_params(TestCase::OneTarget),
+                TestCase::OneTarget,
+            );
+            req_actor.state.compile_state.errors = 2;
+
+            req_actor.send_response(Ok(ExitStatus::from_raw(101 << 8)));
+
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap(), @r#"
+            {
+              "id": "test_req_id",
+              "result": {
+                "data": {
+                  "errors": 2
+                },
+                "dataKind": "cargo",
+                "originId": "test_origin_id",
+                "statusCode": 2
+              }
+            }
+            "#);
+            no_more_msg(receiver_from_actor);
+        }
+
         #[test]
         fn cancel_with_cargo_handle() {
             let mut mock_cargo_handle = MockCargoHandler::new();
@@ -601,7 +978,7 @@ pub mod tests {
                 TestCase::OneTarget,
             );
 
-            req_actor.cancel();
+            req_actor.cancel(CancelReason::Client);
 
             assert_json_snapshot!(receiver_from_actor.recv().unwrap(),
             {
@@ -619,12 +996,57 @@ pub mod tests {
               }
             }
             "#);
-            assert_json_snapshot!(receiver_from_actor.recv().unwrap(), @r#"
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap(),
+            {
+                ".error.data.elapsedMs" => ELAPSED_TIME,
+            }, @r#"
+            {
+              "id": "test_req_id",
+              "error": {
+                "code": -32800,
+                "message": "canceled by client",
+                "data": {
+                  "reason": "client",
+                  "elapsedMs": "elapsed_time"
+                }
+              }
+            }
+            "#);
+            no_more_msg(receiver_from_actor);
+        }
+
+        #[test]
+        fn cancel_on_shutdown() {
+            let mut mock_cargo_handle = MockCargoHandler::new();
+            mock_cargo_handle.expect_cancel().return_const(());
+
+            let TestEndpoints {
+                mut req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetCompile>(
+                mock_cargo_handle,
+                default_compile_params(TestCase::OneTarget),
+                TestCase::OneTarget,
+            );
+
+            req_actor.cancel(CancelReason::Shutdown);
+
+            let _ = receiver_from_actor.recv().unwrap(); // main task notification
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap(),
+            {
+                ".error.data.elapsedMs" => ELAPSED_TIME,
+            }, @r#"
             {
               "id": "test_req_id",
               "error": {
                 "code": -32800,
-                "message": "canceled by client"
+                "message": "canceled due to server shutdown",
+                "data": {
+                  "reason": "shutdown",
+                  "elapsedMs": "elapsed_time"
+                }
               }
             }
             "#);
@@ -649,43 +1071,177 @@ pub mod tests {
                 TestCase::OneTarget,
             );
 
-            req_actor.cancel();
-            req_actor.cancel();
-            req_actor.cancel();
+            req_actor.cancel(CancelReason::Client);
+            req_actor.cancel(CancelReason::Client);
+            req_actor.cancel(CancelReason::Client);
 
             let _ = receiver_from_actor.recv().unwrap(); // main task notification
             let _ = receiver_from_actor.recv().unwrap(); // response
             no_more_msg(receiver_from_actor);
         }
 
-        mod cargo_compile_messages_tests {
-            use super::*;
-            use bsp4rs::bsp::BuildTargetCompile;
-            use cargo_metadata::diagnostic::{DiagnosticBuilder, DiagnosticSpanBuilder};
-            use cargo_metadata::Message::{
-                BuildFinished as BuildFinishedEnum, BuildScriptExecuted, CompilerArtifact,
-                CompilerMessage as CompilerMessageEnum,
-            };
-            use cargo_metadata::{
-                Artifact, ArtifactBuilder, ArtifactDebuginfo, ArtifactProfile,
-                ArtifactProfileBuilder, BuildScript, BuildScriptBuilder, CompilerMessage,
-                CompilerMessageBuilder, PackageId, Target, TargetBuilder,
-            };
-
-            const TEST_KIND: &str = "test_kind";
-            const TEST_MESSAGE: &str = "test_message";
-            const TEST_CRATE_TYPE: &str = "test_crate_type";
-            const TEST_OPT_LEVEL: &str = "test_opt_level";
-            const TEST_PKG_ID: &str = "test_pkg_id";
-            const TEST_MANIFEST_PATH: &str = "test_manifest_path";
-            const TEST_FEATURE: &str = "test_feature";
-            const TEST_EXECUTABLE: &str = "test_executable";
-            const TEST_FILENAME: &str = "test_filename";
-            const TEST_LINKED_LIB: &str = "test_linked_lib";
-            const TEST_LINKED_PATH: &str = "test_linked_path";
-            const TEST_CFG: &str = "test_cfg";
-            const TEST_ENV: &str = "test_env";
-            const TEST_OUT_DIR: &str = "test_out_dir";
+        #[test]
+        fn timeout_without_cargo_messages() {
+            let (_sender_to_actor, receiver_from_cargo) = unbounded::<CargoMessage>();
+            let mut mock_cargo_handle = MockCargoHandler::new();
+            mock_cargo_handle
+                .expect_receiver()
+                .return_const(receiver_from_cargo);
+            mock_cargo_handle.expect_cancel().times(1).return_const(());
+
+            let TestEndpoints {
+                mut req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetCompile>(
+                mock_cargo_handle,
+                default_compile_params(TestCase::OneTarget),
+                TestCase::OneTarget,
+            );
+            req_actor.command_timeout = Some(Duration::from_millis(10));
+
+            req_actor.run();
+
+            let _ = receiver_from_actor.recv().unwrap(); // compile task start
+            let _ = receiver_from_actor.recv().unwrap(); // target compile task start
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap(),
+            {
+                ".params.eventTime" => TIMESTAMP,
+            }, @r#"
+            {
+              "method": "build/taskFinish",
+              "params": {
+                "eventTime": "timestamp",
+                "originId": "test_origin_id",
+                "status": 2,
+                "taskId": {
+                  "id": "test_origin_id"
+                }
+              }
+            }
+            "#);
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap(),
+            {
+                ".error.data.elapsedMs" => ELAPSED_TIME,
+            }, @r#"
+            {
+              "id": "test_req_id",
+              "error": {
+                "code": -32603,
+                "message": "Cargo command produced no output and did not finish within 10ms",
+                "data": {
+                  "reason": "timeout",
+                  "elapsedMs": "elapsed_time"
+                }
+              }
+            }
+            "#);
+            no_more_msg(receiver_from_actor);
+        }
+
+        #[test]
+        fn timeout_reset_by_cargo_messages() {
+            // All the messages are already in the mocked receiver, each of them is handled
+            // before the timer of the next event expires, so the command is not cancelled.
+            let (sender_to_actor, receiver_from_cargo) = unbounded::<CargoMessage>();
+            for _ in 0..4 {
+                sender_to_actor
+                    .send(CargoMessage::CargoStderr("test_stderr".into()))
+                    .unwrap();
+            }
+            drop(sender_to_actor);
+            let mut mock_cargo_handle = MockCargoHandler::new();
+            mock_cargo_handle
+                .expect_receiver()
+                .return_const(receiver_from_cargo);
+            mock_cargo_handle
+                .expect_join()
+                .returning(|| Err(io::Error::from(io::ErrorKind::Other)));
+            mock_cargo_handle.expect_cancel().never();
+
+            let TestEndpoints {
+                mut req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetCompile>(
+                mock_cargo_handle,
+                default_compile_params(TestCase::OneTarget),
+                TestCase::OneTarget,
+            );
+            req_actor.command_timeout = Some(Duration::from_secs(60));
+
+            req_actor.run();
+
+            let messages: Vec<Message> = receiver_from_actor.try_iter().collect();
+            let log_messages = messages
+                .iter()
+                .filter(
+                    |msg| matches!(msg, Message::Notification(n) if n.method == "build/logMessage"),
+                )
+                .count();
+            assert_eq!(log_messages, 4);
+            assert_json_snapshot!(messages.last().unwrap(), @r#"
+            {
+              "id": "test_req_id",
+              "error": {
+                "code": -32603,
+                "message": "other error"
+              }
+            }
+            "#);
+        }
+
+        #[test]
+        fn timeout_while_compiling() {
+            let TestEndpoints {
+                mut req_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetCompile>(
+                MockCargoHandler::new(),
+                default_compile_params(TestCase::OneTarget),
+                TestCase::OneTarget,
+            );
+            req_actor.command_timeout = Some(Duration::from_millis(10));
+
+            assert_eq!(req_actor.event_timeout(), Some(Duration::from_millis(10)));
+            req_actor.state.compile_state.build_finished = true;
+            assert_eq!(req_actor.event_timeout(), Some(Duration::from_millis(10)));
+        }
+
+        mod cargo_compile_messages_tests {
+            use super::*;
+            use bsp4rs::bsp::BuildTargetCompile;
+            use cargo_metadata::diagnostic::{DiagnosticBuilder, DiagnosticSpanBuilder};
+            use cargo_metadata::Message::{
+                BuildFinished as BuildFinishedEnum, BuildScriptExecuted, CompilerArtifact,
+                CompilerMessage as CompilerMessageEnum,
+            };
+            use cargo_metadata::{
+                Artifact, ArtifactBuilder, ArtifactDebuginfo, ArtifactProfile,
+                ArtifactProfileBuilder, BuildScript, BuildScriptBuilder, CompilerMessage,
+                CompilerMessageBuilder, PackageId, Target, TargetBuilder,
+            };
+
+            const TEST_KIND: &str = "test_kind";
+            const TEST_MESSAGE: &str = "test_message";
+            const TEST_MANIFEST_WARNING: &str =
+                "/test_root_path/Cargo.toml: unused manifest key: package.test_key";
+            const TEST_CRATE_TYPE: &str = "test_crate_type";
+            const TEST_OPT_LEVEL: &str = "test_opt_level";
+            const TEST_PKG_ID: &str = "test_pkg_id";
+            const TEST_MANIFEST_PATH: &str = "test_manifest_path";
+            const TEST_FEATURE: &str = "test_feature";
+            const TEST_EXECUTABLE: &str = "test_executable";
+            const TEST_FILENAME: &str = "test_filename";
+            const TEST_FILENAME2: &str = "test_filename2";
+            const TEST_LINKED_LIB: &str = "test_linked_lib";
+            const TEST_LINKED_PATH: &str = "test_linked_path";
+            const TEST_CFG: &str = "test_cfg";
+            const TEST_ENV: &str = "test_env";
+            const TEST_OUT_DIR: &str = "test_out_dir";
 
             #[test]
             fn compiler_artifact() {
@@ -727,6 +1283,106 @@ pub mod tests {
                 no_more_msg(receiver_from_actor);
             }
 
+            #[test]
+            fn first_artifact_time_recorded_for_exact_target() {
+                let TestEndpoints {
+                    mut req_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetCompile>(
+                    MockCargoHandler::new(),
+                    default_compile_params(TestCase::OneTarget),
+                    TestCase::OneTarget,
+                );
+                let target_id = test_target_id(TEST_PACKAGE, TEST_TARGET, BIN_KIND);
+                req_actor.package_target_to_target_id.insert(
+                    (
+                        TEST_PKG_ID.to_string(),
+                        TEST_TARGET.to_string(),
+                        vec![TEST_KIND.to_string()],
+                    ),
+                    target_id.clone(),
+                );
+
+                req_actor.handle_cargo_information(CompilerArtifact(default_compiler_artifact()));
+
+                let target_state = &req_actor.state.compile_state.target_states[&target_id];
+                assert!(target_state.first_artifact_time.is_some());
+            }
+
+            #[test]
+            fn first_artifact_time_not_recorded_for_build_script() {
+                let TestEndpoints {
+                    mut req_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetCompile>(
+                    MockCargoHandler::new(),
+                    default_compile_params(TestCase::OneTarget),
+                    TestCase::OneTarget,
+                );
+                let target_id = test_target_id(TEST_PACKAGE, TEST_TARGET, BIN_KIND);
+                req_actor
+                    .package_to_primary_target_id
+                    .insert(TEST_PKG_ID.to_string(), target_id.clone());
+                let mut build_script_artifact = default_compiler_artifact();
+                build_script_artifact.target.name = "build-script-build".to_string();
+                build_script_artifact.target.kind = vec!["custom-build".to_string()];
+                build_script_artifact.target.src_path =
+                    format!("{}/build.rs", TEST_ROOT_PATH).into();
+
+                req_actor.handle_cargo_information(CompilerArtifact(build_script_artifact));
+
+                let target_state = &req_actor.state.compile_state.target_states[&target_id];
+                assert!(target_state.first_artifact_time.is_none());
+            }
+
+            #[test]
+            #[cfg(unix)]
+            fn doc_index_files_in_result() {
+                use bsp4rs::cargo::{BuildTargetDoc, DocParams};
+                use std::os::unix::process::ExitStatusExt;
+                use std::process::ExitStatus;
+
+                const INDEX_FILE: &str = "/test_root_path/target/doc/test_target/index.html";
+
+                let TestEndpoints {
+                    mut req_actor,
+                    receiver_from_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetDoc>(
+                    MockCargoHandler::new(),
+                    DocParams {
+                        targets: default_compile_params(TestCase::OneTarget).targets,
+                        origin_id: Some(TEST_ORIGIN_ID.into()),
+                        arguments: None,
+                    },
+                    TestCase::OneTarget,
+                );
+                let mut doc_artifact = default_compiler_artifact();
+                doc_artifact.filenames = vec![INDEX_FILE.into()];
+                doc_artifact.executable = None;
+
+                req_actor.handle_cargo_information(CompilerArtifact(doc_artifact));
+                let _ = receiver_from_actor.recv().unwrap(); // compilation step progress
+                req_actor.send_response(Ok(ExitStatus::from_raw(0)));
+
+                assert_json_snapshot!(receiver_from_actor.recv().unwrap(), @r#"
+                {
+                  "id": "test_req_id",
+                  "result": {
+                    "indexFiles": [
+                      "file:///test_root_path/target/doc/test_target/index.html"
+                    ],
+                    "originId": "test_origin_id",
+                    "statusCode": 1
+                  }
+                }
+                "#);
+                no_more_msg(receiver_from_actor);
+            }
+
             #[test]
             fn build_script_out() {
                 let TestEndpoints {
@@ -777,6 +1433,7 @@ pub mod tests {
                     default_compile_params(TestCase::OneTarget),
                     TestCase::OneTarget,
                 );
+                req_actor.stream_diagnostics = true;
 
                 req_actor.handle_cargo_information(CompilerMessageEnum(default_compiler_message(
                     DiagnosticLevel::Error,
@@ -789,7 +1446,7 @@ pub mod tests {
                   "method": "build/publishDiagnostics",
                   "params": {
                     "buildTarget": {
-                      "uri": "test_target"
+                      "uri": "cargo:///test_package?name=test_target&kind=bin"
                     },
                     "diagnostics": [
                       {
@@ -820,7 +1477,9 @@ pub mod tests {
             }
 
             #[test]
-            fn build_finished_simple() {
+            fn compiler_message_with_ansi_rendered_diagnostic() {
+                const ANSI_RENDERED: &str =
+                    "\u{1b}[0m\u{1b}[1m\u{1b}[38;5;9merror\u{1b}[0m\u{1b}[0m\u{1b}[1m: test_message\u{1b}[0m\n";
                 let TestEndpoints {
                     mut req_actor,
                     receiver_from_actor,
@@ -828,97 +1487,29 @@ pub mod tests {
                     ..
                 } = default_req_actor::<BuildTargetCompile>(
                     MockCargoHandler::new(),
-                    default_compile_params(TestCase::MultipleTargets),
-                    TestCase::MultipleTargets,
+                    default_compile_params(TestCase::OneTarget),
+                    TestCase::OneTarget,
                 );
-
-                req_actor.handle_cargo_information(BuildFinishedEnum(default_build_finished()));
-
-                assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
-                    ".params.eventTime" => TIMESTAMP,
-                    ".params.taskId.id" => RANDOM_TASK_ID,
-                    ".params.data.time" => 0,
-                    ".params.taskId.parents" => format!("[{RANDOM_TASK_ID}]"),
-                },@r#"
-                {
-                  "method": "build/taskFinish",
-                  "params": {
-                    "data": {
-                      "errors": 0,
-                      "originId": "test_origin_id",
-                      "target": {
-                        "uri": "test_target"
-                      },
-                      "time": 0,
-                      "warnings": 0
-                    },
-                    "dataKind": "compile-report",
-                    "eventTime": "timestamp",
-                    "originId": "test_origin_id",
-                    "status": 1,
-                    "taskId": {
-                      "id": "random_task_id",
-                      "parents": "[random_task_id]"
-                    }
-                  }
-                }
-                "#);
-                assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
-                    ".params.eventTime" => TIMESTAMP,
-                    ".params.taskId.id" => RANDOM_TASK_ID,
-                    ".params.data.time" => 0,
-                    ".params.taskId.parents" => format!("[{RANDOM_TASK_ID}]"),
-                },@r#"
-                {
-                  "method": "build/taskFinish",
-                  "params": {
-                    "data": {
-                      "errors": 0,
-                      "originId": "test_origin_id",
-                      "target": {
-                        "uri": "test_target2"
-                      },
-                      "time": 0,
-                      "warnings": 0
-                    },
-                    "dataKind": "compile-report",
-                    "eventTime": "timestamp",
-                    "originId": "test_origin_id",
-                    "status": 1,
-                    "taskId": {
-                      "id": "random_task_id",
-                      "parents": "[random_task_id]"
-                    }
-                  }
-                }
-                "#);
-                assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
-                    ".params.eventTime" => TIMESTAMP,
-                    ".params.taskId.id" => RANDOM_TASK_ID,
-                },@r#"
-                {
-                  "method": "build/taskFinish",
-                  "params": {
-                    "eventTime": "timestamp",
-                    "message": "Finished compilation",
-                    "originId": "test_origin_id",
-                    "status": 1,
-                    "taskId": {
-                      "id": "random_task_id",
-                      "parents": [
-                        "test_origin_id"
-                      ]
+                req_actor.stream_diagnostics = true;
+                req_actor.ansi_diagnostics = true;
+                let mut compiler_message = default_compiler_message(DiagnosticLevel::Error);
+                compiler_message.message.rendered = Some(ANSI_RENDERED.to_string());
+
+                req_actor.handle_cargo_information(CompilerMessageEnum(compiler_message));
+
+                match receiver_from_actor.recv().unwrap() {
+                    Message::Notification(n) => {
+                        let diagnostic = &n.params["diagnostics"][0];
+                        assert_eq!(diagnostic["dataKind"], "rendered");
+                        assert_eq!(diagnostic["data"]["rendered"], ANSI_RENDERED);
                     }
-                  }
+                    _ => panic!("Expected notification"),
                 }
-                "#);
                 no_more_msg(receiver_from_actor);
             }
 
             #[test]
-            fn build_finished_with_complex_compile_report() {
-                // Checks if server counts warnings and error and produces a correct compile report.
-
+            fn manifest_warning_without_span() {
                 let TestEndpoints {
                     mut req_actor,
                     receiver_from_actor,
@@ -929,6 +1520,166 @@ pub mod tests {
                     default_compile_params(TestCase::OneTarget),
                     TestCase::OneTarget,
                 );
+                let mut manifest_warning = default_compiler_message(DiagnosticLevel::Warning);
+                manifest_warning.package_id.repr = "unknown_pkg_id".into();
+                manifest_warning.target.src_path = "unknown_src_path".into();
+                manifest_warning.message.message = TEST_MANIFEST_WARNING.to_string();
+                manifest_warning.message.spans = vec![];
+                let json_message =
+                    serde_json::to_string(&CompilerMessageEnum(manifest_warning)).unwrap();
+
+                // The message is deserialized and handled as a diagnostic, not as a text line.
+                req_actor.handle_cargo_event(CargoMessage::CargoStdout(json_message));
+
+                assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
+                    ".params.task.id" => RANDOM_TASK_ID,
+                }, @r#"
+                {
+                  "method": "build/logMessage",
+                  "params": {
+                    "message": "/test_root_path/Cargo.toml: unused manifest key: package.test_key",
+                    "originId": "test_origin_id",
+                    "task": {
+                      "id": "random_task_id",
+                      "parents": [
+                        "test_origin_id"
+                      ]
+                    },
+                    "type": 2
+                  }
+                }
+                "#);
+                no_more_msg(receiver_from_actor);
+            }
+
+            #[test]
+            fn compiler_messages_from_independent_targets() {
+                // With `--keep-going`, Cargo reports errors from all failing targets.
+                let TestEndpoints {
+                    mut req_actor,
+                    receiver_from_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetCompile>(
+                    MockCargoHandler::new(),
+                    default_compile_params(TestCase::MultipleTargets),
+                    TestCase::MultipleTargets,
+                );
+                req_actor.stream_diagnostics = true;
+                req_actor.src_path_to_target_id.insert(
+                    Utf8PathBuf::from(TEST_SRC_PATH),
+                    test_target_id(TEST_PACKAGE, TEST_TARGET, BIN_KIND),
+                );
+                req_actor.src_path_to_target_id.insert(
+                    Utf8PathBuf::from(TEST_SRC_PATH2),
+                    test_target_id(TEST_PACKAGE, TEST_TARGET2, LIB_KIND),
+                );
+                let mut second_target_message = default_compiler_message(DiagnosticLevel::Error);
+                second_target_message.target.src_path = Utf8PathBuf::from(TEST_SRC_PATH2);
+
+                req_actor.handle_cargo_information(CompilerMessageEnum(default_compiler_message(
+                    DiagnosticLevel::Error,
+                )));
+                req_actor.handle_cargo_information(CompilerMessageEnum(second_target_message));
+
+                let published_targets: Vec<_> = (0..2)
+                    .map(|_| match receiver_from_actor.recv().unwrap() {
+                        Message::Notification(notification) => {
+                            notification.params["buildTarget"]["uri"].clone()
+                        }
+                        msg => panic!("Expected diagnostic notification, got {:?}", msg),
+                    })
+                    .collect();
+                assert_eq!(published_targets, [TEST_TARGET, TEST_TARGET2]);
+                assert_eq!(req_actor.state.compile_state.errors, 2);
+                no_more_msg(receiver_from_actor);
+            }
+
+            #[test]
+            fn compiler_message_target_found_by_package_and_name() {
+                let TestEndpoints {
+                    mut req_actor,
+                    receiver_from_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetCompile>(
+                    MockCargoHandler::new(),
+                    default_compile_params(TestCase::OneTarget),
+                    TestCase::OneTarget,
+                );
+                req_actor.stream_diagnostics = true;
+                req_actor.src_path_to_target_id.clear();
+                req_actor.package_target_to_target_id.insert(
+                    (
+                        TEST_PKG_ID.to_string(),
+                        TEST_TARGET.to_string(),
+                        vec![TEST_KIND.to_string()],
+                    ),
+                    test_target_id(TEST_PACKAGE, TEST_TARGET, BIN_KIND),
+                );
+
+                req_actor.handle_cargo_information(CompilerMessageEnum(default_compiler_message(
+                    DiagnosticLevel::Error,
+                )));
+
+                match receiver_from_actor.recv().unwrap() {
+                    Message::Notification(notification) => {
+                        assert_eq!(notification.params["buildTarget"]["uri"], TEST_TARGET)
+                    }
+                    msg => panic!("Expected diagnostic notification, got {:?}", msg),
+                }
+                no_more_msg(receiver_from_actor);
+            }
+
+            #[test]
+            fn compiler_message_in_generated_file_attached_to_package_target() {
+                let TestEndpoints {
+                    mut req_actor,
+                    receiver_from_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetCompile>(
+                    MockCargoHandler::new(),
+                    default_compile_params(TestCase::OneTarget),
+                    TestCase::OneTarget,
+                );
+                req_actor.stream_diagnostics = true;
+                req_actor.package_to_primary_target_id.insert(
+                    TEST_PKG_ID.to_string(),
+                    test_target_id(TEST_PACKAGE, TEST_TARGET2, LIB_KIND),
+                );
+                // Build script generating the file is not a known target.
+                let mut message = default_compiler_message(DiagnosticLevel::Error);
+                message.target.name = "build-script-build".to_string();
+                message.target.kind = vec!["custom-build".to_string()];
+                message.target.src_path = format!("{}/build.rs", TEST_ROOT_PATH).into();
+                message.message.spans[0].file_name = format!("{}/generated.rs", TEST_OUT_DIR);
+
+                req_actor.handle_cargo_information(CompilerMessageEnum(message));
+
+                match receiver_from_actor.recv().unwrap() {
+                    Message::Notification(notification) => {
+                        assert_eq!(notification.params["buildTarget"]["uri"], TEST_TARGET2)
+                    }
+                    msg => panic!("Expected diagnostic notification, got {:?}", msg),
+                }
+                no_more_msg(receiver_from_actor);
+            }
+
+            #[test]
+            fn compiler_messages_batched_per_file() {
+                let TestEndpoints {
+                    mut req_actor,
+                    receiver_from_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetCompile>(
+                    MockCargoHandler::new(),
+                    default_compile_params(TestCase::OneTarget),
+                    TestCase::OneTarget,
+                );
+                let mut other_file_message = default_compiler_message(DiagnosticLevel::Warning);
+                other_file_message.message.spans[0].file_name = TEST_FILENAME2.to_string();
 
                 req_actor.handle_cargo_information(CompilerMessageEnum(default_compiler_message(
                     DiagnosticLevel::Error,
@@ -936,9 +1687,46 @@ pub mod tests {
                 req_actor.handle_cargo_information(CompilerMessageEnum(default_compiler_message(
                     DiagnosticLevel::Warning,
                 )));
+                // Nothing is published until the diagnostics for another file arrive.
+                assert!(receiver_from_actor.try_recv().is_err());
+                req_actor.handle_cargo_information(CompilerMessageEnum(other_file_message));
+
+                let published_diagnostics = |message: Message| match message {
+                    Message::Notification(notification) => (
+                        notification.params["textDocument"]["uri"]
+                            .as_str()
+                            .unwrap()
+                            .to_string(),
+                        notification.params["diagnostics"].as_array().unwrap().len(),
+                    ),
+                    msg => panic!("Expected diagnostic notification, got {:?}", msg),
+                };
+                assert_eq!(
+                    published_diagnostics(receiver_from_actor.recv().unwrap()),
+                    ("file:///test_root_path/test_filename".to_string(), 2)
+                );
+                assert!(receiver_from_actor.try_recv().is_err());
+
+                req_actor.handle_cargo_information(BuildFinishedEnum(default_build_finished()));
+
+                assert_eq!(
+                    published_diagnostics(receiver_from_actor.recv().unwrap()),
+                    ("file:///test_root_path/test_filename2".to_string(), 1)
+                );
+            }
 
-                let _ = receiver_from_actor.recv(); // publish diagnostic
-                let _ = receiver_from_actor.recv(); // publish diagnostic
+            #[test]
+            fn build_finished_simple() {
+                let TestEndpoints {
+                    mut req_actor,
+                    receiver_from_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetCompile>(
+                    MockCargoHandler::new(),
+                    default_compile_params(TestCase::MultipleTargets),
+                    TestCase::MultipleTargets,
+                );
 
                 req_actor.handle_cargo_information(BuildFinishedEnum(default_build_finished()));
 
@@ -952,13 +1740,42 @@ pub mod tests {
                   "method": "build/taskFinish",
                   "params": {
                     "data": {
-                      "errors": 1,
+                      "errors": 0,
                       "originId": "test_origin_id",
                       "target": {
-                        "uri": "test_target"
+                        "uri": "cargo:///test_package?name=test_target&kind=bin"
                       },
                       "time": 0,
-                      "warnings": 1
+                      "warnings": 0
+                    },
+                    "dataKind": "compile-report",
+                    "eventTime": "timestamp",
+                    "originId": "test_origin_id",
+                    "status": 1,
+                    "taskId": {
+                      "id": "random_task_id",
+                      "parents": "[random_task_id]"
+                    }
+                  }
+                }
+                "#);
+                assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
+                    ".params.eventTime" => TIMESTAMP,
+                    ".params.taskId.id" => RANDOM_TASK_ID,
+                    ".params.data.time" => 0,
+                    ".params.taskId.parents" => format!("[{RANDOM_TASK_ID}]"),
+                },@r#"
+                {
+                  "method": "build/taskFinish",
+                  "params": {
+                    "data": {
+                      "errors": 0,
+                      "originId": "test_origin_id",
+                      "target": {
+                        "uri": "cargo:///test_package?name=test_target2&kind=lib"
+                      },
+                      "time": 0,
+                      "warnings": 0
                     },
                     "dataKind": "compile-report",
                     "eventTime": "timestamp",
@@ -994,89 +1811,456 @@ pub mod tests {
                 no_more_msg(receiver_from_actor);
             }
 
-            fn default_target() -> Target {
-                TargetBuilder::default()
-                    .name(TEST_TARGET.to_string())
-                    .kind(vec![TEST_KIND.into()])
-                    .crate_types(vec![TEST_CRATE_TYPE.into()])
-                    .src_path(TEST_SRC_PATH.to_string())
-                    .build()
-                    .unwrap()
-            }
+            #[test]
+            fn build_finished_after_compile_task_start() {
+                let TestEndpoints {
+                    mut req_actor,
+                    receiver_from_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetCompile>(
+                    MockCargoHandler::new(),
+                    default_compile_params(TestCase::OneTarget),
+                    TestCase::OneTarget,
+                );
+                let event_time = |msg: Message| match msg {
+                    Message::Notification(n) => (n.params["eventTime"].as_i64().unwrap(), n.params),
+                    _ => panic!("Expected notification"),
+                };
 
-            fn default_artifact_profile() -> ArtifactProfile {
-                ArtifactProfileBuilder::default()
-                    .opt_level(TEST_OPT_LEVEL.to_string())
-                    .debuginfo(ArtifactDebuginfo::default())
-                    .debug_assertions(false)
-                    .overflow_checks(false)
-                    .test(false)
-                    .build()
-                    .unwrap()
-            }
+                req_actor.start_compile_task();
+                req_actor.handle_cargo_information(BuildFinishedEnum(default_build_finished()));
 
-            fn default_compiler_artifact() -> Artifact {
-                ArtifactBuilder::default()
-                    .package_id(PackageId {
-                        repr: TEST_PKG_ID.into(),
-                    })
-                    .manifest_path(TEST_MANIFEST_PATH.to_string())
-                    .target(default_target())
-                    .profile(default_artifact_profile())
-                    .executable(Some(TEST_EXECUTABLE.into()))
-                    .features(vec![TEST_FEATURE.into()])
-                    .filenames(vec![TEST_FILENAME.into()])
-                    .fresh(false)
-                    .build()
-                    .unwrap()
+                // Compilation start and the build target start.
+                let _ = receiver_from_actor.recv().unwrap();
+                let (start_time, _) = event_time(receiver_from_actor.recv().unwrap());
+                let (finish_time, finish_params) = event_time(receiver_from_actor.recv().unwrap());
+                assert!(finish_time >= start_time);
+                assert!(finish_params["data"]["time"].as_i64().unwrap() >= 0);
             }
 
-            fn default_build_script() -> BuildScript {
-                BuildScriptBuilder::default()
-                    .package_id(PackageId {
-                        repr: TEST_PKG_ID.into(),
+            #[test]
+            fn build_scripts_reported_as_subtasks() {
+                let TestEndpoints {
+                    mut req_actor,
+                    receiver_from_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetCompile>(
+                    MockCargoHandler::new(),
+                    default_compile_params(TestCase::OneTarget),
+                    TestCase::OneTarget,
+                );
+                let mut other_build_script = default_build_script();
+                other_build_script.package_id = PackageId {
+                    repr: "other_pkg_id".into(),
+                };
+
+                req_actor.handle_cargo_information(BuildScriptExecuted(default_build_script()));
+                req_actor.handle_cargo_information(BuildScriptExecuted(other_build_script));
+                // The same build script is reported only once.
+                req_actor.handle_cargo_information(BuildScriptExecuted(default_build_script()));
+
+                let notifications: Vec<_> = receiver_from_actor
+                    .try_iter()
+                    .map(|msg| match msg {
+                        Message::Notification(n) => n,
+                        _ => panic!("Expected notification"),
                     })
-                    .linked_libs(vec![TEST_LINKED_LIB.into()])
-                    .linked_paths(vec![TEST_LINKED_PATH.into()])
-                    .cfgs(vec![TEST_CFG.into()])
-                    .env(vec![(TEST_ENV.into(), TEST_ENV.into())])
-                    .out_dir(TEST_OUT_DIR.to_string())
-                    .build()
-                    .unwrap()
+                    .filter(|n| n.method != "build/taskProgress")
+                    .collect();
+                let compile_task_id = req_actor.state.compile_state.task_id.id.0.clone();
+                let subtasks: Vec<_> = notifications
+                    .chunks(2)
+                    .map(|pair| {
+                        assert_eq!(pair[0].method, "build/taskStart");
+                        assert_eq!(pair[1].method, "build/taskFinish");
+                        assert_eq!(pair[0].params["taskId"], pair[1].params["taskId"]);
+                        assert_eq!(pair[0].params["taskId"]["parents"][0], compile_task_id);
+                        assert_eq!(pair[1].params["status"], 1);
+                        pair[0].params["message"].as_str().unwrap().to_string()
+                    })
+                    .collect();
+                assert_eq!(
+                    subtasks,
+                    vec![
+                        "Running build script for test_pkg_id",
+                        "Running build script for other_pkg_id"
+                    ]
+                );
             }
 
-            fn default_diagnostic_span() -> DiagnosticSpan {
-                DiagnosticSpanBuilder::default()
-                    .file_name(TEST_FILENAME.to_string())
-                    .byte_start(0_u32)
-                    .byte_end(0_u32)
-                    .line_start(0_usize)
-                    .line_end(0_usize)
-                    .column_start(0_usize)
-                    .column_end(0_usize)
-                    .is_primary(true)
-                    .text(vec![])
-                    .label(None)
-                    .suggested_replacement(None)
-                    .suggestion_applicability(None)
-                    .expansion(None)
-                    .build()
-                    .unwrap()
-            }
+            #[test]
+            fn build_script_subtask_spans_its_compilation_and_execution() {
+                let TestEndpoints {
+                    mut req_actor,
+                    receiver_from_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetCompile>(
+                    MockCargoHandler::new(),
+                    default_compile_params(TestCase::OneTarget),
+                    TestCase::OneTarget,
+                );
+                let mut build_script_artifact = default_compiler_artifact();
+                build_script_artifact.target.name = "build-script-build".to_string();
+                build_script_artifact.target.kind = vec!["custom-build".to_string()];
+                let task_notifications = |receiver: &Receiver<Message>| -> Vec<_> {
+                    receiver
+                        .try_iter()
+                        .filter_map(|msg| match msg {
+                            Message::Notification(n) if n.method != "build/taskProgress" => Some(n),
+                            _ => None,
+                        })
+                        .collect()
+                };
+
+                req_actor.handle_cargo_information(CompilerArtifact(build_script_artifact));
+
+                let started = task_notifications(&receiver_from_actor);
+                assert_eq!(started.len(), 1);
+                assert_eq!(started[0].method, "build/taskStart");
+                assert_eq!(
+                    started[0].params["message"],
+                    "Running build script for test_pkg_id"
+                );
 
-            fn default_diagnostic(level: DiagnosticLevel) -> Diagnostic {
-                DiagnosticBuilder::default()
-                    .message(TEST_MESSAGE.to_string())
-                    .level(level)
-                    .code(None)
-                    .spans(vec![default_diagnostic_span()])
-                    .children(vec![])
-                    .rendered(None)
-                    .build()
-                    .unwrap()
+                req_actor.handle_cargo_information(BuildScriptExecuted(default_build_script()));
+
+                let finished = task_notifications(&receiver_from_actor);
+                assert_eq!(finished.len(), 1);
+                assert_eq!(finished[0].method, "build/taskFinish");
+                assert_eq!(finished[0].params["taskId"], started[0].params["taskId"]);
+                assert_eq!(finished[0].params["status"], 1);
             }
 
-            fn default_compiler_message(level: DiagnosticLevel) -> CompilerMessage {
+            #[test]
+            fn failed_build_script_finished_with_build() {
+                let TestEndpoints {
+                    mut req_actor,
+                    receiver_from_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetCompile>(
+                    MockCargoHandler::new(),
+                    default_compile_params(TestCase::OneTarget),
+                    TestCase::OneTarget,
+                );
+                let mut build_script_artifact = default_compiler_artifact();
+                build_script_artifact.target.kind = vec!["custom-build".to_string()];
+
+                req_actor.handle_cargo_information(CompilerArtifact(build_script_artifact));
+                req_actor.handle_cargo_information(BuildFinishedEnum(
+                    BuildFinishedBuilder::default()
+                        .success(false)
+                        .build()
+                        .unwrap(),
+                ));
+
+                let build_script_finish = receiver_from_actor
+                    .try_iter()
+                    .filter_map(|msg| match msg {
+                        Message::Notification(n) if n.method == "build/taskFinish" => Some(n),
+                        _ => None,
+                    })
+                    .next()
+                    .unwrap();
+                assert!(req_actor
+                    .state
+                    .compile_state
+                    .running_build_scripts
+                    .is_empty());
+                assert_eq!(build_script_finish.params["status"], 2);
+            }
+
+            #[test]
+            fn build_finished_with_target_compile_time() {
+                let TestEndpoints {
+                    mut req_actor,
+                    receiver_from_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetCompile>(
+                    MockCargoHandler::new(),
+                    default_compile_params(TestCase::OneTarget),
+                    TestCase::OneTarget,
+                );
+                req_actor
+                    .state
+                    .compile_state
+                    .set_start_time(&test_target_id(TEST_PACKAGE, TEST_TARGET, BIN_KIND));
+                std::thread::sleep(std::time::Duration::from_millis(10));
+
+                req_actor.handle_cargo_information(CompilerArtifact(default_compiler_artifact()));
+                req_actor.handle_cargo_information(BuildFinishedEnum(default_build_finished()));
+
+                let _ = receiver_from_actor.recv(); // compilation progress
+                match receiver_from_actor.recv().unwrap() {
+                    Message::Notification(n) => {
+                        assert_eq!(n.params["dataKind"], "compile-report");
+                        assert!(n.params["data"]["time"].as_i64().unwrap() >= 10);
+                    }
+                    _ => panic!("Expected notification"),
+                }
+            }
+
+            #[test]
+            fn build_finished_with_complex_compile_report() {
+                // Checks if server counts warnings and error and produces a correct compile report.
+
+                let TestEndpoints {
+                    mut req_actor,
+                    receiver_from_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetCompile>(
+                    MockCargoHandler::new(),
+                    default_compile_params(TestCase::OneTarget),
+                    TestCase::OneTarget,
+                );
+
+                req_actor.handle_cargo_information(CompilerMessageEnum(default_compiler_message(
+                    DiagnosticLevel::Error,
+                )));
+                req_actor.handle_cargo_information(CompilerMessageEnum(default_compiler_message(
+                    DiagnosticLevel::Warning,
+                )));
+
+                req_actor.handle_cargo_information(BuildFinishedEnum(default_build_finished()));
+
+                let _ = receiver_from_actor.recv(); // publish batched diagnostics
+
+                assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
+                    ".params.eventTime" => TIMESTAMP,
+                    ".params.taskId.id" => RANDOM_TASK_ID,
+                    ".params.data.time" => 0,
+                    ".params.taskId.parents" => format!("[{RANDOM_TASK_ID}]"),
+                },@r#"
+                {
+                  "method": "build/taskFinish",
+                  "params": {
+                    "data": {
+                      "errors": 1,
+                      "originId": "test_origin_id",
+                      "target": {
+                        "uri": "cargo:///test_package?name=test_target&kind=bin"
+                      },
+                      "time": 0,
+                      "warnings": 1
+                    },
+                    "dataKind": "compile-report",
+                    "eventTime": "timestamp",
+                    "originId": "test_origin_id",
+                    "status": 1,
+                    "taskId": {
+                      "id": "random_task_id",
+                      "parents": "[random_task_id]"
+                    }
+                  }
+                }
+                "#);
+                assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
+                    ".params.eventTime" => TIMESTAMP,
+                    ".params.taskId.id" => RANDOM_TASK_ID,
+                },@r#"
+                {
+                  "method": "build/taskFinish",
+                  "params": {
+                    "eventTime": "timestamp",
+                    "message": "Finished compilation",
+                    "originId": "test_origin_id",
+                    "status": 1,
+                    "taskId": {
+                      "id": "random_task_id",
+                      "parents": [
+                        "test_origin_id"
+                      ]
+                    }
+                  }
+                }
+                "#);
+                no_more_msg(receiver_from_actor);
+            }
+
+            #[test]
+            fn compile_report_folds_cargo_summary() {
+                let TestEndpoints {
+                    mut req_actor,
+                    receiver_from_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetCompile>(
+                    MockCargoHandler::new(),
+                    default_compile_params(TestCase::OneTarget),
+                    TestCase::OneTarget,
+                );
+
+                req_actor.handle_cargo_information(CompilerMessageEnum(default_compiler_message(
+                    DiagnosticLevel::Warning,
+                )));
+                req_actor.handle_cargo_event(CargoMessage::CargoStderr(
+                    "warning: `test_package` (lib) generated 2 warnings".to_string(),
+                ));
+                req_actor.handle_cargo_information(BuildFinishedEnum(default_build_finished()));
+
+                // The summary line is still logged.
+                match receiver_from_actor.recv().unwrap() {
+                    Message::Notification(n) => assert_eq!(
+                        n.params["message"],
+                        "warning: `test_package` (lib) generated 2 warnings"
+                    ),
+                    _ => panic!("Expected notification"),
+                }
+                let _ = receiver_from_actor.recv(); // publish batched diagnostics
+                match receiver_from_actor.recv().unwrap() {
+                    Message::Notification(n) => {
+                        assert_eq!(n.params["dataKind"], "compile-report");
+                        assert_eq!(n.params["data"]["warnings"], 2);
+                        assert_eq!(n.params["data"]["errors"], 0);
+                    }
+                    _ => panic!("Expected notification"),
+                }
+            }
+
+            #[test]
+            fn build_finished_with_fresh_artifacts() {
+                let TestEndpoints {
+                    mut req_actor,
+                    receiver_from_actor,
+                    _cancel_sender,
+                    ..
+                } = default_req_actor::<BuildTargetCompile>(
+                    MockCargoHandler::new(),
+                    default_compile_params(TestCase::OneTarget),
+                    TestCase::OneTarget,
+                );
+                let mut fresh_artifact = default_compiler_artifact();
+                fresh_artifact.fresh = true;
+
+                req_actor.handle_cargo_information(CompilerArtifact(fresh_artifact.clone()));
+                req_actor.handle_cargo_information(CompilerArtifact(fresh_artifact));
+                let _ = receiver_from_actor.recv(); // compilation step progress
+                let _ = receiver_from_actor.recv(); // compilation step progress
+
+                req_actor.handle_cargo_information(BuildFinishedEnum(default_build_finished()));
+
+                assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
+                    ".params.eventTime" => TIMESTAMP,
+                    ".params.taskId.id" => RANDOM_TASK_ID,
+                    ".params.data.time" => 0,
+                    ".params.taskId.parents" => format!("[{RANDOM_TASK_ID}]"),
+                },@r#"
+                {
+                  "method": "build/taskFinish",
+                  "params": {
+                    "data": {
+                      "errors": 0,
+                      "noOp": true,
+                      "originId": "test_origin_id",
+                      "target": {
+                        "uri": "cargo:///test_package?name=test_target&kind=bin"
+                      },
+                      "time": 0,
+                      "warnings": 0
+                    },
+                    "dataKind": "compile-report",
+                    "eventTime": "timestamp",
+                    "originId": "test_origin_id",
+                    "status": 1,
+                    "taskId": {
+                      "id": "random_task_id",
+                      "parents": "[random_task_id]"
+                    }
+                  }
+                }
+                "#);
+                let _ = receiver_from_actor.recv(); // finished compilation
+                no_more_msg(receiver_from_actor);
+            }
+
+            fn default_target() -> Target {
+                TargetBuilder::default()
+                    .name(TEST_TARGET.to_string())
+                    .kind(vec![TEST_KIND.into()])
+                    .crate_types(vec![TEST_CRATE_TYPE.into()])
+                    .src_path(TEST_SRC_PATH.to_string())
+                    .build()
+                    .unwrap()
+            }
+
+            fn default_artifact_profile() -> ArtifactProfile {
+                ArtifactProfileBuilder::default()
+                    .opt_level(TEST_OPT_LEVEL.to_string())
+                    .debuginfo(ArtifactDebuginfo::default())
+                    .debug_assertions(false)
+                    .overflow_checks(false)
+                    .test(false)
+                    .build()
+                    .unwrap()
+            }
+
+            fn default_compiler_artifact() -> Artifact {
+                ArtifactBuilder::default()
+                    .package_id(PackageId {
+                        repr: TEST_PKG_ID.into(),
+                    })
+                    .manifest_path(TEST_MANIFEST_PATH.to_string())
+                    .target(default_target())
+                    .profile(default_artifact_profile())
+                    .executable(Some(TEST_EXECUTABLE.into()))
+                    .features(vec![TEST_FEATURE.into()])
+                    .filenames(vec![TEST_FILENAME.into()])
+                    .fresh(false)
+                    .build()
+                    .unwrap()
+            }
+
+            fn default_build_script() -> BuildScript {
+                BuildScriptBuilder::default()
+                    .package_id(PackageId {
+                        repr: TEST_PKG_ID.into(),
+                    })
+                    .linked_libs(vec![TEST_LINKED_LIB.into()])
+                    .linked_paths(vec![TEST_LINKED_PATH.into()])
+                    .cfgs(vec![TEST_CFG.into()])
+                    .env(vec![(TEST_ENV.into(), TEST_ENV.into())])
+                    .out_dir(TEST_OUT_DIR.to_string())
+                    .build()
+                    .unwrap()
+            }
+
+            fn default_diagnostic_span() -> DiagnosticSpan {
+                DiagnosticSpanBuilder::default()
+                    .file_name(TEST_FILENAME.to_string())
+                    .byte_start(0_u32)
+                    .byte_end(0_u32)
+                    .line_start(0_usize)
+                    .line_end(0_usize)
+                    .column_start(0_usize)
+                    .column_end(0_usize)
+                    .is_primary(true)
+                    .text(vec![])
+                    .label(None)
+                    .suggested_replacement(None)
+                    .suggestion_applicability(None)
+                    .expansion(None)
+                    .build()
+                    .unwrap()
+            }
+
+            fn default_diagnostic(level: DiagnosticLevel) -> Diagnostic {
+                DiagnosticBuilder::default()
+                    .message(TEST_MESSAGE.to_string())
+                    .level(level)
+                    .code(None)
+                    .spans(vec![default_diagnostic_span()])
+                    .children(vec![])
+                    .rendered(None)
+                    .build()
+                    .unwrap()
+            }
+
+            fn default_compiler_message(level: DiagnosticLevel) -> CompilerMessage {
                 CompilerMessageBuilder::default()
                     .package_id(PackageId {
                         repr: TEST_PKG_ID.into(),
@@ -1096,12 +2280,16 @@ pub mod tests {
         };
         use bsp4rs::bsp::BuildTargetIdentifier;
         use bsp4rs::bsp::{BuildTargetRun, RunParams};
-        use cargo_metadata::Message::TextLine;
+        use cargo_metadata::Artifact;
+        use cargo_metadata::Message::{CompilerArtifact, TextLine};
         use serde_json::to_string;
         use std::io;
 
         const TEST_STDOUT: &str = "test_stdout";
         const TEST_STDERR: &str = "test_stderr";
+        const TEST_ERROR_STDERR: &str = "error: test_stderr";
+        const TEST_MANIFEST_WARNING_STDERR: &str =
+            "warning: /test_root_path/Cargo.toml: unused manifest key: package.test_key";
 
         fn default_run_params() -> RunParams {
             RunParams {
@@ -1116,6 +2304,25 @@ pub mod tests {
             }
         }
 
+        #[test]
+        fn timeout_only_while_compiling() {
+            let TestEndpoints {
+                mut req_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetRun>(
+                MockCargoHandler::new(),
+                default_run_params(),
+                TestCase::OneTarget,
+            );
+            req_actor.command_timeout = Some(Duration::from_millis(10));
+
+            assert_eq!(req_actor.event_timeout(), Some(Duration::from_millis(10)));
+            // The binary is executed after the compilation, it may print nothing for long.
+            req_actor.state.compile_state.build_finished = true;
+            assert_eq!(req_actor.event_timeout(), None);
+        }
+
         #[test]
         fn run_lifetime() {
             let mut mock_cargo_handle = MockCargoHandler::new();
@@ -1128,68 +2335,444 @@ pub mod tests {
                 .return_const(receiver_from_cargo);
 
             let TestEndpoints {
-                req_actor,
+                req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetRun>(
+                mock_cargo_handle,
+                default_run_params(),
+                TestCase::OneTarget,
+            );
+
+            let _ = jod_thread::Builder::new()
+                .spawn(move || req_actor.run())
+                .expect("failed to spawn thread")
+                .detach();
+
+            let _ = receiver_from_actor.recv(); // main compilation task started
+            let _ = receiver_from_actor.recv(); // target compilation task started
+
+            sender_to_actor
+                .send(CargoStdout(
+                    to_string(&BuildFinishedEnum(default_build_finished())).unwrap(),
+                ))
+                .unwrap();
+
+            let _ = receiver_from_actor.recv(); // main compilation task finished
+            let _ = receiver_from_actor.recv(); // target compilation task finished
+
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap(),{
+                ".params.taskId.id" => RANDOM_TASK_ID,
+                ".params.eventTime" => TIMESTAMP,
+            }
+            ,@r#"
+            {
+              "method": "build/taskStart",
+              "params": {
+                "eventTime": "timestamp",
+                "message": "Started target execution",
+                "originId": "test_origin_id",
+                "taskId": {
+                  "id": "random_task_id",
+                  "parents": [
+                    "test_origin_id"
+                  ]
+                }
+              }
+            }
+            "#);
+
+            drop(sender_to_actor);
+
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap(),{
+                ".params.taskId.id" => RANDOM_TASK_ID,
+                ".params.eventTime" => TIMESTAMP,
+            }
+            ,@r#"
+            {
+              "method": "build/taskFinish",
+              "params": {
+                "eventTime": "timestamp",
+                "message": "Finished target execution",
+                "originId": "test_origin_id",
+                "status": 1,
+                "taskId": {
+                  "id": "random_task_id",
+                  "parents": [
+                    "test_origin_id"
+                  ]
+                }
+              }
+            }
+            "#);
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap(),{
+                ".params.eventTime" => TIMESTAMP,
+            }
+            ,@r#"
+            {
+              "method": "build/taskFinish",
+              "params": {
+                "eventTime": "timestamp",
+                "originId": "test_origin_id",
+                "status": 1,
+                "taskId": {
+                  "id": "test_origin_id"
+                }
+              }
+            }
+            "#);
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap()
+            ,@r#"
+            {
+              "id": "test_req_id",
+              "error": {
+                "code": -32603,
+                "message": "other error"
+              }
+            }
+            "#);
+            no_more_msg(receiver_from_actor);
+        }
+
+        #[cfg(unix)]
+        fn run_artifact(executable: &str) -> String {
+            to_string(&serde_json::json!({
+                "reason": "compiler-artifact",
+                "package_id": TEST_PACKAGE,
+                "manifest_path": "/test_package/Cargo.toml",
+                "target": {
+                    "name": TEST_TARGET,
+                    "kind": [BIN_KIND],
+                    "crate_types": [BIN_KIND],
+                    "src_path": TEST_SRC_PATH,
+                },
+                "profile": {
+                    "opt_level": "0",
+                    "debuginfo": 2,
+                    "debug_assertions": true,
+                    "overflow_checks": true,
+                    "test": false,
+                },
+                "features": [],
+                "filenames": [executable],
+                "executable": executable,
+                "fresh": false,
+            }))
+            .unwrap()
+        }
+
+        #[cfg(unix)]
+        fn successful_cargo_handle(
+            receiver: Receiver<CargoMessage>,
+        ) -> MockCargoHandler<CargoMessage> {
+            use std::os::unix::process::ExitStatusExt;
+            use std::process::ExitStatus;
+
+            let mut mock_cargo_handle = MockCargoHandler::new();
+            mock_cargo_handle
+                .expect_join()
+                .returning(|| Ok(ExitStatus::from_raw(0)));
+            mock_cargo_handle.expect_receiver().return_const(receiver);
+            mock_cargo_handle
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn built_executable_started() {
+            const TEST_EXECUTABLE: &str = "/test_package/target/debug/test_target";
+
+            let (sender_to_actor, receiver_from_cargo) = unbounded::<CargoMessage>();
+            let (sender_from_executable, receiver_from_executable) = unbounded::<CargoMessage>();
+            let (spawned_sender, spawned_receiver) = unbounded::<Command>();
+            let TestEndpoints {
+                mut req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetRun>(
+                successful_cargo_handle(receiver_from_cargo),
+                RunParams {
+                    target: test_target_id(TEST_PACKAGE, TEST_TARGET, BIN_KIND),
+                    ..default_run_params()
+                },
+                TestCase::OneTarget,
+            );
+            req_actor.spawn_executable = Some(Box::new(move |command: &mut Command| {
+                spawned_sender
+                    .send(Command::new(command.get_program()))
+                    .unwrap();
+                Ok(successful_cargo_handle(receiver_from_executable.clone()))
+            }));
+
+            let _ = jod_thread::Builder::new()
+                .spawn(move || req_actor.run())
+                .expect("failed to spawn thread")
+                .detach();
+
+            sender_to_actor
+                .send(CargoStdout(run_artifact(TEST_EXECUTABLE)))
+                .unwrap();
+            sender_to_actor
+                .send(CargoStdout(
+                    to_string(&BuildFinishedEnum(default_build_finished())).unwrap(),
+                ))
+                .unwrap();
+            drop(sender_to_actor);
+            sender_from_executable
+                .send(CargoStdout(TEST_STDOUT.to_string()))
+                .unwrap();
+            drop(sender_from_executable);
+
+            let messages: Vec<Message> = receiver_from_actor.iter().collect();
+            assert_eq!(
+                spawned_receiver.recv().unwrap().get_program(),
+                TEST_EXECUTABLE
+            );
+            // The output of the executable is logged in the execution task.
+            assert!(messages.iter().any(|msg| matches!(msg,
+                Message::Notification(n) if n.params["message"] == TEST_STDOUT)));
+            match messages.last().unwrap() {
+                Message::Response(resp) => {
+                    assert_eq!(resp.result.as_ref().unwrap()["statusCode"], 1)
+                }
+                _ => panic!("Expected response"),
+            }
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn no_built_executable_to_start() {
+            let (sender_to_actor, receiver_from_cargo) = unbounded::<CargoMessage>();
+            let TestEndpoints {
+                mut req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetRun>(
+                successful_cargo_handle(receiver_from_cargo),
+                default_run_params(),
+                TestCase::OneTarget,
+            );
+            req_actor.spawn_executable = Some(Box::new(|_: &mut Command| {
+                panic!("No executable should be started")
+            }));
+
+            let _ = jod_thread::Builder::new()
+                .spawn(move || req_actor.run())
+                .expect("failed to spawn thread")
+                .detach();
+
+            // The executable of a target other than the requested one is not started.
+            sender_to_actor
+                .send(CargoStdout(run_artifact(
+                    "/test_package/target/debug/other",
+                )))
+                .unwrap();
+            sender_to_actor
+                .send(CargoStdout(
+                    to_string(&BuildFinishedEnum(default_build_finished())).unwrap(),
+                ))
+                .unwrap();
+            drop(sender_to_actor);
+
+            match receiver_from_actor.iter().last().unwrap() {
+                Message::Response(resp) => assert_eq!(
+                    resp.error.unwrap().message,
+                    "The requested target produced no executable to run"
+                ),
+                _ => panic!("Expected response"),
+            }
+        }
+
+        #[test]
+        fn fresh_run_reports_no_compilation_work() {
+            let TestEndpoints {
+                mut req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetRun>(
+                MockCargoHandler::new(),
+                default_run_params(),
+                TestCase::OneTarget,
+            );
+            req_actor.skip_fresh_compile = true;
+            req_actor.state.compile_state.compilation_step = Some(0);
+            let fresh_artifact: Artifact = serde_json::from_value(serde_json::json!({
+                "package_id": TEST_PACKAGE,
+                "manifest_path": "/test_package/Cargo.toml",
+                "target": {
+                    "name": TEST_TARGET,
+                    "kind": [BIN_KIND],
+                    "crate_types": [BIN_KIND],
+                    "src_path": TEST_SRC_PATH,
+                },
+                "profile": {
+                    "opt_level": "0",
+                    "debuginfo": 2,
+                    "debug_assertions": true,
+                    "overflow_checks": true,
+                    "test": false,
+                },
+                "features": [],
+                "filenames": [],
+                "executable": null,
+                "fresh": true,
+            }))
+            .unwrap();
+            let params = |msg: Message| match msg {
+                Message::Notification(notification) => notification.params,
+                _ => panic!("Expected notification"),
+            };
+
+            req_actor.handle_cargo_information(CompilerArtifact(fresh_artifact));
+            // The fresh artifact is counted as a compilation step, but not reported.
+            assert!(receiver_from_actor.try_recv().is_err());
+            assert_eq!(req_actor.state.compile_state.compilation_step, Some(1));
+            req_actor.handle_cargo_information(BuildFinishedEnum(default_build_finished()));
+
+            let compile_report = params(receiver_from_actor.recv().unwrap());
+            assert_eq!(compile_report["dataKind"], "compile-report");
+            assert_eq!(compile_report["data"]["noOp"], true);
+            let compile_finish = params(receiver_from_actor.recv().unwrap());
+            assert_eq!(
+                compile_finish["message"],
+                "Skipped compilation, the build targets are up to date"
+            );
+            let execution_start = params(receiver_from_actor.recv().unwrap());
+            assert_eq!(execution_start["message"], "Started target execution");
+            no_more_msg(receiver_from_actor);
+        }
+
+        #[test]
+        fn simple_stdout() {
+            let TestEndpoints {
+                mut req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetRun>(
+                MockCargoHandler::new(),
+                default_run_params(),
+                TestCase::OneTarget,
+            );
+
+            req_actor.handle_cargo_information(TextLine(TEST_STDOUT.to_string()));
+
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
+                ".params.task.id" => RANDOM_TASK_ID,
+            } ,@r#"
+            {
+              "method": "build/logMessage",
+              "params": {
+                "message": "test_stdout",
+                "originId": "test_origin_id",
+                "task": {
+                  "id": "random_task_id",
+                  "parents": [
+                    "test_origin_id"
+                  ]
+                },
+                "type": 4
+              }
+            }
+            "#);
+            no_more_msg(receiver_from_actor);
+        }
+
+        #[test]
+        fn log_message_carries_request_origin_id() {
+            const RUN_ORIGIN_ID: &str = "run_origin_id";
+            let TestEndpoints {
+                mut req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetRun>(
+                MockCargoHandler::new(),
+                RunParams {
+                    origin_id: Some(RUN_ORIGIN_ID.into()),
+                    ..default_run_params()
+                },
+                TestCase::OneTarget,
+            );
+
+            req_actor.handle_cargo_information(TextLine(TEST_STDOUT.to_string()));
+
+            match receiver_from_actor.recv().unwrap() {
+                Message::Notification(n) => {
+                    assert_eq!(n.method, "build/logMessage");
+                    assert_eq!(n.params["originId"], RUN_ORIGIN_ID);
+                }
+                _ => panic!("Expected notification"),
+            }
+            no_more_msg(receiver_from_actor);
+        }
+
+        #[test]
+        fn simple_stderr() {
+            let TestEndpoints {
+                mut req_actor,
                 receiver_from_actor,
                 _cancel_sender,
                 ..
             } = default_req_actor::<BuildTargetRun>(
-                mock_cargo_handle,
+                MockCargoHandler::new(),
                 default_run_params(),
                 TestCase::OneTarget,
             );
 
-            let _ = jod_thread::Builder::new()
-                .spawn(move || req_actor.run())
-                .expect("failed to spawn thread")
-                .detach();
-
-            let _ = receiver_from_actor.recv(); // main compilation task started
-            let _ = receiver_from_actor.recv(); // target compilation task started
-
-            sender_to_actor
-                .send(CargoStdout(
-                    to_string(&BuildFinishedEnum(default_build_finished())).unwrap(),
-                ))
-                .unwrap();
-
-            let _ = receiver_from_actor.recv(); // main compilation task finished
-            let _ = receiver_from_actor.recv(); // target compilation task finished
+            req_actor.handle_cargo_event(CargoStderr(TEST_STDERR.to_string()));
 
-            assert_json_snapshot!(receiver_from_actor.recv().unwrap(),{
-                ".params.taskId.id" => RANDOM_TASK_ID,
-                ".params.eventTime" => TIMESTAMP,
-            }
-            ,@r#"
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
+                ".params.task.id" => RANDOM_TASK_ID,
+            } ,@r#"
             {
-              "method": "build/taskStart",
+              "method": "build/logMessage",
               "params": {
-                "eventTime": "timestamp",
-                "message": "Started target execution",
+                "message": "test_stderr",
                 "originId": "test_origin_id",
-                "taskId": {
+                "task": {
                   "id": "random_task_id",
                   "parents": [
                     "test_origin_id"
                   ]
-                }
+                },
+                "type": 4
               }
             }
             "#);
+            no_more_msg(receiver_from_actor);
+        }
 
-            drop(sender_to_actor);
+        #[test]
+        fn build_lock_wait_reported() {
+            let TestEndpoints {
+                mut req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetRun>(
+                MockCargoHandler::new(),
+                default_run_params(),
+                TestCase::OneTarget,
+            );
 
-            assert_json_snapshot!(receiver_from_actor.recv().unwrap(),{
+            req_actor.handle_cargo_event(CargoStderr(
+                "    Blocking waiting for file lock on build directory".to_string(),
+            ));
+
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
                 ".params.taskId.id" => RANDOM_TASK_ID,
                 ".params.eventTime" => TIMESTAMP,
-            }
-            ,@r#"
+            } ,@r#"
             {
-              "method": "build/taskFinish",
+              "method": "build/taskProgress",
               "params": {
                 "eventTime": "timestamp",
-                "message": "Finished target execution",
+                "message": "Waiting for build lock held by another process",
                 "originId": "test_origin_id",
-                "status": 1,
                 "taskId": {
                   "id": "random_task_id",
                   "parents": [
@@ -1199,29 +2782,52 @@ pub mod tests {
               }
             }
             "#);
-            assert_json_snapshot!(receiver_from_actor.recv().unwrap(),{
-                ".params.eventTime" => TIMESTAMP,
+            let _ = receiver_from_actor.recv().unwrap(); // stderr log message
+
+            req_actor
+                .handle_cargo_event(CargoStderr("   Compiling test_package v0.1.0".to_string()));
+
+            match receiver_from_actor.recv().unwrap() {
+                Message::Notification(n) => {
+                    assert_eq!(n.method, "build/taskProgress");
+                    assert_eq!(n.params["message"], "Acquired build lock");
+                }
+                _ => panic!("Expected notification"),
             }
-            ,@r#"
+            let _ = receiver_from_actor.recv().unwrap(); // stderr log message
+            no_more_msg(receiver_from_actor);
+        }
+
+        #[test]
+        fn manifest_warning_stderr() {
+            let TestEndpoints {
+                mut req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetRun>(
+                MockCargoHandler::new(),
+                default_run_params(),
+                TestCase::OneTarget,
+            );
+
+            req_actor.handle_cargo_event(CargoStderr(TEST_MANIFEST_WARNING_STDERR.to_string()));
+
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
+                ".params.task.id" => RANDOM_TASK_ID,
+            } ,@r#"
             {
-              "method": "build/taskFinish",
+              "method": "build/logMessage",
               "params": {
-                "eventTime": "timestamp",
+                "message": "warning: /test_root_path/Cargo.toml: unused manifest key: package.test_key",
                 "originId": "test_origin_id",
-                "status": 1,
-                "taskId": {
-                  "id": "test_origin_id"
-                }
-              }
-            }
-            "#);
-            assert_json_snapshot!(receiver_from_actor.recv().unwrap()
-            ,@r#"
-            {
-              "id": "test_req_id",
-              "error": {
-                "code": -32603,
-                "message": "other error"
+                "task": {
+                  "id": "random_task_id",
+                  "parents": [
+                    "test_origin_id"
+                  ]
+                },
+                "type": 2
               }
             }
             "#);
@@ -1229,7 +2835,7 @@ pub mod tests {
         }
 
         #[test]
-        fn simple_stdout() {
+        fn error_stderr() {
             let TestEndpoints {
                 mut req_actor,
                 receiver_from_actor,
@@ -1241,7 +2847,7 @@ pub mod tests {
                 TestCase::OneTarget,
             );
 
-            req_actor.handle_cargo_information(TextLine(TEST_STDOUT.to_string()));
+            req_actor.handle_cargo_event(CargoStderr(TEST_ERROR_STDERR.to_string()));
 
             assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
                 ".params.task.id" => RANDOM_TASK_ID,
@@ -1249,7 +2855,7 @@ pub mod tests {
             {
               "method": "build/logMessage",
               "params": {
-                "message": "test_stdout",
+                "message": "error: test_stderr",
                 "originId": "test_origin_id",
                 "task": {
                   "id": "random_task_id",
@@ -1257,7 +2863,7 @@ pub mod tests {
                     "test_origin_id"
                   ]
                 },
-                "type": 4
+                "type": 1
               }
             }
             "#);
@@ -1265,7 +2871,7 @@ pub mod tests {
         }
 
         #[test]
-        fn simple_stderr() {
+        fn stderr_after_failed_build() {
             let TestEndpoints {
                 mut req_actor,
                 receiver_from_actor,
@@ -1276,7 +2882,14 @@ pub mod tests {
                 default_run_params(),
                 TestCase::OneTarget,
             );
+            let failed_build = BuildFinishedBuilder::default()
+                .success(false)
+                .build()
+                .unwrap();
 
+            req_actor.handle_cargo_information(BuildFinishedEnum(failed_build));
+            let _ = receiver_from_actor.recv(); // compile report
+            let _ = receiver_from_actor.recv(); // finished compilation
             req_actor.handle_cargo_event(CargoStderr(TEST_STDERR.to_string()));
 
             assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
@@ -1313,6 +2926,7 @@ pub mod tests {
         use crate::cargo_communication::utils::test_target_id;
         use bsp4rs::bsp::BuildTargetIdentifier;
         use bsp4rs::bsp::{BuildTargetTest, TestParams};
+        use bsp4rs::cargo::{CargoTestParams, CargoTestParamsData};
         use cargo_metadata::Message::TextLine;
         use crossbeam_channel::unbounded;
         use serde_json::to_string;
@@ -1321,9 +2935,9 @@ pub mod tests {
         const TEST_NAME: &str = "test_name";
 
         fn default_test_params(test_case: TestCase) -> TestParams {
-            let mut targets = vec![test_target_id(TEST_TARGET)];
+            let mut targets = vec![test_target_id(TEST_PACKAGE, TEST_TARGET, BIN_KIND)];
             if let TestCase::MultipleTargets = test_case {
-                targets.push(test_target_id(TEST_TARGET2));
+                targets.push(test_target_id(TEST_PACKAGE, TEST_TARGET2, LIB_KIND));
             }
             TestParams {
                 targets,
@@ -1335,6 +2949,25 @@ pub mod tests {
             }
         }
 
+        #[test]
+        fn timeout_only_while_compiling() {
+            let TestEndpoints {
+                mut req_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetTest>(
+                MockCargoHandler::new(),
+                default_test_params(TestCase::OneTarget),
+                TestCase::OneTarget,
+            );
+            req_actor.command_timeout = Some(Duration::from_millis(10));
+
+            assert_eq!(req_actor.event_timeout(), Some(Duration::from_millis(10)));
+            // The tests are executed after the compilation, they may print nothing for long.
+            req_actor.state.compile_state.build_finished = true;
+            assert_eq!(req_actor.event_timeout(), None);
+        }
+
         #[test]
         fn test_request_lifetime() {
             let mut mock_cargo_handle = MockCargoHandler::new();
@@ -1481,7 +3114,7 @@ pub mod tests {
               "params": {
                 "data": {
                   "target": {
-                    "uri": "test_target2"
+                    "uri": "cargo:///test_package?name=test_target2&kind=lib"
                   }
                 },
                 "dataKind": "test-task",
@@ -1531,6 +3164,7 @@ pub mod tests {
                 to_string(&TestType::Suite(SuiteEvent::Ok(default_suite_results()))).unwrap(),
             ));
 
+            let _ = receiver_from_actor.recv().unwrap(); // suite progress message
             assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
                 ".params.taskId.id" => RANDOM_TASK_ID,
                 ".params.taskId.parents" => format!("[{RANDOM_TASK_ID}]"),
@@ -1543,10 +3177,11 @@ pub mod tests {
                   "cancelled": 0,
                   "failed": 2,
                   "ignored": 3,
+                  "originId": "test_origin_id",
                   "passed": 1,
                   "skipped": 5,
                   "target": {
-                    "uri": "test_target2"
+                    "uri": "cargo:///test_package?name=test_target2&kind=lib"
                   },
                   "time": 6600
                 },
@@ -1564,6 +3199,194 @@ pub mod tests {
             no_more_msg(receiver_from_actor);
         }
 
+        #[test]
+        fn empty_suite_progress_finished() {
+            let TestEndpoints {
+                mut req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetTest>(
+                MockCargoHandler::new(),
+                default_test_params(TestCase::MultipleTargets),
+                TestCase::MultipleTargets,
+            );
+            let all_filtered_out = SuiteResults {
+                passed: 0,
+                failed: 0,
+                ignored: 0,
+                measured: 0,
+                filtered_out: 5,
+                exec_time: 0.0,
+            };
+
+            req_actor.handle_cargo_information(TextLine(
+                to_string(&TestType::Suite(SuiteEvent::Started(SuiteStarted {
+                    test_count: 0,
+                })))
+                .unwrap(),
+            ));
+            req_actor.handle_cargo_information(TextLine(
+                to_string(&TestType::Suite(SuiteEvent::Ok(all_filtered_out))).unwrap(),
+            ));
+
+            let _ = receiver_from_actor.recv().unwrap(); // suite started message
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap(), {
+                ".params.taskId.id" => RANDOM_TASK_ID,
+                ".params.taskId.parents" => format!("[{RANDOM_TASK_ID}]"),
+                ".params.eventTime" => TIMESTAMP,
+            } ,@r#"
+            {
+              "method": "build/taskProgress",
+              "params": {
+                "eventTime": "timestamp",
+                "originId": "test_origin_id",
+                "progress": 0,
+                "taskId": {
+                  "id": "random_task_id",
+                  "parents": "[random_task_id]"
+                },
+                "total": 0,
+                "unit": "tests"
+              }
+            }
+            "#);
+            let _ = receiver_from_actor.recv().unwrap(); // suite finished message
+            no_more_msg(receiver_from_actor);
+        }
+
+        fn debug_test_params() -> TestParams {
+            TestParams {
+                data: Some(
+                    CargoTestParamsData::CargoTestDebug(CargoTestParams {
+                        test_names: vec![TEST_NAME.into()],
+                        test_threads: None,
+                    })
+                    .into(),
+                ),
+                ..default_test_params(TestCase::OneTarget)
+            }
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn debugged_tests_respond_with_debug_session() {
+            use std::os::unix::process::ExitStatusExt;
+            use std::process::ExitStatus;
+
+            let TestEndpoints {
+                mut req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetTest>(
+                MockCargoHandler::new(),
+                debug_test_params(),
+                TestCase::OneTarget,
+            );
+            if let TaskState::Test(ref mut test_state) = req_actor.state.task_state {
+                test_state
+                    .test_executables
+                    .push("/test_package/target/debug/deps/test_target-123".into());
+            }
+
+            req_actor.send_response(Ok(ExitStatus::from_raw(0)));
+
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap(), @r#"
+            {
+              "id": "test_req_id",
+              "result": {
+                "data": {
+                  "uri": "file:///test_package/target/debug/deps/test_target-123?arg=test_arguments&arg=test_name&arg=--exact"
+                },
+                "dataKind": "debug-session-address",
+                "originId": "test_origin_id",
+                "statusCode": 1
+              }
+            }
+            "#);
+            no_more_msg(receiver_from_actor);
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn debugged_tests_without_executable_fail() {
+            use std::os::unix::process::ExitStatusExt;
+            use std::process::ExitStatus;
+
+            let TestEndpoints {
+                req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetTest>(
+                MockCargoHandler::new(),
+                debug_test_params(),
+                TestCase::OneTarget,
+            );
+
+            req_actor.send_response(Ok(ExitStatus::from_raw(0)));
+
+            assert_json_snapshot!(receiver_from_actor.recv().unwrap(), @r#"
+            {
+              "id": "test_req_id",
+              "error": {
+                "code": -32603,
+                "message": "The requested targets produced no test executable to debug"
+              }
+            }
+            "#);
+            no_more_msg(receiver_from_actor);
+        }
+
+        #[test]
+        fn suite_progress_counts_started_tests() {
+            let TestEndpoints {
+                mut req_actor,
+                receiver_from_actor,
+                _cancel_sender,
+                ..
+            } = default_req_actor::<BuildTargetTest>(
+                MockCargoHandler::new(),
+                default_test_params(TestCase::MultipleTargets),
+                TestCase::MultipleTargets,
+            );
+
+            req_actor.handle_cargo_information(TextLine(
+                to_string(&TestType::Suite(SuiteEvent::Started(SuiteStarted {
+                    test_count: 3,
+                })))
+                .unwrap(),
+            ));
+            req_actor.handle_cargo_information(TextLine(
+                to_string(&TestType::Test(Started(TestName {
+                    name: TEST_NAME.into(),
+                })))
+                .unwrap(),
+            ));
+
+            if let TaskState::Test(ref test_state) = req_actor.state.task_state {
+                assert_eq!(test_state.suite_task_progress.started, 1);
+                assert_eq!(test_state.suite_task_progress.total, 3);
+            }
+            req_actor.handle_cargo_information(TextLine(
+                to_string(&TestType::Suite(SuiteEvent::Ok(default_suite_results()))).unwrap(),
+            ));
+
+            let _ = receiver_from_actor.recv().unwrap(); // suite started message
+            let _ = receiver_from_actor.recv().unwrap(); // test started message
+            match receiver_from_actor.recv().unwrap() {
+                Message::Notification(n) => {
+                    assert_eq!(n.method, "build/taskProgress");
+                    assert_eq!(n.params["progress"], 1);
+                    assert_eq!(n.params["total"], 1);
+                }
+                _ => panic!("Expected notification"),
+            }
+            let _ = receiver_from_actor.recv().unwrap(); // suite finished message
+            no_more_msg(receiver_from_actor);
+        }
+
         #[test]
         fn test_started() {
             let TestEndpoints {
@@ -6,15 +6,19 @@ use std::process::ExitStatus;
 
 use bsp_server::{ErrorCode, Message, Notification, Response, ResponseError};
 use serde_json::to_value;
+use url::Url;
 
 use crate::cargo_communication::cargo_handle::CargoHandler;
-use crate::cargo_communication::cargo_types::event::CargoMessage;
+use crate::cargo_communication::cargo_types::command_creation_details::CommandCreationDetails;
+use crate::cargo_communication::cargo_types::event::{CancelData, CancelReason, CargoMessage};
 use crate::cargo_communication::execution::execution_actor::ExecutionActor;
+use crate::cargo_communication::execution::execution_actor_state::TaskState;
 use crate::cargo_communication::execution::execution_types::cargo_result::CargoResult;
 use crate::cargo_communication::execution::execution_types::create_unit_graph_command::CreateUnitGraphCommand;
+use crate::cargo_communication::execution::execution_types::debug_session::test_debug_session_address;
 use crate::cargo_communication::execution::execution_types::origin_id::WithOriginId;
-use crate::cargo_communication::execution::utils::get_current_time;
-use bsp4rs::bsp::{Identifier, OriginId, StatusCode};
+use crate::cargo_communication::execution::utils::now_millis;
+use bsp4rs::bsp::{DebugSessionAddress, Identifier, OriginId, StatusCode, URI};
 use bsp4rs::bsp::{
     LogMessageParams, MessageType, OnBuildLogMessage, OnBuildTaskFinish, OnBuildTaskProgress,
     OnBuildTaskStart, TaskFinishData, TaskFinishParams, TaskId, TaskProgressParams, TaskStartData,
@@ -31,17 +35,41 @@ where
     C: CargoHandler<CargoMessage>,
 {
     pub(super) fn send_response(&self, command_result: io::Result<ExitStatus>) {
+        let debug_session = match (&command_result, self.params.get_debug_arguments()) {
+            (Ok(exit_status), Some(args)) if exit_status.success() => {
+                Some(self.test_debug_session(&args))
+            }
+            _ => None,
+        };
+        let (command_result, debug_session) = match debug_session {
+            Some(Err(e)) => (Err(e), None),
+            Some(Ok(address)) => (command_result, Some(address)),
+            None => (command_result, None),
+        };
         self.send(Message::Response(Response {
             id: self.req_id.clone(),
             result: command_result.as_ref().ok().map(|exit_status| {
-                to_value(R::Result::create_result(
-                    self.params.origin_id(),
+                let result = R::Result::create_result(
+                    self.task_origin_id(),
                     match exit_status.code() {
                         Some(0) => StatusCode::Ok,
                         _ => StatusCode::Error,
                     },
-                ))
-                .unwrap()
+                    self.state.compile_state.errors,
+                );
+                let result = match debug_session {
+                    Some(address) => result.with_debug_session(address),
+                    None => result,
+                };
+                let doc_index_files = self
+                    .state
+                    .compile_state
+                    .doc_index_files
+                    .iter()
+                    .filter_map(|f| Url::from_file_path(f).ok())
+                    .map(|uri| URI::new(uri.to_string()))
+                    .collect();
+                to_value(result.with_doc_index_files(doc_index_files)).unwrap()
             }),
             error: command_result.as_ref().err().map(|e| ResponseError {
                 code: ErrorCode::InternalError as i32,
@@ -51,7 +79,29 @@ where
         }));
     }
 
-    pub(super) fn send_cancel_response(&self) {
+    /// The debugged tests must have been compiled to exactly one test executable.
+    fn test_debug_session(&self, args: &[String]) -> io::Result<DebugSessionAddress> {
+        let executables = match &self.state.task_state {
+            TaskState::Test(test_state) => test_state.test_executables.as_slice(),
+            _ => &[],
+        };
+        match executables {
+            [executable] => test_debug_session_address(executable, args),
+            [] => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "The requested targets produced no test executable to debug",
+            )),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "The requested targets produced {} test executables, only one can be debugged",
+                    executables.len()
+                ),
+            )),
+        }
+    }
+
+    pub(super) fn send_cancel_response(&self, reason: CancelReason) {
         self.report_task_finish(
             self.state.root_task_id.clone(),
             StatusCode::Cancelled,
@@ -60,8 +110,33 @@ where
         );
         let error = ResponseError {
             code: ErrorCode::RequestCanceled as i32,
-            message: "canceled by client".to_string(),
-            data: None,
+            message: reason.message().to_string(),
+            data: Some(self.cancel_data(reason)),
+        };
+        self.send(
+            Response {
+                id: self.req_id.clone(),
+                result: None,
+                error: Some(error),
+            }
+            .into(),
+        );
+    }
+
+    pub(super) fn send_timeout_response(&self) {
+        self.report_task_finish(
+            self.state.root_task_id.clone(),
+            StatusCode::Error,
+            None,
+            None,
+        );
+        let error = ResponseError {
+            code: ErrorCode::InternalError as i32,
+            message: format!(
+                "Cargo command produced no output and did not finish within {:?}",
+                self.command_timeout.unwrap_or_default()
+            ),
+            data: Some(self.cancel_data(CancelReason::Timeout)),
         };
         self.send(
             Response {
@@ -73,6 +148,21 @@ where
         );
     }
 
+    fn cancel_data(&self, reason: CancelReason) -> serde_json::Value {
+        to_value(CancelData::new(reason, self.state.start_time)).unwrap()
+    }
+
+    /// Origin id of the request, attached to all the task notifications (and their
+    /// data) sent for the request, so that the client can correlate them.
+    pub(super) fn task_origin_id(&self) -> Option<Identifier> {
+        self.params.origin_id()
+    }
+
+    /// Origin id of the request, attached to the log messages and the diagnostics.
+    pub(super) fn message_origin_id(&self) -> Option<OriginId> {
+        self.task_origin_id().map(|id| OriginId::new(id.0))
+    }
+
     pub(super) fn report_root_task_start(&self) {
         self.report_task_start(self.state.root_task_id.clone(), None, None);
     }
@@ -85,8 +175,8 @@ where
     ) {
         self.send_notification::<OnBuildTaskStart>(TaskStartParams {
             task_id,
-            origin_id: self.params.origin_id().map(|id| Identifier::new(id.0)),
-            event_time: Some(get_current_time()),
+            origin_id: self.task_origin_id(),
+            event_time: Some(now_millis()),
             message,
             data,
         });
@@ -102,8 +192,8 @@ where
     ) {
         self.send_notification::<OnBuildTaskProgress>(TaskProgressParams {
             task_id,
-            origin_id: self.params.origin_id().map(|id| Identifier::new(id.0)),
-            event_time: Some(get_current_time()),
+            origin_id: self.task_origin_id(),
+            event_time: Some(now_millis()),
             message,
             total,
             progress,
@@ -121,8 +211,8 @@ where
     ) {
         self.send_notification::<OnBuildTaskFinish>(TaskFinishParams {
             task_id,
-            origin_id: self.params.origin_id().map(|id| Identifier::new(id.0)),
-            event_time: Some(get_current_time()),
+            origin_id: self.task_origin_id(),
+            event_time: Some(now_millis()),
             message,
             status,
             data,
@@ -139,11 +229,24 @@ where
         self.send_notification::<OnBuildLogMessage>(LogMessageParams {
             r#type: message_type,
             task: Some(task_id),
-            origin_id: self.params.origin_id().map(|id| OriginId::new(id.0)),
+            origin_id: self.message_origin_id(),
             message,
         });
     }
 
+    pub(super) fn log_dropped_arguments(&self) {
+        for arg in self.params.get_dropped_arguments() {
+            self.log_message(
+                MessageType::Warning,
+                format!(
+                    "Argument `{}` is not allowed or repeated and has been dropped",
+                    arg
+                ),
+                Some(self.state.root_task_id.clone()),
+            );
+        }
+    }
+
     pub(super) fn send_notification<T>(&self, notification: T::Params)
     where
         T: NotificationTrait,
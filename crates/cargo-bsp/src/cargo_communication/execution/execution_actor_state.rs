@@ -3,15 +3,21 @@
 //! creation of notifications and responses for the client (especially the state
 //! sets and stores TaskIds of all tasks that may potentially be started).
 
-use bsp4rs::bsp::{BuildTargetIdentifier, Identifier};
-use std::collections::HashMap;
+use bsp4rs::bsp::{BuildTargetIdentifier, Identifier, PublishDiagnosticsParams};
+use cargo_metadata::camino::Utf8PathBuf;
+use log::debug;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 
 use bsp4rs::bsp::TaskId;
 use bsp4rs::bsp::{BuildTargetRun, BuildTargetTest};
 use bsp4rs::Request;
 
+use crate::cargo_communication::execution::execution_types::compile_summary::{
+    parse_compile_summary, CompileSummary,
+};
 use crate::cargo_communication::execution::utils::{
-    generate_random_id, generate_task_id, get_current_time,
+    generate_random_id, generate_task_id, now_millis,
 };
 
 pub struct ExecutionActorState {
@@ -19,6 +25,7 @@ pub struct ExecutionActorState {
     pub(super) unit_graph_state: UnitGraphState,
     pub(super) compile_state: CompileState,
     pub(super) task_state: TaskState,
+    pub(super) start_time: Instant,
 }
 
 pub enum TaskState {
@@ -38,17 +45,55 @@ pub struct CompileState {
     pub(super) errors: i32,
     pub(super) warnings: i32,
     pub(super) compilation_step: Option<i64>,
+    /// Whether all compiler artifacts produced so far were fresh (up to date),
+    /// `None` if no artifact was produced yet.
+    pub(super) all_artifacts_fresh: Option<bool>,
+    pub(super) build_failed: bool,
+    /// Whether the `BuildFinished` message was received.
+    pub(super) build_finished: bool,
+    /// Whether Cargo is blocked on the file lock held by another process.
+    pub(super) waiting_for_build_lock: bool,
+    /// Stderr lines from the first error reported before the build finished,
+    /// e.g. the error from resolving the dependencies.
+    pub(super) pre_build_errors: Vec<String>,
+    /// Diagnostics for a single file, not yet published to the client.
+    pub(super) pending_diagnostics: Option<PublishDiagnosticsParams>,
     pub(super) target_states: HashMap<BuildTargetIdentifier, CompileTargetState>,
+    /// Packages whose build script execution was already reported.
+    pub(super) executed_build_scripts: HashSet<String>,
+    /// Packages whose build script was compiled, but not yet executed,
+    /// with the ids of their build script subtasks.
+    pub(super) running_build_scripts: HashMap<String, TaskId>,
+    /// Counts from the Cargo summary lines, `None` if Cargo printed no summary.
+    pub(super) summary: Option<CompileSummary>,
+    /// The `index.html` files of the crates documented by the doc request.
+    pub(super) doc_index_files: Vec<Utf8PathBuf>,
 }
 
 #[derive(Default)]
 pub struct CompileTargetState {
     pub(super) task_id: TaskId,
     pub(super) start_time: i64,
+    /// Time of the first compiler artifact of the target. Cargo doesn't report
+    /// when a single target is compiled, so the first artifact approximates it.
+    pub(super) first_artifact_time: Option<i64>,
+}
+
+impl CompileTargetState {
+    /// Elapsed milliseconds from the compilation start to the first artifact of the target
+    /// or, if the target produced no artifact, to the given time of the build finish.
+    pub(super) fn compile_time(&self, build_finish_time: i64) -> i64 {
+        self.first_artifact_time.unwrap_or(build_finish_time) - self.start_time
+    }
 }
 
+#[derive(Default)]
 pub struct RunState {
     pub(super) task_id: TaskId,
+    /// Executables of the compiled run target, started once the build finishes.
+    pub(super) executables: Vec<Utf8PathBuf>,
+    /// Whether the executable was started, after Cargo finished.
+    pub(super) executable_started: bool,
 }
 
 #[derive(Default)]
@@ -61,12 +106,20 @@ pub struct TestState {
     /// Maps single tests name (by which they are recognized by Cargo) to the TaskId
     /// of the task that they started.
     pub(super) single_test_task_ids: HashMap<String, TaskId>,
+    /// Executables of the compiled test targets, launched by the debugger
+    /// if the tests are debugged.
+    pub(super) test_executables: Vec<Utf8PathBuf>,
 }
 
 #[derive(Default)]
 pub struct SuiteTaskProgress {
+    /// Number of the finished tests of the suite.
     pub(super) progress: i64,
+    /// Number of the tests announced by the suite, raised if more tests are started.
     pub(super) total: i64,
+    /// Number of the tests that have actually started, reported as the total
+    /// when the suite finishes (e.g. zero for the suite with all tests filtered out).
+    pub(super) started: i64,
 }
 
 impl CompileState {
@@ -95,11 +148,60 @@ impl CompileState {
         self.compilation_step = self.compilation_step.map(|s| s + 1);
     }
 
+    pub fn record_artifact_freshness(&mut self, fresh: bool) {
+        self.all_artifacts_fresh = Some(self.all_artifacts_fresh.unwrap_or(true) && fresh);
+    }
+
+    /// Compilation was a no-op if all the produced artifacts were fresh.
+    pub fn is_no_op(&self) -> Option<bool> {
+        self.all_artifacts_fresh.filter(|&fresh| fresh)
+    }
+
+    /// Lines are recorded starting from the first error line, so that
+    /// the notes following the error are kept as well.
+    pub fn record_pre_build_stderr(&mut self, msg: &str) {
+        if !self.build_finished
+            && (!self.pre_build_errors.is_empty() || msg.trim_start().starts_with("error"))
+        {
+            self.pre_build_errors.push(msg.to_string());
+        }
+    }
+
+    pub fn record_summary_line(&mut self, msg: &str) {
+        if let Some(summary) = parse_compile_summary(msg) {
+            *self.summary.get_or_insert_with(CompileSummary::default) += summary;
+        }
+    }
+
+    /// Cross-checks the counted diagnostics with the Cargo summary. Cargo counts also
+    /// the diagnostics without a primary span, so the larger of the counts is reported.
+    pub fn fold_summary(&mut self) {
+        let Some(summary) = self.summary else {
+            return;
+        };
+        if summary.errors != self.errors || summary.warnings != self.warnings {
+            debug!(
+                "Cargo reported {} errors and {} warnings, but {} errors and {} warnings were counted",
+                summary.errors, summary.warnings, self.errors, self.warnings
+            );
+        }
+        self.errors = self.errors.max(summary.errors);
+        self.warnings = self.warnings.max(summary.warnings);
+    }
+
     pub fn set_start_time(&mut self, build_target_id: &BuildTargetIdentifier) {
         self.target_states
             .get_mut(build_target_id)
             .unwrap()
-            .start_time = get_current_time();
+            .start_time = now_millis();
+    }
+
+    pub fn record_first_artifact_time(&mut self, build_target_id: &BuildTargetIdentifier) {
+        if let Some(target_state) = self.target_states.get_mut(build_target_id) {
+            target_state
+                .first_artifact_time
+                .get_or_insert_with(now_millis);
+        }
     }
 
     pub fn get_target_task_id(&self, build_target_id: &BuildTargetIdentifier) -> TaskId {
@@ -116,6 +218,7 @@ impl TaskState {
         match R::METHOD {
             BuildTargetRun::METHOD => TaskState::Run(RunState {
                 task_id: generate_task_id(&root_task_id),
+                ..RunState::default()
             }),
             BuildTargetTest::METHOD => {
                 let test_task_id = generate_task_id(&root_task_id);
@@ -147,6 +250,7 @@ impl ExecutionActorState {
             },
             compile_state: CompileState::new(&root_task_id, build_targets),
             task_state: TaskState::new::<R>(root_task_id),
+            start_time: Instant::now(),
         }
     }
 
@@ -43,8 +43,12 @@ where
 
         while let Some(event) = self.next_event() {
             match event {
-                Event::Cancel => {
-                    self.cancel();
+                Event::Cancel(reason) => {
+                    self.cancel(reason);
+                    return UnitGraphStatusCode::Cancelled;
+                }
+                Event::Timeout => {
+                    self.timeout();
                     return UnitGraphStatusCode::Cancelled;
                 }
                 Event::CargoFinish => {
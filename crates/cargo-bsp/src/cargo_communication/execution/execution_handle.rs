@@ -1,7 +1,7 @@
-//! Creates and spawns `cargo check` command and runs a new [`ExecutionActor`] in
-//! a new thread. Implementation of [`RequestHandle`].
+//! Creates and spawns the unit graph command and the requested Cargo command (such as
+//! `cargo build` or `cargo test`) and runs a new [`ExecutionActor`] in a new thread,
+//! which also spawns the binary built for the run request. Implementation of [`RequestHandle`].
 
-use std::io;
 use std::process::Command;
 
 use bsp_server::{Message, RequestId};
@@ -10,9 +10,10 @@ use crossbeam_channel::unbounded;
 use bsp4rs::bsp::StatusCode;
 use bsp4rs::Request;
 
+#[cfg(not(feature = "tokio-output-reader"))]
 use crate::cargo_communication::cargo_handle::CargoHandle;
 use crate::cargo_communication::cargo_types::create_command::CreateCommand;
-use crate::cargo_communication::cargo_types::event::Event;
+use crate::cargo_communication::cargo_types::event::{CancelReason, Event};
 use crate::cargo_communication::cargo_types::params_target::ParamsTarget;
 use crate::cargo_communication::execution::execution_actor::ExecutionActor;
 use crate::cargo_communication::execution::execution_actor_unit_graph::UnitGraphStatusCode;
@@ -21,7 +22,17 @@ use crate::cargo_communication::execution::execution_types::create_unit_graph_co
 use crate::cargo_communication::execution::execution_types::origin_id::WithOriginId;
 use crate::cargo_communication::execution::utils::targets_ids_to_targets_details;
 use crate::cargo_communication::request_handle::RequestHandle;
+#[cfg(feature = "tokio-output-reader")]
+use crate::cargo_communication::tokio_cargo_handle::TokioCargoHandle;
 use crate::server::global_state::GlobalStateSnapshot;
+use crate::server::ServerError;
+
+/// Handle of the unit graph and the requested commands. With the `tokio-output-reader` feature,
+/// the output of the commands is read on the tokio runtime (the actor still runs on its thread).
+#[cfg(not(feature = "tokio-output-reader"))]
+type ExecutionCargoHandle = CargoHandle;
+#[cfg(feature = "tokio-output-reader")]
+type ExecutionCargoHandle = TokioCargoHandle;
 
 impl RequestHandle {
     pub fn spawn<R>(
@@ -29,7 +40,7 @@ impl RequestHandle {
         req_id: RequestId,
         params: R::Params,
         global_state: GlobalStateSnapshot,
-    ) -> io::Result<RequestHandle>
+    ) -> Result<RequestHandle, ServerError>
     where
         R: Request + 'static,
         R::Params: CreateUnitGraphCommand + CreateCommand + ParamsTarget + WithOriginId + Send,
@@ -40,11 +51,20 @@ impl RequestHandle {
             &params.get_targets(global_state.workspace),
             &global_state,
         )?;
-        let mut unit_graph_cmd = params.create_unit_graph_command(root_path, &targets_details);
-        let mut requested_cmd = params.create_requested_command(root_path, &targets_details);
-        let cargo_handle = CargoHandle::spawn(&mut unit_graph_cmd)?;
+        let mut unit_graph_cmd = params.create_unit_graph_command(
+            root_path,
+            &targets_details,
+            &global_state.config.settings,
+        );
+        let mut requested_cmd = params.create_requested_command(
+            root_path,
+            &targets_details,
+            &global_state.config.settings,
+        );
+        let cargo_handle =
+            ExecutionCargoHandle::spawn(&mut unit_graph_cmd).map_err(ServerError::Spawn)?;
         let (cancel_sender, cancel_receiver) = unbounded::<Event>();
-        let actor: ExecutionActor<R, CargoHandle> = ExecutionActor::new(
+        let actor: ExecutionActor<R, ExecutionCargoHandle> = ExecutionActor::new(
             sender_to_main,
             req_id,
             params,
@@ -52,32 +72,37 @@ impl RequestHandle {
             cargo_handle,
             cancel_receiver,
             global_state.workspace,
+            &global_state.config.settings,
         );
-        let thread =
-            jod_thread::Builder::new().spawn(move || run_commands(actor, &mut requested_cmd))?;
+        let thread = jod_thread::Builder::new()
+            .spawn(move || run_commands(actor, &mut requested_cmd))
+            .map_err(ServerError::Spawn)?;
         Ok(RequestHandle {
             cancel_sender,
             _thread: thread,
         })
     }
 
-    pub fn cancel(&self) {
-        self.cancel_sender.send(Event::Cancel).unwrap();
+    pub fn cancel(&self, reason: CancelReason) {
+        // The actor might have already finished, then there is nothing to cancel.
+        let _ = self.cancel_sender.send(Event::Cancel(reason));
     }
 }
 
-fn run_commands<R>(mut actor: ExecutionActor<R, CargoHandle>, requested_cmd: &mut Command)
+fn run_commands<R>(mut actor: ExecutionActor<R, ExecutionCargoHandle>, requested_cmd: &mut Command)
 where
     R: Request + 'static,
-    R::Params: CreateUnitGraphCommand + ParamsTarget + WithOriginId + Send,
+    R::Params: CreateUnitGraphCommand + CreateCommand + ParamsTarget + WithOriginId + Send,
     R::Result: CargoResult,
 {
+    actor.spawn_executable = Some(Box::new(ExecutionCargoHandle::spawn_executable));
     actor.report_root_task_start();
+    actor.log_dropped_arguments();
     let unit_graph_status_code = actor.run_unit_graph();
     // We don't run requested command, if request was cancelled during
     // unit graph command.
     if let UnitGraphStatusCode::Ok = unit_graph_status_code {
-        match CargoHandle::spawn(requested_cmd) {
+        match ExecutionCargoHandle::spawn(requested_cmd) {
             Ok(cargo_handle) => {
                 actor.cargo_handle = Some(cargo_handle);
                 actor.run();
@@ -1,26 +1,49 @@
-//! CargoResult trait implementation for the Compile/Run/TestResult.
+//! CargoResult trait implementation for the Compile/Run/Test/DocResult.
 //! Allows creating the result for the client regardless if its the compile,
 //! run or test request.
 
-use bsp4rs::bsp::{CompileResult, RunResult, TestResult};
-use bsp4rs::bsp::{Identifier, StatusCode};
+use bsp4rs::bsp::{CompileResult, CompileResultData, RunResult, TestResult, TestResultData};
+use bsp4rs::bsp::{DebugSessionAddress, Identifier, StatusCode, URI};
+use bsp4rs::cargo::{CargoCompileResult, DocResult};
 
 pub trait CargoResult {
-    fn create_result(origin_id: Option<Identifier>, status_code: StatusCode) -> Self;
+    /// `errors` is the number of the compilation errors reported to the client.
+    fn create_result(origin_id: Option<Identifier>, status_code: StatusCode, errors: i32) -> Self;
+
+    /// Only the test result carries the address of the debugged tests.
+    fn with_debug_session(self, _: DebugSessionAddress) -> Self
+    where
+        Self: Sized,
+    {
+        self
+    }
+
+    /// Only the doc result carries the generated documentation.
+    fn with_doc_index_files(self, _: Vec<URI>) -> Self
+    where
+        Self: Sized,
+    {
+        self
+    }
 }
 
 impl CargoResult for CompileResult {
-    fn create_result(origin_id: Option<Identifier>, status_code: StatusCode) -> Self {
+    /// The number of errors is included only if the compilation failed.
+    fn create_result(origin_id: Option<Identifier>, status_code: StatusCode, errors: i32) -> Self {
+        let data = match status_code {
+            StatusCode::Error => Some(CompileResultData::cargo(CargoCompileResult { errors })),
+            _ => None,
+        };
         CompileResult {
             origin_id,
             status_code,
-            data: None,
+            data,
         }
     }
 }
 
 impl CargoResult for RunResult {
-    fn create_result(origin_id: Option<Identifier>, status_code: StatusCode) -> Self {
+    fn create_result(origin_id: Option<Identifier>, status_code: StatusCode, _: i32) -> Self {
         RunResult {
             origin_id,
             status_code,
@@ -29,11 +52,35 @@ impl CargoResult for RunResult {
 }
 
 impl CargoResult for TestResult {
-    fn create_result(origin_id: Option<Identifier>, status_code: StatusCode) -> Self {
+    fn create_result(origin_id: Option<Identifier>, status_code: StatusCode, _: i32) -> Self {
         TestResult {
             origin_id,
             status_code,
             data: None,
         }
     }
+
+    fn with_debug_session(self, address: DebugSessionAddress) -> Self {
+        TestResult {
+            data: Some(TestResultData::debug_session_address(address)),
+            ..self
+        }
+    }
+}
+
+impl CargoResult for DocResult {
+    fn create_result(origin_id: Option<Identifier>, status_code: StatusCode, _: i32) -> Self {
+        DocResult {
+            origin_id,
+            status_code,
+            index_files: vec![],
+        }
+    }
+
+    fn with_doc_index_files(self, index_files: Vec<URI>) -> Self {
+        DocResult {
+            index_files,
+            ..self
+        }
+    }
 }
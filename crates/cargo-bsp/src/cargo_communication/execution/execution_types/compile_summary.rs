@@ -0,0 +1,115 @@
+//! Parses the summary lines printed by Cargo on stderr after compiling each unit, e.g.
+//! ``warning: `foo` (lib) generated 3 warnings`` or
+//! ``error: could not compile `foo` (lib) due to 2 previous errors; 1 warning emitted``.
+
+use std::ops::AddAssign;
+
+/// Numbers of the errors and warnings reported by Cargo in the summary lines.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CompileSummary {
+    pub(in crate::cargo_communication::execution) errors: i32,
+    pub(in crate::cargo_communication::execution) warnings: i32,
+}
+
+impl AddAssign for CompileSummary {
+    fn add_assign(&mut self, other: Self) {
+        self.errors += other.errors;
+        self.warnings += other.warnings;
+    }
+}
+
+/// Returns `None` if the line is not a summary line.
+pub(in crate::cargo_communication::execution) fn parse_compile_summary(
+    line: &str,
+) -> Option<CompileSummary> {
+    let line = line.trim();
+    if let Some(rest) = line.strip_prefix("warning: ") {
+        let (_, counts) = rest.split_once(" generated ")?;
+        return Some(CompileSummary {
+            errors: 0,
+            warnings: leading_count(counts, "warning")?,
+        });
+    }
+    let rest = line.strip_prefix("error: could not compile ")?;
+    let (_, counts) = rest.split_once(" due to ")?;
+    let (errors, warnings) = match counts.split_once("; ") {
+        Some((errors, warnings)) => (errors, leading_count(warnings, "warning")?),
+        None => (counts, 0),
+    };
+    // Older Cargo versions print `due to previous error` for a single error.
+    let errors = if errors.starts_with("previous error") {
+        1
+    } else {
+        leading_count(errors, "previous error")?
+    };
+    Some(CompileSummary { errors, warnings })
+}
+
+/// Parses the count from the `<count> <noun>[s]...` text.
+fn leading_count(text: &str, noun: &str) -> Option<i32> {
+    let (count, rest) = text.split_once(' ')?;
+    if !rest.starts_with(noun) {
+        return None;
+    }
+    count.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(errors: i32, warnings: i32) -> Option<CompileSummary> {
+        Some(CompileSummary { errors, warnings })
+    }
+
+    #[test]
+    fn warnings_summary() {
+        assert_eq!(
+            parse_compile_summary("warning: `foo` (lib) generated 3 warnings"),
+            summary(0, 3)
+        );
+        assert_eq!(
+            parse_compile_summary("warning: `foo` (bin \"foo\") generated 1 warning\n"),
+            summary(0, 1)
+        );
+        assert_eq!(
+            parse_compile_summary(
+                "warning: `foo` (lib test) generated 2 warnings (1 duplicate) \
+                (run `cargo fix --lib -p foo --tests` to apply 1 suggestion)"
+            ),
+            summary(0, 2)
+        );
+    }
+
+    #[test]
+    fn errors_summary() {
+        assert_eq!(
+            parse_compile_summary("error: could not compile `foo` (lib) due to 2 previous errors"),
+            summary(2, 0)
+        );
+        assert_eq!(
+            parse_compile_summary(
+                "error: could not compile `foo` (lib) due to 1 previous error; 4 warnings emitted"
+            ),
+            summary(1, 4)
+        );
+        assert_eq!(
+            parse_compile_summary("error: could not compile `foo` due to previous error"),
+            summary(1, 0)
+        );
+    }
+
+    #[test]
+    fn other_lines_ignored() {
+        assert_eq!(parse_compile_summary("warning: unused variable: `x`"), None);
+        assert_eq!(
+            parse_compile_summary("warning: build failed, waiting for other jobs to finish..."),
+            None
+        );
+        assert_eq!(
+            parse_compile_summary("error[E0425]: cannot find value `x`"),
+            None
+        );
+        assert_eq!(parse_compile_summary("   Compiling foo v0.1.0"), None);
+    }
+}
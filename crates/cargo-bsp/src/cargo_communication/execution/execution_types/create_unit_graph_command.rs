@@ -1,4 +1,4 @@
-//! CreateUnitGraphCommand trait implementation for the Compile/Run/TestParams. The trait allows creating
+//! CreateUnitGraphCommand trait implementation for the Compile/Run/Test/DocParams. The trait allows creating
 //! commands regardless if it is the compile, run or test request.
 //!
 //! The created commands are unit graph commands, which are the same as requested commands (see [`create_command`])
@@ -12,13 +12,25 @@ use crate::cargo_communication::cargo_types::command_utils::{
     targets_details_to_args, CommandType,
 };
 use crate::project_model::target_details::TargetDetails;
+use crate::server::config::ServerSettings;
 use bsp4rs::bsp::{CompileParams, RunParams, TestParams};
+use bsp4rs::cargo::DocParams;
 use std::process::Command;
 
 pub(crate) trait CreateUnitGraphCommand: CommandCreationDetails {
-    fn create_unit_graph_command(&self, root: &Path, targets_details: &[TargetDetails]) -> Command {
-        let targets_args = targets_details_to_args(targets_details);
-        cargo_command_with_unit_graph(Self::get_command_type(), root, targets_args)
+    fn create_unit_graph_command(
+        &self,
+        root: &Path,
+        targets_details: &[TargetDetails],
+        settings: &ServerSettings,
+    ) -> Command {
+        let targets_args = targets_details_to_args(targets_details, &settings.feature_mode);
+        cargo_command_with_unit_graph(
+            Self::get_command_type(),
+            root,
+            targets_args,
+            settings.lock_flags(),
+        )
     }
 }
 
@@ -28,10 +40,13 @@ impl CreateUnitGraphCommand for RunParams {}
 
 impl CreateUnitGraphCommand for TestParams {}
 
+impl CreateUnitGraphCommand for DocParams {}
+
 fn cargo_command_with_unit_graph(
     command_type: CommandType,
     root: &Path,
     targets_args: Vec<String>,
+    lock_flags: Vec<String>,
 ) -> Command {
     let mut cmd = Command::new(toolchain::cargo());
     cmd.current_dir(root)
@@ -42,6 +57,7 @@ fn cargo_command_with_unit_graph(
             "-Z",
             "unstable-options",
         ])
-        .args(targets_args);
+        .args(targets_args)
+        .args(lock_flags);
     cmd
 }
@@ -0,0 +1,55 @@
+//! Debug session address of the tests compiled by the test request with the
+//! `cargo-test-debug` data. The server does not host a debug adapter, the address
+//! tells the client which test executable to launch under its debugger and with which
+//! test harness arguments (e.g. the names of the tests to run).
+
+use std::io;
+
+use cargo_metadata::camino::Utf8Path;
+use url::Url;
+
+use bsp4rs::bsp::{DebugSessionAddress, URI};
+
+/// The `file` URI of the executable, with the arguments as the `arg` query parameters (in order).
+pub(crate) fn test_debug_session_address(
+    executable: &Utf8Path,
+    args: &[String],
+) -> io::Result<DebugSessionAddress> {
+    let mut uri = Url::from_file_path(executable).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            format!("Test executable {} is not an absolute path", executable),
+        )
+    })?;
+    if !args.is_empty() {
+        uri.query_pairs_mut()
+            .extend_pairs(args.iter().map(|arg| ("arg", arg)));
+    }
+    Ok(DebugSessionAddress {
+        uri: URI::new(uri.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_session_address_with_test_filter() {
+        let address = test_debug_session_address(
+            Utf8Path::new("/project/target/debug/deps/app-123"),
+            &["tests::single test".to_string(), "--exact".to_string()],
+        )
+        .unwrap();
+
+        assert_eq!(
+            address.uri.0,
+            "file:///project/target/debug/deps/app-123?arg=tests%3A%3Asingle+test&arg=--exact"
+        );
+    }
+
+    #[test]
+    fn relative_executable_rejected() {
+        assert!(test_debug_session_address(Utf8Path::new("target/app"), &[]).is_err());
+    }
+}
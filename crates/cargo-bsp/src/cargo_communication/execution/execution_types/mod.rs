@@ -2,7 +2,9 @@
 //! from Cargo build/run/test commands.
 
 pub(crate) mod cargo_result;
+pub(super) mod compile_summary;
 pub(crate) mod create_unit_graph_command;
+pub(super) mod debug_session;
 pub(crate) mod origin_id;
 pub(super) mod publish_diagnostics;
 pub(super) mod test;
@@ -1,8 +1,13 @@
-//! OriginId trait implementation for the Compile/Run/TestParams. The trait allows getting
-//! origin id regardless if it is the compile, run or test request.
+//! OriginId trait implementation for the Compile/Run/Test/DocParams and the params of the
+//! requests handled by the [`OutputActor`] or spawned in a similar way. The trait allows getting origin id regardless
+//! if it is the compile, run or test request.
+//!
+//! [`OutputActor`]: crate::cargo_communication::output_actor::OutputActor
 
 use bsp4rs::bsp::Identifier;
-use bsp4rs::bsp::{CompileParams, RunParams, TestParams};
+use bsp4rs::bsp::{CleanCacheParams, CompileParams, RunParams, TestParams};
+use bsp4rs::cargo::DocParams;
+use bsp4rs::rust::RustFormatCheckParams;
 
 pub trait WithOriginId {
     fn origin_id(&self) -> Option<Identifier>;
@@ -25,3 +30,29 @@ impl WithOriginId for TestParams {
         self.origin_id.clone()
     }
 }
+
+impl WithOriginId for DocParams {
+    fn origin_id(&self) -> Option<Identifier> {
+        self.origin_id.clone()
+    }
+}
+
+impl WithOriginId for RustFormatCheckParams {
+    fn origin_id(&self) -> Option<Identifier> {
+        self.origin_id.clone()
+    }
+}
+
+/// The clean cache request has no origin id.
+impl WithOriginId for CleanCacheParams {
+    fn origin_id(&self) -> Option<Identifier> {
+        None
+    }
+}
+
+/// The requests without params have no origin id.
+impl WithOriginId for () {
+    fn origin_id(&self) -> Option<Identifier> {
+        None
+    }
+}
@@ -7,13 +7,16 @@ use cargo_metadata::diagnostic::{
 };
 use itertools::Itertools;
 use paths::AbsPath;
+use serde::Serialize;
 use url::Url;
 
 use bsp4rs::bsp::{BuildTargetIdentifier, OriginId, TextDocumentIdentifier, URI};
 use bsp4rs::bsp::{
-    CodeDescription, Diagnostic, DiagnosticCode as Code, DiagnosticRelatedInformation,
-    DiagnosticSeverity, DiagnosticTag, Location, Position, PublishDiagnosticsParams, Range,
+    CodeDescription, Diagnostic, DiagnosticCode as Code, DiagnosticData,
+    DiagnosticRelatedInformation, DiagnosticSeverity, DiagnosticTag, Location, Position,
+    PublishDiagnosticsParams, Range,
 };
+use bsp4rs::OtherData;
 
 /// Diagnostics are sent to the client as `publishDiagnostic` notification.
 /// GlobalMessage is sent to the client as `logMessage` notification.
@@ -42,11 +45,15 @@ enum MappedRustChildDiagnostic {
 ///    `relatedInformation` or additional message lines.
 ///
 /// If the diagnostic has no primary span it will be classified as `GlobalMessage`.
+///
+/// If `keep_rendered` is set, the diagnostic rendered by the compiler (e.g. with the ANSI
+/// colors) is attached to the data of the primary diagnostic (with the `rendered` data kind).
 pub fn map_cargo_diagnostic_to_bsp(
     diagnostic: &MetadataDiagnostic,
     origin_id: Option<OriginId>,
     build_target: &BuildTargetIdentifier,
     workspace_root: &AbsPath,
+    keep_rendered: bool,
 ) -> DiagnosticMessage {
     let primary_spans: Vec<&DiagnosticSpan> =
         diagnostic.spans.iter().filter(|s| s.is_primary).collect();
@@ -78,6 +85,11 @@ pub fn map_cargo_diagnostic_to_bsp(
     }
 
     let tags = diagnostic_tags(&diagnostic.code);
+    let data = diagnostic
+        .rendered
+        .as_ref()
+        .filter(|_| keep_rendered)
+        .and_then(|rendered| rendered_diagnostic_data(rendered));
 
     // Indicates whether primary span label needs to be added to the diagnostics
     // message.
@@ -190,7 +202,7 @@ pub fn map_cargo_diagnostic_to_bsp(
                 }
             },
             tags: tags.as_ref().cloned(),
-            data: None,
+            data: data.clone(),
         };
         add_diagnostic(primary_location.uri.clone(), diagnostic, &mut diagnostics);
 
@@ -217,6 +229,24 @@ pub fn map_cargo_diagnostic_to_bsp(
     DiagnosticMessage::Diagnostics(create_diagnostics(diagnostics, origin_id, build_target))
 }
 
+/// Data of the diagnostic with the diagnostic rendered by the compiler, the ANSI
+/// escape codes are kept.
+#[derive(Serialize)]
+struct RenderedDiagnostic<'a> {
+    rendered: &'a str,
+}
+
+const RENDERED_DIAGNOSTIC_DATA_KIND: &str = "rendered";
+
+fn rendered_diagnostic_data(rendered: &str) -> Option<DiagnosticData> {
+    OtherData::from_typed(
+        RENDERED_DIAGNOSTIC_DATA_KIND,
+        &RenderedDiagnostic { rendered },
+    )
+    .ok()
+    .map(DiagnosticData::Other)
+}
+
 fn create_diagnostics(
     diagnostics: HashMap<URI, Vec<Diagnostic>>,
     origin_id: Option<OriginId>,
@@ -4,8 +4,9 @@
 
 use serde::{Deserialize, Serialize};
 
-use bsp4rs::bsp::BuildTargetIdentifier;
-use bsp4rs::bsp::{TaskFinishData, TestFinish, TestReport, TestStatus};
+use bsp4rs::bsp::{BuildTargetIdentifier, Identifier};
+use bsp4rs::bsp::{TaskFinishData, TestFinish, TestFinishData, TestReport, TestStatus};
+use bsp4rs::cargo::CargoBenchResult;
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
@@ -13,6 +14,8 @@ use bsp4rs::bsp::{TaskFinishData, TestFinish, TestReport, TestStatus};
 pub enum TestType {
     Suite(SuiteEvent),
     Test(TestEvent),
+    /// Result of a benchmark, sent after the `started` event of the benchmark.
+    Bench(BenchResult),
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -41,10 +44,14 @@ pub struct SuiteResults {
 }
 
 impl SuiteResults {
-    pub fn to_test_report(&self, target: BuildTargetIdentifier) -> TaskFinishData {
+    pub fn to_test_report(
+        &self,
+        target: BuildTargetIdentifier,
+        origin_id: Option<Identifier>,
+    ) -> TaskFinishData {
         #[allow(deprecated)]
         TaskFinishData::test_report(TestReport {
-            origin_id: None,
+            origin_id,
             target,
             passed: self.passed,
             failed: self.failed,
@@ -106,3 +113,54 @@ impl TestResult {
         }
     }
 }
+
+/// Timing of a benchmark, in nanoseconds per iteration.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BenchResult {
+    pub(crate) name: String,
+    pub(crate) median: f64,
+    pub(crate) deviation: f64,
+}
+
+impl BenchResult {
+    pub fn map_to_test_notification(self) -> TestFinish {
+        TestFinish {
+            display_name: self.name,
+            message: None,
+            status: TestStatus::Passed,
+            location: None,
+            data: Some(TestFinishData::cargo_bench(CargoBenchResult {
+                median: self.median as u64,
+                deviation: self.deviation as u64,
+            })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn bench_result_parsed() {
+        let line = r#"{ "type": "bench", "name": "benches::bench_add", "median": 1234, "deviation": 56, "mib_per_second": 78 }"#;
+
+        let bench = match serde_json::from_str::<TestType>(line).unwrap() {
+            TestType::Bench(bench) => bench,
+            other => panic!("Expected bench result, got {:?}", other),
+        };
+
+        assert_json_snapshot!(bench.map_to_test_notification(), @r#"
+        {
+          "displayName": "benches::bench_add",
+          "status": 1,
+          "dataKind": "cargo-bench",
+          "data": {
+            "median": 1234,
+            "deviation": 56
+          }
+        }
+        "#);
+    }
+}
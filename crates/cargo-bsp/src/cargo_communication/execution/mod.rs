@@ -7,4 +7,4 @@ mod execution_actor_state;
 mod execution_actor_unit_graph;
 mod execution_handle;
 pub(crate) mod execution_types;
-mod utils;
+pub(crate) mod utils;
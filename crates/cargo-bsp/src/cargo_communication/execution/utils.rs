@@ -1,18 +1,30 @@
 //! Additional functions used within [`Execution`].
 
 use log::warn;
-use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use bsp4rs::bsp::{BuildTargetIdentifier, Identifier};
+use cargo_metadata::PackageId;
 use rand::distributions::{Alphanumeric, DistString};
 
 use crate::project_model::target_details::TargetDetails;
 use crate::server::global_state::GlobalStateSnapshot;
+use crate::server::ServerError;
 use bsp4rs::bsp::TaskId;
 
-pub(super) fn generate_random_id() -> Identifier {
-    Identifier::new(Alphanumeric.sample_string(&mut rand::thread_rng(), 36))
+/// Number of the ids generated in this server session.
+static GENERATED_IDS_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// The counter makes the ids unique within the server session, the random
+/// part makes them unique across the sessions (e.g. after the server restarts).
+pub(crate) fn generate_random_id() -> Identifier {
+    let count = GENERATED_IDS_COUNT.fetch_add(1, Ordering::Relaxed);
+    Identifier::new(format!(
+        "{}-{}",
+        count,
+        Alphanumeric.sample_string(&mut rand::thread_rng(), 16)
+    ))
 }
 
 pub(super) fn generate_task_id(parent: &TaskId) -> TaskId {
@@ -22,18 +34,35 @@ pub(super) fn generate_task_id(parent: &TaskId) -> TaskId {
     }
 }
 
-pub(super) fn get_current_time() -> i64 {
+/// Milliseconds since the Unix epoch, used as the event time of the notifications
+/// and to measure the duration of the tasks.
+pub(crate) fn now_millis() -> i64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
-        .unwrap()
+        .unwrap_or_default()
         .as_millis() as i64
 }
 
+/// Extracts the package name from the package id, which is either in the
+/// `name version (source)` format or in the `source#name@version` format
+/// (with the name omitted if it is the same as the last segment of the source path).
+pub(super) fn package_name(package_id: &PackageId) -> &str {
+    let repr = package_id.repr.as_str();
+    if let Some((name, _)) = repr.split_once(' ') {
+        return name;
+    }
+    match repr.rsplit_once('#') {
+        Some((_, fragment)) if fragment.contains('@') => fragment.split('@').next().unwrap_or(repr),
+        Some((source, _)) => source.rsplit('/').next().unwrap_or(repr),
+        None => repr,
+    }
+}
+
 pub(super) fn targets_ids_to_targets_details(
     targets_ids: &[BuildTargetIdentifier],
     global_state: &GlobalStateSnapshot,
-) -> io::Result<Vec<TargetDetails>> {
-    let targets_details: Vec<TargetDetails> = targets_ids
+) -> Result<Vec<TargetDetails>, ServerError> {
+    targets_ids
         .iter()
         .map(|id| {
             global_state
@@ -41,12 +70,54 @@ pub(super) fn targets_ids_to_targets_details(
                 .get_target_details(id)
                 .ok_or_else(|| {
                     warn!("Target {:?} not found", id);
-                    io::Error::new(
-                        io::ErrorKind::NotFound,
-                        format!("Target {:?} not found", id),
-                    )
+                    ServerError::InvalidParams(format!("Target {:?} not found", id))
                 })
         })
-        .collect::<io::Result<Vec<TargetDetails>>>()?;
-    Ok(targets_details)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn generated_ids_are_unique() {
+        const IDS_COUNT: usize = 10_000;
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    (0..IDS_COUNT)
+                        .map(|_| generate_random_id())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let ids: HashSet<Identifier> = threads
+            .into_iter()
+            .flat_map(|t| t.join().unwrap())
+            .collect();
+
+        assert_eq!(ids.len(), 4 * IDS_COUNT);
+    }
+
+    #[test]
+    fn package_name_from_package_id() {
+        let name = |repr: &str| {
+            package_name(&PackageId {
+                repr: repr.to_string(),
+            })
+            .to_string()
+        };
+        assert_eq!(name("foo 0.1.0 (path+file:///test/foo)"), "foo");
+        assert_eq!(name("bar 0.1.0 (git+https://test/bar#abc)"), "bar");
+        assert_eq!(name("path+file:///test/foo#0.1.0"), "foo");
+        assert_eq!(name("path+file:///test/foo#bar@0.1.0"), "bar");
+        assert_eq!(
+            name("registry+https://github.com/rust-lang/crates.io-index#serde@1.0.0"),
+            "serde"
+        );
+        assert_eq!(name("test_pkg_id"), "test_pkg_id");
+    }
 }
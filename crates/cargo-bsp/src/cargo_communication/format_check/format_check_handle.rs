@@ -0,0 +1,113 @@
+//! Creates and spawns `cargo fmt --check` command for the packages of the requested
+//! targets and runs a new [`OutputActor`] in a new thread. Implementation of
+//! [`RequestHandle`].
+
+use std::process::Command;
+use std::sync::Arc;
+
+use bsp_server::{Message, Notification, RequestId};
+use crossbeam_channel::unbounded;
+
+use bsp4rs::bsp::OnBuildPublishDiagnostics;
+use bsp4rs::rust::{RustFormatCheckParams, RustFormatCheckResult};
+use bsp4rs::Notification as _;
+
+use crate::cargo_communication::cargo_handle::CargoHandle;
+use crate::cargo_communication::cargo_types::event::Event;
+use crate::cargo_communication::format_check::format_check_report::{
+    format_check_diagnostics, parse_format_check_report, PackageTarget,
+};
+use crate::cargo_communication::output_actor::OutputActor;
+use crate::cargo_communication::request_handle::RequestHandle;
+use crate::server::global_state::GlobalState;
+use crate::server::{Result, ServerError};
+
+impl RequestHandle {
+    pub fn spawn_format_check(
+        sender_to_main: Box<dyn Fn(Message) + Send>,
+        req_id: RequestId,
+        params: RustFormatCheckParams,
+        global_state: &mut GlobalState,
+    ) -> Result<RequestHandle, ServerError> {
+        let mut packages: Vec<String> = vec![];
+        let mut package_targets: Vec<PackageTarget> = vec![];
+        for id in &params.targets {
+            let Some(details) = global_state.workspace.get_target_details(id) else {
+                continue;
+            };
+            if !packages.contains(&details.package_name) {
+                packages.push(details.package_name);
+                // The diagnostics of the package files are reported for the first
+                // requested target of the package.
+                package_targets.push(PackageTarget {
+                    package_abs_path: details.package_abs_path,
+                    build_target: id.clone(),
+                });
+            }
+        }
+        // Without any package, `cargo fmt` would check the whole workspace.
+        if packages.is_empty() {
+            return Err(ServerError::InvalidParams(
+                "None of the requested targets belongs to the workspace".to_string(),
+            ));
+        }
+
+        let mut command = Command::new(toolchain::cargo());
+        command.current_dir(global_state.config.root_path()).args([
+            "fmt",
+            "--check",
+            "--message-format",
+            "json",
+        ]);
+        for package in &packages {
+            command.args(["-p", package]);
+        }
+        let cargo_handle = CargoHandle::spawn(&mut command).map_err(ServerError::Spawn)?;
+
+        let (cancel_sender, cancel_receiver) = unbounded::<Event>();
+        let mut actor: OutputActor<CargoHandle> =
+            OutputActor::new(sender_to_main, cargo_handle, req_id, cancel_receiver);
+        let published = Arc::clone(&global_state.published_format_diagnostics);
+        let origin_id = params.origin_id;
+
+        let thread = jod_thread::Builder::new()
+            .spawn(move || {
+                actor.run(|output, send| {
+                    let reported =
+                        parse_format_check_report(&output.stdout, &package_targets, &origin_id);
+                    // Rustfmt fails if any file is not formatted, but the failure without
+                    // any mismatch reported means that the check itself failed.
+                    if !output.exit_status.success() && reported.is_empty() {
+                        return Err(output.command_failed("cargo fmt --check"));
+                    }
+                    let mut published = published.lock().unwrap();
+                    let diagnostics = format_check_diagnostics(
+                        reported,
+                        &package_targets,
+                        &origin_id,
+                        &mut published,
+                    );
+                    let unformatted_files = diagnostics
+                        .iter()
+                        .filter(|d| !d.diagnostics.is_empty())
+                        .map(|d| d.text_document.uri.clone())
+                        .collect();
+                    for params in diagnostics {
+                        send(
+                            Notification::new(
+                                OnBuildPublishDiagnostics::METHOD.to_string(),
+                                params,
+                            )
+                            .into(),
+                        );
+                    }
+                    Ok(RustFormatCheckResult { unformatted_files })
+                })
+            })
+            .map_err(ServerError::Spawn)?;
+        Ok(RequestHandle {
+            cancel_sender,
+            _thread: thread,
+        })
+    }
+}
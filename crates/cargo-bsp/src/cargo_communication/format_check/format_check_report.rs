@@ -0,0 +1,363 @@
+//! Parses the output of `cargo fmt --check --message-format json`. Rustfmt prints
+//! a line with a JSON array for each checked package, with an entry for each file
+//! that is not formatted:
+//!
+//! ```text
+//! [{"name":"/test_root/src/main.rs","mismatches":[{"original_begin_line":1,"original_end_line":3,"expected_begin_line":1,"expected_end_line":1,"original":"fn main()\n{\n}","expected":"fn main() {}"}]}]
+//! ```
+
+use std::collections::BTreeMap;
+
+use cargo_metadata::camino::{Utf8Path, Utf8PathBuf};
+use log::warn;
+use serde::Deserialize;
+use url::Url;
+
+use bsp4rs::bsp::{
+    BuildTargetIdentifier, Diagnostic, DiagnosticData, DiagnosticSeverity, Identifier, Position,
+    PublishDiagnosticsParams, Range, TextDocumentIdentifier, URI,
+};
+use bsp4rs::rust::RustFormatReplacement;
+
+/// Source of the formatting diagnostics, which tells them apart from the diagnostics
+/// of the compiler published for the same build target.
+const FORMATTING_SOURCE: &str = "Formatting";
+
+/// Files with the published formatting diagnostics, mapped to the build target
+/// the diagnostics were published for. Only these files are reset once formatted,
+/// the other files keep the diagnostics of the compiler.
+pub(super) type PublishedFormatDiagnostics = BTreeMap<Utf8PathBuf, BuildTargetIdentifier>;
+
+/// The build target, for which the diagnostics of the package files are published.
+pub(crate) struct PackageTarget {
+    pub(crate) package_abs_path: Utf8PathBuf,
+    pub(crate) build_target: BuildTargetIdentifier,
+}
+
+#[derive(Deserialize)]
+struct FormatReport {
+    name: Utf8PathBuf,
+    mismatches: Vec<Mismatch>,
+}
+
+#[derive(Deserialize)]
+struct Mismatch {
+    original_begin_line: i32,
+    original_end_line: i32,
+    original: String,
+    expected: String,
+}
+
+/// Returns the diagnostics of the reported files which are not formatted and the empty
+/// diagnostics for the files of the checked packages, which were not formatted at the
+/// previous check, but are now. Updates the `published` files accordingly.
+pub(super) fn format_check_diagnostics(
+    reported: Vec<(Utf8PathBuf, PublishDiagnosticsParams)>,
+    package_targets: &[PackageTarget],
+    origin_id: &Option<Identifier>,
+    published: &mut PublishedFormatDiagnostics,
+) -> Vec<PublishDiagnosticsParams> {
+    let fixed: Vec<(Utf8PathBuf, BuildTargetIdentifier)> = published
+        .iter()
+        .filter(|(file, build_target)| {
+            file_build_target(file, package_targets).is_some()
+                && !reported
+                    .iter()
+                    .any(|(f, params)| f == *file && &params.build_target == *build_target)
+        })
+        .map(|(file, build_target)| (file.clone(), build_target.clone()))
+        .collect();
+
+    let mut diagnostics = vec![];
+    for (file, build_target) in fixed {
+        published.remove(&file);
+        let Ok(uri) = Url::from_file_path(&file) else {
+            continue;
+        };
+        diagnostics.push(PublishDiagnosticsParams {
+            text_document: TextDocumentIdentifier {
+                uri: URI::new(uri.to_string()),
+            },
+            build_target,
+            origin_id: origin_id.clone(),
+            diagnostics: vec![],
+            reset: true,
+        });
+    }
+    for (file, params) in reported {
+        published.insert(file, params.build_target.clone());
+        diagnostics.push(params);
+    }
+    diagnostics
+}
+
+/// Returns the diagnostics only for the files which are not formatted, each
+/// replacing the previously published formatting diagnostics of the file.
+pub(super) fn parse_format_check_report(
+    stdout: &[String],
+    package_targets: &[PackageTarget],
+    origin_id: &Option<Identifier>,
+) -> Vec<(Utf8PathBuf, PublishDiagnosticsParams)> {
+    stdout
+        .iter()
+        .filter_map(
+            |line| match serde_json::from_str::<Vec<FormatReport>>(line) {
+                Ok(reports) => Some(reports),
+                Err(e) => {
+                    warn!("Could not parse the rustfmt report {}: {}", line, e);
+                    None
+                }
+            },
+        )
+        .flatten()
+        .filter(|report| !report.mismatches.is_empty())
+        .filter_map(|report| {
+            let build_target = file_build_target(&report.name, package_targets)?;
+            let uri = Url::from_file_path(&report.name).ok()?;
+            let params = PublishDiagnosticsParams {
+                text_document: TextDocumentIdentifier {
+                    uri: URI::new(uri.to_string()),
+                },
+                build_target,
+                origin_id: origin_id.clone(),
+                diagnostics: report.mismatches.into_iter().map(diagnostic).collect(),
+                reset: true,
+            };
+            Some((report.name, params))
+        })
+        .collect()
+}
+
+/// The file belongs to the package with the longest path containing it, as the
+/// packages of the workspace may be nested.
+fn file_build_target(
+    file: &Utf8Path,
+    package_targets: &[PackageTarget],
+) -> Option<BuildTargetIdentifier> {
+    package_targets
+        .iter()
+        .filter(|p| file.starts_with(&p.package_abs_path))
+        .max_by_key(|p| p.package_abs_path.as_str().len())
+        .map(|p| p.build_target.clone())
+}
+
+/// The diagnostic covers the whole original lines, so that the client can replace
+/// them with the expected ones. Rustfmt reports the end line before the begin line
+/// if the expected lines are only inserted, then the range is empty.
+fn diagnostic(mismatch: Mismatch) -> Diagnostic {
+    let start_line = (mismatch.original_begin_line - 1).max(0);
+    let end_line = (mismatch.original_end_line - 1).max(start_line);
+    let last_line_length = mismatch
+        .original
+        .lines()
+        .last()
+        .map(|l| l.encode_utf16().count())
+        .unwrap_or_default();
+    Diagnostic {
+        range: Range {
+            start: Position {
+                line: start_line,
+                character: 0,
+            },
+            end: Position {
+                line: end_line,
+                character: last_line_length as i32,
+            },
+        },
+        severity: Some(DiagnosticSeverity::Warning),
+        source: Some(FORMATTING_SOURCE.to_string()),
+        message: "The code is not formatted according to rustfmt".to_string(),
+        data: Some(DiagnosticData::rust_format_replacement(
+            RustFormatReplacement {
+                replacement: mismatch.expected,
+            },
+        )),
+        ..Diagnostic::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    const TEST_REPORT: &str = r#"[{"name":"/test_root/src/main.rs","mismatches":[{"original_begin_line":1,"original_end_line":3,"expected_begin_line":1,"expected_end_line":1,"original":"fn main()\n{\n}","expected":"fn main() {}"}]},{"name":"/test_root/dep/src/lib.rs","mismatches":[{"original_begin_line":2,"original_end_line":2,"expected_begin_line":2,"expected_end_line":2,"original":"pub fn dep( ) {}","expected":"pub fn dep() {}"}]}]"#;
+
+    fn test_package_targets() -> Vec<PackageTarget> {
+        vec![
+            PackageTarget {
+                package_abs_path: "/test_root".into(),
+                build_target: BuildTargetIdentifier {
+                    uri: "test_root_target".into(),
+                },
+            },
+            PackageTarget {
+                package_abs_path: "/test_root/dep".into(),
+                build_target: BuildTargetIdentifier {
+                    uri: "dep_target".into(),
+                },
+            },
+        ]
+    }
+
+    #[test]
+    fn test_parse_format_check_report() {
+        let stdout = vec![TEST_REPORT.to_string()];
+
+        let reported = parse_format_check_report(
+            &stdout,
+            &test_package_targets(),
+            &Some("test_origin_id".into()),
+        );
+        let diagnostics = format_check_diagnostics(
+            reported,
+            &test_package_targets(),
+            &Some("test_origin_id".into()),
+            &mut PublishedFormatDiagnostics::new(),
+        );
+
+        assert_json_snapshot!(diagnostics, @r#"
+        [
+          {
+            "textDocument": {
+              "uri": "file:///test_root/src/main.rs"
+            },
+            "buildTarget": {
+              "uri": "test_root_target"
+            },
+            "originId": "test_origin_id",
+            "diagnostics": [
+              {
+                "range": {
+                  "start": {
+                    "line": 0,
+                    "character": 0
+                  },
+                  "end": {
+                    "line": 2,
+                    "character": 1
+                  }
+                },
+                "severity": 2,
+                "source": "Formatting",
+                "message": "The code is not formatted according to rustfmt",
+                "dataKind": "rust-format-replacement",
+                "data": {
+                  "replacement": "fn main() {}"
+                }
+              }
+            ],
+            "reset": true
+          },
+          {
+            "textDocument": {
+              "uri": "file:///test_root/dep/src/lib.rs"
+            },
+            "buildTarget": {
+              "uri": "dep_target"
+            },
+            "originId": "test_origin_id",
+            "diagnostics": [
+              {
+                "range": {
+                  "start": {
+                    "line": 1,
+                    "character": 0
+                  },
+                  "end": {
+                    "line": 1,
+                    "character": 16
+                  }
+                },
+                "severity": 2,
+                "source": "Formatting",
+                "message": "The code is not formatted according to rustfmt",
+                "dataKind": "rust-format-replacement",
+                "data": {
+                  "replacement": "pub fn dep() {}"
+                }
+              }
+            ],
+            "reset": true
+          }
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_parse_formatted_files() {
+        let stdout = vec![
+            "[]".to_string(),
+            r#"[{"name":"/test_root/src/main.rs","mismatches":[]}]"#.to_string(),
+        ];
+
+        assert!(parse_format_check_report(&stdout, &test_package_targets(), &None).is_empty());
+    }
+
+    #[test]
+    fn test_clear_diagnostics_of_formatted_files() {
+        let stdout = vec![
+            r#"[{"name":"/test_root/src/main.rs","mismatches":[{"original_begin_line":1,"original_end_line":1,"expected_begin_line":1,"expected_end_line":1,"original":"fn main( ) {}","expected":"fn main() {}"}]}]"#.to_string(),
+        ];
+        let root_target = BuildTargetIdentifier {
+            uri: "test_root_target".into(),
+        };
+        let other_target = BuildTargetIdentifier {
+            uri: "other_target".into(),
+        };
+        let mut published = PublishedFormatDiagnostics::from([
+            ("/test_root/src/main.rs".into(), root_target.clone()),
+            ("/test_root/src/lib.rs".into(), root_target.clone()),
+            ("/other/src/lib.rs".into(), other_target.clone()),
+        ]);
+
+        let reported = parse_format_check_report(&stdout, &test_package_targets(), &None);
+        let diagnostics =
+            format_check_diagnostics(reported, &test_package_targets(), &None, &mut published);
+
+        let published_files: Vec<(&str, usize)> = diagnostics
+            .iter()
+            .map(|d| (d.text_document.uri.0.as_str(), d.diagnostics.len()))
+            .collect();
+        assert_eq!(
+            published_files,
+            vec![
+                ("file:///test_root/src/lib.rs", 0),
+                ("file:///test_root/src/main.rs", 1)
+            ]
+        );
+        assert_eq!(
+            published,
+            PublishedFormatDiagnostics::from([
+                ("/other/src/lib.rs".into(), other_target),
+                ("/test_root/src/main.rs".into(), root_target),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_inserted_lines_diagnostic() {
+        let mismatch = Mismatch {
+            original_begin_line: 3,
+            original_end_line: 2,
+            original: String::new(),
+            expected: "\n".to_string(),
+        };
+
+        let range = diagnostic(mismatch).range;
+
+        assert_eq!(
+            range,
+            Range {
+                start: Position {
+                    line: 2,
+                    character: 0
+                },
+                end: Position {
+                    line: 2,
+                    character: 0
+                },
+            }
+        );
+    }
+}
@@ -0,0 +1,6 @@
+//! Handles communication with Cargo regarding the format check request
+//! ([`RustFormatCheck`](bsp4rs::rust::RustFormatCheck)), which runs `cargo fmt --check`
+//! and publishes the formatting mismatches as diagnostics.
+
+mod format_check_handle;
+mod format_check_report;
@@ -0,0 +1,101 @@
+//! Creates and spawns `cargo build --future-incompat-report` command and runs
+//! a new [`OutputActor`] in a new thread. Implementation of [`RequestHandle`].
+
+use bsp_server::{Message, RequestId};
+use crossbeam_channel::unbounded;
+
+use bsp4rs::rust::RustFutureIncompatReportResult;
+
+use crate::cargo_communication::cargo_handle::CargoHandle;
+use crate::cargo_communication::cargo_types::create_command::create_future_incompat_command;
+use crate::cargo_communication::cargo_types::event::Event;
+use crate::cargo_communication::future_incompat::future_incompat_report::parse_future_incompat_report;
+use crate::cargo_communication::output_actor::{CargoOutput, OutputActor};
+use crate::cargo_communication::request_handle::RequestHandle;
+use crate::server::global_state::GlobalState;
+use crate::server::{Result, ServerError};
+
+impl RequestHandle {
+    pub fn spawn_future_incompat_report(
+        sender_to_main: Box<dyn Fn(Message) + Send>,
+        req_id: RequestId,
+        _params: (),
+        global_state: &mut GlobalState,
+    ) -> Result<RequestHandle, ServerError> {
+        let mut command = create_future_incompat_command(
+            global_state.config.root_path(),
+            &global_state.config.settings,
+        );
+        let cargo_handle = CargoHandle::spawn(&mut command).map_err(ServerError::Spawn)?;
+
+        let (cancel_sender, cancel_receiver) = unbounded::<Event>();
+        let mut actor: OutputActor<CargoHandle> =
+            OutputActor::new(sender_to_main, cargo_handle, req_id, cancel_receiver);
+
+        let thread = jod_thread::Builder::new()
+            .spawn(move || actor.run(|output, _| future_incompat_result(output)))
+            .map_err(ServerError::Spawn)?;
+        Ok(RequestHandle {
+            cancel_sender,
+            _thread: thread,
+        })
+    }
+}
+
+/// The report is printed also when the build fails (e.g. on an error in the workspace),
+/// so the request fails only if the command failed without printing the report.
+fn future_incompat_result(
+    output: CargoOutput,
+) -> Result<RustFutureIncompatReportResult, ServerError> {
+    let packages = parse_future_incompat_report(&output.stderr);
+    if packages.is_empty() && !output.exit_status.success() {
+        return Err(output.command_failed("cargo build --future-incompat-report"));
+    }
+    Ok(RustFutureIncompatReportResult { packages })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    const TEST_REPORT: &[&str] = &[
+        "error: could not compile `app` (bin \"app\") due to 1 previous error",
+        "The package `dep v1.0.0` currently triggers the following future incompatibility lints:",
+        "> warning: trailing semicolon in macro used in expression position",
+        ">   = note: `#[warn(semicolon_in_expressions_from_macros)]` on by default",
+    ];
+
+    fn test_output(stderr: &[&str], exit_code: i32) -> CargoOutput {
+        CargoOutput {
+            stdout: vec![],
+            stderr: stderr.iter().map(|l| l.to_string()).collect(),
+            exit_status: ExitStatus::from_raw(exit_code << 8),
+        }
+    }
+
+    #[test]
+    fn report_of_failed_build() {
+        let result = future_incompat_result(test_output(TEST_REPORT, 101)).unwrap();
+
+        assert_eq!(result.packages.len(), 1);
+        assert_eq!(result.packages[0].package, "dep v1.0.0");
+    }
+
+    #[test]
+    fn failed_build_without_report() {
+        let stderr = &["error: could not find `Cargo.toml` in `/test_root`"];
+
+        assert!(future_incompat_result(test_output(stderr, 101)).is_err());
+    }
+
+    #[test]
+    fn successful_build_without_report() {
+        let stderr = &["    Finished dev [unoptimized + debuginfo] target(s) in 0.50s"];
+
+        let result = future_incompat_result(test_output(stderr, 0)).unwrap();
+
+        assert!(result.packages.is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+//! Parses the future incompatibility report printed by Cargo on stderr, which contains
+//! a section for each affected package:
+//!
+//! ```text
+//! The package `foo v0.1.0` currently triggers the following future incompatibility lints:
+//! > warning: trailing semicolon in macro used in expression position
+//! > ...
+//! > = note: `#[warn(semicolon_in_expressions_from_macros)]` on by default
+//! ```
+
+use bsp4rs::rust::RustFutureIncompatPackage;
+
+const PACKAGE_PREFIX: &str = "The package `";
+const PACKAGE_SUFFIX: &str = "` currently triggers the following future incompatibility lints:";
+
+pub(super) fn parse_future_incompat_report(stderr: &[String]) -> Vec<RustFutureIncompatPackage> {
+    let mut packages: Vec<RustFutureIncompatPackage> = vec![];
+    let mut current_package: Option<usize> = None;
+    for line in stderr.iter().flat_map(|l| l.lines()).map(str::trim) {
+        if let Some(name) = line
+            .strip_prefix(PACKAGE_PREFIX)
+            .and_then(|l| l.strip_suffix(PACKAGE_SUFFIX))
+        {
+            // The package may be reported more than once, e.g. for different targets.
+            let index = match packages.iter().position(|p| p.package == name) {
+                Some(index) => index,
+                None => {
+                    packages.push(RustFutureIncompatPackage {
+                        package: name.to_string(),
+                        lints: vec![],
+                    });
+                    packages.len() - 1
+                }
+            };
+            current_package = Some(index);
+        } else if let (Some(index), Some(lint)) = (current_package, lint_name(line)) {
+            let lints = &mut packages[index].lints;
+            if !lints.contains(&lint) {
+                lints.push(lint);
+            }
+        }
+    }
+    packages
+}
+
+/// The lint is reported in the rendered diagnostic as the lint level attribute,
+/// for example ``= note: `#[warn(lint_name)]` on by default``.
+fn lint_name(line: &str) -> Option<String> {
+    let (_, attribute) = line.split_once("`#[")?;
+    let (attribute, _) = attribute.split_once(")]`")?;
+    let (_, lint) = attribute.split_once('(')?;
+    Some(lint.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_REPORT: &str = r#"warning: the following packages contain code that will be rejected by a future version of Rust: dep v1.0.0, other_dep v0.2.0
+note:
+To solve this problem, you can try the following approaches:
+
+- If the issue is not solved by updating the dependencies, a fix has to be
+implemented by those dependencies. You can help with that by notifying the
+maintainers of this problem (e.g. by creating a bug report) or by finding
+another crate that is compatible with a newer version of Rust.
+
+The package `dep v1.0.0` currently triggers the following future incompatibility lints:
+> warning: trailing semicolon in macro used in expression position
+>  --> /registry/dep-1.0.0/src/lib.rs:3:12
+>   |
+> 3 |         1;
+>   |          ^
+>   |
+>   = note: `#[warn(semicolon_in_expressions_from_macros)]` on by default
+>   = warning: this was previously accepted by the compiler but is being phased out; it will become a hard error in a future release!
+>
+> warning: trailing semicolon in macro used in expression position
+>  --> /registry/dep-1.0.0/src/lib.rs:8:12
+>   |
+>   = note: `#[warn(semicolon_in_expressions_from_macros)]` on by default
+>
+
+The package `other_dep v0.2.0` currently triggers the following future incompatibility lints:
+> error: unaligned reference to a field of a packed struct
+>  --> /registry/other_dep-0.2.0/src/lib.rs:10:5
+>   |
+>   = note: `#[deny(unaligned_references)]` on by default
+>
+"#;
+
+    #[test]
+    fn test_parse_future_incompat_report() {
+        let stderr: Vec<String> = TEST_REPORT.lines().map(String::from).collect();
+
+        let packages = parse_future_incompat_report(&stderr);
+
+        assert_eq!(
+            packages,
+            vec![
+                RustFutureIncompatPackage {
+                    package: "dep v1.0.0".to_string(),
+                    lints: vec!["semicolon_in_expressions_from_macros".to_string()],
+                },
+                RustFutureIncompatPackage {
+                    package: "other_dep v0.2.0".to_string(),
+                    lints: vec!["unaligned_references".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_output_without_report() {
+        let stderr = vec![
+            "   Compiling app v0.1.0 (/test_root)".to_string(),
+            "    Finished dev [unoptimized + debuginfo] target(s) in 0.50s".to_string(),
+        ];
+
+        assert!(parse_future_incompat_report(&stderr).is_empty());
+    }
+}
@@ -0,0 +1,6 @@
+//! Handles communication with Cargo regarding the future incompatibility report request
+//! ([`RustFutureIncompatReport`](bsp4rs::rust::RustFutureIncompatReport)), which requires
+//! building the workspace with `--future-incompat-report` flag.
+
+mod future_incompat_handle;
+mod future_incompat_report;
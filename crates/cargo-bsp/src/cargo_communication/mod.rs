@@ -6,6 +6,12 @@ pub(crate) mod cargo_actor;
 mod cargo_handle;
 pub(crate) mod cargo_types;
 mod check;
+mod clean_cache;
+mod format_check;
+mod future_incompat;
+mod output_actor;
 pub(crate) mod execution;
 pub(crate) mod request_handle;
+#[cfg(feature = "tokio-output-reader")]
+pub mod tokio_cargo_handle;
 mod utils;
@@ -0,0 +1,245 @@
+//! Handles the Cargo commands whose response is created from their whole output, such as
+//! `cargo build --future-incompat-report` or `cargo fmt --check`. Collects the lines of
+//! stdout and stderr until the command finishes, and handles the cancel request from the
+//! client. Works as [`CheckActor`], but the output is only parsed after the finish.
+//!
+//! [`CheckActor`]: crate::cargo_communication::check::check_actor::CheckActor
+
+use std::process::ExitStatus;
+use std::time::Instant;
+
+use bsp_server::{ErrorCode, Message, RequestId, Response, ResponseError};
+use crossbeam_channel::{never, select, Receiver};
+use log::warn;
+use serde::Serialize;
+use serde_json::to_value;
+
+use crate::cargo_communication::cargo_handle::CargoHandler;
+use crate::cargo_communication::cargo_types::event::{
+    CancelData, CancelReason, CargoMessage, Event,
+};
+use crate::server::ServerError;
+
+/// Output of the finished Cargo command.
+pub(crate) struct CargoOutput {
+    pub(crate) stdout: Vec<String>,
+    pub(crate) stderr: Vec<String>,
+    pub(crate) exit_status: ExitStatus,
+}
+
+impl CargoOutput {
+    /// The error of the failed command, with the stderr explaining the failure.
+    pub(crate) fn command_failed(&self, command: &str) -> ServerError {
+        ServerError::Internal(format!(
+            "`{}` failed ({}):\n{}",
+            command,
+            self.exit_status,
+            self.stderr.join("\n")
+        ))
+    }
+}
+
+pub(crate) struct OutputActor<C>
+where
+    C: CargoHandler<CargoMessage>,
+{
+    // sender for notifications and responses to main loop
+    sender: Box<dyn Fn(Message) + Send>,
+    cargo_handle: Option<C>,
+    cancel_receiver: Receiver<Event>,
+    req_id: RequestId,
+    start_time: Instant,
+}
+
+impl<C> OutputActor<C>
+where
+    C: CargoHandler<CargoMessage>,
+{
+    pub fn new(
+        sender: Box<dyn Fn(Message) + Send>,
+        cargo_handle: C,
+        req_id: RequestId,
+        cancel_receiver: Receiver<Event>,
+    ) -> OutputActor<C> {
+        OutputActor {
+            sender,
+            cargo_handle: Some(cargo_handle),
+            cancel_receiver,
+            req_id,
+            start_time: Instant::now(),
+        }
+    }
+
+    fn next_event(&self) -> Option<Event> {
+        let cargo_chan = self.cargo_handle.as_ref().map(|cargo| cargo.receiver());
+        select! {
+            recv(self.cancel_receiver) -> msg => msg.ok(),
+            recv(cargo_chan.unwrap_or(&never())) -> msg => match msg {
+                Ok(msg) => Some(Event::CargoEvent(msg)),
+                Err(_) => Some(Event::CargoFinish),
+            }
+        }
+    }
+
+    /// Responds with the result created by `on_finish` from the output of the finished
+    /// command. The notifications sent with the given sender precede the response.
+    pub fn run<R, F>(&mut self, on_finish: F)
+    where
+        R: Serialize,
+        F: FnOnce(CargoOutput, &dyn Fn(Message)) -> Result<R, ServerError>,
+    {
+        let (mut stdout, mut stderr) = (vec![], vec![]);
+        while let Some(event) = self.next_event() {
+            match event {
+                Event::Cancel(reason) => {
+                    self.cancel(reason);
+                    return;
+                }
+                Event::CargoFinish => {
+                    break;
+                }
+                // The actor does not use the command timeout.
+                Event::Timeout => {}
+                Event::CargoEvent(CargoMessage::CargoStdout(msg)) => stdout.push(msg),
+                Event::CargoEvent(CargoMessage::CargoStderr(msg)) => stderr.push(msg),
+            }
+        }
+        let Some(cargo_handle) = self.cargo_handle.take() else {
+            return;
+        };
+        let result = cargo_handle
+            .join()
+            .map_err(|e| ServerError::Internal(format!("The Cargo command failed: {}", e)))
+            .and_then(|exit_status| {
+                let output = CargoOutput {
+                    stdout,
+                    stderr,
+                    exit_status,
+                };
+                on_finish(output, &*self.sender)
+            });
+        let response = match result {
+            Ok(result) => Response::new_ok(self.req_id.clone(), result),
+            Err(e) => e.into_response(self.req_id.clone()),
+        };
+        self.send(response.into());
+    }
+
+    fn cancel(&mut self, reason: CancelReason) {
+        if let Some(cargo_handle) = self.cargo_handle.take() {
+            cargo_handle.cancel();
+            let error = ResponseError {
+                code: ErrorCode::RequestCanceled as i32,
+                message: reason.message().to_string(),
+                data: Some(to_value(CancelData::new(reason, self.start_time)).unwrap()),
+            };
+            self.send(
+                Response {
+                    id: self.req_id.clone(),
+                    result: None,
+                    error: Some(error),
+                }
+                .into(),
+            );
+        } else {
+            warn!(
+                "Tried to cancel request {} that was already finished",
+                self.req_id.clone()
+            );
+        }
+    }
+
+    fn send(&self, msg: Message) {
+        (self.sender)(msg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cargo_communication::cargo_handle::MockCargoHandler;
+    use crossbeam_channel::{unbounded, Sender};
+    use std::io;
+    use std::os::unix::process::ExitStatusExt;
+
+    const TEST_REQ_ID: &str = "test_req_id";
+
+    fn test_actor(
+        cargo_handle: MockCargoHandler<CargoMessage>,
+    ) -> (
+        OutputActor<MockCargoHandler<CargoMessage>>,
+        Receiver<Message>,
+        Sender<Event>,
+    ) {
+        let (sender_to_main, receiver_from_actor) = unbounded::<Message>();
+        let (cancel_sender, cancel_receiver) = unbounded::<Event>();
+        let actor = OutputActor::new(
+            Box::new(move |msg| sender_to_main.send(msg).unwrap()),
+            cargo_handle,
+            TEST_REQ_ID.to_string().into(),
+            cancel_receiver,
+        );
+        (actor, receiver_from_actor, cancel_sender)
+    }
+
+    fn finished_cargo_handle(
+        messages: Vec<CargoMessage>,
+        join_result: fn() -> io::Result<ExitStatus>,
+    ) -> MockCargoHandler<CargoMessage> {
+        let (sender_to_actor, receiver_from_cargo) = unbounded();
+        for msg in messages {
+            sender_to_actor.send(msg).unwrap();
+        }
+        let mut cargo_handle = MockCargoHandler::new();
+        cargo_handle
+            .expect_receiver()
+            .return_const(receiver_from_cargo);
+        cargo_handle.expect_join().returning(join_result);
+        cargo_handle
+    }
+
+    fn response(receiver: &Receiver<Message>) -> Response {
+        match receiver.try_recv().unwrap() {
+            Message::Response(response) => response,
+            msg => panic!("expected the response, got: {:?}", msg),
+        }
+    }
+
+    #[test]
+    fn output_passed_to_finish() {
+        let cargo_handle = finished_cargo_handle(
+            vec![
+                CargoMessage::CargoStdout("stdout line".to_string()),
+                CargoMessage::CargoStderr("stderr line".to_string()),
+            ],
+            || Ok(ExitStatus::from_raw(0)),
+        );
+        let (mut actor, receiver, _cancel_sender) = test_actor(cargo_handle);
+
+        actor.run(|output, _| Ok((output.stdout, output.stderr)));
+
+        let response = response(&receiver);
+        assert_eq!(
+            response.result,
+            Some(serde_json::json!([["stdout line"], ["stderr line"]]))
+        );
+    }
+
+    #[test]
+    fn finish_error_responded() {
+        let cargo_handle = finished_cargo_handle(
+            vec![CargoMessage::CargoStderr("error: test".to_string())],
+            || Ok(ExitStatus::from_raw(101 << 8)),
+        );
+        let (mut actor, receiver, _cancel_sender) = test_actor(cargo_handle);
+
+        actor.run(|output, _| Err::<(), _>(output.command_failed("cargo test_command")));
+
+        let error = response(&receiver).error.unwrap();
+        assert_eq!(error.code, ErrorCode::InternalError as i32);
+        assert_eq!(
+            error.message,
+            "`cargo test_command` failed (exit status: 101):\nerror: test"
+        );
+    }
+}
@@ -6,6 +6,6 @@ use crate::cargo_communication::cargo_types::event::Event;
 use crossbeam_channel::Sender;
 
 pub(crate) struct RequestHandle {
-    pub(super) cancel_sender: Sender<Event>,
-    pub(super) _thread: jod_thread::JoinHandle,
+    pub(crate) cancel_sender: Sender<Event>,
+    pub(crate) _thread: jod_thread::JoinHandle,
 }
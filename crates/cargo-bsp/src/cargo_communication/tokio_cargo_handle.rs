@@ -0,0 +1,359 @@
+//! Spawns and handles Cargo command, reading its output on the tokio runtime, available with
+//! the `tokio-output-reader` feature. Unlike [`CargoHandle`], which spawns the threads reading
+//! stdout and stderr for each command, the output of all Cargo commands is read by the tasks
+//! of a shared runtime. Only the reading is moved: the messages are passed over the same
+//! crossbeam channel as from [`CargoHandle`], and the [`CargoActor`] (like the other actors)
+//! still runs its `select!` loop on its own thread, so the actors work with both handles.
+//!
+//! [`CargoHandle`]: crate::cargo_communication::cargo_handle::CargoHandle
+//! [`CargoActor`]: crate::cargo_communication::cargo_actor::CargoActor
+
+use std::io;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use command_group::{AsyncCommandGroup, AsyncGroupChild};
+use crossbeam_channel::{bounded, Receiver, Sender, TrySendError};
+use log::{info, warn};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::runtime::Runtime;
+use tokio::select;
+use tokio::task::JoinHandle;
+
+use crate::cargo_communication::cargo_actor::CARGO_MESSAGES_BOUND;
+use crate::cargo_communication::cargo_handle::CargoHandler;
+use crate::cargo_communication::cargo_types::event::CargoMessage;
+
+/// Number of the runtime threads reading the output of all Cargo commands.
+const RUNTIME_WORKER_THREADS: usize = 2;
+
+/// Interval between the attempts to send a message to the full channel.
+const SEND_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+fn runtime() -> &'static Runtime {
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(RUNTIME_WORKER_THREADS)
+            .thread_name("cargo-output")
+            .enable_all()
+            .build()
+            .expect("Failed to start the runtime reading the Cargo output")
+    })
+}
+
+pub struct TokioCargoHandle {
+    /// Spawned in its own process group like in [`CargoHandle`], so that the processes started
+    /// by Cargo (e.g. the run binary or the test executables) are killed together with it.
+    /// The leader is killed on drop, as we cannot cancel the reading of the output directly.
+    ///
+    /// [`CargoHandle`]: crate::cargo_communication::cargo_handle::CargoHandle
+    child: AsyncGroupChild,
+    reader: JoinHandle<io::Result<bool>>,
+    receiver: Receiver<CargoMessage>,
+    /// Cargo prints at least one message, unlike the executables built by Cargo
+    /// (see [`TokioCargoHandle::spawn_executable`]), which may print nothing.
+    expects_messages: bool,
+}
+
+impl CargoHandler<CargoMessage> for TokioCargoHandle {
+    fn receiver(&self) -> &Receiver<CargoMessage> {
+        &self.receiver
+    }
+
+    fn cancel(mut self) {
+        runtime().block_on(async {
+            let _ = self.child.kill().await;
+        });
+    }
+
+    fn join(mut self) -> io::Result<ExitStatus> {
+        // Dropped first, so that the reader blocked on the full channel finishes.
+        drop(self.receiver);
+        runtime().block_on(async {
+            let _ = self.child.start_kill();
+            let exit_status = self.child.wait().await?;
+            let read_at_least_one_message = self.reader.await??;
+            if read_at_least_one_message || !self.expects_messages {
+                Ok(exit_status)
+            } else {
+                Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!(
+                        "Cargo watcher failed, the command produced no valid metadata (exit code: {:?}):\n",
+                        exit_status
+                    ),
+                ))
+            }
+        })
+    }
+}
+
+impl TokioCargoHandle {
+    pub fn spawn(command: &mut Command) -> io::Result<TokioCargoHandle> {
+        TokioCargoHandle::spawn_command(command, true)
+    }
+
+    /// Spawns the executable built by Cargo, such as the binary of the run request.
+    pub fn spawn_executable(command: &mut Command) -> io::Result<TokioCargoHandle> {
+        TokioCargoHandle::spawn_command(command, false)
+    }
+
+    fn spawn_command(
+        command: &mut Command,
+        expects_messages: bool,
+    ) -> io::Result<TokioCargoHandle> {
+        info!("Created command: {:?}", command);
+        let mut command = tokio_command(command);
+        command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+        let _runtime_guard = runtime().enter();
+        let mut child = command.group_spawn().map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Failed to spawn Cargo command {:?}: {}", command, e),
+            )
+        })?;
+
+        let stdout = child.inner().stdout.take().unwrap();
+        let stderr = child.inner().stderr.take().unwrap();
+
+        let (sender, receiver) = bounded(CARGO_MESSAGES_BOUND);
+        let reader = tokio::spawn(read_cargo_output(stdout, stderr, sender));
+        Ok(TokioCargoHandle {
+            child,
+            reader,
+            receiver,
+            expects_messages,
+        })
+    }
+}
+
+/// The created commands are reused (e.g. for the log messages), so the tokio command
+/// is created from the parts of the command instead of taking it.
+fn tokio_command(command: &Command) -> tokio::process::Command {
+    let mut tokio_command = tokio::process::Command::new(command.get_program());
+    tokio_command.args(command.get_args());
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => tokio_command.env(key, value),
+            None => tokio_command.env_remove(key),
+        };
+    }
+    if let Some(dir) = command.get_current_dir() {
+        tokio_command.current_dir(dir);
+    }
+    tokio_command
+}
+
+/// Forwards the lines of Cargo's stdout and stderr until both of them are closed.
+/// Returns whether at least one line was read from stdout (see [`CargoActor::run`]).
+///
+/// [`CargoActor::run`]: crate::cargo_communication::cargo_actor::CargoActor::run
+pub async fn read_cargo_output<O, E>(
+    stdout: O,
+    stderr: E,
+    sender: Sender<CargoMessage>,
+) -> io::Result<bool>
+where
+    O: AsyncRead + Unpin,
+    E: AsyncRead + Unpin,
+{
+    let mut stdout = BufReader::new(stdout).lines();
+    let mut stderr = BufReader::new(stderr).lines();
+    let (mut stdout_done, mut stderr_done) = (false, false);
+    let mut read_at_least_one_message = false;
+
+    while !(stdout_done && stderr_done) {
+        select! {
+            line = stdout.next_line(), if !stdout_done => match line? {
+                Some(line) => {
+                    read_at_least_one_message = true;
+                    send(&sender, CargoMessage::CargoStdout(line)).await;
+                }
+                None => stdout_done = true,
+            },
+            line = stderr.next_line(), if !stderr_done => match line? {
+                Some(line) => send(&sender, CargoMessage::CargoStderr(line)).await,
+                None => stderr_done = true,
+            },
+        }
+    }
+    Ok(read_at_least_one_message)
+}
+
+/// Sends the message without blocking the runtime thread: when the consumer lags
+/// (see [`CARGO_MESSAGES_BOUND`]), the task sleeps until there is space in the channel.
+async fn send(sender: &Sender<CargoMessage>, mut message: CargoMessage) {
+    loop {
+        match sender.try_send(message) {
+            Ok(()) => return,
+            Err(TrySendError::Full(returned)) => {
+                message = returned;
+                tokio::time::sleep(SEND_RETRY_INTERVAL).await;
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                warn!("Could not send a message from cargo: the receiver is disconnected");
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam_channel::unbounded;
+    use std::time::{Duration, Instant};
+
+    const CARGO_STDOUT: &str = concat!(
+        r#"{"reason":"compiler-artifact","package_id":"app 0.1.0"}"#,
+        "\n",
+        r#"{"reason":"build-finished","success":true}"#,
+        "\n",
+    );
+    const CARGO_STDERR: &str =
+        "   Compiling app v0.1.0\n    Finished dev [unoptimized] target(s)\n";
+
+    fn received_messages(receiver: Receiver<CargoMessage>) -> Vec<CargoMessage> {
+        receiver.try_iter().collect()
+    }
+
+    #[tokio::test]
+    async fn canned_cargo_output_forwarded() {
+        let (sender, receiver) = unbounded();
+
+        let result = read_cargo_output(CARGO_STDOUT.as_bytes(), CARGO_STDERR.as_bytes(), sender)
+            .await
+            .unwrap();
+
+        assert!(result);
+        let messages = received_messages(receiver);
+        let stdout: Vec<_> = messages
+            .iter()
+            .filter_map(|m| match m {
+                CargoMessage::CargoStdout(line) => Some(line.as_str()),
+                CargoMessage::CargoStderr(_) => None,
+            })
+            .collect();
+        let stderr: Vec<_> = messages
+            .iter()
+            .filter_map(|m| match m {
+                CargoMessage::CargoStderr(line) => Some(line.as_str()),
+                CargoMessage::CargoStdout(_) => None,
+            })
+            .collect();
+        // The order of the lines is kept within each stream.
+        assert_eq!(stdout, CARGO_STDOUT.lines().collect::<Vec<_>>());
+        assert_eq!(stderr, CARGO_STDERR.lines().collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn empty_stdout_reported() {
+        let (sender, receiver) = unbounded();
+
+        let result = read_cargo_output(&b""[..], CARGO_STDERR.as_bytes(), sender)
+            .await
+            .unwrap();
+
+        assert!(!result);
+        assert_eq!(received_messages(receiver).len(), 2);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn lagging_consumer_does_not_block_runtime() {
+        let (sender, receiver) = bounded(1);
+        let consumer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            receiver.iter().count()
+        });
+
+        // The channel is full until the consumer starts, so the sends wait
+        // without blocking the only thread of the runtime.
+        let result = read_cargo_output(CARGO_STDOUT.as_bytes(), CARGO_STDERR.as_bytes(), sender)
+            .await
+            .unwrap();
+
+        assert!(result);
+        assert_eq!(consumer.join().unwrap(), 4);
+    }
+
+    #[test]
+    fn messages_of_spawned_command() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo '{}'; echo 'warning' >&2"]);
+        let cargo_handle = TokioCargoHandle::spawn(&mut command).unwrap();
+
+        let messages: Vec<_> = cargo_handle.receiver().iter().collect();
+
+        assert!(messages.contains(&CargoMessage::CargoStdout("{}".to_string())));
+        assert!(messages.contains(&CargoMessage::CargoStderr("warning".to_string())));
+        assert!(cargo_handle.join().unwrap().success());
+    }
+
+    #[test]
+    fn executable_may_print_nothing() {
+        let mut command = Command::new("true");
+        let cargo_handle = TokioCargoHandle::spawn(&mut command).unwrap();
+        assert_eq!(cargo_handle.receiver().iter().count(), 0);
+        assert!(cargo_handle.join().is_err());
+
+        let cargo_handle = TokioCargoHandle::spawn_executable(&mut command).unwrap();
+        assert_eq!(cargo_handle.receiver().iter().count(), 0);
+
+        assert!(cargo_handle.join().unwrap().success());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn processes_of_spawned_command_cancelled() {
+        let pid_file = tempfile::NamedTempFile::new().unwrap();
+        let mut command = Command::new("sh");
+        command.args([
+            "-c",
+            &format!("sleep 10 & echo $! > {:?}; wait", pid_file.path()),
+        ]);
+        let cargo_handle = TokioCargoHandle::spawn(&mut command).unwrap();
+        let read_pid = || std::fs::read_to_string(pid_file.path()).unwrap();
+        let start = Instant::now();
+        while read_pid().trim().is_empty() && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        cargo_handle.cancel();
+
+        // The child of the spawned command is killed with its process group
+        // (it may stay a zombie until reaped).
+        let pid = read_pid().trim().to_string();
+        let is_running = || {
+            let output = Command::new("ps")
+                .args(["-o", "stat=", "-p", &pid])
+                .output()
+                .unwrap();
+            let stat = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            !stat.is_empty() && !stat.starts_with('Z')
+        };
+        let start = Instant::now();
+        while is_running() {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn spawned_command_cancelled() {
+        let mut command = Command::new("sleep");
+        command.arg("10");
+        let cargo_handle = TokioCargoHandle::spawn(&mut command).unwrap();
+        let start = Instant::now();
+
+        cargo_handle.cancel();
+
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -5,19 +5,21 @@ use bsp4rs::bsp::BuildTargetIdentifier;
 #[cfg(test)]
 use cargo_metadata::{Target, TargetBuilder};
 #[cfg(test)]
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[cfg(test)]
 use crate::project_model::cargo_package::CargoPackage;
+#[cfg(test)]
+use crate::project_model::target_selector::TargetSelector;
 
 #[cfg(test)]
-pub(super) fn test_target_id(uri: &str) -> BuildTargetIdentifier {
-    BuildTargetIdentifier { uri: uri.into() }
+pub(super) fn test_target_id(package: &str, name: &str, kind: &str) -> BuildTargetIdentifier {
+    TargetSelector::new(format!("/{package}"), name, kind).to_id()
 }
 
 #[cfg(test)]
-pub(super) fn test_target(name: &str, kind: &str) -> Rc<Target> {
-    Rc::new(
+pub(super) fn test_target(name: &str, kind: &str) -> Arc<Target> {
+    Arc::new(
         TargetBuilder::default()
             .name(name.to_string())
             .kind(vec![kind.to_string()])
@@ -28,9 +30,12 @@ pub(super) fn test_target(name: &str, kind: &str) -> Rc<Target> {
 }
 
 #[cfg(test)]
-pub(super) fn test_package(name: &str) -> CargoPackage {
+pub(super) fn test_package(name: &str, targets: Vec<Arc<Target>>) -> CargoPackage {
     CargoPackage {
         name: name.into(),
+        id: name.into(),
+        manifest_path: format!("/{name}/Cargo.toml").into(),
+        targets,
         ..CargoPackage::default()
     }
 }
@@ -0,0 +1,192 @@
+//! Estimates how many packages are added to the dependency graph when changing
+//! the features of a package enables its optional dependencies.
+
+use std::collections::{HashSet, VecDeque};
+
+use cargo_metadata::{Metadata, Node};
+
+use crate::project_model::cargo_package::CargoPackage;
+use crate::project_model::package_dependency::PackageDependency;
+use crate::project_model::rust_extension::get_nodes_from_metadata;
+
+/// Returns the message for the client if the features change of the package (from `before`
+/// to `after` state) enables new dependencies. The resolve graph of the metadata is
+/// obtained with all features enabled, so the count of the added packages is an estimate.
+pub(crate) fn added_dependencies_message(
+    metadata: &Metadata,
+    before: &CargoPackage,
+    after: &CargoPackage,
+) -> Option<String> {
+    let enabled_before = before.get_enabled_dependencies();
+    let added: Vec<&PackageDependency> = after
+        .get_enabled_dependencies()
+        .into_iter()
+        .filter(|d| !enabled_before.iter().any(|b| b.name == d.name))
+        .collect();
+    if added.is_empty() {
+        return None;
+    }
+
+    let nodes = get_nodes_from_metadata(metadata);
+    let reachable_before = reachable_packages(&nodes, package_ids(metadata, &enabled_before));
+    let added_packages_count = reachable_packages(&nodes, package_ids(metadata, &added))
+        .difference(&reachable_before)
+        .count();
+    Some(format!(
+        "Enabled features of package {} add dependencies: {} (approximately {} new packages in the dependency graph)",
+        after.name,
+        added.iter().map(|d| d.name.as_str()).collect::<Vec<_>>().join(", "),
+        added_packages_count
+    ))
+}
+
+fn package_ids(metadata: &Metadata, dependencies: &[&PackageDependency]) -> Vec<String> {
+    metadata
+        .packages
+        .iter()
+        .filter(|p| {
+            dependencies
+                .iter()
+                .any(|d| d.manifest_path == p.manifest_path.as_std_path())
+        })
+        .map(|p| p.id.repr.clone())
+        .collect()
+}
+
+/// Runs BFS on the resolve graph, starting from the given packages.
+fn reachable_packages(nodes: &[Node], start: Vec<String>) -> HashSet<String> {
+    let mut reachable: HashSet<String> = start.iter().cloned().collect();
+    let mut next_packages: VecDeque<String> = VecDeque::from(start);
+
+    while let Some(next) = next_packages.pop_front() {
+        if let Some(node) = nodes.iter().find(|n| n.id.repr == next) {
+            for dependency in &node.dependencies {
+                if reachable.insert(dependency.repr.clone()) {
+                    next_packages.push_back(dependency.repr.clone());
+                }
+            }
+        }
+    }
+    reachable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bsp4rs::rust::{Feature, FeatureDependencyGraph};
+    use serde_json::json;
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::path::PathBuf;
+
+    const HEAVY_FEATURE: &str = "heavy";
+
+    fn test_id(name: &str) -> String {
+        format!(
+            "{} 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)",
+            name
+        )
+    }
+
+    fn test_manifest_path(name: &str) -> String {
+        format!("/test_registry/{}-1.0.0/Cargo.toml", name)
+    }
+
+    /// `light` dependency depends on `shared`. Optional `heavy` dependency depends
+    /// on `shared` and `big`, which depends on `bigger`.
+    fn test_metadata() -> Metadata {
+        let packages: Vec<_> = ["app", "light", "heavy", "shared", "big", "bigger"]
+            .iter()
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "version": "1.0.0",
+                    "id": test_id(name),
+                    "source": null,
+                    "dependencies": [],
+                    "targets": [],
+                    "features": {},
+                    "manifest_path": test_manifest_path(name),
+                })
+            })
+            .collect();
+        let node = |name: &str, dependencies: &[&str]| {
+            json!({
+                "id": test_id(name),
+                "dependencies": dependencies.iter().map(|d| test_id(d)).collect::<Vec<_>>(),
+                "deps": [],
+                "features": [],
+            })
+        };
+        serde_json::from_value(json!({
+            "packages": packages,
+            "workspace_members": [test_id("app")],
+            "resolve": {
+                "nodes": [
+                    node("app", &["light", "heavy"]),
+                    node("light", &["shared"]),
+                    node("heavy", &["shared", "big"]),
+                    node("shared", &[]),
+                    node("big", &["bigger"]),
+                    node("bigger", &[]),
+                ],
+                "root": test_id("app"),
+            },
+            "workspace_root": "/test_root",
+            "target_directory": "/test_root/target",
+            "version": 1,
+        }))
+        .unwrap()
+    }
+
+    fn test_dependency(name: &str, optional: bool) -> PackageDependency {
+        PackageDependency {
+            name: name.to_string(),
+            manifest_path: PathBuf::from(test_manifest_path(name)),
+            optional,
+            ..PackageDependency::default()
+        }
+    }
+
+    fn test_package(enabled_features: &[&str]) -> CargoPackage {
+        CargoPackage {
+            name: "app".to_string(),
+            id: test_id("app"),
+            dependencies: vec![
+                test_dependency("light", false),
+                test_dependency("heavy", true),
+            ],
+            enabled_features: enabled_features.iter().map(|&f| Feature::from(f)).collect(),
+            package_features: FeatureDependencyGraph::new(BTreeMap::from([(
+                Feature::from(HEAVY_FEATURE),
+                BTreeSet::from([Feature::from("dep:heavy")]),
+            )])),
+            ..CargoPackage::default()
+        }
+    }
+
+    #[test]
+    fn test_feature_enabling_heavy_dependency() {
+        let message = added_dependencies_message(
+            &test_metadata(),
+            &test_package(&[]),
+            &test_package(&[HEAVY_FEATURE]),
+        );
+
+        // `heavy`, `big` and `bigger` are added, `shared` is already a dependency of `light`.
+        assert_eq!(
+            message.unwrap(),
+            "Enabled features of package app add dependencies: heavy (approximately 3 new packages in the dependency graph)"
+        );
+    }
+
+    #[test]
+    fn test_feature_not_enabling_dependencies() {
+        let message = added_dependencies_message(
+            &test_metadata(),
+            &test_package(&[HEAVY_FEATURE]),
+            &test_package(&[]),
+        );
+
+        assert_eq!(message, None);
+    }
+}
@@ -0,0 +1,172 @@
+//! Compares the build targets before and after the workspace update, so that
+//! the client is notified (with the `buildTarget/didChange` notification) only
+//! about the build targets that have actually changed.
+
+use std::collections::HashMap;
+
+use bsp4rs::bsp::{BuildTarget, BuildTargetEvent, BuildTargetEventKind, BuildTargetIdentifier};
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct BuildTargetsDiff {
+    pub(crate) added: Vec<BuildTargetIdentifier>,
+    pub(crate) removed: Vec<BuildTargetIdentifier>,
+    /// Build targets with the same id, but e.g. different capabilities, dependencies or data.
+    pub(crate) changed: Vec<BuildTargetIdentifier>,
+}
+
+/// The build targets keep the order of the given lists.
+pub(crate) fn diff_build_targets(
+    old_targets: &[BuildTarget],
+    new_targets: &[BuildTarget],
+) -> BuildTargetsDiff {
+    let old_by_id: HashMap<&BuildTargetIdentifier, &BuildTarget> =
+        old_targets.iter().map(|t| (&t.id, t)).collect();
+    let new_by_id: HashMap<&BuildTargetIdentifier, &BuildTarget> =
+        new_targets.iter().map(|t| (&t.id, t)).collect();
+
+    let mut diff = BuildTargetsDiff::default();
+    for new in new_targets {
+        match old_by_id.get(&new.id) {
+            None => diff.added.push(new.id.clone()),
+            Some(&old) if old != new => diff.changed.push(new.id.clone()),
+            Some(_) => (),
+        }
+    }
+    diff.removed = old_targets
+        .iter()
+        .filter(|old| !new_by_id.contains_key(&old.id))
+        .map(|old| old.id.clone())
+        .collect();
+    diff
+}
+
+impl BuildTargetsDiff {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Events of the `buildTarget/didChange` notification, one per changed build target.
+    pub(crate) fn into_events(self) -> Vec<BuildTargetEvent> {
+        let events = |ids: Vec<BuildTargetIdentifier>, kind: BuildTargetEventKind| {
+            ids.into_iter().map(move |target| BuildTargetEvent {
+                target,
+                kind: Some(kind.clone()),
+                data: None,
+            })
+        };
+        events(self.added, BuildTargetEventKind::Created)
+            .chain(events(self.changed, BuildTargetEventKind::Changed))
+            .chain(events(self.removed, BuildTargetEventKind::Deleted))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bsp4rs::bsp::{BuildTargetCapabilities, BuildTargetTag};
+
+    fn test_id(id: &str) -> BuildTargetIdentifier {
+        BuildTargetIdentifier { uri: id.into() }
+    }
+
+    fn test_target(id: &str) -> BuildTarget {
+        BuildTarget {
+            id: test_id(id),
+            ..BuildTarget::default()
+        }
+    }
+
+    #[test]
+    fn added_targets() {
+        let diff = diff_build_targets(&[test_target("a")], &[test_target("a"), test_target("b")]);
+
+        assert_eq!(
+            diff,
+            BuildTargetsDiff {
+                added: vec![test_id("b")],
+                ..BuildTargetsDiff::default()
+            }
+        );
+    }
+
+    #[test]
+    fn removed_targets() {
+        let diff = diff_build_targets(&[test_target("a"), test_target("b")], &[test_target("b")]);
+
+        assert_eq!(
+            diff,
+            BuildTargetsDiff {
+                removed: vec![test_id("a")],
+                ..BuildTargetsDiff::default()
+            }
+        );
+    }
+
+    #[test]
+    fn changed_capabilities() {
+        let old = test_target("a");
+        let new = BuildTarget {
+            capabilities: BuildTargetCapabilities {
+                can_run: Some(true),
+                ..BuildTargetCapabilities::default()
+            },
+            ..test_target("a")
+        };
+
+        let diff = diff_build_targets(&[old], &[new]);
+
+        assert_eq!(
+            diff,
+            BuildTargetsDiff {
+                changed: vec![test_id("a")],
+                ..BuildTargetsDiff::default()
+            }
+        );
+    }
+
+    #[test]
+    fn unchanged_targets() {
+        let targets = vec![test_target("a"), test_target("b")];
+
+        let diff = diff_build_targets(&targets, &targets);
+
+        assert!(diff.is_empty());
+        assert!(diff.into_events().is_empty());
+    }
+
+    #[test]
+    fn diff_into_events() {
+        let old_targets = vec![
+            test_target("unchanged"),
+            BuildTarget {
+                tags: vec![BuildTargetTag::LIBRARY],
+                ..test_target("changed")
+            },
+            test_target("deleted"),
+        ];
+        let new_targets = vec![
+            test_target("unchanged"),
+            BuildTarget {
+                tags: vec![BuildTargetTag::APPLICATION],
+                ..test_target("changed")
+            },
+            test_target("created"),
+        ];
+
+        let events: Vec<_> = diff_build_targets(&old_targets, &new_targets)
+            .into_events()
+            .into_iter()
+            .map(|e| (e.target.uri.0, e.kind.unwrap()))
+            .collect();
+
+        assert_eq!(
+            events,
+            vec![
+                ("created".to_string(), BuildTargetEventKind::Created),
+                ("changed".to_string(), BuildTargetEventKind::Changed),
+                ("deleted".to_string(), BuildTargetEventKind::Deleted),
+            ]
+        );
+    }
+}
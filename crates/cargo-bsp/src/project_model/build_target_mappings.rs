@@ -1,12 +1,14 @@
 //! Maps Cargo metadata target to the BSP build target.
 
 use std::fmt::Display;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use cargo_metadata::camino::Utf8PathBuf;
 use log::warn;
 
 use crate::project_model::metadata_edition_to_bsp_edition;
+use crate::project_model::rust_extension::metadata_crate_types_to_rust_extension_crate_types;
+use crate::project_model::target_selector::TargetSelector;
 use crate::project_model::RUST_ID;
 use bsp4rs::bsp::{
     BuildTarget, BuildTargetCapabilities, BuildTargetData, BuildTargetIdentifier, BuildTargetTag,
@@ -17,6 +19,15 @@ use bsp4rs::rust::Feature;
 
 use crate::utils::uri::file_uri;
 
+/// Tag of the targets, which are not built with the currently enabled features
+/// (some of their `required-features` are disabled), so the clients can grey them out.
+pub const INACTIVE_TARGET_TAG: BuildTargetTag = BuildTargetTag::new("inactive");
+
+/// Tag of the example targets, which are also tagged as applications,
+/// so the clients can group them separately from the binaries.
+pub const EXAMPLE_TARGET_TAG: BuildTargetTag = BuildTargetTag::new("example");
+
+/// Id of the build targets which are not Cargo targets (e.g. the discovered unit tests).
 pub fn build_target_id_from_name_and_path<T: Display, R: Display>(
     name: T,
     path: R,
@@ -26,6 +37,14 @@ pub fn build_target_id_from_name_and_path<T: Display, R: Display>(
     }
 }
 
+/// See [`TargetSelector::to_id`] for the format of the id.
+pub fn build_target_id_from_cargo_target(
+    package_manifest_path: &Utf8PathBuf,
+    cargo_target: &cargo_metadata::Target,
+) -> BuildTargetIdentifier {
+    TargetSelector::from_cargo_target(package_manifest_path, cargo_target).to_id()
+}
+
 /// We assume that this function is called only with valid path which has a parent
 pub fn parent_path(path: &Utf8PathBuf) -> Utf8PathBuf {
     let mut parent_directory = path.clone();
@@ -37,6 +56,8 @@ pub fn path_parent_directory_uri(path: &Utf8PathBuf) -> URI {
     file_uri(parent_path(path))
 }
 
+/// Libraries can be tested if they have unit tests or doctests and binaries if they
+/// have unit tests (the `test` and `doctest` fields of the target in the manifest).
 fn tags_and_capabilities_from_cargo_kind(
     cargo_target: &cargo_metadata::Target,
 ) -> (Vec<BuildTargetTag>, BuildTargetCapabilities) {
@@ -55,15 +76,19 @@ fn tags_and_capabilities_from_cargo_kind(
                 tags.push(BuildTargetTag::LIBRARY);
                 capabilities.can_debug = Some(false);
                 capabilities.can_run = Some(false);
+                capabilities.can_test = Some(cargo_target.test || cargo_target.doctest);
             }
             "bin" => {
                 tags.push(BuildTargetTag::APPLICATION);
+                capabilities.can_test = Some(cargo_target.test);
             }
             "example" => {
                 tags.push(BuildTargetTag::APPLICATION);
+                tags.push(EXAMPLE_TARGET_TAG);
                 capabilities.can_test = Some(false);
             }
             "test" => {
+                tags.push(BuildTargetTag::TEST);
                 tags.push(BuildTargetTag::INTEGRATION_TEST);
                 capabilities.can_run = Some(false);
             }
@@ -82,7 +107,11 @@ fn tags_and_capabilities_from_cargo_kind(
     (tags, capabilities)
 }
 
-pub fn bsp_build_target_from_cargo_target(
+/// Maps the Cargo target of the package (given by its manifest path) to the BSP build target.
+/// The base directory of the target is the root of its package (the directory with the
+/// package manifest), also for targets with sources outside of it.
+pub fn build_target_from_cargo(
+    package_manifest_path: &Utf8PathBuf,
     cargo_target: &cargo_metadata::Target,
     target_dependencies: &[BuildTargetIdentifier],
 ) -> BuildTarget {
@@ -95,13 +124,16 @@ pub fn bsp_build_target_from_cargo_target(
             .iter()
             .map(|f| Feature::from(f.as_str()))
             .collect(),
+        crate_types: metadata_crate_types_to_rust_extension_crate_types(
+            cargo_target.crate_types.clone(),
+        ),
     });
 
     BuildTarget {
-        id: build_target_id_from_name_and_path(&cargo_target.name, &cargo_target.src_path),
+        id: build_target_id_from_cargo_target(package_manifest_path, cargo_target),
         display_name: Some(cargo_target.name.clone()),
         // We assume that cargo metadata always returns valid paths, which additionally have a parent
-        base_directory: Some(path_parent_directory_uri(&cargo_target.src_path)),
+        base_directory: Some(path_parent_directory_uri(package_manifest_path)),
         tags,
         capabilities,
         language_ids: vec![RUST_ID.into()],
@@ -111,10 +143,192 @@ pub fn bsp_build_target_from_cargo_target(
 }
 
 pub fn build_target_ids_from_cargo_targets(
-    cargo_targets: &[Rc<cargo_metadata::Target>],
+    package_manifest_path: &Utf8PathBuf,
+    cargo_targets: &[Arc<cargo_metadata::Target>],
 ) -> Vec<BuildTargetIdentifier> {
     cargo_targets
         .iter()
-        .map(|target| build_target_id_from_name_and_path(&target.name, &target.src_path))
+        .map(|target| build_target_id_from_cargo_target(package_manifest_path, target))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bsp4rs::rust::RustCrateType;
+    use cargo_metadata::TargetBuilder;
+    use std::collections::BTreeSet;
+    use test_case::test_case;
+
+    const TEST_MANIFEST_PATH: &str = "/test_root/Cargo.toml";
+
+    fn test_target(kind: &str) -> cargo_metadata::Target {
+        TargetBuilder::default()
+            .name("test_target".to_string())
+            .kind(vec![kind.to_string()])
+            .src_path("/test_root/src/test_target.rs".to_string())
+            .build()
+            .unwrap()
+    }
+
+    fn test_dependency_id() -> BuildTargetIdentifier {
+        TargetSelector::new("/dep", "dep", "lib").to_id()
+    }
+
+    fn expected_build_target(
+        kind: &str,
+        tags: Vec<BuildTargetTag>,
+        capabilities: BuildTargetCapabilities,
+        crate_types: Vec<RustCrateType>,
+    ) -> BuildTarget {
+        BuildTarget {
+            id: BuildTargetIdentifier {
+                uri: URI(format!("cargo:///test_root?name=test_target&kind={kind}")),
+            },
+            display_name: Some("test_target".to_string()),
+            base_directory: Some(file_uri("/test_root")),
+            tags,
+            language_ids: vec![RUST_ID.into()],
+            dependencies: vec![test_dependency_id()],
+            capabilities,
+            data: Some(BuildTargetData::cargo(CargoBuildTarget {
+                edition: bsp4rs::rust::RustEdition::E2021,
+                required_features: BTreeSet::new(),
+                crate_types,
+            })),
+        }
+    }
+
+    fn build_target_from_test_target(kind: &str) -> BuildTarget {
+        let mut target = test_target(kind);
+        target.edition = cargo_metadata::Edition::E2021;
+        target.crate_types = vec![kind.replace("example", "bin")];
+        target.test = kind != "example";
+        target.doctest = kind == "lib";
+        let dependencies = [test_dependency_id()];
+
+        build_target_from_cargo(&TEST_MANIFEST_PATH.into(), &target, &dependencies)
+    }
+
+    #[test]
+    fn test_build_target_from_cargo_lib() {
+        assert_eq!(
+            build_target_from_test_target("lib"),
+            expected_build_target(
+                "lib",
+                vec![BuildTargetTag::LIBRARY],
+                BuildTargetCapabilities {
+                    can_compile: Some(true),
+                    can_test: Some(true),
+                    can_run: Some(false),
+                    can_debug: Some(false),
+                },
+                vec![RustCrateType::Lib],
+            )
+        );
+    }
+
+    #[test]
+    fn test_build_target_from_cargo_bin() {
+        assert_eq!(
+            build_target_from_test_target("bin"),
+            expected_build_target(
+                "bin",
+                vec![BuildTargetTag::APPLICATION],
+                BuildTargetCapabilities {
+                    can_compile: Some(true),
+                    can_test: Some(true),
+                    can_run: Some(true),
+                    can_debug: Some(true),
+                },
+                vec![RustCrateType::Bin],
+            )
+        );
+    }
+
+    #[test]
+    fn test_build_target_from_cargo_example() {
+        assert_eq!(
+            build_target_from_test_target("example"),
+            expected_build_target(
+                "example",
+                vec![BuildTargetTag::APPLICATION, EXAMPLE_TARGET_TAG],
+                BuildTargetCapabilities {
+                    can_compile: Some(true),
+                    can_test: Some(false),
+                    can_run: Some(true),
+                    can_debug: Some(true),
+                },
+                vec![RustCrateType::Bin],
+            )
+        );
+    }
+
+    #[test_case("lib", &[BuildTargetTag::LIBRARY] ; "lib")]
+    #[test_case("bin", &[BuildTargetTag::APPLICATION] ; "bin")]
+    #[test_case("example", &[BuildTargetTag::APPLICATION, EXAMPLE_TARGET_TAG] ; "example")]
+    #[test_case("test", &[BuildTargetTag::TEST, BuildTargetTag::INTEGRATION_TEST] ; "test")]
+    #[test_case("bench", &[BuildTargetTag::BENCHMARK] ; "bench")]
+    fn test_tags_from_cargo_kind(kind: &str, expected: &[BuildTargetTag]) {
+        let build_target =
+            build_target_from_cargo(&TEST_MANIFEST_PATH.into(), &test_target(kind), &[]);
+
+        assert_eq!(build_target.tags, expected);
+    }
+
+    #[test_case("lib", false, true ; "lib")]
+    #[test_case("bin", true, true ; "bin")]
+    #[test_case("test", false, true ; "test")]
+    // Benchmarks are run with the test request, `cargo run` accepts only binaries and examples.
+    #[test_case("example", true, false ; "example")]
+    #[test_case("bench", false, true ; "bench")]
+    fn test_capabilities_from_cargo_kind(kind: &str, can_run: bool, can_test: bool) {
+        let build_target =
+            build_target_from_cargo(&TEST_MANIFEST_PATH.into(), &test_target(kind), &[]);
+
+        assert_eq!(
+            build_target.capabilities,
+            BuildTargetCapabilities {
+                can_compile: Some(true),
+                can_test: Some(can_test),
+                can_run: Some(can_run),
+                can_debug: Some(kind != "lib"),
+            }
+        );
+    }
+
+    #[test_case("lib", false, false, false ; "lib_without_tests")]
+    #[test_case("lib", false, true, true ; "lib_with_doctests")]
+    #[test_case("bin", false, false, false ; "bin_without_tests")]
+    #[test_case("bin", true, false, true ; "bin_with_tests")]
+    fn test_can_test_from_target_flags(kind: &str, test: bool, doctest: bool, can_test: bool) {
+        let mut target = test_target(kind);
+        target.test = test;
+        target.doctest = doctest;
+
+        let build_target = build_target_from_cargo(&TEST_MANIFEST_PATH.into(), &target, &[]);
+
+        assert_eq!(build_target.capabilities.can_test, Some(can_test));
+    }
+
+    #[test]
+    fn test_cargo_build_target_data() {
+        let mut target = test_target("lib");
+        target.edition = cargo_metadata::Edition::E2021;
+        target.crate_types = vec!["lib".to_string(), "cdylib".to_string()];
+        target.required_features = vec!["test_feature".to_string()];
+
+        let build_target = build_target_from_cargo(&TEST_MANIFEST_PATH.into(), &target, &[]);
+        let data = serde_json::to_value(build_target.data.unwrap()).unwrap();
+
+        assert_eq!(data["dataKind"], "cargo");
+        assert_eq!(
+            serde_json::from_value::<CargoBuildTarget>(data["data"].clone()).unwrap(),
+            CargoBuildTarget {
+                edition: bsp4rs::rust::RustEdition::E2021,
+                required_features: ["test_feature".into()].into(),
+                crate_types: vec![RustCrateType::Lib, RustCrateType::Cdylib],
+            }
+        );
+    }
+}
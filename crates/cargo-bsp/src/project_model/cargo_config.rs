@@ -0,0 +1,89 @@
+//! Reading of the project's Cargo configuration (`.cargo/config.toml`), as described in
+//! [Cargo documentation](https://doc.rust-lang.org/cargo/reference/config.html).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::warn;
+use toml::{Table, Value};
+
+/// Names of the configuration file in the `.cargo` directory, `config` is the legacy one.
+const CONFIG_FILE_NAMES: [&str; 2] = ["config.toml", "config"];
+
+/// Flags from the `build.rustdocflags` key. Cargo searches for the configuration
+/// in the project directory and all its ancestors, the nearest file defining the key is used.
+pub(crate) fn rustdoc_flags(root: &Path) -> Vec<String> {
+    root.ancestors()
+        .filter_map(read_config)
+        .find_map(|config| rustdoc_flags_from_config(&config))
+        .unwrap_or_default()
+}
+
+fn config_file(dir: &Path) -> Option<PathBuf> {
+    CONFIG_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(".cargo").join(name))
+        .find(|path| path.is_file())
+}
+
+fn read_config(dir: &Path) -> Option<Table> {
+    let path = config_file(dir)?;
+    let content = fs::read_to_string(&path)
+        .map_err(|e| warn!("Failed to read Cargo config {:?}: {}", path, e))
+        .ok()?;
+    content
+        .parse::<Table>()
+        .map_err(|e| warn!("Failed to parse Cargo config {:?}: {}", path, e))
+        .ok()
+}
+
+/// The flags can be either a space-separated string or an array of strings.
+fn rustdoc_flags_from_config(config: &Table) -> Option<Vec<String>> {
+    match config.get("build")?.get("rustdocflags")? {
+        Value::String(flags) => Some(flags.split_whitespace().map(String::from).collect()),
+        Value::Array(flags) => Some(
+            flags
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    use test_case::test_case;
+
+    #[test_case("rustdocflags = \"--cfg  doctest_cfg\"" ; "string")]
+    #[test_case("rustdocflags = [\"--cfg\", \"doctest_cfg\"]" ; "array")]
+    fn test_rustdoc_flags(build_config: &str) {
+        let root = tempdir().unwrap();
+        let nested = root.path().join("nested");
+        fs::create_dir_all(root.path().join(".cargo")).unwrap();
+        fs::create_dir_all(nested.join(".cargo")).unwrap();
+        fs::write(
+            root.path().join(".cargo").join("config.toml"),
+            format!("[build]\n{}\n", build_config),
+        )
+        .unwrap();
+        // Nearer configuration without the key does not hide the one from the ancestor.
+        fs::write(
+            nested.join(".cargo").join("config"),
+            "[alias]\nb = \"build\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(rustdoc_flags(&nested), vec!["--cfg", "doctest_cfg"]);
+    }
+
+    #[test]
+    fn test_rustdoc_flags_without_config() {
+        let root = tempdir().unwrap();
+
+        assert!(rustdoc_flags(root.path()).is_empty());
+    }
+}
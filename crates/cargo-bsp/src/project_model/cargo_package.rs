@@ -2,23 +2,27 @@
 //! available and enabled features (relevant for the Cargo extension for BSP,
 //! which allows toggling the features, not yet added to the BSP documentation).
 
-use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::ops::Deref;
 
-use std::rc::Rc;
+use std::sync::Arc;
 
 use cargo_metadata::camino::Utf8PathBuf;
+use cargo_metadata::DependencyKind;
 use log::{error, warn};
 
-use bsp4rs::bsp::{BuildTarget, BuildTargetIdentifier};
-use bsp4rs::cargo::PackageFeatures;
-use bsp4rs::rust::{Feature, FeatureDependencyGraph};
+use bsp4rs::bsp::{BuildTarget, BuildTargetData, BuildTargetIdentifier, NamedBuildTargetData};
+use bsp4rs::cargo::{FeatureMode, PackageFeatures};
+use bsp4rs::rust::{Feature, FeatureDependencyGraph, RustEdition};
 
 use crate::project_model::build_target_mappings::{
-    bsp_build_target_from_cargo_target, build_target_ids_from_cargo_targets,
+    build_target_from_cargo, build_target_id_from_cargo_target,
+    build_target_ids_from_cargo_targets, INACTIVE_TARGET_TAG,
 };
+use crate::project_model::host_platform::HostPlatform;
 use crate::project_model::package_dependency::PackageDependency;
-use crate::project_model::{CreateFeatureDependencyGraph, DefaultFeature};
+use crate::project_model::target_details::CargoTargetKind;
+use crate::project_model::{CreateFeatureDependencyGraph, DefaultFeature, UnknownEditions};
 
 #[derive(Default, Debug, Clone)]
 pub struct CargoPackage {
@@ -28,6 +32,9 @@ pub struct CargoPackage {
     /// Unique identifier of the package
     pub id: String,
 
+    /// Version of the package
+    pub version: String,
+
     /// Path to the package's manifest
     pub manifest_path: Utf8PathBuf,
 
@@ -35,7 +42,7 @@ pub struct CargoPackage {
     pub dependencies: Vec<PackageDependency>,
 
     /// List of all package targets, from which BSP build targets are created
-    pub targets: Vec<Rc<cargo_metadata::Target>>,
+    pub targets: Vec<Arc<cargo_metadata::Target>>,
 
     /// List of enabled (by BSP client) features. Only top-level features are included.
     /// If `default` feature is not included, default features are disabled.
@@ -44,12 +51,17 @@ pub struct CargoPackage {
     /// Hashmap where key is a feature name and the value are names of other features it enables.
     /// Includes pair for default features if default is defined
     pub package_features: FeatureDependencyGraph,
+
+    /// Editions of the targets unknown to `cargo_metadata`, reported to the client as they are.
+    /// Key is a target name and target kinds
+    pub unknown_target_editions: HashMap<(String, Vec<String>), RustEdition>,
 }
 
 impl CargoPackage {
     pub fn new(
         metadata_package: &cargo_metadata::Package,
         all_packages: &[cargo_metadata::Package],
+        unknown_editions: &UnknownEditions,
     ) -> Self {
         let package_features =
             FeatureDependencyGraph::create_features_dependency_graph(metadata_package);
@@ -63,6 +75,7 @@ impl CargoPackage {
         Self {
             name: metadata_package.name.clone(),
             id: metadata_package.id.repr.clone(),
+            version: metadata_package.version.to_string(),
             manifest_path: metadata_package.manifest_path.clone(),
             dependencies: PackageDependency::create_package_dependencies_from_metadata(
                 &metadata_package.dependencies,
@@ -72,10 +85,12 @@ impl CargoPackage {
                 .targets
                 .iter()
                 .cloned()
-                .map(Rc::new)
+                .map(Arc::new)
                 .collect(),
             enabled_features,
             package_features,
+            unknown_target_editions: unknown_editions
+                .package_target_editions(&metadata_package.id.repr),
         }
     }
 
@@ -125,30 +140,244 @@ impl CargoPackage {
         false
     }
 
-    /// Returns a vector of BuildTargetIdentifiers for all dependencies that
+    /// Returns the library target of the package, the one other packages depend on.
+    fn lib_target(&self) -> Option<&Arc<cargo_metadata::Target>> {
+        self.targets.iter().find(|t| {
+            t.kind.iter().any(|k| {
+                matches!(
+                    k.as_str(),
+                    "lib" | "rlib" | "dylib" | "cdylib" | "staticlib" | "proc-macro"
+                )
+            })
+        })
+    }
+
+    /// Returns a vector of BuildTargetIdentifiers of the library targets of all dependencies that
     /// * are enabled
-    /// * their BuildTargetId could be created
-    fn feature_based_dependencies_as_build_target_ids(&self) -> Vec<BuildTargetIdentifier> {
+    /// * are of the given kinds
+    /// * are workspace packages, external packages are not build targets
+    fn workspace_dependencies_as_build_target_ids(
+        &self,
+        kinds: &[DependencyKind],
+        workspace_packages: &[CargoPackage],
+    ) -> Vec<BuildTargetIdentifier> {
         self.dependencies
             .iter()
+            .filter(|dep| kinds.contains(&dep.kind) && self.is_dependency_enabled(dep))
             .filter_map(|dep| {
-                if !self.is_dependency_enabled(dep) {
-                    return None;
-                }
-                dep.create_id_from_dependency()
+                workspace_packages
+                    .iter()
+                    .find(|p| p.manifest_path.as_std_path() == dep.manifest_path)
+            })
+            .filter_map(|p| {
+                p.lib_target()
+                    .map(|t| build_target_id_from_cargo_target(&p.manifest_path, t))
             })
             .collect()
     }
 
-    /// Returns a vector of BuildTargets for all targets in the package
-    pub fn get_bsp_build_targets(&self) -> Vec<BuildTarget> {
-        let dependencies = self.feature_based_dependencies_as_build_target_ids();
+    /// Returns the enabled features together with the features they enable.
+    /// Runs BFS on the features graph starting from the enabled features.
+    fn all_enabled_features(&self) -> HashSet<Feature> {
+        self.features_closure(self.enabled_features.iter().cloned())
+    }
+
+    /// Returns the features together with all the features they (transitively) enable.
+    fn features_closure(&self, features: impl IntoIterator<Item = Feature>) -> HashSet<Feature> {
+        let mut next_features: VecDeque<Feature> = features.into_iter().collect();
+        let mut checked_features: HashSet<Feature> = HashSet::from_iter(next_features.clone());
+
+        while let Some(f) = next_features.pop_front() {
+            if let Some(dependent_features) = self.package_features.get(&f) {
+                for df in dependent_features {
+                    if self.is_defined_feature(df) && checked_features.insert(df.clone()) {
+                        next_features.push_back(df.clone());
+                    }
+                }
+            }
+        }
+        checked_features
+    }
+
+    /// Dev dependencies are available only to the tests, benchmarks and examples,
+    /// not to the libraries and binaries.
+    fn uses_dev_dependencies(cargo_target: &cargo_metadata::Target) -> bool {
+        cargo_target
+            .kind
+            .iter()
+            .filter_map(|k| k.parse::<CargoTargetKind>().ok())
+            .any(|k| !k.is_lib() && k != CargoTargetKind::Bin)
+    }
+
+    /// Target is built only if all its `required-features` are enabled.
+    fn is_target_active(
+        cargo_target: &cargo_metadata::Target,
+        enabled_features: &HashSet<Feature>,
+    ) -> bool {
+        cargo_target
+            .required_features
+            .iter()
+            .all(|f| enabled_features.contains(&Feature::from(f.as_str())))
+    }
+
+    /// Target is built for the host only if the optional dependencies enabled by its
+    /// `required-features` are available on the host platform, as they may be declared only
+    /// for the other platforms (e.g. in `[target.'cfg(windows)'.dependencies]`).
+    fn is_target_available_on_host(
+        &self,
+        cargo_target: &cargo_metadata::Target,
+        host: &HostPlatform,
+    ) -> bool {
+        let required_features: Vec<Feature> = cargo_target
+            .required_features
+            .iter()
+            .map(|f| Feature::from(f.as_str()))
+            .collect();
+        let enabled_entries: Vec<&Feature> = self
+            .features_closure(required_features.iter().cloned())
+            .iter()
+            .filter_map(|f| self.package_features.get(f))
+            .flatten()
+            .chain(&required_features)
+            .collect();
+        self.dependencies
+            .iter()
+            .filter(|dep| {
+                dep.optional
+                    && enabled_entries
+                        .iter()
+                        .any(|f| CargoPackage::feature_enables_dependency(f, &dep.name))
+            })
+            .all(|dep| {
+                // The dependency may be declared for multiple platforms.
+                self.dependencies
+                    .iter()
+                    .filter(|d| d.name == dep.name)
+                    .any(|d| host.matches(&d.target))
+            })
+    }
+
+    /// Target can be built only if all its `required-features` exist in the package.
+    /// Features of the dependencies (`dependency/feature`) are not checked.
+    fn is_target_satisfiable(&self, cargo_target: &cargo_metadata::Target) -> bool {
+        cargo_target
+            .required_features
+            .iter()
+            .all(|f| f.contains('/') || self.is_defined_feature(&Feature::from(f.as_str())))
+    }
+
+    /// Returns a vector of BuildTargets for all targets in the package,
+    /// targets inactive with the current features are tagged with [`INACTIVE_TARGET_TAG`].
+    /// In the other than default feature modes all features are enabled (`--all-features`)
+    /// or the `required-features` are passed with the target, so only the targets
+    /// not available on the `host` platform are inactive.
+    /// Targets with `required-features` not defined in the package are skipped,
+    /// as they cannot be built with any features.
+    ///
+    /// The dependencies of the targets are the library targets of the `workspace_packages`
+    /// the package depends on: build script depends on the build dependencies, other targets
+    /// on the normal dependencies and on the library target of their own package. The tests,
+    /// benchmarks and examples also depend on the dev dependencies.
+    pub fn get_bsp_build_targets(
+        &self,
+        workspace_packages: &[CargoPackage],
+        feature_mode: &FeatureMode,
+        host: Option<&HostPlatform>,
+    ) -> Vec<BuildTarget> {
+        let build_dependencies = self.workspace_dependencies_as_build_target_ids(
+            &[DependencyKind::Build],
+            workspace_packages,
+        );
+        let dependencies = self.workspace_dependencies_as_build_target_ids(
+            &[DependencyKind::Normal],
+            workspace_packages,
+        );
+        let dev_dependencies = self.workspace_dependencies_as_build_target_ids(
+            &[DependencyKind::Development],
+            workspace_packages,
+        );
+        let lib_target = self.lib_target();
+        let enabled_features = self.all_enabled_features();
         self.targets
             .iter()
-            .map(|t| bsp_build_target_from_cargo_target(t, &dependencies))
+            .filter(|t| self.is_target_satisfiable(t))
+            .map(|t| {
+                let is_build_script = t.kind.iter().any(|k| k == "custom-build");
+                let target_dependencies = if is_build_script {
+                    build_dependencies.clone()
+                } else {
+                    let mut target_dependencies = dependencies.clone();
+                    if CargoPackage::uses_dev_dependencies(t) {
+                        target_dependencies.extend(dev_dependencies.iter().cloned());
+                    }
+                    if let Some(lib) = lib_target.filter(|&lib| !Arc::ptr_eq(lib, t)) {
+                        target_dependencies.insert(
+                            0,
+                            build_target_id_from_cargo_target(&self.manifest_path, lib),
+                        );
+                    }
+                    target_dependencies
+                };
+                let mut build_target =
+                    build_target_from_cargo(&self.manifest_path, t, &target_dependencies);
+                if let Some(edition) = self
+                    .unknown_target_editions
+                    .get(&(t.name.clone(), t.kind.clone()))
+                {
+                    if let Some(BuildTargetData::Named(NamedBuildTargetData::Cargo(data))) =
+                        &mut build_target.data
+                    {
+                        data.edition = edition.clone();
+                    }
+                }
+                let is_feature_gated = *feature_mode == FeatureMode::Default
+                    && !CargoPackage::is_target_active(t, &enabled_features);
+                let is_platform_gated =
+                    host.is_some_and(|host| !self.is_target_available_on_host(t, host));
+                if is_feature_gated || is_platform_gated {
+                    build_target.tags.push(INACTIVE_TARGET_TAG);
+                }
+                build_target
+            })
             .collect()
     }
 
+    /// Enables the features. Only the requested features are stored, the features they
+    /// (transitively) enable are resolved when the targets are tagged and by Cargo itself.
+    /// If some of the features don't exist in the package, the state is not changed.
+    pub fn enable_features(&mut self, features: &BTreeSet<Feature>) -> Result<(), String> {
+        if let Some(f) = features.iter().find(|f| !self.is_defined_feature(f)) {
+            return Err(format!(
+                "Can't enable feature {:?}. It doesn't exist in package {}.",
+                f.as_str(),
+                self.name
+            ));
+        }
+        self.enabled_features.extend(features.iter().cloned());
+        Ok(())
+    }
+
+    /// Disables the features. If some of them are (transitively) enabled by the features
+    /// which stay enabled, the state is not changed.
+    pub fn disable_features(&mut self, features: &BTreeSet<Feature>) -> Result<(), String> {
+        let still_enabled =
+            self.features_closure(self.enabled_features.difference(features).cloned());
+        let required: Vec<&str> = features
+            .iter()
+            .filter(|f| still_enabled.contains(f))
+            .map(|f| f.as_str())
+            .collect();
+        if !required.is_empty() {
+            return Err(format!(
+                "Can't disable features {}. They are required by other enabled features of package {}.",
+                required.join(", "),
+                self.name
+            ));
+        }
+        self.enabled_features.retain(|f| !features.contains(f));
+        Ok(())
+    }
+
     /// Sets features from list, which exist in the package as new package feature state.
     /// If `default` feature is not included in the list, default features are disabled.
     pub fn set_features(&mut self, features: &BTreeSet<Feature>) {
@@ -173,7 +402,7 @@ impl CargoPackage {
     pub fn get_enabled_features(&self) -> PackageFeatures {
         PackageFeatures {
             package_id: self.id.clone(),
-            targets: build_target_ids_from_cargo_targets(&self.targets),
+            targets: build_target_ids_from_cargo_targets(&self.manifest_path, &self.targets),
             enabled_features: self.enabled_features.clone(),
             available_features: self.package_features.clone(),
         }
@@ -183,10 +412,15 @@ impl CargoPackage {
 #[cfg(test)]
 mod tests {
     use bsp4rs::rust::{Feature, FeatureDependencyGraph};
+    use cargo_metadata::TargetBuilder;
+    use cargo_platform::{Cfg, Platform};
     use std::collections::{BTreeMap, BTreeSet};
+    use std::str::FromStr;
     use test_case::test_case;
 
     use super::*;
+    use crate::project_model::metadata_edition_to_bsp_edition;
+    use crate::project_model::target_selector::TargetSelector;
 
     const DEP_NAME: &str = "dependency-name";
     const F1: &str = "feature1";
@@ -292,6 +526,431 @@ mod tests {
         assert_eq!(test_package.enabled_features, expected);
     }
 
+    #[test_case(&[], &[F1], &[F1] ; "stores_only_requested_features")]
+    #[test_case(&[F4], &[F2], &[F2, F4] ; "keeps_enabled_features")]
+    #[test_case(&[F1], &[F2], &[F1, F2] ; "feature_enabled_by_other_feature")]
+    fn test_enable_features(
+        enabled_features_slice: &[&str],
+        features_to_enable: &[&str],
+        expected: &[&str],
+    ) {
+        let mut test_package = default_cargo_package_with_features(
+            &[(F1, &[F2]), (F2, &[F3]), (F3, &[]), (F4, &[])],
+            Some(enabled_features_slice),
+        );
+
+        let result =
+            test_package.enable_features(&create_feature_set_from_slices(features_to_enable));
+
+        assert!(result.is_ok());
+        assert_eq!(
+            test_package.enabled_features,
+            create_feature_set_from_slices(expected)
+        );
+    }
+
+    #[test]
+    fn test_enable_undefined_feature() {
+        let mut test_package = default_cargo_package_with_features(&[(F1, &[])], Some(&[]));
+
+        let result = test_package.enable_features(&create_feature_set_from_slices(&[F1, F2]));
+
+        assert!(result.is_err());
+        assert!(test_package.enabled_features.is_empty());
+    }
+
+    #[test_case(&[F1, F2, F3], &[F2], false ; "blocked_by_enabling_feature")]
+    #[test_case(&[F1, F2, F3], &[F1, F2, F3], true ; "together_with_enabling_feature")]
+    #[test_case(&[F1, F2, F3, F4], &[F4], true ; "not_required_feature")]
+    #[test_case(&[F1], &[F2], false ; "blocked_by_transitively_enabling_feature")]
+    #[test_case(&[F1, F4], &[F1], true ; "top_level_feature")]
+    fn test_disable_features(
+        enabled_features_slice: &[&str],
+        features_to_disable: &[&str],
+        expected_disabled: bool,
+    ) {
+        let mut test_package = default_cargo_package_with_features(
+            &[(F1, &[F2]), (F2, &[F3]), (F3, &[]), (F4, &[])],
+            Some(enabled_features_slice),
+        );
+        let features_to_disable = create_feature_set_from_slices(features_to_disable);
+
+        let result = test_package.disable_features(&features_to_disable);
+
+        assert_eq!(result.is_ok(), expected_disabled);
+        let expected_enabled = match expected_disabled {
+            true => create_feature_set_from_slices(enabled_features_slice)
+                .difference(&features_to_disable)
+                .cloned()
+                .collect(),
+            false => create_feature_set_from_slices(enabled_features_slice),
+        };
+        assert_eq!(test_package.enabled_features, expected_enabled);
+    }
+
+    #[test]
+    fn test_disabling_feature_disables_features_it_enables() {
+        let mut test_package = default_cargo_package_with_features(
+            &[(F1, &[F2]), (F2, &[F3]), (F3, &[]), (F4, &[])],
+            Some(&[]),
+        );
+        test_package.targets = vec![Arc::new(
+            TargetBuilder::default()
+                .name("test_bin".to_string())
+                .kind(vec!["bin".to_string()])
+                .src_path("/test_root/src/main.rs".to_string())
+                .required_features(vec![F3.to_string()])
+                .build()
+                .unwrap(),
+        )];
+        let is_active = |package: &CargoPackage| {
+            !package.get_bsp_build_targets(&[], &FeatureMode::Default, None)[0]
+                .tags
+                .contains(&INACTIVE_TARGET_TAG)
+        };
+
+        test_package
+            .enable_features(&create_feature_set_from_slices(&[F1]))
+            .unwrap();
+        assert!(is_active(&test_package));
+
+        test_package
+            .disable_features(&create_feature_set_from_slices(&[F1]))
+            .unwrap();
+        assert!(test_package.enabled_features.is_empty());
+        assert!(!is_active(&test_package));
+    }
+
+    #[test_case(&[], &[], true ; "no_required_features")]
+    #[test_case(&[F1], &[], false ; "required_feature_disabled")]
+    #[test_case(&[F1], &[F1], true ; "required_feature_enabled")]
+    #[test_case(&[F3], &[F1], true ; "required_feature_enabled_by_other_feature")]
+    #[test_case(&[F1, F4], &[F1], false ; "one_of_required_features_disabled")]
+    fn test_inactive_target_tag(
+        required_features: &[&str],
+        enabled_features_slice: &[&str],
+        expected_active: bool,
+    ) {
+        let mut test_package = default_cargo_package_with_features(
+            &[(F1, &[F2]), (F2, &[F3]), (F3, &[]), (F4, &[])],
+            Some(enabled_features_slice),
+        );
+        test_package.targets = vec![Arc::new(
+            TargetBuilder::default()
+                .name("test_bin".to_string())
+                .kind(vec!["bin".to_string()])
+                .src_path("/test_root/src/main.rs".to_string())
+                .required_features(
+                    required_features
+                        .iter()
+                        .map(|&f| f.to_string())
+                        .collect::<Vec<_>>(),
+                )
+                .build()
+                .unwrap(),
+        )];
+
+        let build_targets = test_package.get_bsp_build_targets(&[], &FeatureMode::Default, None);
+
+        assert_eq!(
+            build_targets[0].tags.contains(&INACTIVE_TARGET_TAG),
+            !expected_active
+        );
+    }
+
+    #[test_case(FeatureMode::AllFeatures ; "all_features")]
+    #[test_case(FeatureMode::NoDefault ; "no_default")]
+    fn test_no_inactive_target_in_feature_mode(feature_mode: FeatureMode) {
+        let mut test_package =
+            default_cargo_package_with_features(&[(F1, &[])], Some(&[] as &[&str]));
+        test_package.targets = vec![Arc::new(
+            TargetBuilder::default()
+                .name("gated_bin".to_string())
+                .kind(vec!["bin".to_string()])
+                .src_path("/test_root/src/bin/gated_bin.rs".to_string())
+                .required_features(vec![F1.to_string()])
+                .build()
+                .unwrap(),
+        )];
+
+        let build_targets = test_package.get_bsp_build_targets(&[], &feature_mode, None);
+
+        assert!(!build_targets[0].tags.contains(&INACTIVE_TARGET_TAG));
+    }
+
+    #[test]
+    fn test_unsatisfiable_target_skipped() {
+        let mut test_package =
+            default_cargo_package_with_features(&[(F1, &[])], Some(&[] as &[&str]));
+        test_package.targets = [(&[F1][..], "gated_bin"), (&[F2][..], "unsatisfiable_bin")]
+            .iter()
+            .map(|(required_features, name)| {
+                Arc::new(
+                    TargetBuilder::default()
+                        .name(name.to_string())
+                        .kind(vec!["bin".to_string()])
+                        .src_path(format!("/test_root/src/bin/{}.rs", name))
+                        .required_features(
+                            required_features
+                                .iter()
+                                .map(|&f| f.to_string())
+                                .collect::<Vec<_>>(),
+                        )
+                        .build()
+                        .unwrap(),
+                )
+            })
+            .collect();
+
+        let build_targets = test_package.get_bsp_build_targets(&[], &FeatureMode::Default, None);
+
+        assert_eq!(build_targets.len(), 1);
+        assert_eq!(build_targets[0].display_name.as_deref(), Some("gated_bin"));
+        assert!(build_targets[0].tags.contains(&INACTIVE_TARGET_TAG));
+    }
+
+    #[test_case(&["cfg(windows)"], false ; "dependency_of_other_platform")]
+    #[test_case(&["x86_64-pc-windows-msvc"], false ; "dependency_of_other_triple")]
+    #[test_case(&["cfg(unix)"], true ; "dependency_of_host_platform")]
+    #[test_case(&["cfg(windows)", "cfg(unix)"], true ; "dependency_of_multiple_platforms")]
+    fn test_platform_gated_target(dependency_platforms: &[&str], expected_active: bool) {
+        // `win_bin` requires the `win` feature, which enables the platform specific dependency.
+        let mut test_package =
+            default_cargo_package_with_features(&[("win", &["dep:winapi"])], Some(&["win"][..]));
+        test_package.dependencies = dependency_platforms
+            .iter()
+            .map(|platform| PackageDependency {
+                name: "winapi".to_string(),
+                optional: true,
+                target: Some(Platform::from_str(platform).unwrap()),
+                ..PackageDependency::default()
+            })
+            .collect();
+        test_package.targets = vec![Arc::new(
+            TargetBuilder::default()
+                .name("win_bin".to_string())
+                .kind(vec!["bin".to_string()])
+                .src_path("/test_root/src/bin/win_bin.rs".to_string())
+                .required_features(vec!["win".to_string()])
+                .build()
+                .unwrap(),
+        )];
+        let host = HostPlatform::new(
+            "x86_64-unknown-linux-gnu".to_string(),
+            ["unix", "target_os=\"linux\""]
+                .iter()
+                .map(|cfg| Cfg::from_str(cfg).unwrap())
+                .collect(),
+        );
+
+        for feature_mode in [FeatureMode::Default, FeatureMode::AllFeatures] {
+            let build_targets = test_package.get_bsp_build_targets(&[], &feature_mode, Some(&host));
+
+            assert_eq!(
+                build_targets[0].tags.contains(&INACTIVE_TARGET_TAG),
+                !expected_active
+            );
+        }
+        // The platform is not checked if the host is unknown.
+        let build_targets = test_package.get_bsp_build_targets(&[], &FeatureMode::Default, None);
+        assert!(!build_targets[0].tags.contains(&INACTIVE_TARGET_TAG));
+    }
+
+    fn test_target(name: &str, kind: &str, src_path: &str) -> Arc<cargo_metadata::Target> {
+        Arc::new(
+            TargetBuilder::default()
+                .name(name.to_string())
+                .kind(vec![kind.to_string()])
+                .src_path(src_path.to_string())
+                .build()
+                .unwrap(),
+        )
+    }
+
+    fn test_dependency(name: &str, manifest_path: &str, kind: DependencyKind) -> PackageDependency {
+        PackageDependency {
+            name: name.to_string(),
+            manifest_path: manifest_path.into(),
+            kind,
+            ..PackageDependency::default()
+        }
+    }
+
+    #[test]
+    fn test_build_target_dependencies() {
+        // Crate `a` depends on the workspace crate `b` and on the external `serde`.
+        let a = CargoPackage {
+            name: "a".to_string(),
+            manifest_path: "/test_root/a/Cargo.toml".into(),
+            targets: vec![
+                test_target("a", "lib", "/test_root/a/src/lib.rs"),
+                test_target("a", "bin", "/test_root/a/src/main.rs"),
+                test_target(
+                    "build-script-build",
+                    "custom-build",
+                    "/test_root/a/build.rs",
+                ),
+            ],
+            dependencies: vec![
+                test_dependency("b", "/test_root/b/Cargo.toml", DependencyKind::Normal),
+                test_dependency(
+                    "serde",
+                    "/registry/serde-1.0.0/Cargo.toml",
+                    DependencyKind::Normal,
+                ),
+            ],
+            ..CargoPackage::default()
+        };
+        let b = CargoPackage {
+            name: "b".to_string(),
+            manifest_path: "/test_root/b/Cargo.toml".into(),
+            targets: vec![test_target("b", "lib", "/test_root/b/src/lib.rs")],
+            ..CargoPackage::default()
+        };
+        let a_lib_id = TargetSelector::new("/test_root/a", "a", "lib").to_id();
+        let b_lib_id = TargetSelector::new("/test_root/b", "b", "lib").to_id();
+        let workspace_packages = [a.clone(), b.clone()];
+
+        let a_targets = a.get_bsp_build_targets(&workspace_packages, &FeatureMode::Default, None);
+        let b_targets = b.get_bsp_build_targets(&workspace_packages, &FeatureMode::Default, None);
+
+        let dependencies: Vec<_> = a_targets.iter().map(|t| t.dependencies.clone()).collect();
+        assert_eq!(
+            dependencies,
+            vec![
+                // lib
+                vec![b_lib_id.clone()],
+                // bin
+                vec![a_lib_id, b_lib_id],
+                // build script
+                vec![],
+            ]
+        );
+        assert!(b_targets[0].dependencies.is_empty());
+    }
+
+    #[test]
+    fn test_dev_dependencies_of_tests_benches_and_examples() {
+        let a = CargoPackage {
+            name: "a".to_string(),
+            manifest_path: "/test_root/a/Cargo.toml".into(),
+            targets: vec![
+                test_target("a", "lib", "/test_root/a/src/lib.rs"),
+                test_target("a", "bin", "/test_root/a/src/main.rs"),
+                test_target("it", "test", "/test_root/a/tests/it.rs"),
+                test_target("bench", "bench", "/test_root/a/benches/bench.rs"),
+                test_target("example", "example", "/test_root/a/examples/example.rs"),
+            ],
+            dependencies: vec![test_dependency(
+                "b",
+                "/test_root/b/Cargo.toml",
+                DependencyKind::Development,
+            )],
+            ..CargoPackage::default()
+        };
+        let b = CargoPackage {
+            name: "b".to_string(),
+            manifest_path: "/test_root/b/Cargo.toml".into(),
+            targets: vec![test_target("b", "lib", "/test_root/b/src/lib.rs")],
+            ..CargoPackage::default()
+        };
+        let a_lib_id = TargetSelector::new("/test_root/a", "a", "lib").to_id();
+        let b_lib_id = TargetSelector::new("/test_root/b", "b", "lib").to_id();
+
+        let a_targets = a.get_bsp_build_targets(&[a.clone(), b], &FeatureMode::Default, None);
+
+        let dependencies: Vec<_> = a_targets.iter().map(|t| t.dependencies.clone()).collect();
+        assert_eq!(
+            dependencies,
+            vec![
+                // lib
+                vec![],
+                // bin
+                vec![a_lib_id.clone()],
+                // test
+                vec![a_lib_id.clone(), b_lib_id.clone()],
+                // bench
+                vec![a_lib_id.clone(), b_lib_id.clone()],
+                // example
+                vec![a_lib_id, b_lib_id],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_target_base_directories() {
+        // The build script of `b` is outside of `src`, the base directory is still the package root.
+        let a = CargoPackage {
+            name: "a".to_string(),
+            manifest_path: "/test_root/a/Cargo.toml".into(),
+            targets: vec![test_target("a", "bin", "/test_root/a/src/bin/a.rs")],
+            ..CargoPackage::default()
+        };
+        let b = CargoPackage {
+            name: "b".to_string(),
+            manifest_path: "/test_root/b/Cargo.toml".into(),
+            targets: vec![
+                test_target("b", "lib", "/test_root/b/src/lib.rs"),
+                test_target(
+                    "build-script-build",
+                    "custom-build",
+                    "/test_root/b/build.rs",
+                ),
+            ],
+            ..CargoPackage::default()
+        };
+        let workspace_packages = [a.clone(), b.clone()];
+
+        let base_directories: Vec<_> = [a, b]
+            .iter()
+            .flat_map(|p| p.get_bsp_build_targets(&workspace_packages, &FeatureMode::Default, None))
+            .map(|t| t.base_directory.unwrap().0)
+            .collect();
+
+        assert_eq!(
+            base_directories,
+            vec![
+                "file:///test_root/a",
+                "file:///test_root/b",
+                "file:///test_root/b",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_target_with_unknown_edition() {
+        let a = CargoPackage {
+            name: "a".to_string(),
+            manifest_path: "/test_root/a/Cargo.toml".into(),
+            targets: vec![
+                test_target("a", "lib", "/test_root/a/src/lib.rs"),
+                test_target("a_bin", "bin", "/test_root/a/src/main.rs"),
+            ],
+            unknown_target_editions: HashMap::from([(
+                ("a_bin".to_string(), vec!["bin".to_string()]),
+                RustEdition::new("2099"),
+            )]),
+            ..CargoPackage::default()
+        };
+
+        let editions: Vec<_> = a
+            .get_bsp_build_targets(&[a.clone()], &FeatureMode::Default, None)
+            .into_iter()
+            .map(|t| match t.data {
+                Some(BuildTargetData::Named(NamedBuildTargetData::Cargo(data))) => data.edition,
+                _ => panic!("build target without the Cargo data"),
+            })
+            .collect();
+
+        assert_eq!(
+            editions,
+            vec![
+                metadata_edition_to_bsp_edition(cargo_metadata::Edition::default()),
+                RustEdition::new("2099"),
+            ]
+        );
+    }
+
     #[test]
     fn test_get_enabled_features() {
         const TEST_FEATURES_SLICE: &[&str] = &[F1, F2, F3];
@@ -0,0 +1,194 @@
+//! Removes the artifacts of the build targets with the `cargo clean` command,
+//! as requested by the `buildTarget/cleanCache` request.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process::Command;
+
+use bsp4rs::bsp::{BuildTargetIdentifier, CleanCacheResult};
+use crossbeam_channel::Receiver;
+
+use crate::cargo_communication::cargo_types::event::{CancelReason, Event};
+use crate::project_model::workspace::ProjectWorkspace;
+
+/// Artifacts are cleaned per package, as Cargo doesn't clean single targets.
+/// If all targets of the workspace are requested, the whole target directory is cleaned,
+/// while no requested target means there is nothing to clean.
+/// One command is created for each package, so that the failure of one package
+/// doesn't prevent the others from being cleaned. The lock flags are passed to each
+/// command, as `cargo clean` can update the lock file as well.
+pub(crate) fn clean_commands(
+    root: &Path,
+    workspace: &ProjectWorkspace,
+    targets: &[BuildTargetIdentifier],
+    lock_flags: &[String],
+) -> Vec<Command> {
+    if targets.is_empty() {
+        return vec![];
+    }
+    let all_targets_requested = workspace
+        .target_id_to_package_id
+        .keys()
+        .all(|id| targets.contains(id));
+    if all_targets_requested {
+        return vec![clean_command(root, None, lock_flags)];
+    }
+
+    let packages: BTreeSet<&str> = targets
+        .iter()
+        .filter_map(|id| workspace.get_package_related_to_target(id))
+        .map(|p| p.name.as_str())
+        .collect();
+    packages
+        .into_iter()
+        .map(|p| clean_command(root, Some(p), lock_flags))
+        .collect()
+}
+
+fn clean_command(root: &Path, package: Option<&str>, lock_flags: &[String]) -> Command {
+    let mut cmd = Command::new(toolchain::cargo());
+    cmd.current_dir(root).arg("clean");
+    if let Some(package) = package {
+        cmd.args(["-p", package]);
+    }
+    cmd.args(lock_flags);
+    cmd
+}
+
+/// Runs the commands one by one, the cache is cleaned only if all of them succeed.
+/// The cancel event is checked before each command, the running one is not interrupted.
+pub(crate) fn clean_cache(
+    commands: Vec<Command>,
+    cancel_receiver: &Receiver<Event>,
+) -> Result<CleanCacheResult, CancelReason> {
+    let mut errors: Vec<String> = vec![];
+    for mut cmd in commands {
+        if let Ok(Event::Cancel(reason)) = cancel_receiver.try_recv() {
+            return Err(reason);
+        }
+        match cmd.output() {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => errors.push(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+            Err(e) => errors.push(format!("Failed to run cargo clean: {}", e)),
+        }
+    }
+    Ok(CleanCacheResult {
+        cleaned: errors.is_empty(),
+        message: (!errors.is_empty()).then(|| errors.join("\n")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project_model::cargo_package::CargoPackage;
+    use std::collections::HashMap;
+    use std::ffi::OsStr;
+
+    fn test_workspace() -> ProjectWorkspace {
+        let package = |name: &str| CargoPackage {
+            name: name.to_string(),
+            id: name.to_string(),
+            ..CargoPackage::default()
+        };
+        let target_id = |uri: &str| BuildTargetIdentifier { uri: uri.into() };
+        ProjectWorkspace {
+            packages: vec![package("a"), package("b")],
+            target_id_to_package_id: HashMap::from([
+                (target_id("a_lib"), "a".to_string()),
+                (target_id("a_bin"), "a".to_string()),
+                (target_id("b_lib"), "b".to_string()),
+            ]),
+            ..ProjectWorkspace::default()
+        }
+    }
+
+    fn commands_args(targets: &[&str]) -> Vec<Vec<String>> {
+        commands_args_with_lock_flags(targets, &[])
+    }
+
+    fn commands_args_with_lock_flags(targets: &[&str], lock_flags: &[String]) -> Vec<Vec<String>> {
+        let targets: Vec<_> = targets
+            .iter()
+            .map(|&uri| BuildTargetIdentifier { uri: uri.into() })
+            .collect();
+        clean_commands(
+            Path::new("/test_root"),
+            &test_workspace(),
+            &targets,
+            lock_flags,
+        )
+        .iter()
+        .map(|cmd| {
+            cmd.get_args()
+                .map(OsStr::to_string_lossy)
+                .map(String::from)
+                .collect()
+        })
+        .collect()
+    }
+
+    #[test]
+    fn clean_commands_of_requested_packages() {
+        assert_eq!(
+            commands_args(&["a_lib", "a_bin", "unknown"]),
+            vec![vec!["clean", "-p", "a"]]
+        );
+        assert_eq!(
+            commands_args(&["b_lib", "a_bin"]),
+            vec![vec!["clean", "-p", "a"], vec!["clean", "-p", "b"]]
+        );
+    }
+
+    #[test]
+    fn clean_command_of_all_targets() {
+        assert_eq!(
+            commands_args(&["a_lib", "a_bin", "b_lib"]),
+            vec![vec!["clean"]]
+        );
+    }
+
+    #[test]
+    fn no_clean_command_without_targets() {
+        assert!(commands_args(&[]).is_empty());
+    }
+
+    #[test]
+    fn clean_commands_with_lock_flags() {
+        let lock_flags = ["--locked".to_string(), "--offline".to_string()];
+
+        assert_eq!(
+            commands_args_with_lock_flags(&["a_lib"], &lock_flags),
+            vec![vec!["clean", "-p", "a", "--locked", "--offline"]]
+        );
+        assert_eq!(
+            commands_args_with_lock_flags(&["a_lib", "a_bin", "b_lib"], &lock_flags),
+            vec![vec!["clean", "--locked", "--offline"]]
+        );
+    }
+
+    #[test]
+    fn failed_clean_reported() {
+        let (_cancel_sender, cancel_receiver) = crossbeam_channel::unbounded();
+        let result =
+            clean_cache(vec![Command::new("/not_existing/cargo")], &cancel_receiver).unwrap();
+
+        assert!(!result.cleaned);
+        assert!(result
+            .message
+            .unwrap()
+            .starts_with("Failed to run cargo clean"));
+    }
+
+    #[test]
+    fn cancelled_clean_runs_no_command() {
+        let (cancel_sender, cancel_receiver) = crossbeam_channel::unbounded();
+        cancel_sender
+            .send(Event::Cancel(CancelReason::Client))
+            .unwrap();
+
+        let result = clean_cache(vec![Command::new("/not_existing/cargo")], &cancel_receiver);
+
+        assert_eq!(result.unwrap_err(), CancelReason::Client);
+    }
+}
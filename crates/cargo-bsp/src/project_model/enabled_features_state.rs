@@ -0,0 +1,144 @@
+//! Stores the features enabled for the workspace packages and the feature mode in the server
+//! state directory, so that they are restored after the server restarts (or the workspace
+//! is reloaded). The stored feature mode takes precedence over the one from the settings.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+
+use cargo_metadata::camino::{Utf8Path, Utf8PathBuf};
+use log::warn;
+
+use bsp4rs::cargo::FeatureMode;
+use bsp4rs::rust::Feature;
+
+use crate::project_model::workspace::ProjectWorkspace;
+
+const ENABLED_FEATURES_FILE_NAME: &str = "enabled_features.json";
+const FEATURE_MODE_FILE_NAME: &str = "feature_mode.json";
+
+/// Key is a package id, value is the set of features enabled for the package.
+pub(crate) type EnabledFeaturesState = BTreeMap<String, BTreeSet<Feature>>;
+
+fn state_file(state_dir: &Utf8Path) -> Utf8PathBuf {
+    state_dir.join(ENABLED_FEATURES_FILE_NAME)
+}
+
+pub(crate) fn save_enabled_features(
+    state_dir: &Utf8Path,
+    workspace: &ProjectWorkspace,
+) -> io::Result<()> {
+    let state: EnabledFeaturesState = workspace
+        .packages
+        .iter()
+        .map(|p| (p.id.clone(), p.enabled_features.clone()))
+        .collect();
+    fs::create_dir_all(state_dir)?;
+    fs::write(state_file(state_dir), serde_json::to_vec_pretty(&state)?)?;
+    fs::write(
+        state_dir.join(FEATURE_MODE_FILE_NAME),
+        serde_json::to_vec_pretty(&workspace.feature_mode)?,
+    )
+}
+
+/// Missing or invalid state is ignored, so the default features stay enabled.
+pub(crate) fn load_enabled_features(state_dir: &Utf8Path) -> EnabledFeaturesState {
+    let file = state_file(state_dir);
+    let content = match fs::read(&file) {
+        Ok(content) => content,
+        Err(_) => return EnabledFeaturesState::default(),
+    };
+    serde_json::from_slice(&content).unwrap_or_else(|e| {
+        warn!("Ignoring invalid enabled features state {}: {}", file, e);
+        EnabledFeaturesState::default()
+    })
+}
+
+/// Missing or invalid mode is ignored, so the mode from the settings is used.
+pub(crate) fn load_feature_mode(state_dir: &Utf8Path) -> Option<FeatureMode> {
+    let file = state_dir.join(FEATURE_MODE_FILE_NAME);
+    let content = fs::read(&file).ok()?;
+    serde_json::from_slice(&content)
+        .map_err(|e| warn!("Ignoring invalid feature mode state {}: {}", file, e))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project_model::cargo_package::CargoPackage;
+    use bsp4rs::rust::FeatureDependencyGraph;
+    use tempfile::tempdir;
+
+    const PACKAGE_ID: &str = "package_id";
+
+    fn test_workspace(enabled_features: &[&str]) -> ProjectWorkspace {
+        ProjectWorkspace {
+            packages: vec![CargoPackage {
+                id: PACKAGE_ID.to_string(),
+                enabled_features: enabled_features.iter().map(|&f| f.into()).collect(),
+                package_features: FeatureDependencyGraph::new(BTreeMap::from([
+                    ("default".into(), BTreeSet::new()),
+                    ("f1".into(), BTreeSet::new()),
+                ])),
+                ..CargoPackage::default()
+            }],
+            ..ProjectWorkspace::default()
+        }
+    }
+
+    fn test_state_dir() -> (tempfile::TempDir, Utf8PathBuf) {
+        let tmp_dir = tempdir().unwrap();
+        let state_dir = Utf8PathBuf::from_path_buf(tmp_dir.path().join(".bsp")).unwrap();
+        (tmp_dir, state_dir)
+    }
+
+    #[test]
+    fn enabled_features_round_trip() {
+        let (_tmp_dir, state_dir) = test_state_dir();
+        save_enabled_features(&state_dir, &test_workspace(&["f1"])).unwrap();
+        let mut restored_workspace = test_workspace(&["default"]);
+
+        restored_workspace.restore_enabled_features(&load_enabled_features(&state_dir));
+
+        assert_eq!(
+            restored_workspace.packages[0].enabled_features,
+            BTreeSet::from(["f1".into()])
+        );
+    }
+
+    #[test]
+    fn feature_mode_round_trip() {
+        let (_tmp_dir, state_dir) = test_state_dir();
+        let workspace = ProjectWorkspace {
+            feature_mode: FeatureMode::AllFeatures,
+            ..test_workspace(&["f1"])
+        };
+
+        save_enabled_features(&state_dir, &workspace).unwrap();
+
+        assert_eq!(
+            load_feature_mode(&state_dir),
+            Some(FeatureMode::AllFeatures)
+        );
+    }
+
+    #[test]
+    fn missing_or_invalid_state_ignored() {
+        let (_tmp_dir, state_dir) = test_state_dir();
+        assert_eq!(
+            load_enabled_features(&state_dir),
+            EnabledFeaturesState::default()
+        );
+
+        fs::create_dir_all(&state_dir).unwrap();
+        fs::write(state_file(&state_dir), "not json").unwrap();
+        assert_eq!(
+            load_enabled_features(&state_dir),
+            EnabledFeaturesState::default()
+        );
+        assert_eq!(load_feature_mode(&state_dir), None);
+        fs::write(state_dir.join(FEATURE_MODE_FILE_NAME), "not json").unwrap();
+        assert_eq!(load_feature_mode(&state_dir), None);
+    }
+}
@@ -0,0 +1,45 @@
+//! The platform the targets are built for (the host), which decides about the targets
+//! inactive on it (e.g. the ones depending on a dependency of the other platforms).
+
+use std::path::Path;
+use std::str::FromStr;
+
+use cargo_platform::{Cfg, Platform};
+use log::warn;
+use rustc_version::version_meta;
+
+use crate::project_model::rustc_cfg::rustc_cfgs;
+
+/// The platform the targets are built for, with its target triple and `cfg` values.
+#[derive(Debug, Clone)]
+pub(crate) struct HostPlatform {
+    triple: String,
+    cfgs: Vec<Cfg>,
+}
+
+impl HostPlatform {
+    pub(crate) fn new(triple: String, cfgs: Vec<Cfg>) -> HostPlatform {
+        HostPlatform { triple, cfgs }
+    }
+
+    /// Returns `None` if the host target cannot be determined.
+    pub(crate) fn discover(root_path: &Path) -> Option<HostPlatform> {
+        let triple = version_meta()
+            .map_err(|e| warn!("Failed to get the host target: {}", e))
+            .ok()?
+            .host;
+        let cfgs = rustc_cfgs(root_path)
+            .iter()
+            .filter_map(|cfg| Cfg::from_str(cfg).ok())
+            .collect();
+        Some(HostPlatform::new(triple, cfgs))
+    }
+
+    /// Checks if the host matches the platform (given by a target triple or a `cfg`
+    /// expression), no platform matches any host.
+    pub(crate) fn matches(&self, platform: &Option<Platform>) -> bool {
+        platform
+            .as_ref()
+            .map_or(true, |p| p.matches(&self.triple, &self.cfgs))
+    }
+}
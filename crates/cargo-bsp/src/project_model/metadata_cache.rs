@@ -0,0 +1,208 @@
+//! Caches the output of `cargo metadata` command, as it is slow for large workspaces.
+//! The cached output is used as long as none of the local packages' manifests
+//! (`Cargo.toml`) and the lock file (`Cargo.lock`) were modified.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use cargo_metadata::camino::Utf8PathBuf;
+use cargo_metadata::{Error, Metadata};
+
+use crate::project_model::workspace::ProjectWorkspace;
+use crate::project_model::UnknownEditions;
+
+const MANIFEST_FILE_NAME: &str = "Cargo.toml";
+const LOCK_FILE_NAME: &str = "Cargo.lock";
+
+/// Modification times of the files which, when changed, may change the metadata.
+#[derive(Debug, PartialEq, Eq)]
+struct ManifestsState(Vec<(Utf8PathBuf, Option<SystemTime>)>);
+
+impl ManifestsState {
+    fn from_metadata(metadata: &Metadata) -> ManifestsState {
+        let mut paths: Vec<Utf8PathBuf> = metadata
+            .packages
+            .iter()
+            // Packages without the source are the local ones (workspace members and path dependencies).
+            .filter(|p| p.source.is_none())
+            .map(|p| p.manifest_path.clone())
+            .chain([
+                metadata.workspace_root.join(MANIFEST_FILE_NAME),
+                metadata.workspace_root.join(LOCK_FILE_NAME),
+            ])
+            .collect();
+        paths.sort();
+        paths.dedup();
+        ManifestsState(
+            paths
+                .into_iter()
+                .map(|path| {
+                    let modified = modification_time(&path);
+                    (path, modified)
+                })
+                .collect(),
+        )
+    }
+
+    fn is_up_to_date(&self) -> bool {
+        self.0
+            .iter()
+            .all(|(path, modified)| modification_time(path) == *modified)
+    }
+}
+
+fn modification_time(path: &Utf8PathBuf) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+struct CachedMetadata {
+    manifest_path: PathBuf,
+    manifests_state: ManifestsState,
+    metadata: Metadata,
+    unknown_editions: UnknownEditions,
+}
+
+#[derive(Default)]
+pub struct MetadataCache {
+    /// Separate entries for the output with and without the `--filter-platform` flag.
+    entries: HashMap<bool, CachedMetadata>,
+    /// Lock file and network flags from the server settings (e.g. `--offline`).
+    lock_flags: Vec<String>,
+}
+
+impl MetadataCache {
+    pub fn new(lock_flags: Vec<String>) -> MetadataCache {
+        MetadataCache {
+            lock_flags,
+            ..MetadataCache::default()
+        }
+    }
+
+    /// Returns the cached metadata (with the editions unknown to `cargo_metadata`) if it is
+    /// up to date, otherwise calls `cargo metadata`.
+    pub fn get(
+        &mut self,
+        manifest_path: &Path,
+        filter_platform: bool,
+    ) -> Result<(Metadata, UnknownEditions), Error> {
+        self.get_or_load(manifest_path, filter_platform, || {
+            ProjectWorkspace::call_cargo_metadata_command(
+                &manifest_path.to_path_buf(),
+                filter_platform,
+                &self.lock_flags,
+            )
+        })
+    }
+
+    fn up_to_date_entry(
+        &self,
+        manifest_path: &Path,
+        filter_platform: bool,
+    ) -> Option<&CachedMetadata> {
+        self.entries.get(&filter_platform).filter(|cached| {
+            cached.manifest_path == manifest_path && cached.manifests_state.is_up_to_date()
+        })
+    }
+
+    pub(crate) fn get_or_load(
+        &mut self,
+        manifest_path: &Path,
+        filter_platform: bool,
+        load: impl FnOnce() -> Result<(Metadata, UnknownEditions), Error>,
+    ) -> Result<(Metadata, UnknownEditions), Error> {
+        if let Some(cached) = self.up_to_date_entry(manifest_path, filter_platform) {
+            return Ok((cached.metadata.clone(), cached.unknown_editions.clone()));
+        }
+        let (metadata, unknown_editions) = load()?;
+        self.entries.insert(
+            filter_platform,
+            CachedMetadata {
+                manifest_path: manifest_path.to_path_buf(),
+                manifests_state: ManifestsState::from_metadata(&metadata),
+                metadata: metadata.clone(),
+                unknown_editions: unknown_editions.clone(),
+            },
+        );
+        Ok((metadata, unknown_editions))
+    }
+
+    /// Forces calling `cargo metadata` on the next request (e.g. on the reload request).
+    pub fn invalidate(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::cell::Cell;
+    use std::fs::File;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn test_metadata(workspace_root: &Path) -> Metadata {
+        serde_json::from_value(json!({
+            "packages": [],
+            "workspace_members": [],
+            "resolve": null,
+            "workspace_root": workspace_root,
+            "target_directory": workspace_root.join("target"),
+            "version": 1,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn metadata_loaded_once_until_manifest_changes() {
+        let root = tempdir().unwrap();
+        let manifest_path = root.path().join(MANIFEST_FILE_NAME);
+        fs::write(&manifest_path, "[workspace]\n").unwrap();
+        let mut cache = MetadataCache::default();
+        let loads = Cell::new(0);
+        let load = || {
+            loads.set(loads.get() + 1);
+            Ok((test_metadata(root.path()), UnknownEditions::default()))
+        };
+
+        cache.get_or_load(&manifest_path, false, load).unwrap();
+        cache.get_or_load(&manifest_path, false, load).unwrap();
+        assert_eq!(loads.get(), 1);
+
+        File::options()
+            .write(true)
+            .open(&manifest_path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        cache.get_or_load(&manifest_path, false, load).unwrap();
+        assert_eq!(loads.get(), 2);
+
+        // Creating the lock file also changes the state.
+        fs::write(root.path().join(LOCK_FILE_NAME), "").unwrap();
+        cache.get_or_load(&manifest_path, false, load).unwrap();
+        assert_eq!(loads.get(), 3);
+    }
+
+    #[test]
+    fn metadata_loaded_again_after_invalidation() {
+        let root = tempdir().unwrap();
+        let manifest_path = root.path().join(MANIFEST_FILE_NAME);
+        let mut cache = MetadataCache::default();
+        let loads = Cell::new(0);
+        let load = || {
+            loads.set(loads.get() + 1);
+            Ok((test_metadata(root.path()), UnknownEditions::default()))
+        };
+
+        cache.get_or_load(&manifest_path, false, load).unwrap();
+        // Output with `--filter-platform` flag is cached separately.
+        cache.get_or_load(&manifest_path, true, load).unwrap();
+        cache.invalidate();
+        cache.get_or_load(&manifest_path, false, load).unwrap();
+
+        assert_eq!(loads.get(), 3);
+    }
+}
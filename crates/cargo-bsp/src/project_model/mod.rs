@@ -1,17 +1,30 @@
 //! [`ProjectModel`] obtains and stores information about the Rust project.
 
 use bsp4rs::rust::{Feature, FeatureDependencyGraph, RustEdition};
-use cargo_metadata::{Edition, Package};
-use std::collections::{BTreeMap, BTreeSet};
+use cargo_metadata::{Edition, Package, Target};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 mod _unit_tests_discovery;
+pub(crate) mod added_dependencies;
+pub(crate) mod build_target_diff;
 pub(crate) mod build_target_mappings;
+pub(crate) mod cargo_config;
 pub(crate) mod cargo_package;
+pub(crate) mod clean_cache;
+pub(crate) mod enabled_features_state;
+pub(crate) mod host_platform;
+pub(crate) mod metadata_cache;
+pub(crate) mod output_paths;
 pub(crate) mod package_dependency;
+pub(crate) mod package_spec;
 pub(crate) mod project_manifest;
 pub(crate) mod rust_extension;
+pub(crate) mod rustc_cfg;
 pub(crate) mod sources;
+pub mod sysroot;
 pub(crate) mod target_details;
+pub(crate) mod target_selector;
+pub(crate) mod toolchain_info;
 pub mod workspace;
 
 pub const RUST_ID: &str = "rust";
@@ -20,6 +33,51 @@ pub(crate) fn metadata_edition_to_bsp_edition(metadata_edition: Edition) -> Rust
     RustEdition::new(metadata_edition.as_str())
 }
 
+/// Editions of the packages and their targets unknown to `cargo_metadata` (e.g. from a newer
+/// Cargo), replaced in the metadata by [`workspace::parse_metadata`] and returned next to it,
+/// so that they are reported to the client as they are.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UnknownEditions {
+    /// Key is a package id.
+    packages: HashMap<String, RustEdition>,
+    /// Key is a package id, target name and target kinds.
+    targets: HashMap<(String, String, Vec<String>), RustEdition>,
+}
+
+impl UnknownEditions {
+    /// Edition of the package as reported by Cargo.
+    pub(crate) fn package_edition(&self, package: &Package) -> RustEdition {
+        self.packages
+            .get(&package.id.repr)
+            .cloned()
+            .unwrap_or_else(|| metadata_edition_to_bsp_edition(package.edition))
+    }
+
+    /// Edition of the package target as reported by Cargo.
+    pub(crate) fn target_edition(&self, package_id: &str, target: &Target) -> RustEdition {
+        self.targets
+            .get(&(
+                package_id.to_string(),
+                target.name.clone(),
+                target.kind.clone(),
+            ))
+            .cloned()
+            .unwrap_or_else(|| metadata_edition_to_bsp_edition(target.edition))
+    }
+
+    /// Unknown editions of the package targets, key is a target name and target kinds.
+    pub(crate) fn package_target_editions(
+        &self,
+        package_id: &str,
+    ) -> HashMap<(String, Vec<String>), RustEdition> {
+        self.targets
+            .iter()
+            .filter(|((id, _, _), _)| id == package_id)
+            .map(|((_, name, kind), edition)| ((name.clone(), kind.clone()), edition.clone()))
+            .collect()
+    }
+}
+
 pub trait DefaultFeature {
     fn default_feature_name() -> Self;
 }
@@ -0,0 +1,73 @@
+//! Output directories of the build targets, where Cargo places the artifacts
+//! built with the given profile, as described in
+//! [Cargo documentation](https://doc.rust-lang.org/cargo/guide/build-cache.html).
+
+use cargo_metadata::camino::{Utf8Path, Utf8PathBuf};
+
+use bsp4rs::bsp::{
+    BuildTargetIdentifier, OutputPathItem, OutputPathItemKind, OutputPathsItem, URI,
+};
+
+use crate::utils::uri::file_uri;
+
+/// Profile used by Cargo if no `--profile` (or `--release`) flag is passed.
+pub(crate) const DEFAULT_PROFILE: &str = "dev";
+
+/// Name of the target directory's subdirectory with the artifacts of the profile.
+/// Built-in `dev` and `test` profiles use `debug`, `release` and `bench` profiles use
+/// `release`, custom profiles use their own name.
+pub(crate) fn profile_dir_name(profile: &str) -> &str {
+    match profile {
+        "dev" | "test" => "debug",
+        "bench" => "release",
+        custom => custom,
+    }
+}
+
+pub(crate) fn profile_output_dir(target_directory: &Utf8Path, profile: &str) -> Utf8PathBuf {
+    target_directory.join(profile_dir_name(profile))
+}
+
+/// The directory entry must end with a slash, according to the BSP specification.
+pub(crate) fn output_paths_item(
+    target: BuildTargetIdentifier,
+    output_dir: &Utf8Path,
+) -> OutputPathsItem {
+    OutputPathsItem {
+        target,
+        output_paths: vec![OutputPathItem {
+            uri: URI(format!("{}/", file_uri(output_dir).0)),
+            kind: OutputPathItemKind::Directory,
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("dev", "debug" ; "dev")]
+    #[test_case("test", "debug" ; "test")]
+    #[test_case("release", "release" ; "release")]
+    #[test_case("bench", "release" ; "bench")]
+    #[test_case("myprofile", "myprofile" ; "custom")]
+    fn test_profile_dir_name(profile: &str, expected: &str) {
+        assert_eq!(profile_dir_name(profile), expected);
+    }
+
+    #[test]
+    fn test_output_paths_item() {
+        let output_dir = profile_output_dir(Utf8Path::new("/test_root/target"), "myprofile");
+
+        let item = output_paths_item(BuildTargetIdentifier::default(), &output_dir);
+
+        assert_eq!(
+            item.output_paths,
+            vec![OutputPathItem {
+                uri: URI("file:///test_root/target/myprofile/".to_string()),
+                kind: OutputPathItemKind::Directory,
+            }]
+        );
+    }
+}
@@ -4,7 +4,7 @@
 //! included only if specific features for `PackageDependency` are set.
 //! Similarly, disabling default features impact the set of dependencies of the `PackageDependency`.
 //!
-//! Currently fields: `features`, `uses_default_features`, `rename`, `kind`, `target` and `source`
+//! Currently fields: `features`, `uses_default_features`, `rename`, `target` and `source`
 //! are used only to handle the BSP Rust extension.
 
 use std::collections::BTreeSet;
@@ -14,11 +14,8 @@ use cargo_metadata::{Dependency, DependencyKind, Package};
 use cargo_platform::Platform;
 use log::error;
 
-use bsp4rs::bsp::BuildTargetIdentifier;
 use bsp4rs::rust::Feature;
 
-use crate::utils::uri::file_uri;
-
 #[derive(Default, Debug, Clone)]
 pub struct PackageDependency {
     /// Dependency name
@@ -43,9 +40,12 @@ pub struct PackageDependency {
 
 impl PackageDependency {
     pub fn new(dependency: &Dependency, all_packages: &[Package]) -> Option<Self> {
+        // Multiple versions of the same package may be present, the one matching
+        // the version requirement of the dependency is chosen.
         all_packages
             .iter()
-            .find(|p| p.name == dependency.name)
+            .filter(|p| p.name == dependency.name)
+            .max_by_key(|p| dependency.req.matches(&p.version))
             .map(|p| Self {
                 name: dependency.name.clone(),
                 manifest_path: p.manifest_path.clone().into(),
@@ -76,18 +76,4 @@ impl PackageDependency {
             .filter_map(|dep| PackageDependency::new(dep, all_packages))
             .collect()
     }
-
-    pub fn create_id_from_dependency(&self) -> Option<BuildTargetIdentifier> {
-        if let Some(manifest_path_str) = self.manifest_path.to_str() {
-            Some(BuildTargetIdentifier {
-                uri: file_uri(manifest_path_str),
-            })
-        } else {
-            error!(
-                "Failed extracting manifest path from dependency: {:?}",
-                self.manifest_path
-            );
-            None
-        }
-    }
 }
@@ -0,0 +1,307 @@
+//! Parsing of the Cargo package ID specifications (as described in
+//! [Cargo documentation](https://doc.rust-lang.org/cargo/reference/pkgid-spec.html))
+//! and matching them against the workspace packages.
+
+use std::fmt;
+
+use crate::project_model::cargo_package::CargoPackage;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PackageSpec {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    /// Source URL of the package, without the source kind (such as `path+`).
+    pub url: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PackageSpecError {
+    Invalid(String),
+    NotMatched(String),
+    Ambiguous(String, Vec<String>),
+}
+
+impl fmt::Display for PackageSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackageSpecError::Invalid(spec) => {
+                write!(f, "invalid package ID specification: `{}`", spec)
+            }
+            PackageSpecError::NotMatched(spec) => write!(
+                f,
+                "package ID specification `{}` did not match any packages",
+                spec
+            ),
+            PackageSpecError::Ambiguous(spec, package_ids) => write!(
+                f,
+                "package ID specification `{}` is ambiguous, it matches: {}",
+                spec,
+                package_ids.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PackageSpecError {}
+
+impl PackageSpec {
+    pub fn parse(spec: &str) -> Result<PackageSpec, PackageSpecError> {
+        let spec = spec.trim();
+        if spec.is_empty() {
+            return Err(PackageSpecError::Invalid(spec.to_string()));
+        }
+        if !spec.contains("://") {
+            let (name, version) = split_name_and_version(spec);
+            return Ok(PackageSpec {
+                name: Some(name.to_string()),
+                version: version.map(String::from),
+                url: None,
+            });
+        }
+
+        let url = strip_source_kind(spec);
+        let (url, fragment) = match url.split_once('#') {
+            Some((url, fragment)) => (url, Some(fragment)),
+            None => (url, None),
+        };
+        let url = url.trim_end_matches('/');
+        // Without the name in the fragment, the last segment of URL is the package name.
+        let last_segment = url.rsplit('/').next().filter(|s| !s.is_empty());
+        let (name, version) = match fragment {
+            Some(fragment) if fragment.starts_with(|c: char| c.is_ascii_digit()) => {
+                (last_segment, Some(fragment))
+            }
+            Some(fragment) => {
+                let (name, version) = split_name_and_version(fragment);
+                (Some(name), version)
+            }
+            None => (last_segment, None),
+        };
+        Ok(PackageSpec {
+            name: name.map(String::from),
+            version: version.map(String::from),
+            url: Some(url.to_string()),
+        })
+    }
+
+    pub fn matches(&self, package: &CargoPackage) -> bool {
+        let name_matches = self.name.as_ref().map_or(true, |n| *n == package.name);
+        let version_matches = self
+            .version
+            .as_ref()
+            // Partial versions (such as `1.2`) match all versions with the same prefix.
+            .map_or(true, |v| {
+                package.version == *v || package.version.starts_with(&format!("{}.", v))
+            });
+        let url_matches = self.url.as_ref().map_or(true, |u| {
+            package_source_url(package).as_deref() == Some(u.as_str())
+        });
+        name_matches && version_matches && url_matches
+    }
+}
+
+/// Finds the only workspace package matching the given specification.
+pub fn find_package_by_spec<'a>(
+    packages: &'a [CargoPackage],
+    spec: &str,
+) -> Result<&'a CargoPackage, PackageSpecError> {
+    let package_spec = PackageSpec::parse(spec)?;
+    let matched: Vec<&CargoPackage> = packages
+        .iter()
+        .filter(|p| package_spec.matches(p))
+        .collect();
+    match matched.as_slice() {
+        [] => Err(PackageSpecError::NotMatched(spec.to_string())),
+        [package] => Ok(package),
+        _ => Err(PackageSpecError::Ambiguous(
+            spec.to_string(),
+            matched.iter().map(|p| p.id.clone()).collect(),
+        )),
+    }
+}
+
+fn split_name_and_version(spec: &str) -> (&str, Option<&str>) {
+    match spec.split_once(['@', ':']) {
+        Some((name, version)) => (name, Some(version)),
+        None => (spec, None),
+    }
+}
+
+fn strip_source_kind(url: &str) -> &str {
+    match url.split_once('+') {
+        Some((kind, url)) if !kind.contains(':') && url.contains("://") => url,
+        _ => url,
+    }
+}
+
+/// Source URL of the package, obtained from its id. Handles both the current
+/// (`path+file:///foo#0.1.0`) and the old (`foo 0.1.0 (path+file:///foo)`) id formats.
+fn package_source_url(package: &CargoPackage) -> Option<String> {
+    let source = match package.id.split_once('#') {
+        Some((source, _)) => source,
+        None => package
+            .id
+            .split_once('(')
+            .and_then(|(_, source)| source.strip_suffix(')'))?,
+    };
+    Some(strip_source_kind(source).trim_end_matches('/').to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project_model::workspace::ProjectWorkspace;
+    use cargo_metadata::TargetBuilder;
+    use insta::assert_debug_snapshot;
+    use std::sync::Arc;
+    use test_case::test_case;
+
+    const TEST_NAME: &str = "mypkg";
+    const TEST_VERSION: &str = "1.2.3";
+    const TEST_URL: &str = "file:///test_root/mypkg";
+
+    fn test_package(name: &str, version: &str, id: &str) -> CargoPackage {
+        CargoPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            id: id.to_string(),
+            ..CargoPackage::default()
+        }
+    }
+
+    fn test_packages() -> Vec<CargoPackage> {
+        vec![
+            test_package(
+                TEST_NAME,
+                TEST_VERSION,
+                &format!("path+{}#{}", TEST_URL, TEST_VERSION),
+            ),
+            test_package(
+                "other",
+                "0.1.0",
+                "other 0.1.0 (path+file:///test_root/other)",
+            ),
+            test_package(
+                "other",
+                "0.2.0",
+                "path+file:///test_root/other2#other@0.2.0",
+            ),
+        ]
+    }
+
+    #[test_case("mypkg", Some("mypkg"), None, None ; "name")]
+    #[test_case("mypkg@1.2.3", Some("mypkg"), Some("1.2.3"), None ; "name_and_version")]
+    #[test_case("mypkg:1.2", Some("mypkg"), Some("1.2"), None ; "name_and_partial_version")]
+    #[test_case("path+file:///test_root/mypkg#1.2.3", Some("mypkg"), Some("1.2.3"),
+    Some(TEST_URL) ; "url_and_version"
+    )]
+    #[test_case("file:///test_root/dir#mypkg@1.2.3", Some("mypkg"), Some("1.2.3"),
+    Some("file:///test_root/dir") ; "url_name_and_version"
+    )]
+    #[test_case("https://github.com/rust-lang/cargo", Some("cargo"), None,
+    Some("https://github.com/rust-lang/cargo") ; "url"
+    )]
+    fn test_parse_package_spec(
+        spec: &str,
+        name: Option<&str>,
+        version: Option<&str>,
+        url: Option<&str>,
+    ) {
+        assert_eq!(
+            PackageSpec::parse(spec).unwrap(),
+            PackageSpec {
+                name: name.map(String::from),
+                version: version.map(String::from),
+                url: url.map(String::from),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_package_spec() {
+        assert_eq!(
+            PackageSpec::parse(" "),
+            Err(PackageSpecError::Invalid("".to_string()))
+        );
+    }
+
+    #[test_case("mypkg", TEST_NAME ; "name")]
+    #[test_case("mypkg@1", TEST_NAME ; "partial_version")]
+    #[test_case("path+file:///test_root/mypkg#1.2.3", TEST_NAME ; "url")]
+    #[test_case("other@0.1.0", "other" ; "name_and_version")]
+    #[test_case("file:///test_root/other", "other" ; "old_id_format")]
+    fn test_find_package_by_spec(spec: &str, expected_name: &str) {
+        let packages = test_packages();
+        let package = find_package_by_spec(&packages, spec).unwrap();
+
+        assert_eq!(package.name, expected_name);
+    }
+
+    #[test]
+    fn test_find_package_by_not_matching_spec() {
+        assert_eq!(
+            find_package_by_spec(&test_packages(), "unknown").unwrap_err(),
+            PackageSpecError::NotMatched("unknown".to_string())
+        );
+        assert_eq!(
+            find_package_by_spec(&test_packages(), "mypkg@2.0.0").unwrap_err(),
+            PackageSpecError::NotMatched("mypkg@2.0.0".to_string())
+        );
+    }
+
+    #[test]
+    fn test_find_package_by_ambiguous_spec() {
+        assert_eq!(
+            find_package_by_spec(&test_packages(), "other").unwrap_err(),
+            PackageSpecError::Ambiguous(
+                "other".to_string(),
+                vec![
+                    "other 0.1.0 (path+file:///test_root/other)".to_string(),
+                    "path+file:///test_root/other2#other@0.2.0".to_string(),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_resolve_package_spec_to_targets() {
+        let mut package = test_packages().remove(0);
+        package.manifest_path = "/test_root/mypkg/Cargo.toml".into();
+        package.targets = ["mypkg", "mybin"]
+            .iter()
+            .map(|name| {
+                Arc::new(
+                    TargetBuilder::default()
+                        .name(name.to_string())
+                        .kind(vec!["bin".to_string()])
+                        .src_path(format!("/test_root/mypkg/src/{}.rs", name))
+                        .build()
+                        .unwrap(),
+                )
+            })
+            .collect();
+        let workspace = ProjectWorkspace {
+            packages: vec![package],
+            ..ProjectWorkspace::default()
+        };
+
+        assert_debug_snapshot!(workspace.resolve_package_spec(TEST_NAME).unwrap(), @r#"
+        [
+            BuildTargetIdentifier {
+                uri: URI(
+                    "cargo:///test_root/mypkg?name=mypkg&kind=bin",
+                ),
+            },
+            BuildTargetIdentifier {
+                uri: URI(
+                    "cargo:///test_root/mypkg?name=mybin&kind=bin",
+                ),
+            },
+        ]
+        "#);
+        assert_eq!(
+            workspace.resolve_package_spec("unknown").unwrap_err(),
+            PackageSpecError::NotMatched("unknown".to_string())
+        );
+    }
+}
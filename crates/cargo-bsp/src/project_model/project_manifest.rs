@@ -1,6 +1,6 @@
 //! Cargo project's manifest discovery for executing `cargo metadata` command properly.
 
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::{
     fs::{self, read_dir, ReadDir},
     io,
@@ -9,6 +9,7 @@ use std::{
 use anyhow::Result;
 use log::warn;
 use rustc_hash::FxHashSet;
+use toml::{Table, Value};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd, Default)]
 pub struct ProjectManifest {
@@ -39,6 +40,9 @@ impl ProjectManifest {
             }
         }
 
+        /// Similarly to Cargo, the nearest manifest with the `[workspace]` table, which has
+        /// the nearest manifest as its member, is the root of the workspace. If there is
+        /// no such manifest, the nearest manifest is used.
         fn find_in_parent_dirs(path: &Path) -> Option<PathBuf> {
             if path.file_name().unwrap_or_default() == "Cargo.toml" {
                 if let Ok(path) = valid_path(path.to_path_buf()) {
@@ -46,19 +50,94 @@ impl ProjectManifest {
                 }
             }
 
-            let mut curr = Some(path.to_path_buf());
+            let manifests: Vec<PathBuf> = path
+                .ancestors()
+                .map(|dir| dir.join("Cargo.toml"))
+                .filter(|candidate| fs::metadata(candidate).is_ok())
+                .filter_map(|candidate| valid_path(candidate).ok())
+                .collect();
+            let nearest = manifests.first()?;
 
-            while let Some(path) = curr {
-                let candidate = path.join("Cargo.toml");
-                if fs::metadata(&candidate).is_ok() {
-                    if let Ok(manifest) = valid_path(candidate) {
-                        return Some(manifest);
-                    }
-                }
-                curr = path.parent().map(PathBuf::from);
+            let root = manifests
+                .iter()
+                .find(|manifest| is_workspace_root_of(manifest, nearest))
+                .unwrap_or(nearest);
+            Some(root.clone())
+        }
+
+        /// The package belongs to the workspace, if it is the root package of the workspace
+        /// or matches one of the `members` and none of the `exclude` paths.
+        fn is_workspace_root_of(workspace_manifest: &Path, package_manifest: &Path) -> bool {
+            let Some(workspace) = fs::read_to_string(workspace_manifest)
+                .ok()
+                .and_then(|content| content.parse::<Table>().ok())
+                .and_then(|mut table| table.remove("workspace"))
+            else {
+                return false;
+            };
+            let (Some(root), Some(package_dir)) =
+                (workspace_manifest.parent(), package_manifest.parent())
+            else {
+                return false;
+            };
+            let Ok(package_path) = package_dir.strip_prefix(root) else {
+                return false;
+            };
+            if package_path.as_os_str().is_empty() {
+                return true;
             }
+            let paths = |key: &str| -> Vec<PathBuf> {
+                workspace
+                    .get(key)
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .map(normalized_path)
+                    .collect()
+            };
+            let is_member = paths("members")
+                .iter()
+                .any(|member| matches_member(member, package_path));
+            let is_excluded = paths("exclude")
+                .iter()
+                .any(|excluded| package_path.starts_with(excluded));
+            is_member && !is_excluded
+        }
+
+        fn normalized_path(path: &str) -> PathBuf {
+            Path::new(path)
+                .components()
+                .filter(|component| *component != Component::CurDir)
+                .collect()
+        }
+
+        /// Members can be glob patterns with the `*` and `?` wildcards in the path components.
+        fn matches_member(member: &Path, package_path: &Path) -> bool {
+            let member: Vec<_> = member.iter().collect();
+            let package_path: Vec<_> = package_path.iter().collect();
+            member.len() == package_path.len()
+                && member.iter().zip(package_path).all(|(pattern, name)| {
+                    matches_wildcards(
+                        pattern.to_string_lossy().as_bytes(),
+                        name.to_string_lossy().as_bytes(),
+                    )
+                })
+        }
 
-            None
+        fn matches_wildcards(pattern: &[u8], name: &[u8]) -> bool {
+            match (pattern.split_first(), name.split_first()) {
+                (None, None) => true,
+                (Some((b'*', rest)), _) => {
+                    matches_wildcards(rest, name)
+                        || (!name.is_empty() && matches_wildcards(pattern, &name[1..]))
+                }
+                (Some((b'?', rest)), Some((_, name_rest))) => matches_wildcards(rest, name_rest),
+                (Some((p, rest)), Some((n, name_rest))) => {
+                    p == n && matches_wildcards(rest, name_rest)
+                }
+                _ => false,
+            }
         }
 
         fn find_cargo_toml_in_child_dir(entities: ReadDir) -> Vec<PathBuf> {
@@ -100,8 +179,8 @@ impl ProjectManifest {
 #[cfg(test)]
 mod tests {
     use std::collections::HashSet;
-    use std::fs::File;
-    use std::path::PathBuf;
+    use std::fs::{self, File};
+    use std::path::{Path, PathBuf};
 
     use tempfile::{tempdir, tempdir_in};
 
@@ -229,4 +308,125 @@ mod tests {
         inner_dir2.close().unwrap();
         main_dir.close().unwrap();
     }
+
+    #[test]
+    fn workspace_toml_in_parent_dir() {
+        let workspace_dir = tempdir().unwrap();
+        let workspace_manifest = workspace_dir.path().join("Cargo.toml");
+        fs::write(&workspace_manifest, "[workspace]\nmembers = [\"member\"]\n").unwrap();
+        let member_dir = workspace_dir.path().join("member");
+        fs::create_dir_all(member_dir.join("src")).unwrap();
+        fs::write(
+            member_dir.join("Cargo.toml"),
+            "[package]\nname = \"member\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+
+        let expected = HashSet::from([ProjectManifest {
+            file: workspace_manifest,
+        }]);
+
+        test_paths(&vec![
+            TestCase {
+                path: &member_dir,
+                at_least_one: true,
+                result: &expected,
+            },
+            TestCase {
+                path: &member_dir.join("src"),
+                at_least_one: true,
+                result: &expected,
+            },
+        ]);
+    }
+
+    #[test]
+    fn nearest_toml_without_workspace() {
+        let main_dir = tempdir().unwrap();
+        File::create(main_dir.path().join("Cargo.toml")).unwrap();
+        let inner_dir = tempdir_in(main_dir.path()).unwrap();
+        let inner_manifest = inner_dir.path().join("Cargo.toml");
+        File::create(&inner_manifest).unwrap();
+
+        test_paths(&vec![TestCase {
+            path: &inner_dir.path().to_path_buf(),
+            at_least_one: true,
+            result: &HashSet::from([ProjectManifest {
+                file: inner_manifest,
+            }]),
+        }]);
+    }
+
+    fn write_package(dir: &Path, name: &str) -> PathBuf {
+        fs::create_dir_all(dir.join("src")).unwrap();
+        let manifest = dir.join("Cargo.toml");
+        fs::write(
+            &manifest,
+            format!("[package]\nname = \"{name}\"\nversion = \"0.1.0\"\n"),
+        )
+        .unwrap();
+        manifest
+    }
+
+    #[test]
+    fn workspace_toml_with_glob_members() {
+        let workspace_dir = tempdir().unwrap();
+        let workspace_manifest = workspace_dir.path().join("Cargo.toml");
+        fs::write(
+            &workspace_manifest,
+            "[workspace]\nmembers = [\"./crates/*\"]\n",
+        )
+        .unwrap();
+        let member_dir = workspace_dir.path().join("crates").join("member");
+        write_package(&member_dir, "member");
+
+        test_paths(&vec![TestCase {
+            path: &member_dir,
+            at_least_one: true,
+            result: &HashSet::from([ProjectManifest {
+                file: workspace_manifest,
+            }]),
+        }]);
+    }
+
+    #[test]
+    fn excluded_package_in_workspace_dir() {
+        let workspace_dir = tempdir().unwrap();
+        fs::write(
+            workspace_dir.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/excluded\"]\n",
+        )
+        .unwrap();
+        let excluded_dir = workspace_dir.path().join("crates").join("excluded");
+        let excluded_manifest = write_package(&excluded_dir, "excluded");
+
+        test_paths(&vec![TestCase {
+            path: &excluded_dir.join("src"),
+            at_least_one: true,
+            result: &HashSet::from([ProjectManifest {
+                file: excluded_manifest,
+            }]),
+        }]);
+    }
+
+    #[test]
+    fn non_member_package_in_workspace_dir() {
+        let workspace_dir = tempdir().unwrap();
+        fs::write(
+            workspace_dir.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"member\"]\n",
+        )
+        .unwrap();
+        write_package(&workspace_dir.path().join("member"), "member");
+        let other_dir = workspace_dir.path().join("other");
+        let other_manifest = write_package(&other_dir, "other");
+
+        test_paths(&vec![TestCase {
+            path: &other_dir,
+            at_least_one: true,
+            result: &HashSet::from([ProjectManifest {
+                file: other_manifest,
+            }]),
+        }]);
+    }
 }
@@ -3,7 +3,7 @@
 
 use std::collections::BTreeMap;
 
-use cargo_metadata::DependencyKind;
+use cargo_metadata::{DependencyKind, Node};
 
 use bsp4rs::rust::{
     Feature, RustDepKind, RustDepKindInfo, RustDependencies, RustDependency, RustPackage,
@@ -58,13 +58,22 @@ fn metadata_dep_kinds_info_to_rust_dep_kinds_info(
         .collect()
 }
 
-fn metadata_node_dep_to_rust_dependency(node_dep: &cargo_metadata::NodeDep) -> RustDependency {
+/// The features of the dependency are taken from its resolve node, which holds the set
+/// unified by Cargo across the whole workspace.
+fn metadata_node_dep_to_rust_dependency(
+    node_dep: &cargo_metadata::NodeDep,
+    nodes: &[Node],
+) -> RustDependency {
     RustDependency {
         name: Some(node_dep.name.clone()),
         pkg: node_dep.pkg.to_string(),
         dep_kinds: Some(metadata_dep_kinds_info_to_rust_dep_kinds_info(
             &node_dep.dep_kinds,
         )),
+        features: nodes
+            .iter()
+            .find(|n| n.id == node_dep.pkg)
+            .map(|n| n.features.iter().cloned().map(Feature).collect()),
     }
 }
 
@@ -106,10 +115,84 @@ pub fn resolve_rust_dependencies(
                 let dependencies = node
                     .deps
                     .iter()
-                    .map(metadata_node_dep_to_rust_dependency)
+                    .map(|d| metadata_node_dep_to_rust_dependency(d, &nodes))
                     .collect::<Vec<RustDependency>>();
                 (id, dependencies)
             })
             .collect::<BTreeMap<String, Vec<RustDependency>>>(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cargo_metadata::Metadata;
+    use serde_json::json;
+    use std::collections::BTreeSet;
+
+    const APP1_ID: &str = "app1 0.1.0 (path+file:///test_root/app1)";
+    const APP2_ID: &str = "app2 0.1.0 (path+file:///test_root/app2)";
+    const SHARED_ID: &str = "shared 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)";
+
+    fn test_node_dep() -> serde_json::Value {
+        json!({
+            "name": "shared",
+            "pkg": SHARED_ID,
+            "dep_kinds": [{"kind": null, "target": null}],
+        })
+    }
+
+    /// Two workspace members depend on `shared`, which has the given features enabled.
+    fn test_metadata(shared_features: &[&str]) -> Metadata {
+        serde_json::from_value(json!({
+            "packages": [],
+            "workspace_members": [APP1_ID, APP2_ID],
+            "resolve": {
+                "nodes": [
+                    {"id": APP1_ID, "dependencies": [SHARED_ID], "deps": [test_node_dep()], "features": []},
+                    {"id": APP2_ID, "dependencies": [SHARED_ID], "deps": [test_node_dep()], "features": []},
+                    {"id": SHARED_ID, "dependencies": [], "deps": [], "features": shared_features},
+                ],
+                "root": null,
+            },
+            "workspace_root": "/test_root",
+            "target_directory": "/test_root/target",
+            "version": 1,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_dependency_features_are_unified() {
+        let packages = [APP1_ID, APP2_ID].map(|id| RustPackage {
+            id: id.to_string(),
+            ..RustPackage::default()
+        });
+
+        // `app1` enables the `f1` feature of `shared` and `app2` its `f2` feature.
+        let dependencies = resolve_rust_dependencies(&test_metadata(&["f1", "f2"]), &packages);
+
+        let unified_features = BTreeSet::from([Feature::from("f1"), Feature::from("f2")]);
+        for id in [APP1_ID, APP2_ID] {
+            let shared = &dependencies.get(id).unwrap()[0];
+            assert_eq!(shared.pkg, SHARED_ID);
+            assert_eq!(shared.features, Some(unified_features.clone()));
+        }
+    }
+
+    #[test]
+    fn test_dependency_with_non_default_feature() {
+        let packages = [RustPackage {
+            id: APP1_ID.to_string(),
+            ..RustPackage::default()
+        }];
+
+        let dependencies =
+            resolve_rust_dependencies(&test_metadata(&["default", "extra"]), &packages);
+
+        let shared = &dependencies.get(APP1_ID).unwrap()[0];
+        let features = shared.features.as_ref().unwrap();
+        assert!(features.contains(&Feature::from("extra")));
+        assert!(features.contains(&Feature::from("default")));
+    }
+}
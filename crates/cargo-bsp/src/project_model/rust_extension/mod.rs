@@ -5,13 +5,17 @@ mod package;
 mod target;
 
 pub use self::package::get_rust_packages_related_to_targets;
+pub(crate) use self::target::{
+    metadata_crate_types_to_rust_extension_crate_types, metadata_kind_to_rust_extension_kind,
+};
 
+use crate::project_model::metadata_cache::MetadataCache;
 use crate::project_model::project_manifest::ProjectManifest;
 use crate::project_model::rust_extension::dependency::{
     resolve_raw_dependencies, resolve_rust_dependencies,
 };
 use crate::project_model::workspace::ProjectWorkspace;
-use crate::server::Error;
+use crate::project_model::UnknownEditions;
 use bsp4rs::bsp::BuildTargetIdentifier;
 use bsp4rs::rust::RustWorkspaceResult;
 use cargo_metadata::{Metadata, Node};
@@ -43,17 +47,23 @@ pub(crate) fn get_nodes_from_metadata(metadata: &Metadata) -> Vec<Node> {
     }
 }
 
-pub fn get_metadata(project_manifest: &ProjectManifest) -> Result<Metadata, Error> {
-    let metadata = ProjectWorkspace::call_cargo_metadata_command(&project_manifest.file, true)?;
-    Ok(metadata)
+/// The metadata filtered by the host platform with the `--filter-platform` flag, cached
+/// (separately from the metadata of the workspace load) until the manifests change.
+pub fn get_metadata(
+    metadata_cache: &mut MetadataCache,
+    project_manifest: &ProjectManifest,
+) -> Result<(Metadata, UnknownEditions), cargo_metadata::Error> {
+    metadata_cache.get(&project_manifest.file, true)
 }
 
 pub fn resolve_rust_workspace_result(
     workspace: &mut ProjectWorkspace,
     targets: &[BuildTargetIdentifier],
     metadata: &Metadata,
+    unknown_editions: &UnknownEditions,
 ) -> RustWorkspaceResult {
-    let packages = get_rust_packages_related_to_targets(workspace, metadata, targets);
+    let packages =
+        get_rust_packages_related_to_targets(workspace, metadata, unknown_editions, targets);
     let raw_dependencies = resolve_raw_dependencies(metadata, &packages);
     let dependencies = resolve_rust_dependencies(metadata, &packages);
 
@@ -7,10 +7,13 @@ use crate::project_model::rust_extension::{
     find_node, get_nodes_from_metadata, target::metadata_targets_to_rust_extension_targets,
 };
 use crate::project_model::workspace::ProjectWorkspace;
-use crate::project_model::{metadata_edition_to_bsp_edition, CreateFeatureDependencyGraph};
+use crate::project_model::{CreateFeatureDependencyGraph, UnknownEditions};
 use crate::utils::uri::file_uri;
 use bsp4rs::bsp::BuildTargetIdentifier;
-use bsp4rs::rust::{Feature, FeatureDependencyGraph, RustPackage, RustPackageOrigin};
+use bsp4rs::rust::{
+    Feature, FeatureDependencyGraph, RustPackage, RustPackageOrigin, RustPackageSourceKind,
+};
+use cargo_metadata::Node;
 use std::collections::{BTreeSet, HashSet, VecDeque};
 
 fn resolve_origin(package: &mut RustPackage, workspace: &ProjectWorkspace) {
@@ -21,6 +24,19 @@ fn resolve_origin(package: &mut RustPackage, workspace: &ProjectWorkspace) {
     }
 }
 
+/// Kind of the source of a package, based on its source id from the metadata.
+/// Packages without the source id are local (workspace members or path dependencies).
+/// The root of a git dependency is its checkout (under `~/.cargo/git/checkouts`)
+/// and the root of a path dependency is its local directory, as the manifest paths
+/// from the metadata point to them.
+fn source_kind(source: Option<&str>) -> RustPackageSourceKind {
+    match source {
+        None => RustPackageSourceKind::PATH,
+        Some(s) if s.starts_with("git+") => RustPackageSourceKind::GIT,
+        Some(_) => RustPackageSourceKind::REGISTRY,
+    }
+}
+
 fn set_and_resolve_enabled_features(
     workspace: &mut ProjectWorkspace,
     package: &mut RustPackage,
@@ -45,15 +61,24 @@ fn set_and_resolve_enabled_features(
 
 fn metadata_package_to_rust_extension_package(
     metadata_package: cargo_metadata::Package,
+    unknown_editions: &UnknownEditions,
 ) -> RustPackage {
     let features = FeatureDependencyGraph::create_features_dependency_graph(&metadata_package);
-    let all_targets = metadata_targets_to_rust_extension_targets(metadata_package.targets);
+    let edition = unknown_editions.package_edition(&metadata_package);
+    let all_targets = metadata_targets_to_rust_extension_targets(
+        &metadata_package.id.repr,
+        metadata_package.targets,
+        unknown_editions,
+    );
     RustPackage {
         id: metadata_package.id.clone().to_string(),
         root_url: file_uri(metadata_package.manifest_path.parent().unwrap().to_string()),
         name: metadata_package.name.clone(),
         version: metadata_package.version.to_string(),
-        edition: metadata_edition_to_bsp_edition(metadata_package.edition),
+        edition,
+        source_kind: Some(source_kind(
+            metadata_package.source.as_ref().map(|s| s.repr.as_str()),
+        )),
         source: metadata_package.source.map(|s| s.to_string()),
         features,
         // In our case targets = all_targets. This field is needed for Bazel
@@ -64,32 +89,31 @@ fn metadata_package_to_rust_extension_package(
     }
 }
 
-/// Returns a list of rust extension packages from which provided targets depend on
+/// Returns a list of rust extension packages from which provided targets depend on.
+/// Packages are identified by their ids, as multiple versions of the same package
+/// may be present in the dependency graph.
 pub fn get_rust_packages_related_to_targets(
     workspace: &mut ProjectWorkspace,
     metadata: &cargo_metadata::Metadata,
+    unknown_editions: &UnknownEditions,
     targets: &[BuildTargetIdentifier],
 ) -> Vec<RustPackage> {
-    let target_related_packages_names: Vec<String> = targets
+    let nodes = get_nodes_from_metadata(metadata);
+
+    let target_related_packages_ids: Vec<String> = targets
         .iter()
         .filter_map(|t| workspace.get_package_related_to_target(t))
-        .flat_map(|p| find_all_packages(p, &metadata.packages))
+        .flat_map(|p| find_all_packages(p, &nodes))
         .collect::<HashSet<_>>()
         .into_iter()
         .collect();
 
-    let nodes = get_nodes_from_metadata(metadata);
-
-    target_related_packages_names
+    target_related_packages_ids
         .iter()
-        .map(|n| {
-            let package = metadata
-                .packages
-                .iter()
-                .find(|p| p.name == *n)
-                .unwrap()
-                .clone();
-            let mut rust_package = metadata_package_to_rust_extension_package(package);
+        .filter_map(|id| metadata.packages.iter().find(|p| p.id.repr == *id))
+        .map(|package| {
+            let mut rust_package =
+                metadata_package_to_rust_extension_package(package.clone(), unknown_editions);
             resolve_origin(&mut rust_package, workspace);
             if workspace.is_package_part_of_workspace(rust_package.id.as_str()) {
                 set_and_resolve_enabled_features(workspace, &mut rust_package, &nodes);
@@ -99,24 +123,184 @@ pub fn get_rust_packages_related_to_targets(
         .collect()
 }
 
-fn find_all_packages(package: &CargoPackage, packages: &[cargo_metadata::Package]) -> Vec<String> {
-    if let Some(package) = packages.iter().find(|p| p.name == package.name) {
-        let mut next_dependencies: VecDeque<&cargo_metadata::Package> = VecDeque::from([package]);
-        let mut checked_dependencies: HashSet<String> = HashSet::from([package.name.clone()]);
-        let mut all_package_names: Vec<String> = vec![package.name.clone()];
-
-        while let Some(next) = next_dependencies.pop_front() {
-            for dependency in &next.dependencies {
-                if !checked_dependencies.contains(&dependency.name) {
-                    checked_dependencies.insert(dependency.name.clone());
-                    if let Some(p) = packages.iter().find(|p| p.name == dependency.name) {
-                        all_package_names.push(p.name.clone());
-                        next_dependencies.push_back(p);
-                    }
+/// Returns ids of the package and all its (transitive) dependencies from the resolve graph.
+fn find_all_packages(package: &CargoPackage, nodes: &[Node]) -> Vec<String> {
+    let mut next_dependencies: VecDeque<&str> = VecDeque::from([package.id.as_str()]);
+    let mut checked_dependencies: HashSet<&str> = HashSet::from([package.id.as_str()]);
+    let mut all_package_ids: Vec<String> = vec![package.id.clone()];
+
+    while let Some(next) = next_dependencies.pop_front() {
+        if let Some(node) = nodes.iter().find(|n| n.id.repr == next) {
+            for dependency in &node.dependencies {
+                if checked_dependencies.insert(dependency.repr.as_str()) {
+                    all_package_ids.push(dependency.repr.clone());
+                    next_dependencies.push_back(dependency.repr.as_str());
                 }
             }
         }
-        return all_package_names;
     }
-    Vec::new()
+    all_package_ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project_model::build_target_mappings::build_target_id_from_cargo_target;
+    use cargo_metadata::Metadata;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use test_case::test_case;
+
+    const APP_ID: &str = "app 0.1.0 (path+file:///test_root/app)";
+    const DEP1_ID: &str = "dep 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)";
+    const DEP2_ID: &str = "dep 2.0.0 (registry+https://github.com/rust-lang/crates.io-index)";
+
+    fn test_metadata_package(
+        name: &str,
+        version: &str,
+        id: &str,
+        features: serde_json::Value,
+    ) -> serde_json::Value {
+        json!({
+            "name": name,
+            "version": version,
+            "id": id,
+            "source": null,
+            "dependencies": [],
+            "targets": [{
+                "name": name,
+                "kind": ["lib"],
+                "src_path": format!("/test_root/{}-{}/src/lib.rs", name, version),
+            }],
+            "features": features,
+            "manifest_path": format!("/test_root/{}-{}/Cargo.toml", name, version),
+            "edition": "2021",
+        })
+    }
+
+    /// Workspace with a package depending on two versions of the same package.
+    fn test_metadata() -> Metadata {
+        serde_json::from_value(json!({
+            "packages": [
+                test_metadata_package("app", "0.1.0", APP_ID, json!({})),
+                test_metadata_package("dep", "1.0.0", DEP1_ID, json!({"old_feature": []})),
+                test_metadata_package("dep", "2.0.0", DEP2_ID, json!({"new_feature": []})),
+            ],
+            "workspace_members": [APP_ID],
+            "resolve": {
+                "nodes": [
+                    {"id": APP_ID, "dependencies": [DEP1_ID, DEP2_ID], "deps": [], "features": []},
+                    {"id": DEP1_ID, "dependencies": [], "deps": [], "features": []},
+                    {"id": DEP2_ID, "dependencies": [], "deps": [], "features": []},
+                ],
+                "root": APP_ID,
+            },
+            "workspace_root": "/test_root",
+            "target_directory": "/test_root/target",
+            "version": 1,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_packages_with_the_same_name_are_distinct() {
+        let metadata = test_metadata();
+        let app_target = &metadata.packages[0].targets[0];
+        let app_target_id =
+            build_target_id_from_cargo_target(&metadata.packages[0].manifest_path, app_target);
+        let mut workspace = ProjectWorkspace {
+            packages: vec![CargoPackage::new(
+                &metadata.packages[0],
+                &metadata.packages,
+                &UnknownEditions::default(),
+            )],
+            target_id_to_package_id: HashMap::from([(app_target_id.clone(), APP_ID.to_string())]),
+            ..ProjectWorkspace::default()
+        };
+
+        let mut packages = get_rust_packages_related_to_targets(
+            &mut workspace,
+            &metadata,
+            &UnknownEditions::default(),
+            &[app_target_id],
+        );
+        packages.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let packages: Vec<_> = packages
+            .iter()
+            .map(|p| {
+                (
+                    p.id.as_str(),
+                    p.name.as_str(),
+                    p.version.as_str(),
+                    p.features.keys().map(|f| f.0.as_str()).collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            packages,
+            vec![
+                (APP_ID, "app", "0.1.0", vec![]),
+                (DEP1_ID, "dep", "1.0.0", vec!["old_feature"]),
+                (DEP2_ID, "dep", "2.0.0", vec!["new_feature"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dependency_target_has_no_related_packages() {
+        let metadata = test_metadata();
+        let dep_target = &metadata.packages[1].targets[0];
+        let dep_target_id =
+            build_target_id_from_cargo_target(&metadata.packages[1].manifest_path, dep_target);
+        let mut workspace = ProjectWorkspace::from_metadata(&metadata, &UnknownEditions::default());
+
+        let packages = get_rust_packages_related_to_targets(
+            &mut workspace,
+            &metadata,
+            &UnknownEditions::default(),
+            &[dep_target_id],
+        );
+
+        assert!(packages.is_empty());
+    }
+
+    #[test_case(
+        Some("registry+https://github.com/rust-lang/crates.io-index"),
+        "/home/.cargo/registry/src/index.crates.io-6f17d22bba15001f/dep-1.0.0/Cargo.toml",
+        RustPackageSourceKind::REGISTRY ;
+        "registry"
+    )]
+    #[test_case(
+        Some("git+https://github.com/test/dep?branch=main#8e4ecfba"),
+        "/home/.cargo/git/checkouts/dep-6f9bc3a3a88b4c8e/8e4ecfb/Cargo.toml",
+        RustPackageSourceKind::GIT ;
+        "git"
+    )]
+    #[test_case(None, "/local/dep/Cargo.toml", RustPackageSourceKind::PATH ; "path")]
+    fn test_dependency_source_kind(
+        source: Option<&str>,
+        manifest_path: &str,
+        expected_source_kind: RustPackageSourceKind,
+    ) {
+        let mut metadata_package = test_metadata_package("dep", "1.0.0", DEP1_ID, json!({}));
+        metadata_package["source"] = json!(source);
+        metadata_package["manifest_path"] = json!(manifest_path);
+        let metadata_package: cargo_metadata::Package =
+            serde_json::from_value(metadata_package).unwrap();
+        let mut package = metadata_package_to_rust_extension_package(
+            metadata_package,
+            &UnknownEditions::default(),
+        );
+
+        resolve_origin(&mut package, &ProjectWorkspace::default());
+
+        // The source kind doesn't change the origin of the dependency.
+        assert_eq!(package.origin, RustPackageOrigin::DEPENDENCY);
+        assert_eq!(package.source_kind, Some(expected_source_kind));
+        assert_eq!(
+            package.root_url,
+            file_uri(manifest_path.trim_end_matches("/Cargo.toml"))
+        );
+    }
 }
@@ -4,10 +4,10 @@
 
 use bsp4rs::rust::{Feature, RustCrateType, RustTarget, RustTargetKind};
 
-use crate::project_model::metadata_edition_to_bsp_edition;
+use crate::project_model::UnknownEditions;
 use crate::utils::uri::file_uri;
 
-fn metadata_kind_to_rust_extension_kind(metadata_kind: &str) -> RustTargetKind {
+pub(crate) fn metadata_kind_to_rust_extension_kind(metadata_kind: &str) -> RustTargetKind {
     match metadata_kind {
         "lib" | "proc-macro" | "rlib" | "dylib" | "cdylib" | "staticlib" => RustTargetKind::Lib,
         "bin" => RustTargetKind::Bin,
@@ -19,7 +19,7 @@ fn metadata_kind_to_rust_extension_kind(metadata_kind: &str) -> RustTargetKind {
     }
 }
 
-fn metadata_crate_types_to_rust_extension_crate_types(
+pub(crate) fn metadata_crate_types_to_rust_extension_crate_types(
     metadata_crate_types: Vec<String>,
 ) -> Vec<RustCrateType> {
     metadata_crate_types
@@ -38,7 +38,9 @@ fn metadata_crate_types_to_rust_extension_crate_types(
 }
 
 pub(crate) fn metadata_targets_to_rust_extension_targets(
+    package_id: &str,
     mut metadata_targets: Vec<cargo_metadata::Target>,
+    unknown_editions: &UnknownEditions,
 ) -> Vec<RustTarget> {
     metadata_targets
         .iter_mut()
@@ -57,8 +59,42 @@ pub(crate) fn metadata_targets_to_rust_extension_targets(
                         .collect(),
                 ),
                 doctest: mt.doctest,
-                edition: metadata_edition_to_bsp_edition(mt.edition),
+                edition: unknown_editions.target_edition(package_id, mt),
+                // Set after the check of the workspace, which builds the proc-macros.
+                proc_macro_artifact: None,
             }
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cargo_metadata::TargetBuilder;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn cdylib_target_crate_types() {
+        let cdylib_target = TargetBuilder::default()
+            .name("test_cdylib".to_string())
+            .kind(vec!["cdylib".to_string()])
+            .crate_types(vec!["cdylib".to_string(), "rlib".to_string()])
+            .src_path("/test_root/src/lib.rs".to_string())
+            .build()
+            .unwrap();
+
+        let rust_targets = metadata_targets_to_rust_extension_targets(
+            "test_package_id",
+            vec![cdylib_target],
+            &UnknownEditions::default(),
+        );
+
+        assert_eq!(rust_targets[0].kind, RustTargetKind::Lib);
+        assert_json_snapshot!(rust_targets[0].crate_types, @r#"
+        [
+          5,
+          3
+        ]
+        "#);
+    }
+}
@@ -0,0 +1,60 @@
+//! Default `cfg` options of the host target (such as `target_os="linux"` or `unix`),
+//! printed by the `rustc --print cfg` command.
+
+use std::path::Path;
+use std::process::Command;
+
+use log::warn;
+
+/// Returns an empty list if the command fails, as the options are not essential.
+pub(crate) fn rustc_cfgs(root_path: &Path) -> Vec<String> {
+    let output = Command::new(toolchain::rustc())
+        .current_dir(root_path)
+        .args(["--print", "cfg"])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_rustc_cfgs(&String::from_utf8_lossy(&output.stdout))
+        }
+        Ok(output) => {
+            warn!(
+                "Failed to get the target cfg options: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            vec![]
+        }
+        Err(e) => {
+            warn!("Failed to get the target cfg options: {}", e);
+            vec![]
+        }
+    }
+}
+
+fn parse_rustc_cfgs(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rustc_cfgs() {
+        let stdout = "debug_assertions\ntarget_arch=\"x86_64\"\ntarget_os=\"linux\"\nunix\n\n";
+
+        assert_eq!(
+            parse_rustc_cfgs(stdout),
+            vec![
+                "debug_assertions",
+                "target_arch=\"x86_64\"",
+                "target_os=\"linux\"",
+                "unix"
+            ]
+        );
+    }
+}
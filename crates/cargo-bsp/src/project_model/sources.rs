@@ -1,9 +1,14 @@
 //! Discovers all sources for build targets.
+//!
+//! The directories ignored by `.gitignore` files, the hidden directories (such as `.git`)
+//! and the `target` directories, including the target directory of the workspace
+//! (which may be set e.g. with `build.target-dir` in `.cargo/config.toml`), are not
+//! searched for the sources.
 
 use std::path::PathBuf;
 
-use cargo_metadata::camino::Utf8PathBuf;
-use walkdir::WalkDir;
+use cargo_metadata::camino::{Utf8Path, Utf8PathBuf};
+use ignore::WalkBuilder;
 
 use bsp4rs::bsp::BuildTargetIdentifier;
 use bsp4rs::bsp::{SourceItem, SourceItemKind, SourcesItem};
@@ -14,23 +19,27 @@ use crate::utils::uri::file_uri;
 pub fn get_sources_for_target(
     id: &BuildTargetIdentifier,
     target_details: TargetDetails,
+    target_directory: &Utf8Path,
 ) -> SourcesItem {
     let package_path = target_details.package_abs_path.clone();
 
     SourcesItem {
         target: id.clone(),
-        sources: list_target_sources(target_details),
+        sources: list_target_sources(target_details, target_directory),
         roots: Some(vec![file_uri(package_path)]),
     }
 }
 
-fn list_target_sources(target_details: TargetDetails) -> Vec<SourceItem> {
-    fn list_source_files_in_path(path: Utf8PathBuf) -> Vec<SourceItem> {
-        get_all_rs_files_in_dir(path.as_str())
+fn list_target_sources(
+    target_details: TargetDetails,
+    target_directory: &Utf8Path,
+) -> Vec<SourceItem> {
+    let list_source_files_in_path = |path: Utf8PathBuf| -> Vec<SourceItem> {
+        get_all_rs_files_in_dir(path.as_str(), target_directory)
             .into_iter()
             .map(create_source_item)
             .collect()
-    }
+    };
 
     let package_path = target_details.package_abs_path;
 
@@ -46,9 +55,20 @@ fn list_target_sources(target_details: TargetDetails) -> Vec<SourceItem> {
     src_sources
 }
 
-fn get_all_rs_files_in_dir(dir: &str) -> Vec<PathBuf> {
-    WalkDir::new(dir)
-        .into_iter()
+const TARGET_DIR_NAME: &str = "target";
+
+fn get_all_rs_files_in_dir(dir: &str, target_directory: &Utf8Path) -> Vec<PathBuf> {
+    let target_directory = target_directory.as_std_path().to_path_buf();
+    WalkBuilder::new(dir)
+        .hidden(true)
+        .git_ignore(true)
+        // `.gitignore` files are respected also outside of git repositories.
+        .require_git(false)
+        .filter_entry(move |e| {
+            let is_dir = e.file_type().is_some_and(|t| t.is_dir());
+            !(is_dir && (e.file_name() == TARGET_DIR_NAME || e.path() == target_directory))
+        })
+        .build()
         .filter_map(|entry| {
             entry.ok().and_then(|e| match e.path().extension() {
                 Some(ext) if ext == "rs" => Some(e.into_path()),
@@ -78,7 +98,7 @@ mod tests {
     use std::fs::File;
     use std::path::{Path, PathBuf};
 
-    use cargo_metadata::camino::Utf8PathBuf;
+    use cargo_metadata::camino::{Utf8Path, Utf8PathBuf};
     use insta::assert_json_snapshot;
     use tempfile::tempdir;
 
@@ -86,9 +106,11 @@ mod tests {
 
     use crate::project_model::sources::get_sources_for_target;
     use crate::project_model::target_details::{CargoTargetKind, TargetDetails};
+    use crate::utils::uri::file_uri;
 
     const RUST_FILE_NAMES: [&str; 3] = ["test1.rs", "test2.rs", "test3.rs"];
     const NOT_RUST_FILE_NAMES: [&str; 3] = ["test1.txt", "test4", "test5.rs.java"];
+    const TEST_TARGET_DIR: &str = "/test_target_dir";
 
     fn create_files(files_names: &[&str], dir: &Path) -> HashSet<PathBuf> {
         let files_paths = files_names
@@ -227,7 +249,10 @@ mod tests {
             let test_cases = create_test_cases(&dir_root);
 
             for case in test_cases {
-                let source_item = get_all_rs_files_in_dir(case.dir_path.to_str().unwrap());
+                let source_item = get_all_rs_files_in_dir(
+                    case.dir_path.to_str().unwrap(),
+                    Utf8Path::new(TEST_TARGET_DIR),
+                );
                 assert!(source_item.iter().all(|item| case.files.contains(item)));
             }
         }
@@ -329,16 +354,80 @@ mod tests {
                     ..TargetDetails::default()
                 };
 
-                let source_item = list_target_sources(test_target_details.clone());
+                let source_item = list_target_sources(
+                    test_target_details.clone(),
+                    Utf8Path::new(TEST_TARGET_DIR),
+                );
                 assert!(source_item.iter().all(|item| case.expected.contains(item)));
 
                 test_target_details.package_abs_path = test_dir2.root_dir_path_str.clone();
-                let source_item = list_target_sources(test_target_details);
+                let source_item =
+                    list_target_sources(test_target_details, Utf8Path::new(TEST_TARGET_DIR));
                 assert_eq!(source_item.len(), 0);
             }
         }
     }
 
+    #[test]
+    fn sources_exclude_target_hidden_and_ignored_dirs() {
+        let package_dir = tempdir().unwrap();
+        let package_path = Utf8PathBuf::try_from(package_dir.path().to_path_buf()).unwrap();
+        let src_path = package_path.join("src");
+        for dir in ["target", ".hidden", "generated", "module"] {
+            std::fs::create_dir_all(src_path.join(dir)).unwrap();
+            File::create(src_path.join(dir).join("file.rs")).unwrap();
+        }
+        File::create(src_path.join("lib.rs")).unwrap();
+        std::fs::write(package_path.join(".gitignore"), "generated/\n").unwrap();
+        let test_target_details = TargetDetails {
+            kind: CargoTargetKind::Lib,
+            package_abs_path: package_path,
+            ..TargetDetails::default()
+        };
+
+        let sources_item = get_sources_for_target(
+            &BuildTargetIdentifier::default(),
+            test_target_details,
+            Utf8Path::new(TEST_TARGET_DIR),
+        );
+
+        let mut sources: Vec<String> = sources_item.sources.into_iter().map(|s| s.uri.0).collect();
+        sources.sort();
+        assert_eq!(
+            sources,
+            vec![
+                file_uri(src_path.join("lib.rs")).0,
+                file_uri(src_path.join("module").join("file.rs")).0,
+            ]
+        );
+    }
+
+    #[test]
+    fn sources_exclude_configured_target_directory() {
+        let package_dir = tempdir().unwrap();
+        let package_path = Utf8PathBuf::try_from(package_dir.path().to_path_buf()).unwrap();
+        let src_path = package_path.join("src");
+        // As with `build.target-dir = "src/build-out"` in `.cargo/config.toml`.
+        let target_directory = src_path.join("build-out");
+        std::fs::create_dir_all(target_directory.join("debug")).unwrap();
+        File::create(target_directory.join("debug").join("generated.rs")).unwrap();
+        File::create(src_path.join("lib.rs")).unwrap();
+        let test_target_details = TargetDetails {
+            kind: CargoTargetKind::Lib,
+            package_abs_path: package_path,
+            ..TargetDetails::default()
+        };
+
+        let sources_item = get_sources_for_target(
+            &BuildTargetIdentifier::default(),
+            test_target_details,
+            &target_directory,
+        );
+
+        let sources: Vec<String> = sources_item.sources.into_iter().map(|s| s.uri.0).collect();
+        assert_eq!(sources, vec![file_uri(src_path.join("lib.rs")).0]);
+    }
+
     #[test]
     fn get_sources_for_target_test() {
         let test_id = BuildTargetIdentifier {
@@ -351,7 +440,7 @@ mod tests {
         };
 
         assert_json_snapshot!(
-            get_sources_for_target(&test_id, test_target_details),
+            get_sources_for_target(&test_id, test_target_details, Utf8Path::new(TEST_TARGET_DIR)),
             @r#"
         {
           "target": {
@@ -0,0 +1,143 @@
+//! Discovery of the sysroot and the standard library sources of the project.
+//!
+//! By default the sysroot of the toolchain used in the project is taken.
+//! If the standard library is built from sources (`-Zbuild-std`, enabled with
+//! [`ServerSettings::build_std`]), the sysroot passed with `--sysroot` flag in rustflags
+//! takes precedence, as it is the one used by the build.
+
+use std::env;
+use std::io;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::server::config::ServerSettings;
+
+/// Path to the standard library sources, relative to the sysroot.
+const STD_SOURCES_RELATIVE_PATH: &str = "lib/rustlib/src/rust/library";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sysroot {
+    pub root: PathBuf,
+    pub src_root: PathBuf,
+}
+
+impl Sysroot {
+    pub fn discover(root_path: &Path, settings: &ServerSettings) -> io::Result<Sysroot> {
+        Sysroot::discover_with_rustflags(root_path, settings, &rustflags_from_env())
+    }
+
+    pub(crate) fn discover_with_rustflags(
+        root_path: &Path,
+        settings: &ServerSettings,
+        rustflags: &[String],
+    ) -> io::Result<Sysroot> {
+        let root = resolve_sysroot(settings.build_std, rustflags, || {
+            toolchain_sysroot(root_path)
+        })?;
+        Ok(Sysroot::from_root(root))
+    }
+
+    fn from_root(root: PathBuf) -> Sysroot {
+        Sysroot {
+            src_root: root.join(STD_SOURCES_RELATIVE_PATH),
+            root,
+        }
+    }
+}
+
+fn resolve_sysroot(
+    build_std: bool,
+    rustflags: &[String],
+    toolchain_sysroot: impl FnOnce() -> io::Result<PathBuf>,
+) -> io::Result<PathBuf> {
+    match build_std
+        .then(|| sysroot_from_rustflags(rustflags))
+        .flatten()
+    {
+        Some(sysroot) => Ok(sysroot),
+        None => toolchain_sysroot(),
+    }
+}
+
+/// Rustflags in the same way as Cargo reads them from the environment.
+fn rustflags_from_env() -> Vec<String> {
+    if let Ok(encoded) = env::var("CARGO_ENCODED_RUSTFLAGS") {
+        return encoded
+            .split('\x1f')
+            .filter(|f| !f.is_empty())
+            .map(String::from)
+            .collect();
+    }
+    env::var("RUSTFLAGS")
+        .map(|flags| flags.split_whitespace().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+fn sysroot_from_rustflags(rustflags: &[String]) -> Option<PathBuf> {
+    let mut flags = rustflags.iter();
+    let mut sysroot = None;
+    while let Some(flag) = flags.next() {
+        if flag == "--sysroot" {
+            sysroot = flags.next().map(PathBuf::from);
+        } else if let Some(path) = flag.strip_prefix("--sysroot=") {
+            sysroot = Some(PathBuf::from(path));
+        }
+    }
+    sysroot
+}
+
+fn toolchain_sysroot(root_path: &Path) -> io::Result<PathBuf> {
+    let output = Command::new(toolchain::rustc())
+        .current_dir(root_path)
+        .args(["--print", "sysroot"])
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::new(
+            ErrorKind::Other,
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    const TOOLCHAIN_SYSROOT: &str = "/toolchain/sysroot";
+    const BUILD_STD_SYSROOT: &str = "/build-std/sysroot";
+
+    fn flags(flags: &[&str]) -> Vec<String> {
+        flags.iter().map(|f| f.to_string()).collect()
+    }
+
+    #[test_case(&["--sysroot", BUILD_STD_SYSROOT] ; "separate_value")]
+    #[test_case(&["-C", "opt-level=2", "--sysroot=/build-std/sysroot"] ; "joined_value")]
+    fn build_std_sources_resolve_to_build_std_sysroot(rustflags: &[&str]) {
+        let root = resolve_sysroot(true, &flags(rustflags), || Ok(TOOLCHAIN_SYSROOT.into()));
+
+        assert_eq!(
+            Sysroot::from_root(root.unwrap()),
+            Sysroot {
+                root: PathBuf::from(BUILD_STD_SYSROOT),
+                src_root: PathBuf::from(BUILD_STD_SYSROOT).join(STD_SOURCES_RELATIVE_PATH),
+            }
+        );
+    }
+
+    #[test_case(true, &[] ; "build_std_without_sysroot_flag")]
+    #[test_case(false, &["--sysroot", BUILD_STD_SYSROOT] ; "build_std_disabled")]
+    fn sources_resolve_to_toolchain_sysroot(build_std: bool, rustflags: &[&str]) {
+        let root = resolve_sysroot(
+            build_std,
+            &flags(rustflags),
+            || Ok(TOOLCHAIN_SYSROOT.into()),
+        );
+
+        assert_eq!(root.unwrap(), PathBuf::from(TOOLCHAIN_SYSROOT));
+    }
+}
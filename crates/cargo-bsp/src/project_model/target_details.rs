@@ -21,6 +21,8 @@ pub struct TargetDetails {
     pub name: String,
     pub package_abs_path: Utf8PathBuf,
     pub enabled_features: BTreeSet<Feature>,
+    /// Features which must be enabled to build the target (`required-features`).
+    pub required_features: BTreeSet<Feature>,
 }
 
 impl TargetDetails {
@@ -31,6 +33,11 @@ impl TargetDetails {
             name: target_data.name.clone(),
             package_abs_path: parent_path(&package.manifest_path),
             enabled_features: package.enabled_features.clone(),
+            required_features: target_data
+                .required_features
+                .iter()
+                .map(|f| Feature::from(f.as_str()))
+                .collect(),
         })
     }
     fn get_kind(target_data: &cargo_metadata::Target) -> Option<CargoTargetKind> {
@@ -0,0 +1,225 @@
+//! Build target ids are the URIs from which the Cargo target can be selected again
+//! (e.g. when creating the Cargo commands), without the mappings of the workspace.
+
+use cargo_metadata::camino::{Utf8Path, Utf8PathBuf};
+use url::Url;
+
+use bsp4rs::bsp::{BuildTargetIdentifier, URI};
+
+use crate::project_model::build_target_mappings::parent_path;
+
+const TARGET_ID_SCHEME: &str = "cargo";
+
+/// Selects the Cargo target: the package is selected by the directory of its manifest,
+/// the target by its name and kind (the names are unique per kind within the package).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TargetSelector {
+    pub manifest_dir: Utf8PathBuf,
+    pub name: String,
+    /// The first kind reported by Cargo, e.g. `lib`, `proc-macro` or `custom-build`.
+    pub kind: String,
+}
+
+impl TargetSelector {
+    pub fn new(
+        manifest_dir: impl Into<Utf8PathBuf>,
+        name: impl Into<String>,
+        kind: impl Into<String>,
+    ) -> TargetSelector {
+        TargetSelector {
+            manifest_dir: manifest_dir.into(),
+            name: name.into(),
+            kind: kind.into(),
+        }
+    }
+
+    pub fn from_cargo_target(
+        package_manifest_path: &Utf8PathBuf,
+        cargo_target: &cargo_metadata::Target,
+    ) -> TargetSelector {
+        TargetSelector {
+            manifest_dir: parent_path(package_manifest_path),
+            name: cargo_target.name.clone(),
+            kind: cargo_target.kind.first().cloned().unwrap_or_default(),
+        }
+    }
+
+    /// The id has the `cargo://<manifest dir>?name=<name>&kind=<kind>` URI,
+    /// e.g. `cargo:///project?name=app&kind=bin`. The directory is percent-encoded
+    /// as in its `file://` URI (`C:\project` becomes `/C:/project`).
+    pub fn to_id(&self) -> BuildTargetIdentifier {
+        let mut url = Url::parse(&format!("{TARGET_ID_SCHEME}:///")).unwrap();
+        url.set_path(&dir_url_path(&self.manifest_dir));
+        url.query_pairs_mut()
+            .append_pair("name", &self.name)
+            .append_pair("kind", &self.kind);
+        BuildTargetIdentifier {
+            uri: URI(url.into()),
+        }
+    }
+}
+
+fn dir_url_path(dir: &Utf8Path) -> String {
+    match Url::from_file_path(dir) {
+        Ok(file_url) => file_url.path().to_string(),
+        // Not an absolute path of this platform, only the characters not allowed
+        // in the URI path are encoded.
+        Err(()) => dir.as_str().replace('\\', "/"),
+    }
+}
+
+/// Returns `None` for the ids not created by [`TargetSelector::to_id`].
+pub fn parse_target_id(id: &BuildTargetIdentifier) -> Option<TargetSelector> {
+    let url = Url::parse(&id.uri.0).ok()?;
+    if url.scheme() != TARGET_ID_SCHEME {
+        return None;
+    }
+    let mut query = url.query_pairs();
+    let (name, kind) = match (query.next(), query.next(), query.next()) {
+        (Some((name_key, name)), Some((kind_key, kind)), None)
+            if name_key == "name" && kind_key == "kind" =>
+        {
+            (name, kind)
+        }
+        _ => return None,
+    };
+    if name.is_empty() || kind.is_empty() {
+        return None;
+    }
+    let mut file_url = Url::parse("file:///").unwrap();
+    file_url.set_path(url.path());
+    let manifest_dir = Utf8PathBuf::from_path_buf(file_url.to_file_path().ok()?).ok()?;
+    Some(TargetSelector::new(manifest_dir, name, kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project_model::build_target_mappings::build_target_from_cargo;
+    use cargo_metadata::TargetBuilder;
+    use test_case::test_case;
+
+    fn test_target(name: &str, kind: &str, src_path: &str) -> cargo_metadata::Target {
+        TargetBuilder::default()
+            .name(name.to_string())
+            .kind(vec![kind.to_string()])
+            .src_path(src_path.to_string())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn target_id_uri() {
+        let id = TargetSelector::new("/project", "app", "bin").to_id();
+
+        assert_eq!(id.uri.0, "cargo:///project?name=app&kind=bin");
+    }
+
+    #[test]
+    fn target_id_uri_is_percent_encoded() {
+        let id = TargetSelector::new("/my project/50%#1?", "a&b=c", "bin").to_id();
+
+        assert_eq!(
+            id.uri.0,
+            "cargo:///my%20project/50%25%231%3F?name=a%26b%3Dc&kind=bin"
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_target_id_uri() {
+        let selector = TargetSelector::new(r"C:\my project", "app", "bin");
+        let id = selector.to_id();
+
+        assert_eq!(id.uri.0, "cargo:///C:/my%20project?name=app&kind=bin");
+        assert_eq!(parse_target_id(&id), Some(selector));
+    }
+
+    const MANIFEST_DIRS: &[&str] = &[
+        "/test_root",
+        "/test_root/member",
+        "/test_root/with space",
+        "/test_root/what?",
+        "/test_root/100%+#1",
+        "/test_root/a&b=c/ünïcödé",
+        "/test_root/semi;colon/[brackets]",
+    ];
+    const TARGET_NAMES: &[&str] = &["app", "build-script-build", "a+b", "my_example", "x&y=z#?"];
+    const TARGET_KINDS: &[&str] = &[
+        "lib",
+        "rlib",
+        "proc-macro",
+        "bin",
+        "test",
+        "bench",
+        "example",
+        "custom-build",
+    ];
+
+    /// All combinations of the manifest directories, target names and kinds.
+    fn all_packages_and_targets() -> impl Iterator<Item = (Utf8PathBuf, cargo_metadata::Target)> {
+        MANIFEST_DIRS.iter().flat_map(|dir| {
+            TARGET_NAMES.iter().flat_map(move |name| {
+                TARGET_KINDS.iter().map(move |kind| {
+                    (
+                        Utf8PathBuf::from(format!("{dir}/Cargo.toml")),
+                        test_target(name, kind, &format!("{dir}/src/{name}.rs")),
+                    )
+                })
+            })
+        })
+    }
+
+    #[test]
+    fn build_targets_round_trip() {
+        for (manifest_path, target) in all_packages_and_targets() {
+            let build_target = build_target_from_cargo(&manifest_path, &target, &[]);
+
+            assert_eq!(
+                parse_target_id(&build_target.id),
+                Some(TargetSelector::from_cargo_target(&manifest_path, &target)),
+                "{:?}",
+                build_target.id
+            );
+        }
+    }
+
+    #[test]
+    fn ids_of_all_selectors_differ() {
+        let ids: std::collections::HashSet<_> = all_packages_and_targets()
+            .map(|(manifest_path, target)| {
+                TargetSelector::from_cargo_target(&manifest_path, &target).to_id()
+            })
+            .collect();
+
+        assert_eq!(
+            ids.len(),
+            MANIFEST_DIRS.len() * TARGET_NAMES.len() * TARGET_KINDS.len()
+        );
+    }
+
+    #[test]
+    fn ids_of_same_named_targets_differ() {
+        let manifest_path = Utf8PathBuf::from("/test_root/Cargo.toml");
+        let lib = test_target("app", "lib", "/test_root/src/lib.rs");
+        let bin = test_target("app", "bin", "/test_root/src/main.rs");
+
+        assert_ne!(
+            build_target_from_cargo(&manifest_path, &lib, &[]).id,
+            build_target_from_cargo(&manifest_path, &bin, &[]).id
+        );
+    }
+
+    #[test_case("targetId://test_root/src/lib.rs:app" ; "other_scheme")]
+    #[test_case("cargo:///test_root" ; "no_query")]
+    #[test_case("cargo:///test_root?name=app" ; "no_kind")]
+    #[test_case("cargo:///test_root?kind=lib&name=app" ; "wrong_order")]
+    #[test_case("cargo:///test_root?name=&kind=lib" ; "empty_name")]
+    #[test_case("cargo:///test_root?name=app&kind=lib&name=other" ; "extra_pair")]
+    #[test_case("" ; "empty")]
+    fn invalid_target_ids(uri: &str) {
+        let id = BuildTargetIdentifier { uri: uri.into() };
+
+        assert_eq!(parse_target_id(&id), None);
+    }
+}
@@ -0,0 +1,106 @@
+//! Information about the Rust toolchain used to build the project, printed by
+//! the `rustc -vV` command, and its sysroot found with [`Sysroot::discover`].
+
+use std::io;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::process::Command;
+
+use rustc_version::{version_meta_for, Channel};
+
+use bsp4rs::rust::RustToolchainResult;
+
+use crate::project_model::sysroot::Sysroot;
+use crate::server::config::ServerSettings;
+use crate::utils::uri::file_uri;
+
+/// The commands are run in the project's root, so that the toolchain
+/// overrides of the project (such as a `rust-toolchain.toml` file) are applied.
+pub(crate) fn toolchain_info(
+    root_path: &Path,
+    settings: &ServerSettings,
+) -> io::Result<RustToolchainResult> {
+    let sysroot = Sysroot::discover(root_path, settings)?;
+    rustc_info(root_path, sysroot)
+}
+
+fn rustc_info(root_path: &Path, sysroot: Sysroot) -> io::Result<RustToolchainResult> {
+    let output = Command::new(toolchain::rustc())
+        .current_dir(root_path)
+        .arg("-vV")
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::new(
+            ErrorKind::Other,
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ));
+    }
+    let mut info = parse_rustc_verbose_version(&String::from_utf8_lossy(&output.stdout))?;
+    info.sysroot = file_uri(sysroot.root.display());
+    Ok(info)
+}
+
+/// Fills all fields of the result except for the sysroot.
+fn parse_rustc_verbose_version(stdout: &str) -> io::Result<RustToolchainResult> {
+    let version_meta =
+        version_meta_for(stdout).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+    let channel = match version_meta.channel {
+        Channel::Dev => "dev",
+        Channel::Nightly => "nightly",
+        Channel::Beta => "beta",
+        Channel::Stable => "stable",
+    };
+    Ok(RustToolchainResult {
+        version: version_meta.semver.to_string(),
+        host: version_meta.host,
+        channel: channel.to_string(),
+        ..RustToolchainResult::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rustc_verbose_version() {
+        let stdout = "rustc 1.74.0-nightly (5ae769f06 2023-09-26)
+binary: rustc
+commit-hash: 5ae769f06bbe1dfb2a349bce2a1a13aa1bd1d3bd
+commit-date: 2023-09-26
+host: x86_64-unknown-linux-gnu
+release: 1.74.0-nightly
+LLVM version: 17.0.0
+";
+
+        assert_eq!(
+            parse_rustc_verbose_version(stdout).unwrap(),
+            RustToolchainResult {
+                version: "1.74.0-nightly".to_string(),
+                host: "x86_64-unknown-linux-gnu".to_string(),
+                channel: "nightly".to_string(),
+                ..RustToolchainResult::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_rustc_verbose_version() {
+        assert!(parse_rustc_verbose_version("invalid").is_err());
+    }
+
+    #[test]
+    fn build_std_sysroot_in_toolchain_info() {
+        let root_path = std::env::current_dir().unwrap();
+        let settings = ServerSettings {
+            build_std: true,
+            ..ServerSettings::default()
+        };
+        let rustflags = ["--sysroot".to_string(), "/build-std/sysroot".to_string()];
+
+        let sysroot = Sysroot::discover_with_rustflags(&root_path, &settings, &rustflags).unwrap();
+        let info = rustc_info(&root_path, sysroot).unwrap();
+
+        assert_eq!(info.sysroot, file_uri("/build-std/sysroot"));
+    }
+}
@@ -3,41 +3,68 @@
 
 use std::collections::{BTreeSet, HashMap};
 use std::path::PathBuf;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use cargo_metadata::camino::Utf8PathBuf;
-use cargo_metadata::{CargoOpt, Error, MetadataCommand};
-use log::error;
+use cargo_metadata::{CargoOpt, Edition, Error, MetadataCommand};
+use log::{error, warn};
+use rayon::prelude::*;
 use rustc_version::version_meta;
 use unzip_n::unzip_n;
 
 use bsp4rs::bsp::{BuildTarget, BuildTargetIdentifier, StatusCode};
-use bsp4rs::cargo::PackageFeatures;
-use bsp4rs::rust::Feature;
+use bsp4rs::cargo::{FeatureMode, PackageFeatures};
+use bsp4rs::rust::{Feature, RustEdition, RustTargetKind};
 
-use crate::project_model::build_target_mappings::build_target_id_from_name_and_path;
+use crate::project_model::build_target_mappings::build_target_id_from_cargo_target;
 use crate::project_model::cargo_package::CargoPackage;
-use crate::project_model::target_details::TargetDetails;
+use crate::project_model::enabled_features_state::EnabledFeaturesState;
+use crate::project_model::host_platform::HostPlatform;
+use crate::project_model::package_spec::{find_package_by_spec, PackageSpecError};
+use crate::project_model::rust_extension::metadata_kind_to_rust_extension_kind;
+use crate::project_model::target_details::{CargoTargetKind, TargetDetails};
+use crate::project_model::target_selector::{parse_target_id, TargetSelector};
+use crate::project_model::UnknownEditions;
 
-pub type TargetIdToPackageName = HashMap<BuildTargetIdentifier, String>;
-pub type TargetIdToTargetData = HashMap<BuildTargetIdentifier, Rc<cargo_metadata::Target>>;
+pub type TargetIdToPackageId = HashMap<BuildTargetIdentifier, String>;
+pub type TargetIdToTargetData = HashMap<BuildTargetIdentifier, Arc<cargo_metadata::Target>>;
 pub type SrcPathToTargetId = HashMap<Utf8PathBuf, BuildTargetIdentifier>;
+pub type TargetIdToSelector = HashMap<BuildTargetIdentifier, TargetSelector>;
+/// Key is a package id, target name and target kinds, as reported in Cargo messages.
+pub type PackageTargetToTargetId = HashMap<(String, String, Vec<String>), BuildTargetIdentifier>;
+/// Key is a package id, value is the package's primary target (see [`ProjectWorkspace::get_package_to_primary_target_id`]).
+pub type PackageToPrimaryTargetId = HashMap<String, BuildTargetIdentifier>;
 
-unzip_n!(3);
+unzip_n!(4);
 
 #[derive(Default, Debug, Clone)]
 pub struct ProjectWorkspace {
     /// List of all packages in a workspace (no external packages)
     pub packages: Vec<CargoPackage>,
 
-    /// Map creating an easy access from BuildTargetIdentifier of a target to package name
-    pub target_id_to_package_name: TargetIdToPackageName,
+    /// Map creating an easy access from BuildTargetIdentifier of a target to package id
+    pub target_id_to_package_id: TargetIdToPackageId,
 
     /// Map creating an easy access from BuildTargetIdentifier of a target to its details
     pub target_id_to_target_data: TargetIdToTargetData,
 
     /// Map creating an easy access from src path of a target to its BuildTargetIdentifier
     pub src_path_to_target_id: SrcPathToTargetId,
+
+    /// Map from BuildTargetIdentifier of a target to its selector, computed once at the load
+    /// and recomputed with the workspace on reload
+    pub target_id_to_selector: TargetIdToSelector,
+
+    /// Directory for all generated artifacts of the workspace
+    pub target_directory: Utf8PathBuf,
+
+    /// Mode of enabling the features, which decides about the inactive targets,
+    /// set from the server settings
+    pub feature_mode: FeatureMode,
+
+    /// Platform the targets are built for, which decides about the inactive targets.
+    /// None if it cannot be determined, then no target is inactive due to the platform
+    pub(crate) host: Option<HostPlatform>,
 }
 
 impl ProjectWorkspace {
@@ -48,24 +75,49 @@ impl ProjectWorkspace {
     ///
     /// Skips unit_tests discovery, see: [get_unit_tests_build_targets](crate::project_model::_unit_tests_discovery::get_unit_tests_build_targets).
     pub fn new(project_manifest_path: PathBuf) -> Result<ProjectWorkspace, Error> {
-        let metadata =
-            ProjectWorkspace::call_cargo_metadata_command(&project_manifest_path, false)?;
+        let (metadata, unknown_editions) =
+            ProjectWorkspace::call_cargo_metadata_command(&project_manifest_path, false, &[])?;
+        let mut workspace = ProjectWorkspace::from_metadata(&metadata, &unknown_editions);
+        workspace.host = HostPlatform::discover(metadata.workspace_root.as_std_path());
+        Ok(workspace)
+    }
 
+    /// Creates new ProjectWorkspace instance from the already obtained *'cargo metadata'* output.
+    pub fn from_metadata(
+        metadata: &cargo_metadata::Metadata,
+        unknown_editions: &UnknownEditions,
+    ) -> ProjectWorkspace {
         let bsp_packages: Vec<CargoPackage> = metadata
             .workspace_packages()
             .into_iter()
-            .map(|p| CargoPackage::new(p, &metadata.packages))
+            .map(|p| CargoPackage::new(p, &metadata.packages, unknown_editions))
             .collect();
+        ProjectWorkspace::from_packages(bsp_packages, metadata.target_directory.clone())
+    }
 
-        let (target_id_to_package_name, target_id_to_target_data, src_path_to_target_id) =
-            ProjectWorkspace::create_hashmaps(&bsp_packages);
+    /// Creates new ProjectWorkspace instance from the workspace packages, the maps
+    /// of their targets are created at once.
+    pub fn from_packages(
+        bsp_packages: Vec<CargoPackage>,
+        target_directory: Utf8PathBuf,
+    ) -> ProjectWorkspace {
+        let (
+            target_id_to_package_id,
+            target_id_to_target_data,
+            src_path_to_target_id,
+            target_id_to_selector,
+        ) = ProjectWorkspace::create_hashmaps(&bsp_packages);
 
-        Ok(ProjectWorkspace {
+        ProjectWorkspace {
             packages: bsp_packages,
-            target_id_to_package_name,
+            target_id_to_package_id,
             target_id_to_target_data,
             src_path_to_target_id,
-        })
+            target_id_to_selector,
+            target_directory,
+            feature_mode: FeatureMode::default(),
+            host: None,
+        }
     }
 
     // Cargo metadata is called with `--all-features`, so we can get all features because
@@ -76,38 +128,68 @@ impl ProjectWorkspace {
     pub fn call_cargo_metadata_command(
         project_manifest_path: &PathBuf,
         filter_platform: bool,
-    ) -> Result<cargo_metadata::Metadata, Error> {
-        let filter_platform_options = if filter_platform {
+        lock_flags: &[String],
+    ) -> Result<(cargo_metadata::Metadata, UnknownEditions), Error> {
+        let output = ProjectWorkspace::cargo_metadata_command(
+            project_manifest_path,
+            filter_platform,
+            lock_flags,
+        )
+        .cargo_command()
+        .output()?;
+        if !output.status.success() {
+            return Err(Error::CargoMetadata {
+                stderr: String::from_utf8(output.stderr)?,
+            });
+        }
+        let stdout = std::str::from_utf8(&output.stdout)?
+            .lines()
+            .find(|line| line.starts_with('{'))
+            .ok_or(Error::NoJson)?;
+        parse_metadata(stdout)
+    }
+
+    fn cargo_metadata_command(
+        project_manifest_path: &PathBuf,
+        filter_platform: bool,
+        lock_flags: &[String],
+    ) -> MetadataCommand {
+        let mut other_options = if filter_platform {
             version_meta().map_or(vec![], |v| vec!["--filter-platform".to_string(), v.host])
         } else {
             vec![]
         };
-        MetadataCommand::new()
-            .manifest_path(project_manifest_path)
+        other_options.extend(lock_flags.iter().cloned());
+        let mut cmd = MetadataCommand::new();
+        cmd.manifest_path(project_manifest_path)
             .features(CargoOpt::AllFeatures)
-            .other_options(filter_platform_options)
-            .exec()
+            .other_options(other_options);
+        cmd
     }
 
     fn create_hashmaps(
         bsp_packages: &[CargoPackage],
     ) -> (
-        TargetIdToPackageName,
+        TargetIdToPackageId,
         TargetIdToTargetData,
         SrcPathToTargetId,
+        TargetIdToSelector,
     ) {
         bsp_packages
             .iter()
             .flat_map(|p| {
                 p.targets.iter().map(|tr| {
-                    let target_id = build_target_id_from_name_and_path(&tr.name, &tr.src_path);
+                    let selector = TargetSelector::from_cargo_target(&p.manifest_path, tr);
+                    let target_id = selector.to_id();
                     (
-                        // BuildTargetIdentifier to package name map (key, value)
-                        (target_id.clone(), p.name.clone()),
+                        // BuildTargetIdentifier to package id map (key, value)
+                        (target_id.clone(), p.id.clone()),
                         // BuildTargetIdentifier to target_details map (key, value)
-                        (target_id.clone(), Rc::clone(tr)),
+                        (target_id.clone(), Arc::clone(tr)),
                         // Src path of a build target to its id (key, value)
-                        (tr.src_path.clone(), target_id),
+                        (tr.src_path.clone(), target_id.clone()),
+                        // BuildTargetIdentifier to the target selector (key, value)
+                        (target_id, selector),
                     )
                 })
             })
@@ -118,42 +200,86 @@ impl ProjectWorkspace {
         &self,
         target_id: &BuildTargetIdentifier,
     ) -> Option<&CargoPackage> {
-        let package_name = self.target_id_to_package_name.get(target_id).or_else(|| {
+        let package_id = self.target_id_to_package_id.get(target_id).or_else(|| {
             error!("Package not found for target: {:?}", target_id);
             None
         })?;
 
         self.packages
             .iter()
-            .find(|p| p.name == *package_name)
+            .find(|p| p.id == *package_id)
             .or_else(|| {
                 error!("Package not found for target: {:?}", target_id);
                 None
             })
     }
 
-    fn get_target_data(
-        &self,
-        target_id: &BuildTargetIdentifier,
-    ) -> Option<&Rc<cargo_metadata::Target>> {
-        self.target_id_to_target_data.get(target_id).or_else(|| {
-            error!("Target details not found for id: {:?}", target_id);
-            None
-        })
+    /// Returns a list of all BSP build targets in a workspace, sorted by their ids.
+    /// The build targets of the packages are created in parallel.
+    pub fn get_bsp_build_targets(&self) -> Vec<BuildTarget> {
+        let mut targets: Vec<BuildTarget> = self
+            .packages
+            .par_iter()
+            .flat_map_iter(|p| {
+                p.get_bsp_build_targets(&self.packages, &self.feature_mode, self.host.as_ref())
+            })
+            .collect();
+        targets.sort_by(|t1, t2| t1.id.cmp(&t2.id));
+        targets
     }
 
-    /// Returns a list of all BSP build targets in a workspace
-    pub fn get_bsp_build_targets(&self) -> Vec<BuildTarget> {
-        self.packages
-            .iter()
-            .flat_map(|p| p.get_bsp_build_targets())
+    /// Returns the BSP build targets of the workspace with any of the given kinds.
+    pub fn get_bsp_build_targets_of_kinds(&self, kinds: &[RustTargetKind]) -> Vec<BuildTarget> {
+        self.get_bsp_build_targets()
+            .into_iter()
+            .filter(|t| {
+                self.target_id_to_target_data
+                    .get(&t.id)
+                    .is_some_and(|data| {
+                        data.kind
+                            .iter()
+                            .any(|k| kinds.contains(&metadata_kind_to_rust_extension_kind(k)))
+                    })
+            })
             .collect()
     }
 
-    /// Returns target details for a given build target identifier
+    /// Returns the selector of the target precomputed at the load, the ids missing in the map
+    /// are parsed (see [`parse_target_id`]).
+    pub fn get_target_selector(&self, id: &BuildTargetIdentifier) -> Option<TargetSelector> {
+        self.target_id_to_selector
+            .get(id)
+            .cloned()
+            .or_else(|| parse_target_id(id))
+    }
+
+    /// Finds the workspace package and its target selected by the selector. The package
+    /// is found by its manifest directory, the target by its name and kind.
+    pub fn find_target(
+        &self,
+        selector: &TargetSelector,
+    ) -> Option<(&CargoPackage, &Arc<cargo_metadata::Target>)> {
+        let package = self
+            .packages
+            .iter()
+            .find(|p| p.manifest_path.parent() == Some(selector.manifest_dir.as_path()))?;
+        let target = package
+            .targets
+            .iter()
+            .find(|t| t.name == selector.name && t.kind.first() == Some(&selector.kind))?;
+        Some((package, target))
+    }
+
+    /// Returns target details for a given build target identifier, the target is selected
+    /// by its selector (see [`ProjectWorkspace::get_target_selector`]).
     pub fn get_target_details(&self, id: &BuildTargetIdentifier) -> Option<TargetDetails> {
-        let package = self.get_package_related_to_target(id)?;
-        let target_data = self.get_target_data(id)?;
+        let (package, target_data) = self
+            .get_target_selector(id)
+            .and_then(|selector| self.find_target(&selector))
+            .or_else(|| {
+                error!("Target details not found for id: {:?}", id);
+                None
+            })?;
         TargetDetails::new(package, target_data)
     }
 
@@ -166,6 +292,45 @@ impl ProjectWorkspace {
             .collect()
     }
 
+    /// Returns the workspace package with the given id
+    pub fn get_package(&self, package_id: &str) -> Option<&CargoPackage> {
+        self.packages.iter().find(|p| p.id == package_id)
+    }
+
+    /// Enables given features (together with the features they enable) for a given package.
+    pub fn enable_features_for_the_package(
+        &mut self,
+        package_id: &str,
+        features: &BTreeSet<Feature>,
+    ) -> Result<(), String> {
+        self.get_package_mut(package_id)?.enable_features(features)
+    }
+
+    /// Disables given features for a given package, unless other enabled features require them.
+    pub fn disable_features_for_the_package(
+        &mut self,
+        package_id: &str,
+        features: &BTreeSet<Feature>,
+    ) -> Result<(), String> {
+        self.get_package_mut(package_id)?.disable_features(features)
+    }
+
+    /// Sets the stored features state for the packages, which are still part of the workspace.
+    pub(crate) fn restore_enabled_features(&mut self, state: &EnabledFeaturesState) {
+        for package in &mut self.packages {
+            if let Some(features) = state.get(&package.id) {
+                package.set_features(features);
+            }
+        }
+    }
+
+    fn get_package_mut(&mut self, package_id: &str) -> Result<&mut CargoPackage, String> {
+        self.packages
+            .iter_mut()
+            .find(|p| p.id == package_id)
+            .ok_or_else(|| format!("Package not found for id: {:?}", package_id))
+    }
+
     /// Sets given set of features as a new features state for a given package.
     /// When default is not included in the set, default features are disabled
     pub fn set_features_for_the_package(
@@ -186,6 +351,63 @@ impl ProjectWorkspace {
         }
     }
 
+    /// Returns a map from the package id and target (as identified in Cargo messages)
+    /// to its BuildTargetIdentifier
+    pub fn get_package_target_to_target_id(&self) -> PackageTargetToTargetId {
+        self.packages
+            .iter()
+            .flat_map(|p| {
+                p.targets.iter().map(|t| {
+                    (
+                        (p.id.clone(), t.name.clone(), t.kind.clone()),
+                        build_target_id_from_cargo_target(&p.manifest_path, t),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Returns a map from the package id to its primary target - the library target or,
+    /// if the package doesn't have one, its first target other than the build script
+    pub fn get_package_to_primary_target_id(&self) -> PackageToPrimaryTargetId {
+        self.packages
+            .iter()
+            .filter_map(|p| {
+                let mut candidates = p
+                    .targets
+                    .iter()
+                    .filter(|t| !t.kind.iter().any(|k| k == "custom-build"));
+                let primary_target = candidates
+                    .clone()
+                    .find(|t| {
+                        t.kind
+                            .iter()
+                            .filter_map(|k| k.parse::<CargoTargetKind>().ok())
+                            .any(|k| k.is_lib())
+                    })
+                    .or_else(|| candidates.next())?;
+                Some((
+                    p.id.clone(),
+                    build_target_id_from_cargo_target(&p.manifest_path, primary_target),
+                ))
+            })
+            .collect()
+    }
+
+    /// Returns build targets of the workspace package matching the given
+    /// Cargo package ID specification
+    pub fn resolve_package_spec(
+        &self,
+        spec: &str,
+    ) -> Result<Vec<BuildTargetIdentifier>, PackageSpecError> {
+        let package = find_package_by_spec(&self.packages, spec)?;
+        Ok(package
+            .targets
+            .iter()
+            .map(|t| build_target_id_from_cargo_target(&package.manifest_path, t))
+            .collect())
+    }
+
     pub fn is_package_part_of_workspace(&self, package_id: &str) -> bool {
         self.packages.iter().any(|p| p.id == *package_id)
     }
@@ -200,3 +422,426 @@ impl ProjectWorkspace {
             .collect()
     }
 }
+
+/// Parses the output of `cargo metadata`. Editions unknown to `cargo_metadata` (e.g. from
+/// a newer Cargo) would fail the whole workspace load, so they are replaced with the
+/// newest known edition and returned separately, to be reported to the client unchanged.
+pub(crate) fn parse_metadata(
+    json: &str,
+) -> Result<(cargo_metadata::Metadata, UnknownEditions), Error> {
+    let mut metadata: serde_json::Value = serde_json::from_str(json)?;
+    let mut unknown_editions = UnknownEditions::default();
+    let packages = metadata
+        .get_mut("packages")
+        .and_then(|p| p.as_array_mut())
+        .into_iter()
+        .flatten();
+    for package in packages {
+        let package_id = package["id"].as_str().unwrap_or_default().to_string();
+        let package_name = package["name"].as_str().unwrap_or_default().to_string();
+        if let Some(edition) = replace_unknown_edition(package.get_mut("edition"), &package_name) {
+            unknown_editions
+                .packages
+                .insert(package_id.clone(), edition);
+        }
+        let targets = package
+            .get_mut("targets")
+            .and_then(|t| t.as_array_mut())
+            .into_iter()
+            .flatten();
+        for target in targets {
+            if let Some(edition) = replace_unknown_edition(target.get_mut("edition"), &package_name)
+            {
+                let name = target["name"].as_str().unwrap_or_default().to_string();
+                let kind = serde_json::from_value(target["kind"].clone()).unwrap_or_default();
+                unknown_editions
+                    .targets
+                    .insert((package_id.clone(), name, kind), edition);
+            }
+        }
+    }
+    Ok((serde_json::from_value(metadata)?, unknown_editions))
+}
+
+/// The edition is optional in the metadata, missing editions are left missing.
+/// Returns the replaced edition.
+fn replace_unknown_edition(
+    edition: Option<&mut serde_json::Value>,
+    package_name: &str,
+) -> Option<RustEdition> {
+    let edition = edition?;
+    if serde_json::from_value::<Edition>(edition.clone()).is_ok() {
+        return None;
+    }
+    warn!(
+        "Unknown edition {} of package {}, passing it to the client as is",
+        edition, package_name
+    );
+    let replaced = std::mem::replace(edition, serde_json::Value::from(RustEdition::E2024.0));
+    Some(RustEdition(replaced.as_str()?.to_string().into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project_model::metadata_edition_to_bsp_edition;
+    use bsp4rs::rust::FeatureDependencyGraph;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use test_case::test_case;
+
+    const APP_ID: &str = "app 0.1.0 (path+file:///test_root)";
+    const SERDE_ID: &str = "serde 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)";
+
+    fn test_metadata_package(
+        name: &str,
+        id: &str,
+        root: &str,
+        features: serde_json::Value,
+    ) -> serde_json::Value {
+        json!({
+            "name": name,
+            "version": "1.0.0",
+            "id": id,
+            "source": null,
+            "dependencies": [],
+            "targets": [{
+                "name": name,
+                "kind": ["lib"],
+                "src_path": format!("{}/src/lib.rs", root),
+            }],
+            "features": features,
+            "manifest_path": format!("{}/Cargo.toml", root),
+        })
+    }
+
+    /// `app` defines `default` feature enabling `f1`, which enables `f2`.
+    fn test_metadata() -> cargo_metadata::Metadata {
+        serde_json::from_value(json!({
+            "packages": [
+                test_metadata_package(
+                    "app",
+                    APP_ID,
+                    "/test_root",
+                    json!({"default": ["f1"], "f1": ["f2"], "f2": []}),
+                ),
+                test_metadata_package("serde", SERDE_ID, "/registry/serde-1.0.0", json!({})),
+            ],
+            "workspace_members": [APP_ID],
+            "resolve": null,
+            "workspace_root": "/test_root",
+            "target_directory": "/test_root/target",
+            "version": 1,
+        }))
+        .unwrap()
+    }
+
+    fn metadata_with_edition(edition: &str) -> String {
+        let mut package = test_metadata_package("app", APP_ID, "/test_root", json!({}));
+        package["edition"] = json!(edition);
+        package["targets"][0]["edition"] = json!(edition);
+        json!({
+            "packages": [package],
+            "workspace_members": [APP_ID],
+            "resolve": null,
+            "workspace_root": "/test_root",
+            "target_directory": "/test_root/target",
+            "version": 1,
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn metadata_with_edition_2024() {
+        let (metadata, _) = parse_metadata(&metadata_with_edition("2024")).unwrap();
+        let target = &metadata.packages[0].targets[0];
+
+        assert_eq!(
+            metadata_edition_to_bsp_edition(target.edition),
+            RustEdition::E2024
+        );
+    }
+
+    #[test]
+    fn metadata_with_unknown_edition() {
+        let (metadata, unknown_editions) = parse_metadata(&metadata_with_edition("2099")).unwrap();
+        let package = &metadata.packages[0];
+
+        assert_eq!(
+            unknown_editions.package_edition(package),
+            RustEdition::new("2099")
+        );
+        assert_eq!(
+            unknown_editions.target_edition(APP_ID, &package.targets[0]),
+            RustEdition::new("2099")
+        );
+        // The metadata itself is left without the unknown editions.
+        assert!(package.metadata.is_null());
+    }
+
+    #[test]
+    fn metadata_with_known_edition_has_no_unknown_editions() {
+        let (metadata, unknown_editions) = parse_metadata(&metadata_with_edition("2021")).unwrap();
+        let package = &metadata.packages[0];
+
+        assert_eq!(unknown_editions, UnknownEditions::default());
+        assert_eq!(
+            unknown_editions.package_edition(package),
+            RustEdition::E2021
+        );
+    }
+
+    #[test]
+    fn metadata_command_with_offline_flag() {
+        let cmd = ProjectWorkspace::cargo_metadata_command(
+            &PathBuf::from("/test_root/Cargo.toml"),
+            false,
+            &["--offline".to_string()],
+        )
+        .cargo_command();
+
+        assert!(cmd.get_args().any(|arg| arg == "--offline"));
+    }
+
+    #[test]
+    fn test_build_targets_filtered_by_kinds() {
+        let mut metadata = test_metadata();
+        let bin_target: cargo_metadata::Target = serde_json::from_value(json!({
+            "name": "app_bin",
+            "kind": ["bin"],
+            "src_path": "/test_root/src/main.rs",
+        }))
+        .unwrap();
+        metadata.packages[0].targets.push(bin_target);
+        let workspace = ProjectWorkspace::from_metadata(&metadata, &UnknownEditions::default());
+
+        let target_names = |kinds: &[RustTargetKind]| -> Vec<String> {
+            workspace
+                .get_bsp_build_targets_of_kinds(kinds)
+                .into_iter()
+                .filter_map(|t| t.display_name)
+                .collect()
+        };
+
+        assert_eq!(target_names(&[RustTargetKind::Bin]), vec!["app_bin"]);
+        assert_eq!(target_names(&[RustTargetKind::Lib]), vec!["app"]);
+        assert!(target_names(&[RustTargetKind::Test]).is_empty());
+    }
+
+    #[test]
+    fn build_targets_of_many_packages_sorted() {
+        const PACKAGES_COUNT: usize = 200;
+        let ids: Vec<String> = (0..PACKAGES_COUNT)
+            .map(|i| format!("crate{i} 1.0.0 (path+file:///test_root/crate{i})"))
+            .collect();
+        let mut packages: Vec<serde_json::Value> = (0..PACKAGES_COUNT)
+            .map(|i| {
+                test_metadata_package(
+                    &format!("crate{i}"),
+                    &ids[i],
+                    &format!("/test_root/crate{i}"),
+                    json!({}),
+                )
+            })
+            .collect();
+        // Every crate depends on the previous one.
+        for (i, package) in packages.iter_mut().enumerate().skip(1) {
+            package["dependencies"] = json!([{
+                "name": format!("crate{}", i - 1),
+                "source": null,
+                "req": "*",
+                "kind": null,
+                "optional": false,
+                "uses_default_features": true,
+                "features": [],
+                "target": null,
+                "path": format!("/test_root/crate{}", i - 1),
+            }]);
+        }
+        let metadata = serde_json::from_value(json!({
+            "packages": packages,
+            "workspace_members": ids,
+            "resolve": null,
+            "workspace_root": "/test_root",
+            "target_directory": "/test_root/target",
+            "version": 1,
+        }))
+        .unwrap();
+        let workspace = ProjectWorkspace::from_metadata(&metadata, &UnknownEditions::default());
+
+        let targets = workspace.get_bsp_build_targets();
+
+        assert_eq!(targets.len(), PACKAGES_COUNT);
+        assert!(targets.windows(2).all(|t| t[0].id < t[1].id));
+        assert_eq!(targets, workspace.get_bsp_build_targets());
+        let sequential_targets: BTreeSet<_> = workspace
+            .packages
+            .iter()
+            .flat_map(|p| {
+                p.get_bsp_build_targets(&workspace.packages, &workspace.feature_mode, None)
+            })
+            .map(|t| (t.id, t.dependencies))
+            .collect();
+        let parallel_targets: BTreeSet<_> = targets
+            .into_iter()
+            .map(|t| (t.id, t.dependencies))
+            .collect();
+        assert_eq!(parallel_targets, sequential_targets);
+        let crate1 = TargetSelector::new("/test_root/crate1", "crate1", "lib").to_id();
+        let crate0 = TargetSelector::new("/test_root/crate0", "crate0", "lib").to_id();
+        assert_eq!(
+            workspace
+                .get_bsp_build_targets()
+                .into_iter()
+                .find(|t| t.id == crate1)
+                .unwrap()
+                .dependencies,
+            vec![crate0]
+        );
+    }
+
+    #[test]
+    fn target_selectors_precomputed_at_load() {
+        let metadata = test_metadata();
+        let workspace = ProjectWorkspace::from_metadata(&metadata, &UnknownEditions::default());
+        let package = &metadata.packages[0];
+        let id = workspace.get_bsp_build_targets().remove(0).id;
+
+        let cached = workspace.target_id_to_selector.get(&id).unwrap();
+
+        assert_eq!(
+            cached,
+            &TargetSelector::from_cargo_target(&package.manifest_path, &package.targets[0])
+        );
+        assert_eq!(workspace.get_target_selector(&id).as_ref(), Some(cached));
+        assert_eq!(workspace.target_id_to_selector.len(), 1);
+    }
+
+    #[test]
+    fn target_details_resolved_through_selector() {
+        let mut workspace =
+            ProjectWorkspace::from_metadata(&test_metadata(), &UnknownEditions::default());
+        let id = TargetSelector::new("/test_root", "app", "lib").to_id();
+        // A cached selector pointing to other target is the one used.
+        let other_id = TargetSelector::new("/test_root", "other", "bin").to_id();
+        workspace.target_id_to_selector.insert(
+            other_id.clone(),
+            TargetSelector::new("/test_root", "app", "lib"),
+        );
+
+        let details = workspace.get_target_details(&other_id).unwrap();
+
+        assert_eq!(Some(details), workspace.get_target_details(&id));
+        assert_eq!(
+            workspace.get_target_details(&TargetSelector::new("/test_root", "app", "bin").to_id()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_registry_dependency_is_not_build_target() {
+        let workspace =
+            ProjectWorkspace::from_metadata(&test_metadata(), &UnknownEditions::default());
+
+        let target_names: Vec<_> = workspace
+            .get_bsp_build_targets()
+            .into_iter()
+            .filter_map(|t| t.display_name)
+            .collect();
+        assert_eq!(target_names, vec!["app"]);
+        assert!(!workspace.is_package_part_of_workspace(SERDE_ID));
+    }
+
+    #[test]
+    fn test_cargo_features_state() {
+        let workspace =
+            ProjectWorkspace::from_metadata(&test_metadata(), &UnknownEditions::default());
+
+        let features_state = workspace.get_cargo_features_state();
+
+        let feature_set = |features: &[&str]| -> BTreeSet<Feature> {
+            features.iter().map(|&f| Feature::from(f)).collect()
+        };
+        assert_eq!(
+            features_state,
+            vec![PackageFeatures {
+                package_id: APP_ID.to_string(),
+                targets: vec![TargetSelector::new("/test_root", "app", "lib").to_id()],
+                enabled_features: feature_set(&["default"]),
+                available_features: FeatureDependencyGraph::new(BTreeMap::from([
+                    (Feature::from("default"), feature_set(&["f1"])),
+                    (Feature::from("f1"), feature_set(&["f2"])),
+                    (Feature::from("f2"), feature_set(&[])),
+                ])),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_target_details_reflect_changed_features() {
+        let mut workspace =
+            ProjectWorkspace::from_metadata(&test_metadata(), &UnknownEditions::default());
+        let target_id = TargetSelector::new("/test_root", "app", "lib").to_id();
+        let f1 = BTreeSet::from([Feature::from("f1")]);
+
+        workspace
+            .disable_features_for_the_package(APP_ID, &BTreeSet::from([Feature::from("default")]))
+            .unwrap();
+        workspace
+            .enable_features_for_the_package(APP_ID, &f1)
+            .unwrap();
+        let disable_result = workspace
+            .disable_features_for_the_package(APP_ID, &BTreeSet::from([Feature::from("f2")]));
+
+        assert!(disable_result.is_err());
+        assert_eq!(
+            workspace
+                .get_target_details(&target_id)
+                .unwrap()
+                .enabled_features,
+            f1
+        );
+        assert!(workspace
+            .enable_features_for_the_package("unknown", &f1)
+            .is_err());
+    }
+
+    #[test_case(&[("app", "lib")], Some(("app", "lib")) ; "lib_only")]
+    #[test_case(&[("app", "bin")], Some(("app", "bin")) ; "bin_only")]
+    #[test_case(&[("app", "bin"), ("app", "lib")], Some(("app", "lib")) ; "lib_and_bin")]
+    #[test_case(&[("build-script-build", "custom-build"), ("app", "bin")],
+    Some(("app", "bin")) ; "build_script_and_bin")]
+    #[test_case(&[("build-script-build", "custom-build"), ("app", "lib")],
+    Some(("app", "lib")) ; "build_script_and_lib")]
+    #[test_case(&[("build-script-build", "custom-build")], None ; "build_script_only")]
+    fn test_package_primary_target(targets: &[(&str, &str)], expected: Option<(&str, &str)>) {
+        let mut package = test_metadata_package("app", APP_ID, "/test_root", json!({}));
+        package["targets"] = targets
+            .iter()
+            .map(|&(name, kind)| {
+                json!({
+                    "name": name,
+                    "kind": [kind],
+                    "src_path": format!("/test_root/src/{}.rs", name),
+                })
+            })
+            .collect();
+        let metadata = serde_json::from_value(json!({
+            "packages": [package],
+            "workspace_members": [APP_ID],
+            "resolve": null,
+            "workspace_root": "/test_root",
+            "target_directory": "/test_root/target",
+            "version": 1,
+        }))
+        .unwrap();
+        let workspace = ProjectWorkspace::from_metadata(&metadata, &UnknownEditions::default());
+
+        let primary_target_id = workspace.get_package_to_primary_target_id().remove(APP_ID);
+
+        assert_eq!(
+            primary_target_id,
+            expected.map(|(name, kind)| TargetSelector::new("/test_root", name, kind).to_id())
+        );
+    }
+}
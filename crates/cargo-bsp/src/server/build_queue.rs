@@ -0,0 +1,48 @@
+//! Queue of the Cargo requests waiting for the other ones to finish, when the number
+//! of concurrently running Cargo commands reaches the `maxConcurrentBuilds` from
+//! the [`ServerSettings`].
+//!
+//! [`ServerSettings`]: crate::server::config::ServerSettings
+
+use std::collections::VecDeque;
+
+use bsp_server::RequestId;
+
+use bsp4rs::bsp::{Identifier, TaskId};
+
+use crate::cargo_communication::request_handle::RequestHandle;
+use crate::server::global_state::GlobalState;
+use crate::server::ServerError;
+
+/// Spawns the actor of the request, called once the request leaves the queue.
+pub(crate) type SpawnHandle =
+    Box<dyn FnOnce(&mut GlobalState) -> Result<RequestHandle, ServerError>>;
+
+pub(crate) struct QueuedRequest {
+    pub(crate) id: RequestId,
+    /// Task reported to the client while the request is waiting in the queue.
+    pub(crate) task_id: TaskId,
+    pub(crate) origin_id: Option<Identifier>,
+    pub(crate) spawn: SpawnHandle,
+}
+
+#[derive(Default)]
+pub(crate) struct BuildQueue {
+    requests: VecDeque<QueuedRequest>,
+}
+
+impl BuildQueue {
+    pub(crate) fn push(&mut self, request: QueuedRequest) {
+        self.requests.push_back(request);
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<QueuedRequest> {
+        self.requests.pop_front()
+    }
+
+    /// Removes the request (e.g. when it is cancelled), so it is never spawned.
+    pub(crate) fn remove(&mut self, id: &RequestId) -> Option<QueuedRequest> {
+        let position = self.requests.iter().position(|r| &r.id == id)?;
+        self.requests.remove(position)
+    }
+}
@@ -1,8 +1,12 @@
 //! Sets the server capabilities for the client.
 
-use bsp4rs::bsp::{BuildServerCapabilities, CompileProvider, RunProvider, TestProvider};
+use bsp4rs::bsp::{
+    BuildServerCapabilities, CompileProvider, RunProvider, TestProvider, WorkspaceReload,
+};
+use bsp4rs::Request;
 
 use crate::server::config::Config;
+use crate::server::main_loop::is_request_handled;
 
 pub fn server_capabilities(config: &Config) -> BuildServerCapabilities {
     BuildServerCapabilities {
@@ -18,14 +22,32 @@ pub fn server_capabilities(config: &Config) -> BuildServerCapabilities {
         // This server does support BSP Cargo extension, but the client does not yet expect this field to appear.
         cargo_features_provider: None,
         debug_provider: None,
-        inverse_sources_provider: Some(false),
+        inverse_sources_provider: Some(true),
         dependency_sources_provider: Some(false),
         dependency_modules_provider: Some(false),
         resources_provider: Some(false),
-        output_paths_provider: Some(false),
-        build_target_changed_provider: Some(false),
+        output_paths_provider: Some(true),
+        build_target_changed_provider: Some(true),
         jvm_run_environment_provider: Some(false),
         jvm_test_environment_provider: Some(false),
-        can_reload: Some(true),
+        can_reload: Some(is_request_handled(WorkspaceReload::METHOD)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bsp4rs::bsp::BuildClientCapabilities;
+    use std::path::PathBuf;
+
+    #[test]
+    fn can_reload_if_reload_request_handled() {
+        let config = Config::new(PathBuf::from("test"), BuildClientCapabilities::default());
+
+        let capabilities = server_capabilities(&config);
+
+        assert!(is_request_handled(WorkspaceReload::METHOD));
+        assert_eq!(capabilities.can_reload, Some(true));
+        assert!(!is_request_handled("workspace/unknownRequest"));
     }
 }
@@ -1,21 +1,139 @@
 //! Project's configuration, can be changed upon reload request.
 
 use std::env;
+use std::io;
 use std::path::PathBuf;
+use std::time::Duration;
 
-use log::error;
+use log::{error, warn};
+use serde::{Deserialize, Deserializer};
 use url::Url;
 
-use bsp4rs::bsp::{BuildClientCapabilities, InitializeBuildParams};
+use bsp4rs::bsp::{BuildClientCapabilities, InitializeBuildParams, InitializeBuildParamsData};
+use bsp4rs::cargo::FeatureMode;
 
 use crate::project_model::project_manifest::ProjectManifest;
 use crate::server::Result;
 
+/// Data kind of the `build/initialize` request data containing the [`ServerSettings`].
+pub const SERVER_SETTINGS_DATA_KIND: &str = "cargo-bsp";
+
+/// Settings of the server, which can be passed by the client in the data
+/// of the `build/initialize` request (with [`SERVER_SETTINGS_DATA_KIND`] data kind).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ServerSettings {
+    /// The standard library is built from sources (`-Zbuild-std`), so its sources
+    /// are resolved from the sysroot used by the build (which may be a custom one).
+    pub build_std: bool,
+    /// Pass `--keep-going` to the build commands, so that all the errors are collected
+    /// instead of stopping at the first failing crate.
+    pub keep_going: bool,
+    /// Cargo command is cancelled if it produces no output and does not finish
+    /// within this time (passed by the client in seconds). Applies only to the compilation,
+    /// not to the execution of the binaries and tests.
+    #[serde(rename = "commandTimeoutSecs", deserialize_with = "deserialize_secs")]
+    pub command_timeout: Option<Duration>,
+    /// Flags passed to rustdoc when running doctests, appended to the `build.rustdocflags`
+    /// from the Cargo configuration.
+    pub rustdoc_flags: Vec<String>,
+    /// Publish the diagnostics as soon as they are reported by the compiler, instead
+    /// of sending them in a single notification per file.
+    pub stream_diagnostics: bool,
+    /// Cargo reports the diagnostics rendered with the ANSI colors
+    /// (`--message-format=json-diagnostic-rendered-ansi`), the rendered diagnostic
+    /// is attached to the data of the published diagnostic.
+    pub ansi_diagnostics: bool,
+    /// Cargo profile passed to the build, run and test commands (`--profile`), its output
+    /// directory is reported in `buildTarget/outputPaths`. Cargo's `dev` profile by default.
+    pub profile: Option<String>,
+    /// Responses larger than this (in bytes of the serialized JSON) are replaced with
+    /// the [`RESPONSE_TOO_LARGE`] error, so the client can request less data at once.
+    /// Larger notifications are not sent, the user is warned about them instead.
+    ///
+    /// [`RESPONSE_TOO_LARGE`]: crate::server::RESPONSE_TOO_LARGE
+    pub max_message_size: Option<usize>,
+    /// Maximal number of the Cargo commands (compile, run, test and check requests)
+    /// running at the same time, further requests wait in a queue. There is no limit
+    /// if not set or set to 0.
+    #[serde(deserialize_with = "deserialize_limit")]
+    pub max_concurrent_builds: Option<usize>,
+    /// Mode of enabling the features in the Cargo commands, can be changed with
+    /// the `workspace/setCargoFeatureMode` request.
+    pub feature_mode: FeatureMode,
+    /// Time to wait for the running requests to respond after they are cancelled
+    /// by the `build/shutdown` request (passed by the client in seconds).
+    #[serde(
+        rename = "shutdownGracePeriodSecs",
+        deserialize_with = "deserialize_secs"
+    )]
+    pub shutdown_grace_period: Option<Duration>,
+    /// Level of the server's logs (`error`, `warn`, `info`, `debug` or `trace`),
+    /// overrides the level from the environment variable.
+    pub log_level: Option<String>,
+    /// Mirror the logged warnings and errors to the client as the log messages.
+    pub log_to_client: bool,
+    /// Pass `--locked` to the Cargo commands, so that `Cargo.lock` is never updated.
+    pub locked: bool,
+    /// Pass `--offline` to the Cargo commands, so that the network is never accessed.
+    pub offline: bool,
+    /// Pass `--frozen` to the Cargo commands (both `--locked` and `--offline`).
+    pub frozen: bool,
+    /// The run and test requests report no compilation work if all the artifacts are fresh
+    /// (e.g. after the compile request of the same targets): the fresh artifacts are not
+    /// reported as the compilation steps and the compilation is reported as skipped.
+    pub skip_fresh_compile: bool,
+}
+
+fn deserialize_secs<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_secs))
+}
+
+/// The limit of 0 would never be satisfied, so it means no limit.
+fn deserialize_limit<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<usize>, D::Error> {
+    Ok(Option::<usize>::deserialize(deserializer)?.filter(|limit| *limit > 0))
+}
+
+impl ServerSettings {
+    /// Flags of the lock file and network access, passed to every Cargo command
+    /// (including `cargo metadata`).
+    pub(crate) fn lock_flags(&self) -> Vec<String> {
+        [
+            (self.locked, "--locked"),
+            (self.offline, "--offline"),
+            (self.frozen, "--frozen"),
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, flag)| flag.to_string())
+        .collect()
+    }
+
+    fn from_initialize_data(data: Option<InitializeBuildParamsData>) -> Self {
+        match data {
+            Some(InitializeBuildParamsData::Other(other))
+                if other.data_kind == SERVER_SETTINGS_DATA_KIND =>
+            {
+                serde_json::from_value(other.data).unwrap_or_else(|e| {
+                    warn!("Invalid server settings, using the default ones: {}", e);
+                    ServerSettings::default()
+                })
+            }
+            _ => ServerSettings::default(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     // we assume project has only one workspace, therefore one root Cargo.toml - ProjectManifest
     pub workspace_manifest: ProjectManifest,
     pub caps: BuildClientCapabilities,
+    pub settings: ServerSettings,
     root_path: PathBuf,
 }
 
@@ -24,9 +142,13 @@ impl Config {
         let mut this = Config {
             workspace_manifest: ProjectManifest::default(),
             caps,
+            settings: ServerSettings::default(),
             root_path,
         };
-        this.update_project_manifest();
+        // The missing manifest is reported to the client when the workspace is loaded.
+        if let Err(e) = this.update_project_manifest() {
+            error!("{}", e);
+        }
         this
     }
 
@@ -34,16 +156,17 @@ impl Config {
         &self.root_path
     }
 
-    pub fn update_project_manifest(&mut self) {
-        match ProjectManifest::discover(&self.root_path) {
-            Ok(workspace_manifest) => {
-                self.workspace_manifest = workspace_manifest;
-            }
-            Err(e) => {
-                error!("No Cargo.toml found: {}", e);
-                todo!("Add Logging to client and change server state to waiting for reload");
-            }
-        }
+    /// Discovers the manifest of the workspace again, the previous manifest is kept
+    /// if none is found.
+    pub fn update_project_manifest(&mut self) -> io::Result<()> {
+        let workspace_manifest = ProjectManifest::discover(&self.root_path).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} in {}", e, self.root_path.display()),
+            )
+        })?;
+        self.workspace_manifest = workspace_manifest;
+        Ok(())
     }
 
     pub(crate) fn from_initialize_params(
@@ -54,6 +177,93 @@ impl Config {
             .and_then(|it| it.to_file_path().ok())
             .unwrap_or(env::current_dir()?);
 
-        Ok(Config::new(root_path, initialize_params.capabilities))
+        let mut config = Config::new(root_path, initialize_params.capabilities);
+        config.settings = ServerSettings::from_initialize_data(initialize_params.data);
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bsp4rs::OtherData;
+
+    #[test]
+    fn server_settings_from_initialize_data() {
+        let data = InitializeBuildParamsData::Other(OtherData {
+            data_kind: SERVER_SETTINGS_DATA_KIND.to_string(),
+            data: serde_json::json!({
+                "buildStd": true,
+                "keepGoing": true,
+                "commandTimeoutSecs": 60,
+                "rustdocFlags": ["--cfg", "doctest"],
+                "streamDiagnostics": true,
+                "ansiDiagnostics": true,
+                "profile": "myprofile",
+                "maxMessageSize": 1024,
+                "maxConcurrentBuilds": 2,
+                "featureMode": "noDefault",
+                "shutdownGracePeriodSecs": 10,
+                "logLevel": "debug",
+                "logToClient": true,
+                "locked": true,
+                "offline": true,
+                "frozen": true,
+                "skipFreshCompile": true
+            }),
+        });
+
+        assert_eq!(
+            ServerSettings::from_initialize_data(Some(data)),
+            ServerSettings {
+                build_std: true,
+                keep_going: true,
+                command_timeout: Some(Duration::from_secs(60)),
+                rustdoc_flags: vec!["--cfg".to_string(), "doctest".to_string()],
+                stream_diagnostics: true,
+                ansi_diagnostics: true,
+                profile: Some("myprofile".to_string()),
+                max_message_size: Some(1024),
+                max_concurrent_builds: Some(2),
+                feature_mode: FeatureMode::NoDefault,
+                shutdown_grace_period: Some(Duration::from_secs(10)),
+                log_level: Some("debug".to_string()),
+                log_to_client: true,
+                locked: true,
+                offline: true,
+                frozen: true,
+                skip_fresh_compile: true,
+            }
+        );
+    }
+
+    #[test]
+    fn server_settings_with_no_concurrent_builds_limit() {
+        let data = InitializeBuildParamsData::Other(OtherData {
+            data_kind: SERVER_SETTINGS_DATA_KIND.to_string(),
+            data: serde_json::json!({"maxConcurrentBuilds": 0}),
+        });
+
+        assert_eq!(
+            ServerSettings::from_initialize_data(Some(data)).max_concurrent_builds,
+            None
+        );
+    }
+
+    #[test]
+    fn server_settings_with_other_data_kind() {
+        let data = InitializeBuildParamsData::Other(OtherData {
+            data_kind: "other".to_string(),
+            data: serde_json::json!({"buildStd": true}),
+        });
+
+        assert_eq!(
+            ServerSettings::from_initialize_data(Some(data)),
+            ServerSettings::default()
+        );
+        assert_eq!(
+            ServerSettings::from_initialize_data(None),
+            ServerSettings::default()
+        );
     }
 }
@@ -1,9 +1,9 @@
 //! Handles raw JSON notifications and routes raw JSON requests from the client.
 
-use std::{fmt, io, panic};
+use std::{fmt, panic};
 
-use bsp_server::{ErrorCode, ExtractError, Notification, Request, RequestId, Response};
-use log::warn;
+use bsp_server::{ErrorCode, ExtractError, Message, Notification, Request, RequestId, Response};
+use log::{debug, warn};
 use serde::{de::DeserializeOwned, Serialize};
 
 use bsp4rs;
@@ -15,14 +15,26 @@ use crate::cargo_communication::execution::execution_types::create_unit_graph_co
 use crate::cargo_communication::execution::execution_types::origin_id::WithOriginId;
 use crate::cargo_communication::request_handle::RequestHandle;
 use crate::server::global_state::{GlobalState, GlobalStateSnapshot};
-use crate::server::{from_json, LspError, Result};
+use crate::server::{from_json, Result, ServerError};
+
+/// Spawns the [`RequestHandle`] for the request with the given params, see
+/// [`RequestRouter::on_cargo_output_run`].
+pub(crate) type SpawnOutputHandle<P> = fn(
+    Box<dyn Fn(Message) + Send>,
+    RequestId,
+    P,
+    &mut GlobalState,
+) -> Result<RequestHandle, ServerError>;
 
 pub(crate) struct RequestDispatcher<'a> {
     pub(crate) req: Option<Request>,
     pub(crate) global_state: &'a mut GlobalState,
 }
 
-/// A visitor for routing a raw JSON request to an appropriate handler function.
+/// A visitor for routing a raw JSON request to an appropriate handler function. The routing
+/// is written once (see `route_requests` in the main loop) for all the implementations,
+/// so the methods reported by [`RoutedMethods`] are the methods dispatched by
+/// [`RequestDispatcher`].
 ///
 /// Most requests are read-only and are immediately handled on the main loop
 /// thread (`on_sync` method). These are typically requests asking for
@@ -32,12 +44,67 @@ pub(crate) struct RequestDispatcher<'a> {
 /// (`on_cargo_run` method). These are the compile, run and test requests.
 ///
 /// Some requests modify the state (`on_sync_mut` method).
-impl<'a> RequestDispatcher<'a> {
+///
+/// Some requests spawn a `cargo check` (`on_cargo_check_run` method) or a Cargo command,
+/// whose whole output makes up the response, such as a build collecting its future
+/// incompatibility report or a `cargo fmt --check` (`on_cargo_output_run` method).
+pub(crate) trait RequestRouter {
     /// Dispatches the request onto the current thread, given full access to
     /// mutable global state.
-    pub(crate) fn on_sync_mut<R>(
+    fn on_sync_mut<R>(
+        &mut self,
+        f: fn(&mut GlobalState, R::Params) -> Result<R::Result, ServerError>,
+    ) -> &mut Self
+    where
+        R: bsp4rs::Request,
+        R::Params: DeserializeOwned + panic::UnwindSafe + fmt::Debug,
+        R::Result: Serialize;
+
+    /// Dispatches the request onto the current thread.
+    fn on_sync<R>(
+        &mut self,
+        f: fn(GlobalStateSnapshot, R::Params) -> Result<R::Result, ServerError>,
+    ) -> &mut Self
+    where
+        R: bsp4rs::Request,
+        R::Params: DeserializeOwned + panic::UnwindSafe + fmt::Debug,
+        R::Result: Serialize;
+
+    /// Dispatches a new [`RequestHandle`].
+    fn on_cargo_run<R>(&mut self) -> &mut Self
+    where
+        R: bsp4rs::Request + 'static,
+        R::Params: CreateUnitGraphCommand
+            + CreateCommand
+            + ParamsTarget
+            + WithOriginId
+            + Send
+            + fmt::Debug,
+        R::Result: Serialize + CargoResult;
+
+    fn on_cargo_check_run<R>(&mut self) -> &mut Self
+    where
+        R: bsp4rs::Request + 'static,
+        R::Params: CreateCommand + ParamsTarget + Send + fmt::Debug,
+        R::Result: Serialize;
+
+    /// Dispatches a new [`RequestHandle`] created by `spawn`, which runs the Cargo command
+    /// responding after the whole output of the command is collected, such as the future
+    /// incompatibility report or the format check, or runs the Cargo commands itself,
+    /// such as the clean cache.
+    fn on_cargo_output_run<R>(&mut self, spawn: SpawnOutputHandle<R::Params>) -> &mut Self
+    where
+        R: bsp4rs::Request + 'static,
+        R::Params: WithOriginId + Send + fmt::Debug;
+
+    /// Handles the request not routed to any handler.
+    fn finish(&mut self);
+}
+
+impl RequestRouter for RequestDispatcher<'_> {
+    fn on_sync_mut<R>(
         &mut self,
-        f: fn(&mut GlobalState, R::Params) -> Result<R::Result>,
+        f: fn(&mut GlobalState, R::Params) -> Result<R::Result, ServerError>,
     ) -> &mut Self
     where
         R: bsp4rs::Request,
@@ -49,17 +116,15 @@ impl<'a> RequestDispatcher<'a> {
             None => return self,
         };
         let result = { f(self.global_state, params) };
-        if let Ok(response) = result_to_response::<R>(req.id, result) {
-            self.global_state.respond(response);
-        }
+        let response = result_to_response::<R>(req.id, result);
+        self.global_state.respond(response);
 
         self
     }
 
-    /// Dispatches the request onto the current thread.
-    pub(crate) fn on_sync<R>(
+    fn on_sync<R>(
         &mut self,
-        f: fn(GlobalStateSnapshot, R::Params) -> Result<R::Result>,
+        f: fn(GlobalStateSnapshot, R::Params) -> Result<R::Result, ServerError>,
     ) -> &mut Self
     where
         R: bsp4rs::Request,
@@ -74,15 +139,13 @@ impl<'a> RequestDispatcher<'a> {
 
         let result = { f(global_state_snapshot, params) };
 
-        if let Ok(response) = result_to_response::<R>(req.id, result) {
-            self.global_state.respond(response);
-        }
+        let response = result_to_response::<R>(req.id, result);
+        self.global_state.respond(response);
 
         self
     }
 
-    /// Dispatches a new [`RequestHandle`].
-    pub(crate) fn on_cargo_run<R>(&mut self) -> &mut Self
+    fn on_cargo_run<R>(&mut self) -> &mut Self
     where
         R: bsp4rs::Request + 'static,
         R::Params: CreateUnitGraphCommand
@@ -97,18 +160,29 @@ impl<'a> RequestDispatcher<'a> {
             Some(it) => it,
             None => return self,
         };
-        let sender_to_main = self.global_state.handlers_sender.clone();
-        let global_state_snapshot = self.global_state.snapshot();
-        let request_handle = RequestHandle::spawn::<R>(
-            Box::new(move |msg| sender_to_main.send(msg).unwrap()),
-            req.id.clone(),
-            params,
-            global_state_snapshot,
+        if let Err(e) = params.validate_arguments() {
+            self.global_state.respond(e.into_response(req.id));
+            return self;
+        }
+        let origin_id = params.origin_id();
+        let req_id = req.id.clone();
+        self.global_state.spawn_or_queue(
+            req.id,
+            origin_id,
+            Box::new(move |global_state: &mut GlobalState| {
+                let sender_to_main = global_state.handlers_sender.clone();
+                RequestHandle::spawn::<R>(
+                    Box::new(move |msg| sender_to_main.send(msg).unwrap()),
+                    req_id,
+                    params,
+                    global_state.snapshot(),
+                )
+            }),
         );
-        self.update_handlers(request_handle, req)
+        self
     }
 
-    pub(crate) fn on_cargo_check_run<R>(&mut self) -> &mut Self
+    fn on_cargo_check_run<R>(&mut self) -> &mut Self
     where
         R: bsp4rs::Request + 'static,
         R::Params: CreateCommand + ParamsTarget + Send + fmt::Debug,
@@ -118,28 +192,72 @@ impl<'a> RequestDispatcher<'a> {
             Some(it) => it,
             None => return self,
         };
-        let sender_to_main = self.global_state.handlers_sender.clone();
-        let request_handle = RequestHandle::spawn_check::<R>(
-            Box::new(move |msg| sender_to_main.send(msg).unwrap()),
-            req.id.clone(),
-            params,
-            self.global_state,
+        if let Err(e) = params.validate_arguments() {
+            self.global_state.respond(e.into_response(req.id));
+            return self;
+        }
+        let req_id = req.id.clone();
+        self.global_state.spawn_or_queue(
+            req.id,
+            None,
+            Box::new(move |global_state: &mut GlobalState| {
+                let sender_to_main = global_state.handlers_sender.clone();
+                RequestHandle::spawn_check::<R>(
+                    Box::new(move |msg| sender_to_main.send(msg).unwrap()),
+                    req_id,
+                    params,
+                    global_state,
+                )
+            }),
         );
-        self.update_handlers(request_handle, req)
+        self
     }
 
-    pub(crate) fn finish(&mut self) {
+    fn on_cargo_output_run<R>(&mut self, spawn: SpawnOutputHandle<R::Params>) -> &mut Self
+    where
+        R: bsp4rs::Request + 'static,
+        R::Params: WithOriginId + Send + fmt::Debug,
+    {
+        let (req, params, _) = match self.parse::<R>() {
+            Some(it) => it,
+            None => return self,
+        };
+        let origin_id = params.origin_id();
+        let req_id = req.id.clone();
+        self.global_state.spawn_or_queue(
+            req.id,
+            origin_id,
+            Box::new(move |global_state: &mut GlobalState| {
+                let sender_to_main = global_state.handlers_sender.clone();
+                spawn(
+                    Box::new(move |msg| sender_to_main.send(msg).unwrap()),
+                    req_id,
+                    params,
+                    global_state,
+                )
+            }),
+        );
+        self
+    }
+
+    /// Responds with the `MethodNotFound` error to the request not routed to any handler.
+    /// Each unknown method is logged only once, as the client is likely to send it again.
+    fn finish(&mut self) {
         if let Some(req) = self.req.take() {
-            warn!("unknown request: {:?}", req);
+            if self.global_state.unknown_methods.insert(req.method.clone()) {
+                warn!("unknown request: {:?}", req);
+            }
             let response = Response::new_err(
                 req.id,
                 ErrorCode::MethodNotFound as i32,
-                "unknown request".to_string(),
+                format!("unknown request: {}", req.method),
             );
             self.global_state.respond(response);
         }
     }
+}
 
+impl RequestDispatcher<'_> {
     fn parse<R>(&mut self) -> Option<(Request, R::Params, String)>
     where
         R: bsp4rs::Request,
@@ -157,48 +275,79 @@ impl<'a> RequestDispatcher<'a> {
                 Some((req, params, panic_context))
             }
             Err(err) => {
-                let response =
-                    Response::new_err(req.id, ErrorCode::InvalidParams as i32, err.to_string());
+                let response = ServerError::InvalidParams(err.to_string()).into_response(req.id);
                 self.global_state.respond(response);
                 None
             }
         }
     }
-
-    fn update_handlers(
-        &mut self,
-        request_handle: io::Result<RequestHandle>,
-        req: Request,
-    ) -> &mut Self {
-        match request_handle {
-            Ok(request_handle) => {
-                self.global_state.handlers.insert(req.id, request_handle);
-            }
-            Err(e) => {
-                let response =
-                    Response::new_err(req.id, ErrorCode::InternalError as i32, e.to_string());
-                self.global_state.respond(response);
-            }
-        }
-
-        self
-    }
 }
 
-fn result_to_response<R>(id: RequestId, result: Result<R::Result>) -> Result<Response>
+fn result_to_response<R>(id: RequestId, result: Result<R::Result, ServerError>) -> Response
 where
     R: bsp4rs::Request,
     R::Params: DeserializeOwned,
     R::Result: Serialize,
 {
-    let res = match result {
+    match result {
         Ok(resp) => Response::new_ok(id, &resp),
-        Err(e) => match e.downcast::<LspError>() {
-            Ok(lsp_error) => Response::new_err(id, lsp_error.code, lsp_error.message),
-            Err(e) => Response::new_err(id, ErrorCode::InternalError as i32, e.to_string()),
-        },
-    };
-    Ok(res)
+        Err(e) => e.into_response(id),
+    }
+}
+
+/// Collects the methods of the routed requests instead of handling them, used to advertise
+/// the capabilities tied to a request only if it is routed.
+#[derive(Default)]
+pub(crate) struct RoutedMethods(pub(crate) Vec<&'static str>);
+
+impl RequestRouter for RoutedMethods {
+    fn on_sync_mut<R>(
+        &mut self,
+        _: fn(&mut GlobalState, R::Params) -> Result<R::Result, ServerError>,
+    ) -> &mut Self
+    where
+        R: bsp4rs::Request,
+    {
+        self.0.push(R::METHOD);
+        self
+    }
+
+    fn on_sync<R>(
+        &mut self,
+        _: fn(GlobalStateSnapshot, R::Params) -> Result<R::Result, ServerError>,
+    ) -> &mut Self
+    where
+        R: bsp4rs::Request,
+    {
+        self.0.push(R::METHOD);
+        self
+    }
+
+    fn on_cargo_run<R>(&mut self) -> &mut Self
+    where
+        R: bsp4rs::Request + 'static,
+    {
+        self.0.push(R::METHOD);
+        self
+    }
+
+    fn on_cargo_check_run<R>(&mut self) -> &mut Self
+    where
+        R: bsp4rs::Request + 'static,
+    {
+        self.0.push(R::METHOD);
+        self
+    }
+
+    fn on_cargo_output_run<R>(&mut self, _: SpawnOutputHandle<R::Params>) -> &mut Self
+    where
+        R: bsp4rs::Request + 'static,
+    {
+        self.0.push(R::METHOD);
+        self
+    }
+
+    fn finish(&mut self) {}
 }
 
 /// Handles a raw JSON notification.
@@ -234,11 +383,10 @@ impl<'a> NotificationDispatcher<'a> {
         Ok(self)
     }
 
+    /// Notifications not routed to any handler are ignored, as they expect no response.
     pub(crate) fn finish(&mut self) {
         if let Some(not) = &self.not {
-            if !not.method.starts_with("$/") {
-                warn!("unhandled notification: {:?}", not);
-            }
+            debug!("unhandled notification: {:?}", not);
         }
     }
 }
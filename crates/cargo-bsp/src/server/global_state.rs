@@ -1,16 +1,47 @@
 //! The context or environment in which the server functions.
 
-use std::collections::HashMap;
-use std::time::Instant;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use bsp_server;
 use bsp_server::{Message, Notification, Request, RequestId, Response};
+use cargo_metadata::camino::Utf8PathBuf;
 use crossbeam_channel::{unbounded, Receiver, Sender};
-use log::{error, info};
+use log::{error, info, warn};
+use serde::Serialize;
 
+use bsp4rs::bsp::{
+    BuildTarget, BuildTargetIdentifier, DidChangeBuildTarget, Identifier, MessageType,
+    OnBuildShowMessage, OnBuildTargetDidChange, OnBuildTaskFinish, OnBuildTaskStart,
+    ShowMessageParams, StatusCode, TaskFinishParams, TaskId, TaskStartParams,
+};
+use bsp4rs::rust::RustToolchainResult;
+use bsp4rs::Notification as _;
+
+use crate::cargo_communication::cargo_types::event::CancelReason;
+use crate::cargo_communication::execution::utils::{generate_random_id, now_millis};
 use crate::cargo_communication::request_handle::RequestHandle;
+use crate::project_model::build_target_diff::diff_build_targets;
+use crate::project_model::enabled_features_state::{
+    load_enabled_features, load_feature_mode, save_enabled_features,
+};
+use crate::project_model::host_platform::HostPlatform;
+use crate::project_model::metadata_cache::MetadataCache;
 use crate::project_model::workspace::ProjectWorkspace;
+use crate::server::build_queue::{BuildQueue, QueuedRequest, SpawnHandle};
 use crate::server::config::Config;
+use crate::server::manifest_watcher::ManifestWatcher;
+use crate::server::{ServerError, RESPONSE_TOO_LARGE};
+
+/// Directory (in the target directory) with the files stored by the server.
+pub(crate) const SERVER_STATE_DIR_NAME: &str = ".bsp";
+
+/// Used if the client doesn't set the shutdown grace period in the server settings.
+const DEFAULT_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(5);
 
 pub(crate) type ReqHandler = fn(&mut GlobalState, Response);
 pub(crate) type ReqQueue = bsp_server::ReqQueue<(String, Instant), ReqHandler>;
@@ -25,10 +56,31 @@ pub(crate) struct GlobalState {
     pub(crate) config: Config,
 
     pub(crate) handlers: HashMap<RequestId, RequestHandle>,
+    build_queue: BuildQueue,
     pub(crate) handlers_sender: Sender<Message>,
     pub(crate) handlers_receiver: Receiver<Message>,
 
     pub(crate) workspace: ProjectWorkspace,
+    pub(crate) metadata_cache: MetadataCache,
+    pub(crate) manifest_watcher: Option<ManifestWatcher>,
+    /// Toolchain used in the project, computed on the first `rust/toolchain` request.
+    pub(crate) toolchain_info: Option<RustToolchainResult>,
+    /// Reported in the response to the `build/ping` request.
+    pub(crate) start_time: Instant,
+    /// Method of the last handled request, except for the `build/ping` requests.
+    pub(crate) last_handled_method: Option<String>,
+    /// Methods of the received requests that are not handled by the server.
+    pub(crate) unknown_methods: HashSet<String>,
+    /// Error of the last workspace load (including the stderr of `cargo metadata`), if it
+    /// failed. The workspace is empty then, so the requests for it respond with the error.
+    pub(crate) workspace_load_error: Option<Arc<cargo_metadata::Error>>,
+    /// Files with the formatting diagnostics published by the `rust/formatCheck` requests,
+    /// cleared by the next check once the file is formatted.
+    pub(crate) published_format_diagnostics:
+        Arc<Mutex<BTreeMap<Utf8PathBuf, BuildTargetIdentifier>>>,
+    /// Buffer the messages are serialized into to check their size against the
+    /// `maxMessageSize`, reused so that checking a message doesn't allocate.
+    message_buffer: Vec<u8>,
 }
 
 /// Snapshot of server state for request handlers.
@@ -40,29 +92,80 @@ pub(crate) struct GlobalState {
 pub(crate) struct GlobalStateSnapshot<'a> {
     pub(crate) config: &'a Config,
     pub(crate) workspace: &'a ProjectWorkspace,
+    pub(crate) workspace_load_error: Option<&'a Arc<cargo_metadata::Error>>,
 }
 
 impl GlobalState {
     pub(crate) fn new(sender: Sender<Message>, config: Config) -> GlobalState {
         let (handlers_sender, handlers_receiver) = unbounded();
+        let metadata_cache = MetadataCache::new(config.settings.lock_flags());
         let mut this = GlobalState {
             sender,
             req_queue: ReqQueue::default(),
             shutdown_requested: false,
             config,
             handlers: HashMap::new(),
+            build_queue: BuildQueue::default(),
             handlers_sender,
             handlers_receiver,
             workspace: ProjectWorkspace::default(),
+            metadata_cache,
+            manifest_watcher: ManifestWatcher::new()
+                .map_err(|e| error!("Failed to start watching manifests: {}", e))
+                .ok(),
+            toolchain_info: None,
+            start_time: Instant::now(),
+            last_handled_method: None,
+            unknown_methods: HashSet::new(),
+            workspace_load_error: None,
+            published_format_diagnostics: Arc::default(),
+            message_buffer: Vec::new(),
         };
         this.update_workspace_data();
         this
     }
 
+    /// Notifications exceeding the maximal message size are dropped, the user is warned
+    /// about it with a message, which is not checked against the maximal size itself.
     pub(crate) fn send_notification(&mut self, not: Notification) {
+        if let Some((size, max_size)) = self.oversized_message_size(&not) {
+            warn!(
+                "Notification {} has {} bytes, more than the maximal message size",
+                not.method, size
+            );
+            let message = format!(
+                "Notification {} not sent: {} bytes exceed the maximal message size of {} bytes",
+                not.method, size, max_size
+            );
+            self.send(
+                Notification::new(
+                    OnBuildShowMessage::METHOD.to_string(),
+                    ShowMessageParams {
+                        r#type: MessageType::Warning,
+                        message,
+                        ..ShowMessageParams::default()
+                    },
+                )
+                .into(),
+            );
+            return;
+        }
         self.send(not.into());
     }
 
+    /// Shows the message to the user (e.g. in a popup), unlike the log messages
+    /// which are attached to the tasks. Used for the failures the user has to fix.
+    pub(crate) fn show_message(&mut self, r#type: MessageType, message: String) {
+        self.send_notification(Notification::new(
+            OnBuildShowMessage::METHOD.to_string(),
+            ShowMessageParams {
+                r#type,
+                message,
+                ..ShowMessageParams::default()
+            },
+        ));
+    }
+
     pub(crate) fn register_request(&mut self, request: &Request, request_received: Instant) {
         self.req_queue.incoming.register(
             request.id.clone(),
@@ -74,6 +177,7 @@ impl GlobalState {
         GlobalStateSnapshot {
             config: &self.config,
             workspace: &self.workspace,
+            workspace_load_error: self.workspace_load_error.as_ref(),
         }
     }
 
@@ -84,36 +188,286 @@ impl GlobalState {
                 "handled {} - ({}) in {:0.2?}",
                 method, response.id, duration
             );
+            let response = self.limit_response_size(response);
             self.send(response.into());
         }
     }
 
+    /// Responses exceeding the maximal message size are replaced with an error,
+    /// instead of being sent to the client, which might fail to read them.
+    fn limit_response_size(&mut self, response: Response) -> Response {
+        let Some((size, max_size)) = self.oversized_message_size(&response) else {
+            return response;
+        };
+        warn!(
+            "Response to {} has {} bytes, more than the maximal message size",
+            response.id, size
+        );
+        Response::new_err(
+            response.id,
+            RESPONSE_TOO_LARGE,
+            format!(
+                "Response too large: {} bytes exceed the maximal message size of {} bytes, \
+                request less data at once (e.g. fewer build targets)",
+                size, max_size
+            ),
+        )
+    }
+
+    /// Returns the size of the serialized message together with the maximal message size,
+    /// if the message exceeds it. The message is serialized once, into the reused buffer.
+    fn oversized_message_size(&mut self, message: &impl Serialize) -> Option<(usize, usize)> {
+        let max_size = self.config.settings.max_message_size?;
+        self.message_buffer.clear();
+        serde_json::to_writer(&mut self.message_buffer, message).ok()?;
+        let size = self.message_buffer.len();
+        (size > max_size).then_some((size, max_size))
+    }
+
+    /// Cancels the request, if it is still handled. Handled Cargo requests respond
+    /// with the cancellation error themselves (after killing the Cargo process),
+    /// so they have to stay in the queue until then. Queued Cargo requests are never spawned.
     pub(crate) fn cancel(&mut self, request_id: RequestId) {
-        if let Some(response) = self.req_queue.incoming.cancel(request_id) {
-            if let Some(handler) = self.handlers.get(&response.id) {
-                handler.cancel()
-            } else {
+        if let Some(handler) = self.handlers.get(&request_id) {
+            handler.cancel(CancelReason::Client)
+        } else if let Some(response) = self.req_queue.incoming.cancel(request_id) {
+            if let Some(queued) = self.build_queue.remove(&response.id) {
+                self.report_queued_task_finish(&queued, StatusCode::Cancelled);
+            }
+            self.send(response.into());
+        }
+    }
+
+    /// Cancels all currently handled requests, which kills their Cargo processes,
+    /// and the queued ones.
+    pub(crate) fn cancel_all_handlers(&mut self) {
+        while let Some(queued) = self.build_queue.pop() {
+            self.report_queued_task_finish(&queued, StatusCode::Cancelled);
+            if let Some(response) = self.req_queue.incoming.cancel(queued.id) {
                 self.send(response.into());
             }
         }
+        self.handlers
+            .values()
+            .for_each(|handler| handler.cancel(CancelReason::Shutdown));
+    }
+
+    /// Cancels all the handled requests and waits (up to the grace period) for their
+    /// responses, so that the client gets a terminal response for each running request.
+    /// No more requests are handled afterwards, only the exit notification is expected.
+    pub(crate) fn shutdown(&mut self) {
+        self.shutdown_requested = true;
+        self.cancel_all_handlers();
+
+        let grace_period = self
+            .config
+            .settings
+            .shutdown_grace_period
+            .unwrap_or(DEFAULT_SHUTDOWN_GRACE_PERIOD);
+        let deadline = Instant::now() + grace_period;
+        while !self.handlers.is_empty() {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match self.handlers_receiver.recv_timeout(timeout) {
+                Ok(Message::Response(resp)) => {
+                    self.handlers.remove(&resp.id);
+                    self.respond(resp);
+                }
+                Ok(Message::Notification(not)) => self.send_notification(not),
+                Ok(Message::Request(_)) => {}
+                Err(_) => {
+                    warn!(
+                        "{} requests did not respond within the shutdown grace period",
+                        self.handlers.len()
+                    );
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Spawns the Cargo request, or queues it if the maximal number of concurrent
+    /// builds is reached. The client is informed about the queued request with a task.
+    pub(crate) fn spawn_or_queue(
+        &mut self,
+        request_id: RequestId,
+        origin_id: Option<Identifier>,
+        spawn: SpawnHandle,
+    ) {
+        if self.can_spawn_build() {
+            let request_handle = spawn(self);
+            self.add_handler(request_id, request_handle);
+            return;
+        }
+        let queued = QueuedRequest {
+            id: request_id,
+            task_id: TaskId {
+                id: generate_random_id(),
+                parents: None,
+            },
+            origin_id,
+            spawn,
+        };
+        self.send_notification(Notification::new(
+            OnBuildTaskStart::METHOD.to_string(),
+            TaskStartParams {
+                task_id: queued.task_id.clone(),
+                origin_id: queued.origin_id.clone(),
+                event_time: Some(now_millis()),
+                message: Some(format!(
+                    "Request queued, waiting for {} running builds to finish",
+                    self.handlers.len()
+                )),
+                data: None,
+            },
+        ));
+        self.build_queue.push(queued);
+    }
+
+    /// Spawns the queued requests, called after a handled request has finished.
+    pub(crate) fn spawn_queued_requests(&mut self) {
+        while self.can_spawn_build() {
+            let queued = match self.build_queue.pop() {
+                Some(queued) => queued,
+                None => return,
+            };
+            self.report_queued_task_finish(&queued, StatusCode::Ok);
+            let request_handle = (queued.spawn)(self);
+            self.add_handler(queued.id, request_handle);
+        }
+    }
+
+    fn can_spawn_build(&self) -> bool {
+        match self.config.settings.max_concurrent_builds {
+            Some(max) => self.handlers.len() < max,
+            None => true,
+        }
+    }
+
+    fn add_handler(
+        &mut self,
+        request_id: RequestId,
+        request_handle: Result<RequestHandle, ServerError>,
+    ) {
+        match request_handle {
+            Ok(request_handle) => {
+                self.handlers.insert(request_id, request_handle);
+            }
+            Err(error) => {
+                self.show_message(MessageType::Error, error.to_string());
+                self.respond(error.into_response(request_id));
+            }
+        }
+    }
+
+    fn report_queued_task_finish(&mut self, queued: &QueuedRequest, status: StatusCode) {
+        self.send_notification(Notification::new(
+            OnBuildTaskFinish::METHOD.to_string(),
+            TaskFinishParams {
+                task_id: queued.task_id.clone(),
+                origin_id: queued.origin_id.clone(),
+                event_time: Some(now_millis()),
+                message: None,
+                status,
+                data: None,
+            },
+        ));
     }
 
     fn send(&mut self, message: Message) {
         self.sender.send(message).unwrap()
     }
 
-    // update the workspace data - called when (to be yet added) cargo watch discovers changes
+    // update the workspace data - called on start and when the manifests change
     pub(crate) fn update_workspace_data(&mut self) {
-        let mutable_config = &mut self.config;
-        mutable_config.update_project_manifest();
+        if let Err(e) = self.reload_workspace() {
+            error!("Updating workspace state failed: {}", e);
+            let error = Arc::new(e);
+            let message = ServerError::CargoMetadata(Arc::clone(&error)).to_string();
+            self.show_message(MessageType::Error, message);
+            self.workspace_load_error = Some(error);
+        }
+    }
 
-        match ProjectWorkspace::new(self.config.workspace_manifest.file.clone()) {
-            Ok(updated_workspace) => {
-                self.workspace = updated_workspace;
-            }
-            Err(e) => {
-                error!("Updating workspace state failed: {}", e);
-            }
+    /// Rebuilds the project model from the `cargo metadata` output. The model is replaced
+    /// only if the metadata was obtained. Already handled requests are not affected,
+    /// as they copy the needed data from the model when they start.
+    pub(crate) fn reload_workspace(&mut self) -> Result<(), cargo_metadata::Error> {
+        self.config
+            .update_project_manifest()
+            .map_err(cargo_metadata::Error::Io)?;
+
+        let (metadata, unknown_editions) = self
+            .metadata_cache
+            .get(&self.config.workspace_manifest.file, false)?;
+        self.workspace = ProjectWorkspace::from_metadata(&metadata, &unknown_editions);
+        self.workspace_load_error = None;
+        let state_dir = self.state_dir();
+        self.workspace
+            .restore_enabled_features(&load_enabled_features(&state_dir));
+        if let Some(feature_mode) = load_feature_mode(&state_dir) {
+            self.config.settings.feature_mode = feature_mode;
+        }
+        self.workspace.feature_mode = self.config.settings.feature_mode.clone();
+        self.workspace.host = HostPlatform::discover(metadata.workspace_root.as_std_path());
+        self.watch_manifests();
+        Ok(())
+    }
+
+    /// Directory of the files stored by the server, in the target directory of the workspace.
+    fn state_dir(&self) -> Utf8PathBuf {
+        self.workspace.target_directory.join(SERVER_STATE_DIR_NAME)
+    }
+
+    /// Stores the enabled features and the feature mode, so they are restored after
+    /// the server restarts.
+    pub(crate) fn save_enabled_features(&self) {
+        if let Err(e) = save_enabled_features(&self.state_dir(), &self.workspace) {
+            warn!("Failed to store the enabled features: {}", e);
+        }
+    }
+
+    /// Removes the files stored by the server and resets the in-memory caches.
+    /// The build artifacts in the target directory are left untouched.
+    pub(crate) fn clean_server_state(&mut self) -> io::Result<()> {
+        self.metadata_cache.invalidate();
+        self.toolchain_info = None;
+        let state_dir = self.state_dir();
+        if state_dir.exists() {
+            fs::remove_dir_all(state_dir)?;
+        }
+        Ok(())
+    }
+
+    /// Recomputes the build targets and notifies the client about the changed ones.
+    pub(crate) fn on_manifests_changed(&mut self) {
+        let old_targets = self.workspace.get_bsp_build_targets();
+        self.update_workspace_data();
+        self.notify_build_target_changes(&old_targets);
+    }
+
+    /// Sends the `buildTarget/didChange` notification, if the current build targets
+    /// differ from the given ones (e.g. after the features of a package were changed).
+    pub(crate) fn notify_build_target_changes(&mut self, old_targets: &[BuildTarget]) {
+        let diff = diff_build_targets(old_targets, &self.workspace.get_bsp_build_targets());
+        if !diff.is_empty() {
+            let changes = diff.into_events();
+            self.send_notification(Notification::new(
+                OnBuildTargetDidChange::METHOD.to_string(),
+                DidChangeBuildTarget { changes },
+            ));
+        }
+    }
+
+    fn watch_manifests(&mut self) {
+        if let Some(watcher) = &mut self.manifest_watcher {
+            let manifests: Vec<&Path> = self
+                .workspace
+                .packages
+                .iter()
+                .map(|p| p.manifest_path.as_std_path())
+                .chain([self.config.workspace_manifest.file.as_path()])
+                .collect();
+            watcher.watch(&manifests);
         }
     }
 }
@@ -121,3 +475,593 @@ impl GlobalState {
 impl Drop for GlobalState {
     fn drop(&mut self) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bsp4rs::bsp::BuildClientCapabilities;
+    use bsp_server::ErrorCode;
+    use cargo_metadata::Metadata;
+    use serde_json::json;
+    use std::cell::Cell;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+    use tempfile::tempdir;
+
+    use crate::project_model::build_target_mappings::INACTIVE_TARGET_TAG;
+    use crate::project_model::rust_extension::get_metadata;
+    use crate::project_model::target_selector::parse_target_id;
+    use crate::project_model::UnknownEditions;
+    use crate::server::config::ServerSettings;
+    use crate::server::handlers;
+    use crate::utils::tests::test_sources_req;
+    use bsp4rs::bsp::{BuildTargetEventKind, InverseSourcesParams, TextDocumentIdentifier, URI};
+    use bsp4rs::cargo::{
+        FeatureMode, SetCargoFeatureModeParams, SetCargoFeaturesParams, WorkspaceReloadResult,
+    };
+    use bsp4rs::rust::Feature;
+    use std::collections::BTreeSet;
+
+    const TEST_REQ_ID: i32 = 1;
+
+    fn test_global_state(settings: ServerSettings) -> (GlobalState, Receiver<Message>) {
+        let (sender, receiver) = unbounded();
+        let mut config = Config::new(PathBuf::from("test"), BuildClientCapabilities::default());
+        config.settings = settings;
+        (GlobalState::new(sender, config), receiver)
+    }
+
+    fn test_response_with_result_of_size(size: usize) -> Message {
+        let (mut global_state, receiver) = test_global_state(ServerSettings {
+            max_message_size: Some(100),
+            ..ServerSettings::default()
+        });
+        global_state.register_request(&test_sources_req(TEST_REQ_ID), Instant::now());
+
+        global_state.respond(Response::new_ok(TEST_REQ_ID.into(), "x".repeat(size)));
+        receiver.try_recv().unwrap()
+    }
+
+    #[test]
+    fn response_within_max_message_size() {
+        match test_response_with_result_of_size(10) {
+            Message::Response(resp) => assert!(resp.error.is_none()),
+            msg => panic!("expected a response, got: {:?}", msg),
+        }
+    }
+
+    #[test]
+    fn response_exceeding_max_message_size() {
+        match test_response_with_result_of_size(1000) {
+            Message::Response(resp) => {
+                assert_eq!(resp.id, TEST_REQ_ID.into());
+                assert!(resp.result.is_none());
+                let error = resp.error.unwrap();
+                assert_eq!(error.code, RESPONSE_TOO_LARGE);
+                assert!(error.message.starts_with("Response too large"));
+            }
+            msg => panic!("expected a response, got: {:?}", msg),
+        }
+    }
+
+    #[test]
+    fn notification_exceeding_max_message_size() {
+        let (mut global_state, receiver) = test_global_state(ServerSettings {
+            max_message_size: Some(100),
+            ..ServerSettings::default()
+        });
+
+        global_state.send_notification(Notification::new("test/small".to_string(), "x"));
+        global_state.send_notification(Notification::new(
+            "test/large".to_string(),
+            "x".repeat(1000),
+        ));
+
+        match receiver.try_recv().unwrap() {
+            Message::Notification(not) => assert_eq!(not.method, "test/small"),
+            msg => panic!("expected a notification, got: {:?}", msg),
+        }
+        match receiver.try_recv().unwrap() {
+            Message::Notification(not) => {
+                assert_eq!(not.method, OnBuildShowMessage::METHOD);
+                let params: ShowMessageParams = serde_json::from_value(not.params).unwrap();
+                assert_eq!(params.r#type, MessageType::Warning);
+                assert!(params
+                    .message
+                    .starts_with("Notification test/large not sent"));
+            }
+            msg => panic!("expected a notification, got: {:?}", msg),
+        }
+        assert!(receiver.try_recv().is_err());
+    }
+
+    /// Registers the request and spawns (or queues) an actor that does nothing,
+    /// records whether it was spawned.
+    fn spawn_test_request(global_state: &mut GlobalState, id: i32) -> Rc<Cell<bool>> {
+        global_state.register_request(&test_sources_req(id), Instant::now());
+        let spawned = Rc::new(Cell::new(false));
+        let spawned_clone = spawned.clone();
+        global_state.spawn_or_queue(
+            id.into(),
+            None,
+            Box::new(move |_: &mut GlobalState| {
+                spawned_clone.set(true);
+                let (cancel_sender, _) = unbounded();
+                Ok(RequestHandle {
+                    cancel_sender,
+                    _thread: jod_thread::spawn(|| {}),
+                })
+            }),
+        );
+        spawned
+    }
+
+    fn expect_notification(receiver: &Receiver<Message>, method: &str) -> serde_json::Value {
+        match receiver.try_recv() {
+            Ok(Message::Notification(not)) if not.method == method => not.params,
+            msg => panic!("expected {} notification, got: {:?}", method, msg),
+        }
+    }
+
+    #[test]
+    fn requests_over_limit_queued_until_build_finishes() {
+        let (mut global_state, receiver) = test_global_state(ServerSettings {
+            max_concurrent_builds: Some(1),
+            ..ServerSettings::default()
+        });
+
+        let first_spawned = spawn_test_request(&mut global_state, 1);
+        let second_spawned = spawn_test_request(&mut global_state, 2);
+        assert!(first_spawned.get());
+        assert!(!second_spawned.get());
+        let task_start = expect_notification(&receiver, OnBuildTaskStart::METHOD);
+        assert_eq!(
+            task_start["message"],
+            "Request queued, waiting for 1 running builds to finish"
+        );
+
+        global_state.handlers.remove(&1.into());
+        global_state.spawn_queued_requests();
+        assert!(second_spawned.get());
+        let task_finish = expect_notification(&receiver, OnBuildTaskFinish::METHOD);
+        assert_eq!(task_finish["taskId"], task_start["taskId"]);
+        assert_eq!(task_finish["status"], StatusCode::Ok as u8);
+    }
+
+    #[test]
+    fn cancelled_queued_request_never_spawned() {
+        let (mut global_state, receiver) = test_global_state(ServerSettings {
+            max_concurrent_builds: Some(1),
+            ..ServerSettings::default()
+        });
+        spawn_test_request(&mut global_state, 1);
+        let second_spawned = spawn_test_request(&mut global_state, 2);
+        expect_notification(&receiver, OnBuildTaskStart::METHOD);
+
+        global_state.cancel(2.into());
+        let task_finish = expect_notification(&receiver, OnBuildTaskFinish::METHOD);
+        assert_eq!(task_finish["status"], StatusCode::Cancelled as u8);
+        match receiver.try_recv() {
+            Ok(Message::Response(resp)) => {
+                assert_eq!(resp.id, 2.into());
+                assert_eq!(resp.error.unwrap().code, ErrorCode::RequestCanceled as i32);
+            }
+            msg => panic!("expected the cancel response, got: {:?}", msg),
+        }
+
+        global_state.handlers.remove(&1.into());
+        global_state.spawn_queued_requests();
+        assert!(!second_spawned.get());
+    }
+
+    #[test]
+    fn clean_server_state_removes_state_files_and_caches() {
+        let (mut global_state, _receiver) = test_global_state(ServerSettings::default());
+        let tmp_dir = tempdir().unwrap();
+        let target_dir = Utf8PathBuf::from_path_buf(tmp_dir.path().join("target")).unwrap();
+        let state_dir = target_dir.join(SERVER_STATE_DIR_NAME);
+        fs::create_dir_all(&state_dir).unwrap();
+        fs::write(state_dir.join("state.json"), "{}").unwrap();
+        global_state.workspace.target_directory = target_dir.clone();
+
+        let manifest_path = tmp_dir.path().join("Cargo.toml");
+        let metadata: Metadata = serde_json::from_value(json!({
+            "packages": [],
+            "workspace_members": [],
+            "resolve": null,
+            "workspace_root": tmp_dir.path(),
+            "target_directory": target_dir,
+            "version": 1,
+        }))
+        .unwrap();
+        let loads = Cell::new(0);
+        let get_metadata = |global_state: &mut GlobalState| {
+            global_state
+                .metadata_cache
+                .get_or_load(&manifest_path, false, || {
+                    loads.set(loads.get() + 1);
+                    Ok((metadata.clone(), UnknownEditions::default()))
+                })
+                .unwrap();
+        };
+        get_metadata(&mut global_state);
+        get_metadata(&mut global_state);
+        assert_eq!(loads.get(), 1);
+        global_state.toolchain_info = Some(RustToolchainResult::default());
+
+        global_state.clean_server_state().unwrap();
+
+        assert!(!state_dir.exists());
+        assert_eq!(global_state.toolchain_info, None);
+        assert!(target_dir.exists());
+        get_metadata(&mut global_state);
+        assert_eq!(loads.get(), 2);
+    }
+
+    #[test]
+    fn rust_workspace_reuses_cached_filtered_metadata() {
+        let (mut global_state, _receiver) = test_global_state(ServerSettings::default());
+        let tmp_dir = tempdir().unwrap();
+        let manifest_path = tmp_dir.path().join("Cargo.toml");
+        fs::write(&manifest_path, "[workspace]\n").unwrap();
+        global_state.config.workspace_manifest.file = manifest_path.clone();
+        let metadata: Metadata = serde_json::from_value(json!({
+            "packages": [],
+            "workspace_members": [],
+            "resolve": null,
+            "workspace_root": tmp_dir.path(),
+            "target_directory": tmp_dir.path().join("target"),
+            "version": 1,
+        }))
+        .unwrap();
+        // As cached by the previous Rust workspace request (with the `--filter-platform` flag).
+        global_state
+            .metadata_cache
+            .get_or_load(&manifest_path, true, || {
+                Ok((metadata.clone(), UnknownEditions::default()))
+            })
+            .unwrap();
+
+        let (rust_workspace_metadata, _) = get_metadata(
+            &mut global_state.metadata_cache,
+            &global_state.config.workspace_manifest,
+        )
+        .unwrap();
+
+        assert_eq!(rust_workspace_metadata, metadata);
+    }
+
+    const GATED_APP_ID: &str = "app 0.1.0 (path+file:///test_root)";
+
+    /// Sets the workspace with a binary requiring the `x` feature, with the state
+    /// directory in the returned temporary directory.
+    fn set_gated_target_workspace(global_state: &mut GlobalState) -> tempfile::TempDir {
+        let metadata: Metadata = serde_json::from_value(json!({
+            "packages": [{
+                "name": "app",
+                "version": "0.1.0",
+                "id": GATED_APP_ID,
+                "source": null,
+                "dependencies": [],
+                "targets": [{
+                    "name": "gated",
+                    "kind": ["bin"],
+                    "src_path": "/test_root/src/bin/gated.rs",
+                    "required-features": ["x"],
+                }],
+                "features": {"x": []},
+                "manifest_path": "/test_root/Cargo.toml",
+            }],
+            "workspace_members": [GATED_APP_ID],
+            "resolve": null,
+            "workspace_root": "/test_root",
+            "target_directory": "/test_root/target",
+            "version": 1,
+        }))
+        .unwrap();
+        global_state.workspace =
+            ProjectWorkspace::from_metadata(&metadata, &UnknownEditions::default());
+        let tmp_dir = tempdir().unwrap();
+        global_state.workspace.target_directory =
+            Utf8PathBuf::from_path_buf(tmp_dir.path().to_path_buf()).unwrap();
+        tmp_dir
+    }
+
+    #[test]
+    fn enabling_required_feature_changes_build_target() {
+        let (mut global_state, receiver) = test_global_state(ServerSettings::default());
+        let _tmp_dir = set_gated_target_workspace(&mut global_state);
+        let gated_target = global_state.workspace.get_bsp_build_targets().remove(0);
+        assert!(gated_target.tags.contains(&INACTIVE_TARGET_TAG));
+
+        let result = handlers::handle_enable_cargo_features(
+            &mut global_state,
+            SetCargoFeaturesParams {
+                package_id: GATED_APP_ID.to_string(),
+                features: BTreeSet::from([Feature::from("x")]),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(result.status_code, StatusCode::Ok);
+        let did_change: DidChangeBuildTarget = serde_json::from_value(expect_notification(
+            &receiver,
+            OnBuildTargetDidChange::METHOD,
+        ))
+        .unwrap();
+        assert_eq!(did_change.changes.len(), 1);
+        assert_eq!(did_change.changes[0].target, gated_target.id);
+        assert_eq!(
+            did_change.changes[0].kind,
+            Some(BuildTargetEventKind::Changed)
+        );
+        assert!(!global_state.workspace.get_bsp_build_targets()[0]
+            .tags
+            .contains(&INACTIVE_TARGET_TAG));
+    }
+
+    #[test]
+    fn all_features_mode_activates_gated_target() {
+        let (mut global_state, receiver) = test_global_state(ServerSettings::default());
+        let tmp_dir = set_gated_target_workspace(&mut global_state);
+        let gated_target = global_state.workspace.get_bsp_build_targets().remove(0);
+        assert!(gated_target.tags.contains(&INACTIVE_TARGET_TAG));
+
+        let result = handlers::handle_set_cargo_feature_mode(
+            &mut global_state,
+            SetCargoFeatureModeParams {
+                feature_mode: FeatureMode::AllFeatures,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(result.status_code, StatusCode::Ok);
+        let did_change: DidChangeBuildTarget = serde_json::from_value(expect_notification(
+            &receiver,
+            OnBuildTargetDidChange::METHOD,
+        ))
+        .unwrap();
+        assert_eq!(did_change.changes.len(), 1);
+        assert_eq!(did_change.changes[0].target, gated_target.id);
+        assert!(!global_state.workspace.get_bsp_build_targets()[0]
+            .tags
+            .contains(&INACTIVE_TARGET_TAG));
+        let state_dir =
+            Utf8PathBuf::from_path_buf(tmp_dir.path().join(SERVER_STATE_DIR_NAME)).unwrap();
+        assert_eq!(
+            load_feature_mode(&state_dir),
+            Some(FeatureMode::AllFeatures)
+        );
+    }
+
+    #[test]
+    fn enabled_features_restored_after_reload() {
+        let tmp_dir = tempdir().unwrap();
+        fs::write(
+            tmp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[features]\nx = []\n",
+        )
+        .unwrap();
+        fs::create_dir(tmp_dir.path().join("src")).unwrap();
+        fs::write(tmp_dir.path().join("src").join("lib.rs"), "").unwrap();
+        let (sender, _receiver) = unbounded();
+        let config = Config::new(
+            tmp_dir.path().to_path_buf(),
+            BuildClientCapabilities::default(),
+        );
+        let mut global_state = GlobalState::new(sender, config);
+        let package_id = global_state.workspace.packages[0].id.clone();
+        let x = BTreeSet::from([Feature::from("x")]);
+
+        handlers::handle_set_cargo_features(
+            &mut global_state,
+            SetCargoFeaturesParams {
+                package_id,
+                features: x.clone(),
+            },
+        )
+        .unwrap();
+        global_state.reload_workspace().unwrap();
+
+        assert_eq!(global_state.workspace.packages[0].enabled_features, x);
+    }
+
+    #[test]
+    fn reload_reports_changed_build_targets() {
+        let tmp_dir = tempdir().unwrap();
+        fs::write(
+            tmp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::create_dir(tmp_dir.path().join("src")).unwrap();
+        fs::write(tmp_dir.path().join("src").join("lib.rs"), "").unwrap();
+        let (sender, _receiver) = unbounded();
+        let config = Config::new(
+            tmp_dir.path().to_path_buf(),
+            BuildClientCapabilities::default(),
+        );
+        let mut global_state = GlobalState::new(sender, config);
+
+        let result = handlers::handle_cargo_reload(&mut global_state, ()).unwrap();
+
+        assert_eq!(result, WorkspaceReloadResult::default());
+
+        fs::write(tmp_dir.path().join("src").join("main.rs"), "fn main() {}").unwrap();
+        let result = handlers::handle_cargo_reload(&mut global_state, ()).unwrap();
+
+        assert!(result.changed);
+        assert_eq!(result.added.len(), 1);
+        assert!(result.removed.is_empty());
+    }
+
+    #[test]
+    fn inverse_sources_of_integration_test() {
+        let tmp_dir = tempdir().unwrap();
+        fs::write(
+            tmp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::create_dir(tmp_dir.path().join("src")).unwrap();
+        fs::write(tmp_dir.path().join("src").join("lib.rs"), "").unwrap();
+        fs::create_dir(tmp_dir.path().join("tests")).unwrap();
+        fs::write(tmp_dir.path().join("tests").join("it.rs"), "").unwrap();
+        let (sender, _receiver) = unbounded();
+        let config = Config::new(
+            tmp_dir.path().to_path_buf(),
+            BuildClientCapabilities::default(),
+        );
+        let global_state = GlobalState::new(sender, config);
+        let document = tmp_dir.path().join("tests").join("it.rs");
+
+        let result = handlers::handle_inverse_sources(
+            global_state.snapshot(),
+            InverseSourcesParams {
+                text_document: TextDocumentIdentifier {
+                    uri: URI::from_file_path(document.canonicalize().unwrap()),
+                },
+            },
+        )
+        .unwrap();
+
+        let names: Vec<String> = result
+            .targets
+            .iter()
+            .map(|id| parse_target_id(id).unwrap().name)
+            .collect();
+        assert_eq!(names, vec!["it"]);
+    }
+
+    #[test]
+    fn inverse_sources_of_non_file_uri() {
+        let (sender, _receiver) = unbounded();
+        let global_state = GlobalState::new(
+            sender,
+            Config::new(
+                PathBuf::from("/test_root"),
+                BuildClientCapabilities::default(),
+            ),
+        );
+
+        let result = handlers::handle_inverse_sources(
+            global_state.snapshot(),
+            InverseSourcesParams {
+                text_document: TextDocumentIdentifier {
+                    uri: "cargo:///test_root?name=app&kind=lib".into(),
+                },
+            },
+        );
+
+        assert!(matches!(result, Err(ServerError::InvalidParams(_))));
+    }
+
+    #[test]
+    fn metadata_failure_shown_to_user() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "invalid manifest").unwrap();
+        let (sender, receiver) = unbounded();
+
+        let _global_state = GlobalState::new(
+            sender,
+            Config::new(dir.path().to_path_buf(), BuildClientCapabilities::default()),
+        );
+
+        let params: ShowMessageParams =
+            serde_json::from_value(expect_notification(&receiver, OnBuildShowMessage::METHOD))
+                .unwrap();
+        assert_eq!(params.r#type, MessageType::Error);
+        assert!(params.message.starts_with("cargo metadata failed"));
+    }
+
+    #[test]
+    fn missing_manifest_shown_to_user() {
+        let dir = tempdir().unwrap();
+        let (sender, receiver) = unbounded();
+
+        let global_state = GlobalState::new(
+            sender,
+            Config::new(dir.path().to_path_buf(), BuildClientCapabilities::default()),
+        );
+
+        let params: ShowMessageParams =
+            serde_json::from_value(expect_notification(&receiver, OnBuildShowMessage::METHOD))
+                .unwrap();
+        assert_eq!(params.r#type, MessageType::Error);
+        assert!(params.message.starts_with("cargo metadata failed"));
+        assert!(params.message.contains("Cargo.toml not found"));
+        assert!(global_state.workspace_load_error.is_some());
+    }
+
+    #[test]
+    fn requests_fail_with_metadata_error() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "invalid manifest").unwrap();
+        let (sender, _receiver) = unbounded();
+        let mut global_state = GlobalState::new(
+            sender,
+            Config::new(dir.path().to_path_buf(), BuildClientCapabilities::default()),
+        );
+
+        match handlers::handle_workspace_build_targets(global_state.snapshot(), None) {
+            Err(error @ ServerError::CargoMetadata(_)) => {
+                let message = error.to_string();
+                assert!(message.starts_with("cargo metadata failed"));
+                assert!(message.contains("failed to parse manifest"));
+            }
+            other => panic!("expected the metadata error, got: {:?}", other),
+        }
+        let rust_workspace = RequestHandle::spawn_check::<bsp4rs::rust::RustWorkspace>(
+            Box::new(|_| {}),
+            TEST_REQ_ID.into(),
+            bsp4rs::rust::RustWorkspaceParams::default(),
+            &mut global_state,
+        );
+        assert!(matches!(rust_workspace, Err(ServerError::CargoMetadata(_))));
+    }
+
+    #[test]
+    fn output_paths_use_target_directory_of_metadata() {
+        const APP_ID: &str = "app 0.1.0 (path+file:///test_root)";
+        let (mut global_state, _receiver) = test_global_state(ServerSettings::default());
+        // As with `build.target-dir = "/test_target_dir"` in `.cargo/config.toml`.
+        let metadata: Metadata = serde_json::from_value(json!({
+            "packages": [{
+                "name": "app",
+                "version": "0.1.0",
+                "id": APP_ID,
+                "source": null,
+                "dependencies": [],
+                "targets": [{
+                    "name": "app",
+                    "kind": ["lib"],
+                    "src_path": "/test_root/src/lib.rs",
+                }],
+                "features": {},
+                "manifest_path": "/test_root/Cargo.toml",
+            }],
+            "workspace_members": [APP_ID],
+            "resolve": null,
+            "workspace_root": "/test_root",
+            "target_directory": "/test_target_dir",
+            "version": 1,
+        }))
+        .unwrap();
+        global_state.workspace =
+            ProjectWorkspace::from_metadata(&metadata, &UnknownEditions::default());
+        let target = global_state.workspace.get_bsp_build_targets().remove(0);
+
+        let result = handlers::handle_output_paths(
+            global_state.snapshot(),
+            bsp4rs::bsp::OutputPathsParams {
+                targets: vec![target.id],
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            result.items[0].output_paths[0].uri,
+            URI("file:///test_target_dir/debug/".to_string())
+        );
+    }
+}
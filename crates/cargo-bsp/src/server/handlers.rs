@@ -1,25 +1,41 @@
 //! Handles the upcoming requests from the client that does not require the
 //! communication with Cargo (such as compile, run or test requests).
 
+use std::sync::Arc;
+
+use bsp_server::Notification;
 use log::warn;
 
+use bsp4rs::bsp::{LogMessageParams, MessageType, OnBuildLogMessage, StatusCode};
+use bsp4rs::Notification as _;
+
+use crate::project_model::added_dependencies::added_dependencies_message;
+use crate::project_model::build_target_diff::diff_build_targets;
+use crate::project_model::cargo_package::CargoPackage;
+use crate::project_model::output_paths::{output_paths_item, profile_output_dir, DEFAULT_PROFILE};
 use crate::project_model::sources::get_sources_for_target;
+use crate::project_model::toolchain_info::toolchain_info;
 use crate::server::global_state::{GlobalState, GlobalStateSnapshot};
-use crate::server::Result;
+use crate::server::{Result, ServerError};
 
 pub(crate) fn handle_workspace_build_targets(
     state: GlobalStateSnapshot,
-    _: (),
-) -> Result<bsp4rs::bsp::WorkspaceBuildTargetsResult> {
-    Ok(bsp4rs::bsp::WorkspaceBuildTargetsResult {
-        targets: state.workspace.get_bsp_build_targets(),
-    })
+    params: Option<bsp4rs::cargo::WorkspaceBuildTargetsParams>,
+) -> Result<bsp4rs::bsp::WorkspaceBuildTargetsResult, ServerError> {
+    if let Some(error) = state.workspace_load_error {
+        return Err(ServerError::CargoMetadata(Arc::clone(error)));
+    }
+    let targets = match params.and_then(|p| p.kinds) {
+        Some(kinds) => state.workspace.get_bsp_build_targets_of_kinds(&kinds),
+        None => state.workspace.get_bsp_build_targets(),
+    };
+    Ok(bsp4rs::bsp::WorkspaceBuildTargetsResult { targets })
 }
 
 pub(crate) fn handle_sources(
     state: GlobalStateSnapshot,
     params: bsp4rs::bsp::SourcesParams,
-) -> Result<bsp4rs::bsp::SourcesResult> {
+) -> Result<bsp4rs::bsp::SourcesResult, ServerError> {
     let sources_items = params
         .targets
         .into_iter()
@@ -31,7 +47,9 @@ pub(crate) fn handle_sources(
                     warn!("Failed to get target details for: {:?}", id);
                     None
                 })
-                .map(|details| get_sources_for_target(&id, details))
+                .map(|details| {
+                    get_sources_for_target(&id, details, &state.workspace.target_directory)
+                })
         })
         .collect();
 
@@ -44,23 +62,15 @@ pub(crate) fn handle_sources(
 pub(crate) fn handle_resources(
     _: GlobalStateSnapshot,
     _: bsp4rs::bsp::ResourcesParams,
-) -> Result<bsp4rs::bsp::ResourcesResult> {
+) -> Result<bsp4rs::bsp::ResourcesResult, ServerError> {
     Ok(bsp4rs::bsp::ResourcesResult::default())
 }
 
-// TODO: Not properly handled yet
-pub(crate) fn handle_clean_cache(
-    _: GlobalStateSnapshot,
-    _: bsp4rs::bsp::CleanCacheParams,
-) -> Result<bsp4rs::bsp::CleanCacheResult> {
-    Ok(bsp4rs::bsp::CleanCacheResult::default())
-}
-
 // TODO: Not properly handled yet
 pub(crate) fn handle_dependency_modules(
     _: GlobalStateSnapshot,
     _: bsp4rs::bsp::DependencyModulesParams,
-) -> Result<bsp4rs::bsp::DependencyModulesResult> {
+) -> Result<bsp4rs::bsp::DependencyModulesResult, ServerError> {
     Ok(bsp4rs::bsp::DependencyModulesResult::default())
 }
 
@@ -68,31 +78,75 @@ pub(crate) fn handle_dependency_modules(
 pub(crate) fn handle_dependency_sources(
     _: GlobalStateSnapshot,
     _: bsp4rs::bsp::DependencySourcesParams,
-) -> Result<bsp4rs::bsp::DependencySourcesResult> {
+) -> Result<bsp4rs::bsp::DependencySourcesResult, ServerError> {
     Ok(bsp4rs::bsp::DependencySourcesResult::default())
 }
 
-// TODO: Not properly handled yet
+/// The build targets are selected by their ids, so that the ids which are not Cargo targets
+/// (e.g. the discovered unit tests) are skipped. The Cargo target is found with the selector
+/// of the id (see [`ProjectWorkspace::get_target_selector`]).
+///
+/// [`ProjectWorkspace::get_target_selector`]: crate::project_model::workspace::ProjectWorkspace::get_target_selector
 pub(crate) fn handle_inverse_sources(
-    _: GlobalStateSnapshot,
-    _: bsp4rs::bsp::InverseSourcesParams,
-) -> Result<bsp4rs::bsp::InverseSourcesResult> {
-    Ok(bsp4rs::bsp::InverseSourcesResult::default())
+    state: GlobalStateSnapshot,
+    params: bsp4rs::bsp::InverseSourcesParams,
+) -> Result<bsp4rs::bsp::InverseSourcesResult, ServerError> {
+    let uri = params.text_document.uri;
+    let document = uri
+        .to_file_path()
+        .ok_or_else(|| ServerError::InvalidParams(format!("Not a file URI: {}", uri.0)))?;
+    let targets = state
+        .workspace
+        .get_bsp_build_targets()
+        .into_iter()
+        .map(|t| t.id)
+        .filter(|id| {
+            state
+                .workspace
+                .get_target_details(id)
+                .is_some_and(|details| {
+                    get_sources_for_target(id, details, &state.workspace.target_directory)
+                        .sources
+                        .iter()
+                        .any(|source| source.uri.to_file_path().as_ref() == Some(&document))
+                })
+        })
+        .collect();
+    Ok(bsp4rs::bsp::InverseSourcesResult { targets })
 }
 
-// TODO: Not properly handled yet
 pub(crate) fn handle_output_paths(
-    _: GlobalStateSnapshot,
-    _: bsp4rs::bsp::OutputPathsParams,
-) -> Result<bsp4rs::bsp::OutputPathsResult> {
-    Ok(bsp4rs::bsp::OutputPathsResult::default())
+    state: GlobalStateSnapshot,
+    params: bsp4rs::bsp::OutputPathsParams,
+) -> Result<bsp4rs::bsp::OutputPathsResult, ServerError> {
+    let profile = state
+        .config
+        .settings
+        .profile
+        .as_deref()
+        .unwrap_or(DEFAULT_PROFILE);
+    let output_dir = profile_output_dir(&state.workspace.target_directory, profile);
+    let items = params
+        .targets
+        .into_iter()
+        .filter(|id| {
+            let known = state.workspace.target_id_to_package_id.contains_key(id);
+            if !known {
+                warn!("Failed to get output paths for: {:?}", id);
+            }
+            known
+        })
+        .map(|id| output_paths_item(id, &output_dir))
+        .collect();
+
+    Ok(bsp4rs::bsp::OutputPathsResult { items })
 }
 
 // TODO: Not properly handled yet
 pub(crate) fn handle_workspace_libraries(
     _: GlobalStateSnapshot,
     _: (),
-) -> Result<bsp4rs::bazel::WorkspaceLibrariesResult> {
+) -> Result<bsp4rs::bazel::WorkspaceLibrariesResult, ServerError> {
     Ok(bsp4rs::bazel::WorkspaceLibrariesResult::default())
 }
 
@@ -100,32 +154,205 @@ pub(crate) fn handle_workspace_libraries(
 pub(crate) fn handle_workspace_directories(
     _: GlobalStateSnapshot,
     _: (),
-) -> Result<bsp4rs::bazel::WorkspaceDirectoriesResult> {
+) -> Result<bsp4rs::bazel::WorkspaceDirectoriesResult, ServerError> {
     Ok(bsp4rs::bazel::WorkspaceDirectoriesResult::default())
 }
 
-pub(crate) fn handle_reload(global_state: &mut GlobalState, _: ()) -> Result<()> {
-    global_state.update_workspace_data();
-    Ok(())
+pub(crate) fn handle_reload(global_state: &mut GlobalState, _: ()) -> Result<(), ServerError> {
+    handle_cargo_reload(global_state, ()).map(|_| ())
+}
+
+// BSP Rust Extension handlers
+
+pub(crate) fn handle_resolve_spec(
+    state: GlobalStateSnapshot,
+    params: bsp4rs::rust::RustResolveSpecParams,
+) -> Result<bsp4rs::rust::RustResolveSpecResult, ServerError> {
+    let targets = state
+        .workspace
+        .resolve_package_spec(&params.spec)
+        .map_err(|e| ServerError::InvalidParams(e.to_string()))?;
+    Ok(bsp4rs::rust::RustResolveSpecResult { targets })
+}
+
+pub(crate) fn handle_clean_server_state(
+    global_state: &mut GlobalState,
+    _: (),
+) -> Result<bsp4rs::rust::RustCleanServerStateResult, ServerError> {
+    global_state
+        .clean_server_state()
+        .map_err(|e| ServerError::Internal(format!("Failed to clean the server state: {}", e)))?;
+    Ok(bsp4rs::rust::RustCleanServerStateResult { cleaned: true })
+}
+
+pub(crate) fn handle_toolchain(
+    global_state: &mut GlobalState,
+    _: (),
+) -> Result<bsp4rs::rust::RustToolchainResult, ServerError> {
+    if let Some(info) = &global_state.toolchain_info {
+        return Ok(info.clone());
+    }
+    let info = toolchain_info(
+        global_state.config.root_path(),
+        &global_state.config.settings,
+    )
+    .map_err(|e| {
+        ServerError::Internal(format!("Failed to get the toolchain information: {}", e))
+    })?;
+    global_state.toolchain_info = Some(info.clone());
+    Ok(info)
+}
+
+/// Doesn't depend on the state of the builds, so the client can use it to check
+/// if the server is responsive.
+pub(crate) fn handle_ping(
+    global_state: &mut GlobalState,
+    _: (),
+) -> Result<bsp4rs::bsp::PingResult, ServerError> {
+    Ok(bsp4rs::bsp::PingResult {
+        uptime_ms: global_state.start_time.elapsed().as_millis() as u64,
+        last_handled_method: global_state.last_handled_method.clone(),
+    })
 }
 
 // BSP Cargo Extension handlers
 
+/// Reloads the workspace like the `workspace/reload` request, responds with the changed targets.
+pub(crate) fn handle_cargo_reload(
+    global_state: &mut GlobalState,
+    _: (),
+) -> Result<bsp4rs::cargo::WorkspaceReloadResult, ServerError> {
+    let old_targets = global_state.workspace.get_bsp_build_targets();
+    global_state.metadata_cache.invalidate();
+    global_state.reload_workspace()?;
+    let diff = diff_build_targets(
+        &old_targets,
+        &global_state.workspace.get_bsp_build_targets(),
+    );
+    Ok(bsp4rs::cargo::WorkspaceReloadResult {
+        changed: !diff.is_empty(),
+        added: diff.added,
+        removed: diff.removed,
+    })
+}
+
 pub(crate) fn handle_set_cargo_features(
     state: &mut GlobalState,
     params: bsp4rs::cargo::SetCargoFeaturesParams,
-) -> Result<bsp4rs::cargo::SetCargoFeaturesResult> {
+) -> Result<bsp4rs::cargo::SetCargoFeaturesResult, ServerError> {
+    let package_before = state.workspace.get_package(&params.package_id).cloned();
+    let old_targets = state.workspace.get_bsp_build_targets();
     let mutable_workspace = &mut state.workspace;
     let status_code =
-        mutable_workspace.set_features_for_the_package(params.package_id, &params.features);
-    Ok(bsp4rs::cargo::SetCargoFeaturesResult { status_code })
+        mutable_workspace.set_features_for_the_package(params.package_id.clone(), &params.features);
+    if let Some(package_before) = package_before {
+        report_added_dependencies(state, &package_before);
+    }
+    state.notify_build_target_changes(&old_targets);
+    state.save_enabled_features();
+    Ok(bsp4rs::cargo::SetCargoFeaturesResult {
+        status_code,
+        message: None,
+    })
+}
+
+pub(crate) fn handle_enable_cargo_features(
+    state: &mut GlobalState,
+    params: bsp4rs::cargo::SetCargoFeaturesParams,
+) -> Result<bsp4rs::cargo::SetCargoFeaturesResult, ServerError> {
+    let package_before = state.workspace.get_package(&params.package_id).cloned();
+    let old_targets = state.workspace.get_bsp_build_targets();
+    let result = state
+        .workspace
+        .enable_features_for_the_package(&params.package_id, &params.features);
+    if let (Ok(()), Some(package_before)) = (&result, package_before) {
+        report_added_dependencies(state, &package_before);
+    }
+    state.notify_build_target_changes(&old_targets);
+    state.save_enabled_features();
+    Ok(features_change_result(result))
+}
+
+pub(crate) fn handle_disable_cargo_features(
+    state: &mut GlobalState,
+    params: bsp4rs::cargo::SetCargoFeaturesParams,
+) -> Result<bsp4rs::cargo::SetCargoFeaturesResult, ServerError> {
+    let old_targets = state.workspace.get_bsp_build_targets();
+    let result = state
+        .workspace
+        .disable_features_for_the_package(&params.package_id, &params.features);
+    state.notify_build_target_changes(&old_targets);
+    state.save_enabled_features();
+    Ok(features_change_result(result))
+}
+
+pub(crate) fn handle_set_cargo_feature_mode(
+    state: &mut GlobalState,
+    params: bsp4rs::cargo::SetCargoFeatureModeParams,
+) -> Result<bsp4rs::cargo::SetCargoFeaturesResult, ServerError> {
+    let old_targets = state.workspace.get_bsp_build_targets();
+    state.config.settings.feature_mode = params.feature_mode.clone();
+    state.workspace.feature_mode = params.feature_mode;
+    state.notify_build_target_changes(&old_targets);
+    state.save_enabled_features();
+    Ok(features_change_result(Ok(())))
+}
+
+/// The failure is reported in the result, as the request itself was handled.
+fn features_change_result(result: Result<(), String>) -> bsp4rs::cargo::SetCargoFeaturesResult {
+    match result {
+        Ok(()) => bsp4rs::cargo::SetCargoFeaturesResult {
+            status_code: StatusCode::Ok,
+            message: None,
+        },
+        Err(message) => {
+            warn!("Couldn't change features state: {}", message);
+            bsp4rs::cargo::SetCargoFeaturesResult {
+                status_code: StatusCode::Error,
+                message: Some(message),
+            }
+        }
+    }
+}
+
+/// Informs the client about the dependencies enabled by the new features of the package.
+/// Purely informational, the features are changed anyway.
+fn report_added_dependencies(state: &mut GlobalState, package_before: &CargoPackage) {
+    let metadata = match state
+        .metadata_cache
+        .get(&state.config.workspace_manifest.file, false)
+    {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            warn!("Couldn't estimate the added dependencies: {}", e);
+            return;
+        }
+    };
+    let message = state
+        .workspace
+        .get_package(&package_before.id)
+        .and_then(|package| added_dependencies_message(&metadata, package_before, package));
+    if let Some(message) = message {
+        state.send_notification(Notification::new(
+            OnBuildLogMessage::METHOD.to_string(),
+            LogMessageParams {
+                r#type: MessageType::Info,
+                task: None,
+                origin_id: None,
+                message,
+            },
+        ));
+    }
 }
 
 pub(crate) fn handle_cargo_features_state(
     state: GlobalStateSnapshot,
     _: (),
-) -> Result<bsp4rs::cargo::CargoFeaturesStateResult> {
+) -> Result<bsp4rs::cargo::CargoFeaturesStateResult, ServerError> {
     let packages_features = state.workspace.get_cargo_features_state();
 
-    Ok(bsp4rs::cargo::CargoFeaturesStateResult { packages_features })
+    Ok(bsp4rs::cargo::CargoFeaturesStateResult {
+        packages_features,
+        feature_mode: state.config.settings.feature_mode.clone(),
+    })
 }
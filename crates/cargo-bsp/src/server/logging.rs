@@ -0,0 +1,150 @@
+//! Level of the server's logs, set with the [`LOG_LEVEL_ENV`] environment variable
+//! or by the client in the server settings, and the logger mirroring the warnings
+//! and errors to the client as the `build/logMessage` notifications.
+
+use std::sync::Mutex;
+
+use bsp_server::{Message, Notification};
+use crossbeam_channel::Sender;
+use log::{warn, Level, LevelFilter, Log, Metadata, Record};
+
+use bsp4rs::bsp::{LogMessageParams, MessageType, OnBuildLogMessage};
+use bsp4rs::Notification as _;
+
+use crate::server::config::ServerSettings;
+
+/// Environment variable with the level of the logs, used until the client sets it.
+pub const LOG_LEVEL_ENV: &str = "CARGO_BSP_LOG";
+
+/// Sender to the client, set if the client requested the logs to be mirrored.
+static CLIENT_SENDER: Mutex<Option<Sender<Message>>> = Mutex::new(None);
+
+/// Invalid levels are reported and the default `info` level is used instead.
+pub fn parse_log_level(level: &str) -> LevelFilter {
+    level.parse().unwrap_or_else(|_| {
+        warn!("Invalid log level `{}`, using `info`", level);
+        LevelFilter::Info
+    })
+}
+
+pub fn log_level_from_env() -> LevelFilter {
+    std::env::var(LOG_LEVEL_ENV)
+        .map(|level| parse_log_level(&level))
+        .unwrap_or(LevelFilter::Info)
+}
+
+/// Applies the log settings passed by the client in the `build/initialize` request.
+pub(crate) fn configure(settings: &ServerSettings, sender: &Sender<Message>) {
+    if let Some(level) = &settings.log_level {
+        log::set_max_level(parse_log_level(level));
+    }
+    if settings.log_to_client {
+        *CLIENT_SENDER.lock().unwrap() = Some(sender.clone());
+    }
+}
+
+/// Drops the sender to the client, so that the writer thread sees the channel closed
+/// and the IO threads can be joined.
+pub(crate) fn disconnect() {
+    CLIENT_SENDER.lock().unwrap().take();
+}
+
+/// Sends the warnings and errors to the client, once it is configured to receive them.
+pub struct ClientLogger;
+
+impl Log for ClientLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Some(sender) = CLIENT_SENDER.lock().unwrap().as_ref() {
+            let _ = sender.send(log_message_notification(record).into());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl simplelog::SharedLogger for ClientLogger {
+    fn level(&self) -> LevelFilter {
+        LevelFilter::Warn
+    }
+
+    fn config(&self) -> Option<&simplelog::Config> {
+        None
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        self
+    }
+}
+
+fn log_message_notification(record: &Record) -> Notification {
+    let r#type = match record.level() {
+        Level::Error => MessageType::Error,
+        Level::Warn => MessageType::Warning,
+        Level::Info => MessageType::Info,
+        Level::Debug | Level::Trace => MessageType::Log,
+    };
+    Notification::new(
+        OnBuildLogMessage::METHOD.to_string(),
+        LogMessageParams {
+            r#type,
+            message: record.args().to_string(),
+            ..LogMessageParams::default()
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_level_parsed() {
+        assert_eq!(parse_log_level("error"), LevelFilter::Error);
+        assert_eq!(parse_log_level("warn"), LevelFilter::Warn);
+        assert_eq!(parse_log_level("debug"), LevelFilter::Debug);
+        assert_eq!(parse_log_level("TRACE"), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn invalid_log_level_falls_back_to_info() {
+        assert_eq!(parse_log_level("verbose"), LevelFilter::Info);
+        assert_eq!(parse_log_level(""), LevelFilter::Info);
+    }
+
+    #[test]
+    fn warning_mirrored_as_log_message() {
+        let notification = log_message_notification(
+            &Record::builder()
+                .level(Level::Warn)
+                .args(format_args!("test warning"))
+                .build(),
+        );
+
+        assert_eq!(notification.method, OnBuildLogMessage::METHOD);
+        let params: LogMessageParams = serde_json::from_value(notification.params).unwrap();
+        assert_eq!(params.r#type, MessageType::Warning);
+        assert_eq!(params.message, "test warning");
+    }
+
+    #[test]
+    fn disconnect_drops_client_sender() {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let settings = ServerSettings {
+            log_to_client: true,
+            ..ServerSettings::default()
+        };
+
+        configure(&settings, &sender);
+        drop(sender);
+        disconnect();
+
+        assert!(receiver.recv().is_err());
+    }
+}
@@ -4,18 +4,75 @@
 use std::time::Instant;
 
 use bsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
-use crossbeam_channel::{select, Receiver};
+use crossbeam_channel::{never, select, Receiver};
 
 use bsp4rs;
 use bsp4rs::cancel::CancelRequest;
 use bsp4rs::Notification as _;
+use bsp4rs::Request as _;
 
+use crate::cargo_communication::request_handle::RequestHandle;
 use crate::server::config::Config;
-use crate::server::dispatch::{NotificationDispatcher, RequestDispatcher};
+use crate::server::dispatch::{
+    NotificationDispatcher, RequestDispatcher, RequestRouter, RoutedMethods,
+};
 use crate::server::global_state::GlobalState;
 use crate::server::{handlers, Result};
 use crate::utils::request_id::bsp_request_id_to_lsp_request_id;
 
+/// Methods of the requests routed in [`route_requests`]. The capabilities tied to
+/// a request (such as `canReload`) are advertised only if it is routed.
+fn handled_requests() -> Vec<&'static str> {
+    let mut routed = RoutedMethods::default();
+    route_requests(&mut routed);
+    routed.0
+}
+
+pub(crate) fn is_request_handled(method: &str) -> bool {
+    handled_requests().contains(&method)
+}
+
+/// Routes the requests to their handlers, the same routing is used to collect
+/// the [`handled_requests`].
+fn route_requests<D: RequestRouter>(dispatcher: &mut D) {
+    dispatcher
+        .on_sync_mut::<bsp4rs::bsp::BuildShutdown>(|s, ()| {
+            s.shutdown();
+            Ok(())
+        })
+        .on_sync_mut::<bsp4rs::bsp::BuildPing>(handlers::handle_ping)
+        .on_sync_mut::<bsp4rs::bsp::WorkspaceReload>(handlers::handle_reload)
+        .on_sync_mut::<bsp4rs::cargo::CargoWorkspaceReload>(handlers::handle_cargo_reload)
+        .on_sync_mut::<bsp4rs::cargo::SetCargoFeatures>(handlers::handle_set_cargo_features)
+        .on_sync_mut::<bsp4rs::cargo::EnableCargoFeatures>(handlers::handle_enable_cargo_features)
+        .on_sync_mut::<bsp4rs::cargo::DisableCargoFeatures>(handlers::handle_disable_cargo_features)
+        .on_sync_mut::<bsp4rs::cargo::SetCargoFeatureMode>(handlers::handle_set_cargo_feature_mode)
+        .on_sync::<bsp4rs::bsp::WorkspaceBuildTargets>(handlers::handle_workspace_build_targets)
+        .on_sync::<bsp4rs::bsp::BuildTargetSources>(handlers::handle_sources)
+        .on_sync::<bsp4rs::bsp::BuildTargetResources>(handlers::handle_resources)
+        .on_sync::<bsp4rs::bsp::BuildTargetDependencyModules>(handlers::handle_dependency_modules)
+        .on_sync::<bsp4rs::bsp::BuildTargetDependencySources>(handlers::handle_dependency_sources)
+        .on_sync::<bsp4rs::bsp::BuildTargetInverseSources>(handlers::handle_inverse_sources)
+        .on_sync::<bsp4rs::bsp::BuildTargetOutputPaths>(handlers::handle_output_paths)
+        .on_sync::<bsp4rs::bazel::WorkspaceLibraries>(handlers::handle_workspace_libraries)
+        .on_sync::<bsp4rs::bazel::WorkspaceDirectories>(handlers::handle_workspace_directories)
+        .on_sync::<bsp4rs::cargo::CargoFeaturesState>(handlers::handle_cargo_features_state)
+        .on_sync::<bsp4rs::rust::RustResolveSpec>(handlers::handle_resolve_spec)
+        .on_sync_mut::<bsp4rs::rust::RustCleanServerState>(handlers::handle_clean_server_state)
+        .on_sync_mut::<bsp4rs::rust::RustToolchain>(handlers::handle_toolchain)
+        .on_cargo_run::<bsp4rs::bsp::BuildTargetCompile>()
+        .on_cargo_run::<bsp4rs::bsp::BuildTargetRun>()
+        .on_cargo_run::<bsp4rs::bsp::BuildTargetTest>()
+        .on_cargo_run::<bsp4rs::cargo::BuildTargetDoc>()
+        .on_cargo_check_run::<bsp4rs::rust::RustWorkspace>()
+        .on_cargo_output_run::<bsp4rs::rust::RustFutureIncompatReport>(
+            RequestHandle::spawn_future_incompat_report,
+        )
+        .on_cargo_output_run::<bsp4rs::rust::RustFormatCheck>(RequestHandle::spawn_format_check)
+        .on_cargo_output_run::<bsp4rs::bsp::BuildTargetCleanCache>(RequestHandle::spawn_clean_cache)
+        .finish();
+}
+
 pub fn main_loop(config: Config, connection: Connection) -> Result<()> {
     GlobalState::new(connection.sender, config).run(connection.receiver)
 }
@@ -24,10 +81,12 @@ pub fn main_loop(config: Config, connection: Connection) -> Result<()> {
 /// Bsp means it comes from the client and the received request/notification should be handled.
 /// FromThread means it is from one of the handled requests in the server and the received
 /// response/notification should be sent back to the client.
+/// ManifestsChanged means the manifests of the workspace have been modified.
 #[derive(Debug)]
 enum Event {
     Bsp(Message),
     FromThread(Message),
+    ManifestsChanged,
 }
 
 impl GlobalState {
@@ -35,6 +94,9 @@ impl GlobalState {
         while let Some(event) = self.next_message(&inbox) {
             if let Event::Bsp(Message::Notification(not)) = &event {
                 if not.method == bsp4rs::bsp::OnBuildExit::METHOD {
+                    self.cancel_all_handlers();
+                    // Dropping the handles waits for the actors to finish.
+                    self.handlers.clear();
                     if !self.shutdown_requested {
                         break;
                     }
@@ -48,12 +110,16 @@ impl GlobalState {
     }
 
     fn next_message(&self, inbox: &Receiver<Message>) -> Option<Event> {
+        let manifests_chan = self.manifest_watcher.as_ref().map(|w| &w.receiver);
         select! {
             recv(inbox) -> msg =>
                 msg.ok().map(Event::Bsp),
 
             recv(self.handlers_receiver) -> msg =>
                 msg.ok().map(Event::FromThread),
+
+            recv(manifests_chan.unwrap_or(&never())) -> msg =>
+                msg.ok().map(|()| Event::ManifestsChanged),
         }
     }
 
@@ -71,9 +137,11 @@ impl GlobalState {
                 Message::Notification(not) => self.send_notification(not.to_owned()),
                 Message::Response(resp) => {
                     self.handlers.remove(&resp.id);
-                    self.respond(resp.to_owned())
+                    self.respond(resp.to_owned());
+                    self.spawn_queued_requests();
                 }
             },
+            Event::ManifestsChanged => self.on_manifests_changed(),
         }
 
         Ok(())
@@ -84,55 +152,26 @@ impl GlobalState {
         self.on_request(req);
     }
 
-    /// Handles a request.
+    /// Handles a request, routed in [`route_requests`].
     fn on_request(&mut self, req: Request) {
+        // After the shutdown request, only the exit notification is expected.
+        if self.shutdown_requested {
+            self.respond(Response::new_err(
+                req.id,
+                ErrorCode::InvalidRequest as i32,
+                "Shutdown already requested.".to_owned(),
+            ));
+            return;
+        }
+
+        if req.method != bsp4rs::bsp::BuildPing::METHOD {
+            self.last_handled_method = Some(req.method.clone());
+        }
         let mut dispatcher = RequestDispatcher {
             req: Some(req),
             global_state: self,
         };
-        dispatcher.on_sync_mut::<bsp4rs::bsp::BuildShutdown>(|s, ()| {
-            s.shutdown_requested = true;
-            Ok(())
-        });
-
-        if let RequestDispatcher {
-            req: Some(req),
-            global_state: this,
-        } = &mut dispatcher
-        {
-            if this.shutdown_requested {
-                this.respond(Response::new_err(
-                    req.id.clone(),
-                    ErrorCode::InvalidRequest as i32,
-                    "Shutdown already requested.".to_owned(),
-                ));
-                return;
-            }
-        }
-
-        dispatcher
-            .on_sync_mut::<bsp4rs::bsp::WorkspaceReload>(handlers::handle_reload)
-            .on_sync_mut::<bsp4rs::cargo::SetCargoFeatures>(handlers::handle_set_cargo_features)
-            .on_sync::<bsp4rs::bsp::WorkspaceBuildTargets>(handlers::handle_workspace_build_targets)
-            .on_sync::<bsp4rs::bsp::BuildTargetSources>(handlers::handle_sources)
-            .on_sync::<bsp4rs::bsp::BuildTargetResources>(handlers::handle_resources)
-            .on_sync::<bsp4rs::bsp::BuildTargetCleanCache>(handlers::handle_clean_cache)
-            .on_sync::<bsp4rs::bsp::BuildTargetDependencyModules>(
-                handlers::handle_dependency_modules,
-            )
-            .on_sync::<bsp4rs::bsp::BuildTargetDependencySources>(
-                handlers::handle_dependency_sources,
-            )
-            .on_sync::<bsp4rs::bsp::BuildTargetInverseSources>(handlers::handle_inverse_sources)
-            .on_sync::<bsp4rs::bsp::BuildTargetOutputPaths>(handlers::handle_output_paths)
-            .on_sync::<bsp4rs::bazel::WorkspaceLibraries>(handlers::handle_workspace_libraries)
-            .on_sync::<bsp4rs::bazel::WorkspaceDirectories>(handlers::handle_workspace_directories)
-            .on_sync::<bsp4rs::cargo::CargoFeaturesState>(handlers::handle_cargo_features_state)
-            .on_cargo_run::<bsp4rs::bsp::BuildTargetCompile>()
-            .on_cargo_run::<bsp4rs::bsp::BuildTargetRun>()
-            .on_cargo_run::<bsp4rs::bsp::BuildTargetTest>()
-            .on_cargo_check_run::<bsp4rs::rust::RustWorkspace>()
-            .finish();
+        route_requests(&mut dispatcher);
     }
 
     /// Handles an incoming notification.
@@ -153,13 +192,487 @@ impl GlobalState {
 
 #[cfg(test)]
 mod tests {
-    mod test_shutdown_order {
+    mod test_handled_requests {
+        use std::path::PathBuf;
+        use std::time::Instant;
+
+        use bsp_server::{ErrorCode, Message, Request};
+        use crossbeam_channel::{unbounded, Receiver};
+        use serde_json::json;
+
+        use bsp4rs::bsp::BuildClientCapabilities;
+        use bsp4rs::Request as _;
+
+        use crate::server::caps::server_capabilities;
+        use crate::server::config::Config;
+        use crate::server::global_state::GlobalState;
+        use crate::server::main_loop::handled_requests;
+
+        fn test_global_state() -> (GlobalState, Receiver<Message>) {
+            let (sender, receiver) = unbounded();
+            let config = Config::new(PathBuf::from("test"), BuildClientCapabilities::default());
+            (GlobalState::new(sender, config), receiver)
+        }
+
+        /// Sends the request with invalid params, which are rejected by the handler (so nothing
+        /// is executed), while the request not routed to any handler is an unknown method.
+        fn is_dispatched(
+            global_state: &mut GlobalState,
+            receiver: &Receiver<Message>,
+            method: &str,
+        ) -> bool {
+            let req = Request {
+                id: method.to_string().into(),
+                method: method.to_string(),
+                params: json!("invalid_params"),
+            };
+            global_state.register_request(&req, Instant::now());
+            global_state.on_request(req);
+
+            match receiver.try_recv() {
+                Ok(Message::Response(resp)) => {
+                    let code = resp.error.unwrap().code;
+                    assert!(
+                        code == ErrorCode::InvalidParams as i32
+                            || code == ErrorCode::MethodNotFound as i32,
+                        "unexpected error code {} of {}",
+                        code,
+                        method
+                    );
+                    code == ErrorCode::InvalidParams as i32
+                }
+                msg => panic!("expected a response to {}, got: {:?}", method, msg),
+            }
+        }
+
+        #[test]
+        fn handled_requests_are_routed() {
+            let (mut global_state, receiver) = test_global_state();
+
+            for method in handled_requests() {
+                assert!(
+                    is_dispatched(&mut global_state, &receiver, method),
+                    "request {} is not routed",
+                    method
+                );
+            }
+            assert!(!is_dispatched(
+                &mut global_state,
+                &receiver,
+                "workspace/unknownRequest"
+            ));
+        }
+
+        #[test]
+        fn advertised_capabilities_are_dispatched() {
+            let (mut global_state, receiver) = test_global_state();
+            let caps = server_capabilities(&global_state.config);
+
+            assert_eq!(
+                caps.can_reload,
+                Some(is_dispatched(
+                    &mut global_state,
+                    &receiver,
+                    bsp4rs::bsp::WorkspaceReload::METHOD
+                ))
+            );
+            let advertised = [
+                (
+                    caps.compile_provider.is_some(),
+                    bsp4rs::bsp::BuildTargetCompile::METHOD,
+                ),
+                (
+                    caps.test_provider.is_some(),
+                    bsp4rs::bsp::BuildTargetTest::METHOD,
+                ),
+                (
+                    caps.run_provider.is_some(),
+                    bsp4rs::bsp::BuildTargetRun::METHOD,
+                ),
+                (
+                    caps.debug_provider.is_some(),
+                    bsp4rs::bsp::DebugSessionStart::METHOD,
+                ),
+                (
+                    caps.inverse_sources_provider == Some(true),
+                    bsp4rs::bsp::BuildTargetInverseSources::METHOD,
+                ),
+                (
+                    caps.dependency_sources_provider == Some(true),
+                    bsp4rs::bsp::BuildTargetDependencySources::METHOD,
+                ),
+                (
+                    caps.dependency_modules_provider == Some(true),
+                    bsp4rs::bsp::BuildTargetDependencyModules::METHOD,
+                ),
+                (
+                    caps.resources_provider == Some(true),
+                    bsp4rs::bsp::BuildTargetResources::METHOD,
+                ),
+                (
+                    caps.output_paths_provider == Some(true),
+                    bsp4rs::bsp::BuildTargetOutputPaths::METHOD,
+                ),
+            ];
+            for (is_advertised, method) in advertised {
+                // The providers might be disabled for the requests returning no information,
+                // but an advertised provider must be backed by a handler.
+                if is_advertised {
+                    assert!(
+                        is_dispatched(&mut global_state, &receiver, method),
+                        "{} is advertised, but not routed",
+                        method
+                    );
+                }
+            }
+        }
+    }
+
+    mod test_shutdown_cancels_handlers {
+        use std::path::PathBuf;
+        use std::time::{Duration, Instant};
+
+        use bsp_server::{ErrorCode, Message, Response};
+        use crossbeam_channel::unbounded;
+
+        use bsp4rs::bsp::BuildClientCapabilities;
+
+        use crate::cargo_communication::cargo_types::event::{CancelReason, Event as ActorEvent};
+        use crate::cargo_communication::request_handle::RequestHandle;
+        use crate::server::config::Config;
+        use crate::server::global_state::GlobalState;
+        use crate::server::main_loop::Event;
+        use crate::utils::tests::{test_exit_notif, test_shutdown_req, test_sources_req};
+
+        /// Spawns an actor that runs until it is cancelled and reports the cancellation
+        /// (which kills the Cargo process in the real actors).
+        fn long_running_handle() -> (RequestHandle, crossbeam_channel::Receiver<()>) {
+            let (cancel_sender, cancel_receiver) = unbounded::<ActorEvent>();
+            let (killed_sender, killed_receiver) = unbounded();
+            let thread = jod_thread::spawn(move || {
+                if let Ok(ActorEvent::Cancel(CancelReason::Shutdown)) = cancel_receiver.recv() {
+                    killed_sender.send(()).unwrap();
+                }
+            });
+            let handle = RequestHandle {
+                cancel_sender,
+                _thread: thread,
+            };
+            (handle, killed_receiver)
+        }
+
+        #[test]
+        fn shutdown_and_exit_cancel_active_handlers() {
+            let (sender, _receiver) = unbounded();
+            let mut global_state = GlobalState::new(
+                sender,
+                Config::new(PathBuf::from("test"), BuildClientCapabilities::default()),
+            );
+            // The handle never responds, so the shutdown waits for the whole grace period.
+            global_state.config.settings.shutdown_grace_period = Some(Duration::from_millis(10));
+            let (handle, killed_receiver) = long_running_handle();
+            global_state.handlers.insert(1.into(), handle);
+
+            global_state
+                .handle_message(Event::Bsp(test_shutdown_req(2).into()))
+                .unwrap();
+            killed_receiver
+                .recv_timeout(std::time::Duration::from_secs(5))
+                .expect("actor should be cancelled on shutdown");
+
+            let (inbox_sender, inbox) = unbounded();
+            inbox_sender.send(test_exit_notif().into()).unwrap();
+            global_state.run(inbox).unwrap();
+        }
+
+        #[test]
+        fn shutdown_waits_for_cancelled_build_response() {
+            let (sender, client_receiver) = unbounded();
+            let mut global_state = GlobalState::new(
+                sender,
+                Config::new(PathBuf::from("test"), BuildClientCapabilities::default()),
+            );
+            global_state.register_request(&test_sources_req(1), Instant::now());
+            // Responds only after a while, like an actor killing the Cargo process.
+            let (cancel_sender, cancel_receiver) = unbounded::<ActorEvent>();
+            let sender_to_main = global_state.handlers_sender.clone();
+            let thread = jod_thread::spawn(move || {
+                if let Ok(ActorEvent::Cancel(CancelReason::Shutdown)) = cancel_receiver.recv() {
+                    std::thread::sleep(Duration::from_millis(50));
+                    let response = Response::new_err(
+                        1.into(),
+                        ErrorCode::RequestCanceled as i32,
+                        "server is shutting down".to_string(),
+                    );
+                    sender_to_main.send(response.into()).unwrap();
+                }
+            });
+            let handle = RequestHandle {
+                cancel_sender,
+                _thread: thread,
+            };
+            global_state.handlers.insert(1.into(), handle);
+
+            global_state
+                .handle_message(Event::Bsp(test_shutdown_req(2).into()))
+                .unwrap();
+
+            match client_receiver.try_recv() {
+                Ok(Message::Response(resp)) => {
+                    assert_eq!(resp.id, 1.into());
+                    assert_eq!(resp.error.unwrap().code, ErrorCode::RequestCanceled as i32);
+                }
+                msg => panic!("expected the build response, got: {:?}", msg),
+            }
+            match client_receiver.try_recv() {
+                Ok(Message::Response(resp)) => assert_eq!(resp.id, 2.into()),
+                msg => panic!("expected the shutdown response, got: {:?}", msg),
+            }
+            assert!(global_state.handlers.is_empty());
+        }
+
+        #[test]
+        fn exit_cancels_active_handlers() {
+            let (sender, _receiver) = unbounded();
+            let mut global_state = GlobalState::new(
+                sender,
+                Config::new(PathBuf::from("test"), BuildClientCapabilities::default()),
+            );
+            let (handle, killed_receiver) = long_running_handle();
+            global_state.handlers.insert(1.into(), handle);
+
+            let (inbox_sender, inbox) = unbounded();
+            inbox_sender.send(test_exit_notif().into()).unwrap();
+            assert!(global_state.run(inbox).is_err());
+            assert!(killed_receiver.try_recv().is_ok());
+        }
+    }
+
+    mod test_cancel_request {
         use std::path::PathBuf;
+        use std::time::Instant;
 
-        use bsp_server::Connection;
+        use bsp_server::{ErrorCode, Message, Response};
+        use crossbeam_channel::{unbounded, Receiver};
 
         use bsp4rs::bsp::BuildClientCapabilities;
 
+        use crate::cargo_communication::cargo_types::event::{CancelReason, Event as ActorEvent};
+        use crate::cargo_communication::request_handle::RequestHandle;
+        use crate::server::config::Config;
+        use crate::server::global_state::GlobalState;
+        use crate::server::main_loop::Event;
+        use crate::utils::tests::{test_cancel_notif, test_sources_req};
+
+        const TEST_REQ_ID: i32 = 1;
+
+        fn test_global_state() -> (GlobalState, Receiver<Message>) {
+            let (sender, receiver) = unbounded();
+            let global_state = GlobalState::new(
+                sender,
+                Config::new(PathBuf::from("test"), BuildClientCapabilities::default()),
+            );
+            (global_state, receiver)
+        }
+
+        /// Spawns an actor that responds with an error when it is cancelled by the client.
+        fn cancellable_handle(global_state: &GlobalState) -> RequestHandle {
+            let (cancel_sender, cancel_receiver) = unbounded::<ActorEvent>();
+            let sender_to_main = global_state.handlers_sender.clone();
+            let thread = jod_thread::spawn(move || {
+                if let Ok(ActorEvent::Cancel(CancelReason::Client)) = cancel_receiver.recv() {
+                    let response = Response::new_err(
+                        TEST_REQ_ID.into(),
+                        ErrorCode::RequestCanceled as i32,
+                        "canceled by client".to_string(),
+                    );
+                    sender_to_main.send(response.into()).unwrap();
+                }
+            });
+            RequestHandle {
+                cancel_sender,
+                _thread: thread,
+            }
+        }
+
+        #[test]
+        fn cancel_request_routed_to_handle() {
+            let (mut global_state, client_receiver) = test_global_state();
+            global_state.register_request(&test_sources_req(TEST_REQ_ID), Instant::now());
+            let handle = cancellable_handle(&global_state);
+            global_state.handlers.insert(TEST_REQ_ID.into(), handle);
+
+            global_state
+                .handle_message(Event::Bsp(test_cancel_notif(TEST_REQ_ID).into()))
+                .unwrap();
+            let actor_msg = global_state
+                .handlers_receiver
+                .recv_timeout(std::time::Duration::from_secs(5))
+                .expect("actor should respond when cancelled");
+            global_state
+                .handle_message(Event::FromThread(actor_msg))
+                .unwrap();
+
+            match client_receiver.try_recv() {
+                Ok(Message::Response(resp)) => {
+                    assert_eq!(resp.id, TEST_REQ_ID.into());
+                    assert_eq!(resp.error.unwrap().code, ErrorCode::RequestCanceled as i32);
+                }
+                msg => panic!("expected the cancel response, got: {:?}", msg),
+            }
+            assert!(global_state.handlers.is_empty());
+        }
+
+        #[test]
+        fn cancel_unknown_request_is_noop() {
+            let (mut global_state, client_receiver) = test_global_state();
+
+            global_state
+                .handle_message(Event::Bsp(test_cancel_notif(TEST_REQ_ID).into()))
+                .unwrap();
+
+            assert!(client_receiver.try_recv().is_err());
+        }
+    }
+
+    mod test_ping {
+        use std::path::PathBuf;
+        use std::time::{Duration, Instant};
+
+        use bsp_server::{Message, Request};
+        use crossbeam_channel::unbounded;
+
+        use bsp4rs::bsp::{BuildClientCapabilities, BuildPing, PingResult};
+        use bsp4rs::Request as _;
+
+        use crate::cargo_communication::cargo_types::event::Event as ActorEvent;
+        use crate::cargo_communication::request_handle::RequestHandle;
+        use crate::server::config::Config;
+        use crate::server::global_state::GlobalState;
+        use crate::utils::tests::test_sources_req;
+
+        /// Spawns an actor that runs until the end of the test, like an unfinished build.
+        fn in_flight_handle() -> RequestHandle {
+            let (cancel_sender, cancel_receiver) = unbounded::<ActorEvent>();
+            let thread = jod_thread::spawn(move || {
+                let _ = cancel_receiver.recv();
+            });
+            RequestHandle {
+                cancel_sender,
+                _thread: thread,
+            }
+        }
+
+        #[test]
+        fn ping_responds_during_in_flight_build() {
+            let (sender, receiver) = unbounded();
+            let mut global_state = GlobalState::new(
+                sender,
+                Config::new(PathBuf::from("test"), BuildClientCapabilities::default()),
+            );
+            global_state.handlers.insert(1.into(), in_flight_handle());
+
+            let sources_req = test_sources_req(2);
+            global_state.register_request(&sources_req, Instant::now());
+            global_state.on_request(sources_req);
+            let _ = receiver.try_recv();
+
+            let ping_req = Request {
+                id: 3.into(),
+                method: BuildPing::METHOD.to_string(),
+                params: Default::default(),
+            };
+            let start = Instant::now();
+            global_state.register_request(&ping_req, start);
+            global_state.on_request(ping_req);
+
+            match receiver.try_recv() {
+                Ok(Message::Response(resp)) => {
+                    assert_eq!(resp.id, 3.into());
+                    let result: PingResult = serde_json::from_value(resp.result.unwrap()).unwrap();
+                    assert_eq!(
+                        result.last_handled_method.as_deref(),
+                        Some("buildTarget/sources")
+                    );
+                }
+                msg => panic!("expected the ping response, got: {:?}", msg),
+            }
+            assert!(start.elapsed() < Duration::from_secs(1));
+            assert_eq!(global_state.handlers.len(), 1);
+        }
+    }
+
+    mod test_unknown_methods {
+        use std::path::PathBuf;
+        use std::time::Instant;
+
+        use bsp_server::{ErrorCode, Message, Notification, Request};
+        use crossbeam_channel::unbounded;
+
+        use bsp4rs::bsp::BuildClientCapabilities;
+
+        use crate::server::config::Config;
+        use crate::server::global_state::GlobalState;
+
+        fn test_global_state() -> (GlobalState, crossbeam_channel::Receiver<Message>) {
+            let (sender, receiver) = unbounded();
+            let global_state = GlobalState::new(
+                sender,
+                Config::new(PathBuf::from("test"), BuildClientCapabilities::default()),
+            );
+            (global_state, receiver)
+        }
+
+        #[test]
+        fn unknown_request_method_not_found() {
+            let (mut global_state, receiver) = test_global_state();
+
+            for id in 1..=2 {
+                let req = Request {
+                    id: id.into(),
+                    method: "unknown/method".to_string(),
+                    params: Default::default(),
+                };
+                global_state.register_request(&req, Instant::now());
+                global_state.on_request(req);
+
+                match receiver.try_recv() {
+                    Ok(Message::Response(resp)) => {
+                        assert_eq!(resp.id, id.into());
+                        assert_eq!(resp.error.unwrap().code, ErrorCode::MethodNotFound as i32);
+                    }
+                    msg => panic!("expected the error response, got: {:?}", msg),
+                }
+            }
+            assert_eq!(global_state.unknown_methods.len(), 1);
+        }
+
+        #[test]
+        fn unknown_notification_ignored() {
+            let (mut global_state, receiver) = test_global_state();
+
+            global_state
+                .on_notification(Notification {
+                    method: "unknown/notification".to_string(),
+                    params: Default::default(),
+                })
+                .unwrap();
+
+            assert!(receiver.try_recv().is_err());
+        }
+    }
+
+    mod test_shutdown_order {
+        use std::path::PathBuf;
+
+        use bsp_server::{Connection, ErrorCode, Response};
+
+        use bsp4rs::bsp::{
+            BuildClientCapabilities, BuildShutdown, BuildTargetCompile, BuildTargetSources,
+            WorkspaceReload,
+        };
+        use bsp4rs::Request;
+
         use crate::server::config::Config;
         use crate::server::global_state::GlobalState;
         use crate::server::Result;
@@ -210,6 +723,48 @@ mod tests {
             case.test();
         }
 
+        #[test]
+        fn requests_after_shutdown_req() {
+            let methods = [
+                BuildTargetSources::METHOD,
+                BuildTargetCompile::METHOD,
+                WorkspaceReload::METHOD,
+                BuildShutdown::METHOD,
+            ];
+            let requests: Vec<bsp_server::Request> = (0i32..)
+                .zip(methods)
+                .map(|(id, method)| bsp_server::Request {
+                    id: id.into(),
+                    method: method.to_string(),
+                    params: Default::default(),
+                })
+                .collect();
+            let mut case = ConnectionTestCase::new(Channel::WorksOk, FuncReturns::Ok);
+            case.to_send.push(test_shutdown_req(234).into());
+            case.expected_recv.push(test_shutdown_resp(234).into());
+            for req in requests {
+                case.expected_recv.push(
+                    Response::new_err(
+                        req.id.clone(),
+                        ErrorCode::InvalidRequest as i32,
+                        "Shutdown already requested.".to_owned(),
+                    )
+                    .into(),
+                );
+                case.to_send.push(req.into());
+            }
+            case.to_send.push(test_exit_notif().into());
+            case.func_to_test = |server: Connection| -> Result<()> {
+                let global_state = GlobalState::new(
+                    server.sender,
+                    Config::new(PathBuf::from("test"), BuildClientCapabilities::default()),
+                );
+                global_state.run(server.receiver)
+            };
+
+            case.test();
+        }
+
         #[test]
         fn proper_shutdown_order() {
             shutdown_order_test(
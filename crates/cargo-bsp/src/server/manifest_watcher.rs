@@ -0,0 +1,113 @@
+//! Watches the manifests (`Cargo.toml`) of the workspace packages, so that the
+//! build targets can be recomputed and the client notified about their changes
+//! with the `buildTarget/didChange` notification.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use log::warn;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+const MANIFEST_FILE_NAME: &str = "Cargo.toml";
+/// Editors often save a file in a few steps, so the changes that happen within
+/// this time are reported once.
+const DEBOUNCE_TIME: Duration = Duration::from_millis(300);
+
+pub(crate) struct ManifestWatcher {
+    // The watcher is dropped first, which disconnects the debounce thread.
+    watcher: RecommendedWatcher,
+    watched_dirs: Vec<PathBuf>,
+    /// Receives a message when the manifests have changed (after the debounce time).
+    pub(crate) receiver: Receiver<()>,
+    _thread: jod_thread::JoinHandle,
+}
+
+impl ManifestWatcher {
+    pub(crate) fn new() -> notify::Result<ManifestWatcher> {
+        let (events_sender, events_receiver) = unbounded();
+        let watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) if is_manifest_change(&event) => {
+                    let _ = events_sender.send(());
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Watching manifests failed: {}", e),
+            })?;
+        let (sender, receiver) = unbounded();
+        let thread = jod_thread::Builder::new()
+            .spawn(move || debounce(events_receiver, sender))
+            .map_err(notify::Error::io)?;
+        Ok(ManifestWatcher {
+            watcher,
+            watched_dirs: vec![],
+            receiver,
+            _thread: thread,
+        })
+    }
+
+    /// Watches the directories of the given manifests instead of the previously watched ones.
+    /// Directories are watched (not the files), as editors may replace the file on save.
+    pub(crate) fn watch(&mut self, manifests: &[&Path]) {
+        for dir in self.watched_dirs.drain(..) {
+            let _ = self.watcher.unwatch(&dir);
+        }
+        let mut dirs: Vec<PathBuf> = manifests
+            .iter()
+            .filter_map(|m| m.parent())
+            .map(Path::to_path_buf)
+            .collect();
+        dirs.sort();
+        dirs.dedup();
+        for dir in dirs {
+            match self.watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                Ok(()) => self.watched_dirs.push(dir),
+                Err(e) => warn!("Failed to watch directory {:?}: {}", dir, e),
+            }
+        }
+    }
+}
+
+fn is_manifest_change(event: &notify::Event) -> bool {
+    matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) && event
+        .paths
+        .iter()
+        .any(|p| p.file_name() == Some(OsStr::new(MANIFEST_FILE_NAME)))
+}
+
+/// Sends a message after no more changes happen within [`DEBOUNCE_TIME`].
+fn debounce(events: Receiver<()>, sender: Sender<()>) {
+    while events.recv().is_ok() {
+        while events.recv_timeout(DEBOUNCE_TIME).is_ok() {}
+        if sender.send(()).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn changes_within_debounce_time_reported_once() {
+        let (events_sender, events_receiver) = unbounded();
+        let (sender, receiver) = unbounded();
+        let _thread = jod_thread::spawn(move || debounce(events_receiver, sender));
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            events_sender.send(()).unwrap();
+        }
+        receiver.recv().unwrap();
+        assert!(start.elapsed() >= DEBOUNCE_TIME);
+        assert!(receiver.recv_timeout(DEBOUNCE_TIME * 2).is_err());
+        // Disconnecting finishes the thread.
+        drop(events_sender);
+    }
+}
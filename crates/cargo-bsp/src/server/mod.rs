@@ -1,20 +1,32 @@
 //! [`Server`] manages the server and communication with the client and routes
 //! some requests to [`CargoCommunication`] and [`ProjectModel`].
 
-use std::fmt;
+use std::sync::Arc;
+use std::{fmt, io};
 
+use bsp_server::{ErrorCode, RequestId, Response};
 use serde::de::DeserializeOwned;
 
 pub use main_loop::main_loop;
-pub use server_run::run_server;
+pub use server_run::{run_server, Transport};
 
+mod build_queue;
 pub(crate) mod caps;
 pub(crate) mod config;
 mod dispatch;
 pub(crate) mod global_state;
 mod handlers;
+pub mod logging;
 mod main_loop;
+mod manifest_watcher;
 mod server_run;
+pub mod socket;
+
+/// Error code of the responses exceeding the `maxMessageSize` from the [`ServerSettings`],
+/// from the range reserved for implementation-defined server errors.
+///
+/// [`ServerSettings`]: config::ServerSettings
+pub const RESPONSE_TOO_LARGE: i32 = -32001;
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -25,20 +37,100 @@ pub fn from_json<T: DeserializeOwned>(what: &'static str, json: &serde_json::Val
     Ok(res)
 }
 
+/// Failure of handling a request, mapped into the error response with the matching code.
 #[derive(Debug)]
-pub struct LspError {
-    code: i32,
-    message: String,
+pub enum ServerError {
+    /// The `cargo metadata` command failed, e.g. because of an invalid manifest.
+    /// Shared, as the error of the workspace load is kept to respond to the later requests.
+    CargoMetadata(Arc<cargo_metadata::Error>),
+    /// Spawning the Cargo command (or the thread handling it) failed.
+    Spawn(io::Error),
+    /// The params of the request are invalid, with the reason.
+    InvalidParams(String),
+    /// The request was received before the server was initialized.
+    NotInitialized,
+    /// Any other failure, with its description.
+    Internal(String),
+}
+
+impl ServerError {
+    pub fn code(&self) -> i32 {
+        match self {
+            ServerError::CargoMetadata(_) | ServerError::Spawn(_) | ServerError::Internal(_) => {
+                ErrorCode::InternalError as i32
+            }
+            ServerError::InvalidParams(_) => ErrorCode::InvalidParams as i32,
+            ServerError::NotInitialized => ErrorCode::ServerNotInitialized as i32,
+        }
+    }
+
+    pub fn into_response(self, id: RequestId) -> Response {
+        Response::new_err(id, self.code(), self.to_string())
+    }
 }
 
-impl fmt::Display for LspError {
+impl fmt::Display for ServerError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "Language Server request failed with {}. ({})",
-            self.code, self.message
-        )
+        match self {
+            ServerError::CargoMetadata(e) => write!(f, "cargo metadata failed: {}", e),
+            ServerError::Spawn(e) => write!(f, "Failed to spawn the Cargo command: {}", e),
+            ServerError::InvalidParams(message) | ServerError::Internal(message) => {
+                write!(f, "{}", message)
+            }
+            ServerError::NotInitialized => write!(f, "server not initialized"),
+        }
+    }
+}
+
+impl std::error::Error for ServerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ServerError::CargoMetadata(e) => Some(e.as_ref()),
+            ServerError::Spawn(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<cargo_metadata::Error> for ServerError {
+    fn from(e: cargo_metadata::Error) -> ServerError {
+        ServerError::CargoMetadata(Arc::new(e))
     }
 }
 
-impl std::error::Error for LspError {}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(ServerError::from(cargo_metadata::Error::Io(io::Error::from(io::ErrorKind::NotFound))),
+    ErrorCode::InternalError ; "cargo_metadata")]
+    #[test_case(ServerError::Spawn(io::Error::from(io::ErrorKind::NotFound)),
+    ErrorCode::InternalError ; "spawn")]
+    #[test_case(ServerError::InvalidParams("invalid spec".to_string()),
+    ErrorCode::InvalidParams ; "invalid_params")]
+    #[test_case(ServerError::NotInitialized, ErrorCode::ServerNotInitialized ; "not_initialized")]
+    #[test_case(ServerError::Internal("failure".to_string()),
+    ErrorCode::InternalError ; "internal")]
+    fn test_server_error_code(error: ServerError, expected: ErrorCode) {
+        let message = error.to_string();
+
+        let response = error.into_response(1.into());
+
+        let response_error = response.error.unwrap();
+        assert_eq!(response_error.code, expected as i32);
+        assert_eq!(response_error.message, message);
+    }
+
+    #[test]
+    fn cargo_metadata_error_message_includes_source() {
+        let error = ServerError::from(cargo_metadata::Error::CargoMetadata {
+            stderr: "error: failed to parse manifest".to_string(),
+        });
+
+        assert!(error.to_string().starts_with("cargo metadata failed: "));
+        assert!(error
+            .to_string()
+            .ends_with("error: failed to parse manifest"));
+    }
+}
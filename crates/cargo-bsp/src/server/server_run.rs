@@ -1,31 +1,91 @@
 //! Runs the server and establishes connection with the client.
 
-use bsp_server::Connection;
-use log::info;
+use bsp_server::{Connection, ErrorCode, Message, RequestId, Response};
+use log::{info, warn};
 
-use bsp4rs::bsp::{InitializeBuildParams, InitializeBuildResult};
+use bsp4rs::bsp::{
+    BuildShutdown, InitializeBuildParams, InitializeBuildResult, OnBuildExit, OnBuildInitialized,
+};
+use bsp4rs::Notification as _;
+use bsp4rs::Request as _;
 
 use crate::server;
 use crate::server::caps::server_capabilities;
 use crate::server::config::Config;
-use crate::server::{from_json, Result};
-
-pub fn run_server() -> Result<()> {
-    info!("server will start");
+use crate::server::{from_json, logging, socket, Result, ServerError};
+
+/// Channel of the communication with the client, selected with the command line arguments.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub enum Transport {
+    #[default]
+    Stdio,
+    /// `--listen <addr>`, the server waits for the client to connect.
+    Listen(String),
+    /// `--connect <addr>`, the server connects to the listening client.
+    Connect(String),
+}
 
-    let (connection, io_threads) = Connection::bsp_stdio();
+impl Transport {
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Transport> {
+        let mut args = args.into_iter();
+        let transport = match args.next().as_deref() {
+            None => Transport::Stdio,
+            Some(flag @ ("--listen" | "--connect")) => {
+                let addr = args
+                    .next()
+                    .ok_or_else(|| format!("missing address after {}", flag))?;
+                match flag {
+                    "--listen" => Transport::Listen(addr),
+                    _ => Transport::Connect(addr),
+                }
+            }
+            Some(arg) => return Err(format!("unexpected argument: {}", arg).into()),
+        };
+        match args.next() {
+            Some(arg) => Err(format!("unexpected argument: {}", arg).into()),
+            None => Ok(transport),
+        }
+    }
+}
 
-    let config = initialize(&connection)?;
+pub fn run_server(transport: Transport) -> Result<()> {
+    info!("server will start");
 
-    server::main_loop(config, connection)?;
+    match transport {
+        Transport::Stdio => {
+            let (connection, io_threads) = Connection::bsp_stdio();
+            serve(connection)?;
+            io_threads.join()?;
+        }
+        Transport::Listen(addr) => {
+            let (connection, io_threads) = socket::listen(addr)?;
+            serve(connection)?;
+            io_threads.join()?;
+        }
+        Transport::Connect(addr) => {
+            let (connection, io_threads) = socket::connect(addr)?;
+            serve(connection)?;
+            io_threads.join()?;
+        }
+    }
 
-    io_threads.join()?;
     info!("server did shut down");
     Ok(())
 }
 
-/// Handles the initialize handshake with the client.
-fn initialize(connection: &Connection) -> Result<Config> {
+fn serve(connection: Connection) -> Result<()> {
+    let Some(config) = initialize(&connection)? else {
+        return Ok(());
+    };
+    logging::configure(&config.settings, &connection.sender);
+    let result = server::main_loop(config, connection);
+    logging::disconnect();
+    result
+}
+
+/// Handles the initialize handshake with the client. Returns `None` if the client shut
+/// the server down before the handshake was finished.
+fn initialize(connection: &Connection) -> Result<Option<Config>> {
     let (initialize_id, initialize_params) = connection.bsp_initialize_start()?;
     let initialize_params =
         from_json::<InitializeBuildParams>("InitializeParams", &initialize_params)?;
@@ -33,12 +93,79 @@ fn initialize(connection: &Connection) -> Result<Config> {
     let config = Config::from_initialize_params(initialize_params)?;
     let initialize_result = create_initialize_result(&config);
 
-    connection.bsp_initialize_finish(
+    let initialized = initialize_finish(
+        connection,
         initialize_id,
         serde_json::to_value(initialize_result).unwrap(),
     )?;
 
-    Ok(config)
+    Ok(initialized.then_some(config))
+}
+
+/// Sends the initialize response and waits for the `build/initialized` notification.
+/// Requests received in the meantime are rejected, as the server is not initialized yet.
+/// The client may also shut the server down in the meantime: `false` is returned after
+/// the exit notification following the shutdown request, while the exit without
+/// the shutdown request is an error, as in the main loop.
+fn initialize_finish(
+    connection: &Connection,
+    initialize_id: RequestId,
+    initialize_result: serde_json::Value,
+) -> Result<bool> {
+    connection
+        .sender
+        .send(Response::new_ok(initialize_id, initialize_result).into())?;
+    let mut shutdown_requested = false;
+    loop {
+        match connection.receiver.recv() {
+            Ok(Message::Notification(not)) if not.method == OnBuildExit::METHOD => {
+                if !shutdown_requested {
+                    return Err("client exited without proper shutdown sequence".into());
+                }
+                return Ok(false);
+            }
+            Ok(Message::Notification(not))
+                if not.method == OnBuildInitialized::METHOD && !shutdown_requested =>
+            {
+                return Ok(true)
+            }
+            Ok(Message::Notification(not)) if shutdown_requested => {
+                warn!("Notification received after shutdown request: {:?}", not);
+            }
+            Ok(Message::Request(req)) if req.method == BuildShutdown::METHOD => {
+                info!("Shutdown requested before initialized notification");
+                shutdown_requested = true;
+                connection
+                    .sender
+                    .send(Response::new_ok(req.id, ()).into())?;
+            }
+            Ok(Message::Request(req)) if shutdown_requested => {
+                connection.sender.send(
+                    Response::new_err(
+                        req.id,
+                        ErrorCode::InvalidRequest as i32,
+                        "Shutdown already requested.".to_owned(),
+                    )
+                    .into(),
+                )?;
+            }
+            Ok(Message::Request(req)) => {
+                warn!(
+                    "Request received before initialized notification: {:?}",
+                    req
+                );
+                connection
+                    .sender
+                    .send(ServerError::NotInitialized.into_response(req.id).into())?;
+            }
+            Ok(msg) => {
+                return Err(format!("expected initialized notification, got: {:?}", msg).into())
+            }
+            Err(e) => {
+                return Err(format!("expected initialized notification, got error: {}", e).into())
+            }
+        }
+    }
 }
 
 fn create_initialize_result(config: &Config) -> InitializeBuildResult {
@@ -53,16 +180,60 @@ fn create_initialize_result(config: &Config) -> InitializeBuildResult {
 
 #[cfg(test)]
 mod tests {
+    mod test_transport {
+        use crate::server::server_run::Transport;
+
+        fn transport(args: &[&str]) -> Result<Transport, String> {
+            Transport::from_args(args.iter().map(|a| a.to_string())).map_err(|e| e.to_string())
+        }
+
+        #[test]
+        fn transport_from_args() {
+            assert_eq!(transport(&[]), Ok(Transport::Stdio));
+            assert_eq!(
+                transport(&["--listen", "127.0.0.1:1234"]),
+                Ok(Transport::Listen("127.0.0.1:1234".to_string()))
+            );
+            assert_eq!(
+                transport(&["--connect", "127.0.0.1:1234"]),
+                Ok(Transport::Connect("127.0.0.1:1234".to_string()))
+            );
+        }
+
+        #[test]
+        fn invalid_transport_args() {
+            assert_eq!(
+                transport(&["--listen"]),
+                Err("missing address after --listen".to_string())
+            );
+            assert_eq!(
+                transport(&["--stdio"]),
+                Err("unexpected argument: --stdio".to_string())
+            );
+            assert_eq!(
+                transport(&["--connect", "127.0.0.1:1234", "other"]),
+                Err("unexpected argument: other".to_string())
+            );
+        }
+    }
+
     mod test_initialize {
         use bsp_server::{Connection, ErrorCode, Message, Response};
         use crossbeam_channel::RecvError;
 
+        use bsp4rs::bsp::{
+            BuildTargetCompile, BuildTargetResources, BuildTargetRun, BuildTargetSources,
+            BuildTargetTest,
+        };
+        use bsp4rs::Request;
+
         use crate::server::config::Config;
         use crate::server::server_run::{create_initialize_result, initialize};
         use crate::server::Result;
         use crate::utils::tests::{
             test_exit_notif, test_init_notif, test_init_params, test_init_req, test_init_resp,
-            test_sources_req, test_sources_resp, Channel, ConnectionTestCase, FuncReturns,
+            test_shutdown_req, test_shutdown_resp, test_sources_req, test_sources_resp, Channel,
+            ConnectionTestCase, FuncReturns,
         };
 
         enum InitReq {
@@ -96,7 +267,7 @@ mod tests {
                 }
                 InitReq::SendAsFirst => {
                     case.to_send.insert(0, init_req.into());
-                    case.expected_recv.push(init_resp.into());
+                    case.expected_recv.insert(0, init_resp.into());
                 }
                 InitReq::Omit => {}
             }
@@ -141,6 +312,45 @@ mod tests {
             );
         }
 
+        #[test]
+        fn requests_before_init_notif() {
+            let methods = [
+                BuildTargetSources::METHOD,
+                BuildTargetCompile::METHOD,
+                BuildTargetRun::METHOD,
+                BuildTargetTest::METHOD,
+                BuildTargetResources::METHOD,
+            ];
+            let requests: Vec<bsp_server::Request> = (0i32..)
+                .zip(methods)
+                .map(|(id, method)| bsp_server::Request {
+                    id: id.into(),
+                    method: method.to_string(),
+                    params: Default::default(),
+                })
+                .collect();
+
+            initialize_order_test(
+                ConnectionTestCase {
+                    to_send: requests.iter().cloned().map(Message::from).collect(),
+                    expected_recv: requests
+                        .into_iter()
+                        .map(|req| {
+                            Response::new_err(
+                                req.id,
+                                ErrorCode::ServerNotInitialized as i32,
+                                "server not initialized".to_string(),
+                            )
+                            .into()
+                        })
+                        .collect(),
+                    ..ConnectionTestCase::new(Channel::WorksOk, FuncReturns::Ok)
+                },
+                InitReq::SendAsFirst,
+                InitNotif::Send,
+            );
+        }
+
         #[test]
         fn some_notif_before_init_req() {
             initialize_order_test(
@@ -204,6 +414,54 @@ mod tests {
             );
         }
 
+        #[test]
+        fn shutdown_before_init_notif() {
+            let init_params = test_init_params();
+            let config = Config::from_initialize_params(init_params.clone()).unwrap();
+            let request_after_shutdown = test_sources_req(3);
+
+            ConnectionTestCase {
+                to_send: vec![
+                    test_init_req(&init_params, 1).into(),
+                    test_shutdown_req(2).into(),
+                    request_after_shutdown.clone().into(),
+                    test_init_notif().into(),
+                    test_exit_notif().into(),
+                ],
+                expected_recv: vec![
+                    test_init_resp(&create_initialize_result(&config), 1).into(),
+                    test_shutdown_resp(2).into(),
+                    Response::new_err(
+                        request_after_shutdown.id,
+                        ErrorCode::InvalidRequest as i32,
+                        "Shutdown already requested.".to_string(),
+                    )
+                    .into(),
+                ],
+                func_to_test: |server: Connection| -> Result<()> {
+                    match initialize(&server)? {
+                        Some(_) => Err("server initialized after shutdown".into()),
+                        None => Ok(()),
+                    }
+                },
+                ..ConnectionTestCase::new(Channel::WorksOk, FuncReturns::Ok)
+            }
+            .test();
+        }
+
+        #[test]
+        fn exit_notif_before_init_notif() {
+            initialize_order_test(
+                ConnectionTestCase {
+                    to_send: vec![test_exit_notif().into()],
+                    expected_err: "client exited without proper shutdown sequence".to_string(),
+                    ..ConnectionTestCase::new(Channel::WorksOk, FuncReturns::Error)
+                },
+                InitReq::SendAsFirst,
+                InitNotif::Omit,
+            );
+        }
+
         #[test]
         fn wrong_msg_before_init_notif() {
             let wrong_msg = test_sources_resp(123);
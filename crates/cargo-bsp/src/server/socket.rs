@@ -0,0 +1,84 @@
+//! Communication with the client over a TCP socket, as an alternative to the stdio.
+//! The messages are framed in the same way (with the `Content-Length` headers),
+//! so the client can use the same protocol implementation for both.
+
+use std::io::{self, BufReader};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+
+use bsp_server::{Connection, Message};
+use crossbeam_channel::{bounded, Receiver, Sender};
+use log::info;
+
+use bsp4rs::bsp::OnBuildExit;
+use bsp4rs::Notification as _;
+
+/// Waits for the client to connect on the given address.
+pub fn listen<A: ToSocketAddrs>(addr: A) -> io::Result<(Connection, IoThreads)> {
+    let listener = TcpListener::bind(addr)?;
+    let (stream, client_addr) = listener.accept()?;
+    info!("client connected from {}", client_addr);
+    socket_transport(stream)
+}
+
+/// Connects to the client listening on the given address.
+pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<(Connection, IoThreads)> {
+    let stream = TcpStream::connect(addr)?;
+    socket_transport(stream)
+}
+
+fn socket_transport(stream: TcpStream) -> io::Result<(Connection, IoThreads)> {
+    let (receiver, reader) = make_reader(stream.try_clone()?);
+    let (sender, writer) = make_writer(stream);
+    Ok((
+        Connection { sender, receiver },
+        IoThreads { reader, writer },
+    ))
+}
+
+/// Reads the messages until the exit notification, which is the last message
+/// expected from the client.
+fn make_reader(stream: TcpStream) -> (Receiver<Message>, thread::JoinHandle<io::Result<()>>) {
+    let (reader_sender, reader_receiver) = bounded::<Message>(0);
+    let reader = thread::spawn(move || {
+        let mut buf_read = BufReader::new(stream);
+        while let Some(msg) = Message::read(&mut buf_read)? {
+            let is_exit =
+                matches!(&msg, Message::Notification(n) if n.method == OnBuildExit::METHOD);
+            if reader_sender.send(msg).is_err() || is_exit {
+                break;
+            }
+        }
+        Ok(())
+    });
+    (reader_receiver, reader)
+}
+
+fn make_writer(mut stream: TcpStream) -> (Sender<Message>, thread::JoinHandle<io::Result<()>>) {
+    let (writer_sender, writer_receiver) = bounded::<Message>(0);
+    let writer = thread::spawn(move || {
+        writer_receiver
+            .into_iter()
+            .try_for_each(|msg| msg.write(&mut stream))
+    });
+    (writer_sender, writer)
+}
+
+/// Threads reading and writing the messages, joined after the server has finished.
+pub struct IoThreads {
+    reader: thread::JoinHandle<io::Result<()>>,
+    writer: thread::JoinHandle<io::Result<()>>,
+}
+
+impl IoThreads {
+    pub fn join(self) -> io::Result<()> {
+        match self.reader.join() {
+            Ok(r) => r?,
+            Err(err) => std::panic::panic_any(err),
+        }
+        match self.writer.join() {
+            Ok(r) => r,
+            Err(err) => std::panic::panic_any(err),
+        }
+    }
+}
@@ -12,6 +12,7 @@ use bsp4rs::bsp::{
     InitializeBuildResult, SourcesParams, SourcesResult,
 };
 use bsp4rs::bsp::{OnBuildExit, OnBuildInitialized};
+use bsp4rs::cancel::{CancelRequest, CancelRequestParams};
 use bsp4rs::{Notification, Request};
 
 use crate::server::Result;
@@ -151,3 +152,13 @@ pub fn test_exit_notif() -> bsp_server::Notification {
         params: Default::default(),
     }
 }
+
+pub fn test_cancel_notif(id: i32) -> bsp_server::Notification {
+    bsp_server::Notification {
+        method: CancelRequest::METHOD.to_string(),
+        params: to_value(CancelRequestParams {
+            id: bsp4rs::cancel::RequestId::I32(id),
+        })
+        .unwrap(),
+    }
+}
@@ -5,5 +5,5 @@ use std::fmt::Display;
 use bsp4rs::bsp::URI;
 
 pub fn file_uri<T: Display>(path: T) -> URI {
-    URI(format!("file://{}", path))
+    URI::from_file_path(path.to_string())
 }
@@ -3,21 +3,30 @@
 
 use std::io::prelude::*;
 use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, ChildStdin, ChildStdout};
+use std::net::TcpStream;
+use std::process::Child;
 
 use bsp_server::Message;
 use serde_json::from_str;
 
 pub struct Client<'a> {
-    buf_reader: BufReader<&'a mut ChildStdout>,
-    child_stdin: ChildStdin,
+    buf_reader: BufReader<Box<dyn Read + 'a>>,
+    writer: Box<dyn Write + 'a>,
 }
 
 impl<'a> Client<'a> {
     pub fn new(child: &'a mut Child) -> Self {
         Self {
-            buf_reader: BufReader::new(child.stdout.as_mut().unwrap()),
-            child_stdin: child.stdin.take().unwrap(),
+            buf_reader: BufReader::new(Box::new(child.stdout.as_mut().unwrap())),
+            writer: Box::new(child.stdin.take().unwrap()),
+        }
+    }
+
+    /// Communicates with the server connected over the TCP socket.
+    pub fn from_stream(stream: TcpStream) -> Self {
+        Self {
+            buf_reader: BufReader::new(Box::new(stream.try_clone().unwrap())),
+            writer: Box::new(stream),
         }
     }
 
@@ -55,7 +64,7 @@ impl<'a> Client<'a> {
 
     pub fn send(&mut self, msg: &str) {
         let msg_with_headers = self.add_headers(msg);
-        self.child_stdin
+        self.writer
             .write_all(msg_with_headers.as_bytes())
             .expect("Failed to send a message");
         println!("Client has send a message:\n{}", msg_with_headers);
@@ -1,10 +1,16 @@
 //! Testing connection with the client - initialize handshake and shutdown request.
 
+use std::net::TcpListener;
+use std::process::Stdio;
+
+use assert_cmd::cargo::CommandCargoExt;
 use cargo_bsp::utils::tests::*;
 use serde_json::to_string;
 
 mod common;
-use crate::common::{init_connection, spawn_server, spawn_server_with_proper_life_time, Client};
+use crate::common::{
+    init_connection, shutdown_connection, spawn_server, spawn_server_with_proper_life_time, Client,
+};
 
 #[test]
 fn proper_lifetime() {
@@ -30,3 +36,24 @@ fn exit_notif_without_shutdown() {
     cl.send(&to_string(&test_exit_notif()).unwrap());
     assert_eq!(child.wait().unwrap().code(), Some(1));
 }
+
+#[test]
+fn proper_lifetime_over_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut child = std::process::Command::cargo_bin("server")
+        .unwrap()
+        .args(["--connect", &addr.to_string()])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let (stream, _) = listener.accept().unwrap();
+    let mut cl = Client::from_stream(stream);
+
+    init_connection(&mut cl);
+    shutdown_connection(&mut cl);
+
+    assert_eq!(child.wait().unwrap().code(), Some(0));
+}
@@ -0,0 +1,78 @@
+//! Integration test of the `workspace/cargoReload` request. The test in this module changes working
+//! directory, when adding extra tests remember to execute them sequentially.
+
+use bsp4rs::bsp::{WorkspaceBuildTargets, WorkspaceBuildTargetsResult};
+use bsp4rs::cargo::{CargoWorkspaceReload, WorkspaceReloadResult};
+use bsp4rs::Request;
+use bsp_server::Response;
+use serde_json::to_string;
+use std::env::{current_dir, set_current_dir};
+use std::fs;
+use std::process::Command;
+use tempfile::tempdir;
+
+const TEST_REQUEST_ID: i32 = 123;
+const TEST_PROJECT_NAME: &str = "tmp_test_project";
+const TEST_NEW_BIN_NAME: &str = "new_bin";
+
+mod common;
+
+use common::{spawn_server_with_proper_life_time, Client};
+
+fn send_request_and_get_response(cl: &mut Client, method: &str) -> Response {
+    let req = bsp_server::Request {
+        id: TEST_REQUEST_ID.into(),
+        method: method.into(),
+        params: Default::default(),
+    };
+    cl.send(&to_string(&req).unwrap());
+    serde_json::from_str(&cl.recv_resp()).unwrap()
+}
+
+fn build_target_names(cl: &mut Client) -> Vec<String> {
+    let resp = send_request_and_get_response(cl, WorkspaceBuildTargets::METHOD);
+    let result: WorkspaceBuildTargetsResult = serde_json::from_value(resp.result.unwrap()).unwrap();
+    let mut names: Vec<String> = result
+        .targets
+        .into_iter()
+        .filter_map(|t| t.display_name)
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn reload_discovers_new_target() {
+    let starting_path = current_dir().unwrap();
+    let tmp_dir = tempdir().unwrap();
+    set_current_dir(tmp_dir.path()).unwrap();
+    Command::new(toolchain::cargo())
+        .args(["init", ".", "--name", TEST_PROJECT_NAME])
+        .output()
+        .expect("Failed to create new temporary project for testing.");
+
+    let test_fn = |cl: &mut Client| {
+        assert_eq!(build_target_names(cl), vec![TEST_PROJECT_NAME]);
+
+        // Binaries in `src/bin` are discovered by Cargo, the manifest doesn't change.
+        fs::create_dir_all("src/bin").unwrap();
+        fs::write(
+            format!("src/bin/{}.rs", TEST_NEW_BIN_NAME),
+            "fn main() {}\n",
+        )
+        .unwrap();
+        let resp = send_request_and_get_response(cl, CargoWorkspaceReload::METHOD);
+        assert!(resp.error.is_none());
+        let result: WorkspaceReloadResult = serde_json::from_value(resp.result.unwrap()).unwrap();
+        assert!(result.changed);
+        assert_eq!(result.added.len(), 1);
+
+        assert_eq!(
+            build_target_names(cl),
+            vec![TEST_NEW_BIN_NAME, TEST_PROJECT_NAME]
+        );
+    };
+
+    spawn_server_with_proper_life_time(test_fn);
+    set_current_dir(starting_path).unwrap();
+}